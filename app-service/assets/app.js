@@ -10,6 +10,9 @@ logoutLink.addEventListener("click", (e) => {
     fetch(url, {
         method: 'POST',
         credentials: 'include', // This will include cookies in the request
+        headers: {
+            'X-CSRF-Token': getCsrfToken(),
+        },
     }).then(response => {
         if (response.ok) {
             loginLink.style.display = "block";
@@ -1,22 +1,47 @@
 use std::env;
 
 use askama::Template;
+use auth_middleware::{
+    require_role, validator::RemoteValidator, AuthGuard, AuthenticatedUser, RequireRole,
+    TokenValidator,
+};
 use axum::{
-    http::StatusCode,
+    middleware,
     response::{Html, IntoResponse},
     routing::get,
     Json, Router,
 };
-use axum_extra::extract::CookieJar;
 use serde::Serialize;
 use tower_http::services::ServeDir;
 
 #[tokio::main]
 async fn main() {
+    // tokens are checked by the auth service so logged out ones are rejected,
+    // the verdicts are cached for a short while
+    let auth_hostname = env::var("AUTH_SERVICE_HOST_NAME").unwrap_or("localhost".to_owned());
+    let mut validator = RemoteValidator::new(&format!("http://{}:3000", auth_hostname));
+    // introspection needs a client registered in the auth service
+    if let (Ok(client_id), Ok(client_secret)) =
+        (env::var("AUTH_CLIENT_ID"), env::var("AUTH_CLIENT_SECRET"))
+    {
+        if !client_id.is_empty() && !client_secret.is_empty() {
+            validator = validator.with_client_credentials(&client_id, &client_secret);
+        }
+    }
+    let auth_guard = AuthGuard::new(TokenValidator::Remote(validator));
+
     let app = Router::new()
         .nest_service("/assets", ServeDir::new("assets"))
         .route("/", get(root))
-        .route("/protected", get(protected));
+        .route("/protected", get(protected))
+        .route(
+            "/admin",
+            get(admin).layer(middleware::from_fn_with_state(
+                (auth_guard.clone(), RequireRole("admin")),
+                require_role,
+            )),
+        )
+        .with_state(auth_guard);
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8000").await.unwrap();
 
@@ -32,10 +57,6 @@ struct IndexTemplate {
 }
 
 async fn root() -> impl IntoResponse {
-    let mut address = env::var("AUTH_SERVICE_IP").unwrap_or("localhost".to_owned());
-    if address.is_empty() {
-        address = "localhost".to_owned();
-    }
     let login_link = "/auth".to_owned();
     let logout_link = "/auth/logout".to_owned();
 
@@ -46,43 +67,28 @@ async fn root() -> impl IntoResponse {
     Html(template.render().unwrap())
 }
 
-async fn protected(jar: CookieJar) -> impl IntoResponse {
-    let jwt_cookie = match jar.get("jwt") {
-        Some(cookie) => cookie,
-        None => {
-            return StatusCode::UNAUTHORIZED.into_response();
-        }
-    };
-
-    let api_client = reqwest::Client::builder().build().unwrap();
-
-    let verify_token_body = serde_json::json!({
-        "token": &jwt_cookie.value(),
-    });
-
-    let auth_hostname = env::var("AUTH_SERVICE_HOST_NAME").unwrap_or("localhost".to_owned());
-    let url = format!("http://{}:3000/verify-token", auth_hostname);
-
-    let response = match api_client.post(&url).json(&verify_token_body).send().await {
-        Ok(response) => response,
-        Err(_) => {
-            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-        }
-    };
-
-    match response.status() {
-        reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::BAD_REQUEST => {
-            StatusCode::UNAUTHORIZED.into_response()
-        }
-        reqwest::StatusCode::OK => Json(ProtectedRouteResponse {
-            img_url: "https://i.ibb.co/YP90j68/Light-Live-Bootcamp-Certificate.png".to_owned(),
-        })
-        .into_response(),
-        _ => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-    }
+async fn protected(user: AuthenticatedUser) -> impl IntoResponse {
+    Json(ProtectedRouteResponse {
+        img_url: "https://i.ibb.co/YP90j68/Light-Live-Bootcamp-Certificate.png".to_owned(),
+        email: user.email().to_owned(),
+    })
 }
 
 #[derive(Serialize)]
 pub struct ProtectedRouteResponse {
     pub img_url: String,
+    pub email: String,
+}
+
+async fn admin(user: AuthenticatedUser) -> impl IntoResponse {
+    Json(AdminRouteResponse {
+        email: user.email().to_owned(),
+        roles: user.claims.roles,
+    })
+}
+
+#[derive(Serialize)]
+pub struct AdminRouteResponse {
+    pub email: String,
+    pub roles: Vec<String>,
 }
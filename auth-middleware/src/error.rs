@@ -0,0 +1,39 @@
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum AuthError {
+    #[error("Missing token")]
+    MissingToken,
+    #[error("Invalid token")]
+    InvalidToken,
+    #[error("Missing role {0}")]
+    MissingRole(String),
+    #[error("Auth service unavailable: {0}")]
+    Unavailable(String),
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        let (status, error_message) = match self {
+            AuthError::MissingToken => (StatusCode::UNAUTHORIZED, "Missing token"),
+            AuthError::InvalidToken => (StatusCode::UNAUTHORIZED, "JWT is not valid"),
+            AuthError::MissingRole(_) => (StatusCode::FORBIDDEN, "Insufficient role"),
+            AuthError::Unavailable(_) => (StatusCode::SERVICE_UNAVAILABLE, "Unexpected error"),
+        };
+        let body = Json(ErrorResponse {
+            error: error_message.to_owned(),
+        });
+        (status, body).into_response()
+    }
+}
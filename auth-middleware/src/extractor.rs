@@ -0,0 +1,136 @@
+use async_trait::async_trait;
+use axum::{
+    extract::{FromRef, FromRequestParts, Request, State},
+    http::{header::AUTHORIZATION, request::Parts},
+    middleware::Next,
+    response::Response,
+};
+use axum_extra::extract::cookie::CookieJar;
+
+use crate::error::AuthError;
+use crate::validator::{Claims, TokenValidator};
+
+const DEFAULT_COOKIE_NAME: &str = "jwt";
+
+/// Where to look for tokens and how to validate them. Put it in the router
+/// state (or make it reachable through `FromRef`) to use the extractor.
+#[derive(Clone)]
+pub struct AuthGuard {
+    validator: TokenValidator,
+    cookie_name: String,
+}
+
+impl AuthGuard {
+    pub fn new(validator: TokenValidator) -> Self {
+        Self {
+            validator,
+            cookie_name: DEFAULT_COOKIE_NAME.to_owned(),
+        }
+    }
+
+    /// Name of the auth cookie, including any prefix such as `__Host-`.
+    pub fn with_cookie_name(mut self, cookie_name: &str) -> Self {
+        self.cookie_name = cookie_name.to_owned();
+        self
+    }
+
+    fn token(&self, parts: &Parts) -> Option<String> {
+        let bearer = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split_once(' '))
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+            .map(|(_, token)| token.trim().to_owned())
+            .filter(|token| !token.is_empty());
+        bearer.or_else(|| {
+            CookieJar::from_headers(&parts.headers)
+                .get(&self.cookie_name)
+                .map(|cookie| cookie.value().to_owned())
+        })
+    }
+
+    pub async fn authenticate(&self, parts: &Parts) -> Result<AuthenticatedUser, AuthError> {
+        let token = self.token(parts).ok_or(AuthError::MissingToken)?;
+        let claims = self.validator.validate(&token).await?;
+        Ok(AuthenticatedUser { claims, token })
+    }
+}
+
+/// A request authenticated by a token from an `Authorization: Bearer` header
+/// or, failing that, from the auth cookie.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    pub claims: Claims,
+    pub token: String,
+}
+
+impl AuthenticatedUser {
+    /// The user email.
+    pub fn email(&self) -> &str {
+        &self.claims.sub
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.claims.has_role(role)
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.claims.has_scope(scope)
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AuthenticatedUser
+where
+    AuthGuard: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        // already validated by `require_auth`
+        if let Some(user) = parts.extensions.get::<AuthenticatedUser>() {
+            return Ok(user.clone());
+        }
+        AuthGuard::from_ref(state).authenticate(parts).await
+    }
+}
+
+/// Middleware rejecting unauthenticated requests, for whole routers:
+/// `router.layer(middleware::from_fn_with_state(guard, require_auth))`.
+/// Handlers behind it can still take an [`AuthenticatedUser`].
+pub async fn require_auth(
+    State(guard): State<AuthGuard>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AuthError> {
+    let (mut parts, body) = request.into_parts();
+    let user = guard.authenticate(&parts).await?;
+    parts.extensions.insert(user);
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
+
+/// Role a [`require_role`] layer asks for.
+#[derive(Debug, Clone, Copy)]
+pub struct RequireRole(pub &'static str);
+
+/// Middleware rejecting requests whose token lacks a role, with 403:
+/// `router.layer(middleware::from_fn_with_state((guard, RequireRole("admin")), require_role))`.
+/// Roles come from the token, so changes apply from the user's next login.
+pub async fn require_role(
+    State((guard, RequireRole(role))): State<(AuthGuard, RequireRole)>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AuthError> {
+    let (mut parts, body) = request.into_parts();
+    let user = match parts.extensions.get::<AuthenticatedUser>() {
+        Some(user) => user.clone(),
+        None => guard.authenticate(&parts).await?,
+    };
+    if !user.has_role(role) {
+        return Err(AuthError::MissingRole(role.to_owned()));
+    }
+    parts.extensions.insert(user);
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
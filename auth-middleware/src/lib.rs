@@ -0,0 +1,16 @@
+//! Authentication for services sitting behind the auth service.
+//!
+//! An [`AuthGuard`] validates the tokens issued by the auth service, either
+//! JWTs locally with the shared secret or any token, personal access tokens
+//! included, remotely through its `/verify-token` endpoint, and the
+//! [`AuthenticatedUser`] extractor (or the [`require_auth`] layer) rejects
+//! requests without a valid token. [`require_role`] also checks the roles
+//! embedded in the token.
+
+pub mod error;
+pub mod extractor;
+pub mod validator;
+
+pub use error::AuthError;
+pub use extractor::{AuthGuard, AuthenticatedUser, RequireRole, require_auth, require_role};
+pub use validator::{Claims, TokenValidator};
@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use jsonwebtoken::{DecodingKey, Validation, decode};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AuthError;
+
+#[cfg(test)]
+mod tests;
+
+const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(30);
+const DEFAULT_MAX_CACHE_ENTRIES: usize = 10_000;
+
+/// The claims of a token issued by the auth service.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub exp: usize,
+    /// Space-separated scopes of service and personal access tokens, user
+    /// sessions have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// Roles of the user when the token was issued.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+}
+
+impl Claims {
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scope
+            .as_deref()
+            .is_some_and(|scopes| scopes.split_whitespace().any(|s| s == scope))
+    }
+}
+
+/// How tokens are validated.
+///
+/// `Local` checks the signature with the secret shared with the auth service
+/// and never leaves the process, but can't see tokens banned by a logout.
+/// `Remote` asks the auth service, so it does, at the cost of a request per
+/// token every `cache_ttl`.
+#[derive(Clone)]
+pub enum TokenValidator {
+    Local(LocalValidator),
+    Remote(RemoteValidator),
+}
+
+impl TokenValidator {
+    pub fn local(jwt_secret: &str) -> Self {
+        Self::Local(LocalValidator::new(jwt_secret))
+    }
+
+    /// `auth_service_url` is the base URL, e.g. `http://auth-service:3000`.
+    pub fn remote(auth_service_url: &str) -> Self {
+        Self::Remote(RemoteValidator::new(auth_service_url))
+    }
+
+    pub async fn validate(&self, token: &str) -> Result<Claims, AuthError> {
+        match self {
+            Self::Local(validator) => validator.validate(token),
+            Self::Remote(validator) => validator.validate(token).await,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct LocalValidator {
+    decoding_key: DecodingKey,
+    validation: Arc<Validation>,
+}
+
+impl LocalValidator {
+    pub fn new(jwt_secret: &str) -> Self {
+        Self {
+            decoding_key: DecodingKey::from_secret(jwt_secret.as_bytes()),
+            validation: Arc::new(Validation::default()),
+        }
+    }
+
+    pub fn validate(&self, token: &str) -> Result<Claims, AuthError> {
+        decode::<Claims>(token, &self.decoding_key, &self.validation)
+            .map(|data| data.claims)
+            .map_err(|_| AuthError::InvalidToken)
+    }
+}
+
+struct CachedVerdict {
+    claims: Option<Claims>,
+    expires_at: Instant,
+}
+
+struct TokenCache {
+    entries: HashMap<String, CachedVerdict>,
+    max_entries: usize,
+}
+
+impl TokenCache {
+    fn new(max_entries: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            max_entries,
+        }
+    }
+
+    // Outer None means "not cached", inner None means "cached as invalid".
+    fn get(&self, token: &str, now: Instant) -> Option<Option<Claims>> {
+        self.entries
+            .get(token)
+            .filter(|verdict| verdict.expires_at > now)
+            .map(|verdict| verdict.claims.clone())
+    }
+
+    fn insert(&mut self, token: String, verdict: CachedVerdict, now: Instant) {
+        if self.entries.len() >= self.max_entries {
+            self.entries.retain(|_, verdict| verdict.expires_at > now);
+        }
+        if self.entries.len() >= self.max_entries {
+            self.entries.clear();
+        }
+        self.entries.insert(token, verdict);
+    }
+}
+
+// A valid token is never cached past its own expiry.
+fn seconds_until_exp(claims: &Claims) -> Duration {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    Duration::from_secs((claims.exp as u64).saturating_sub(now))
+}
+
+#[derive(Deserialize)]
+struct IntrospectResponse {
+    active: bool,
+    sub: Option<String>,
+    exp: Option<usize>,
+    scope: Option<String>,
+    #[serde(default)]
+    roles: Vec<String>,
+}
+
+#[derive(Clone)]
+struct ClientCredentials {
+    client_id: String,
+    client_secret: String,
+}
+
+/// Validates tokens through the auth service, reusing one HTTP client and
+/// caching verdicts for `cache_ttl`. Uses `/introspect` when given client
+/// credentials and `/verify-token` otherwise.
+#[derive(Clone)]
+pub struct RemoteValidator {
+    client: reqwest::Client,
+    auth_service_url: String,
+    credentials: Option<ClientCredentials>,
+    cache_ttl: Duration,
+    cache: Arc<Mutex<TokenCache>>,
+}
+
+impl RemoteValidator {
+    pub fn new(auth_service_url: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            auth_service_url: auth_service_url.trim_end_matches('/').to_owned(),
+            credentials: None,
+            cache_ttl: DEFAULT_CACHE_TTL,
+            cache: Arc::new(Mutex::new(TokenCache::new(DEFAULT_MAX_CACHE_ENTRIES))),
+        }
+    }
+
+    /// Credentials of an introspection client registered in the auth service.
+    pub fn with_client_credentials(mut self, client_id: &str, client_secret: &str) -> Self {
+        self.credentials = Some(ClientCredentials {
+            client_id: client_id.to_owned(),
+            client_secret: client_secret.to_owned(),
+        });
+        self
+    }
+
+    /// A zero TTL disables the cache.
+    pub fn with_cache_ttl(mut self, cache_ttl: Duration) -> Self {
+        self.cache_ttl = cache_ttl;
+        self
+    }
+
+    pub async fn validate(&self, token: &str) -> Result<Claims, AuthError> {
+        let now = Instant::now();
+        if let Some(verdict) = self.cache.lock().unwrap().get(token, now) {
+            return verdict.ok_or(AuthError::InvalidToken);
+        }
+
+        let claims = self.verify_remotely(token).await;
+        let claims = match claims {
+            Ok(claims) => Some(claims),
+            Err(AuthError::InvalidToken) => None,
+            Err(e) => return Err(e),
+        };
+
+        if !self.cache_ttl.is_zero() {
+            let ttl = match &claims {
+                Some(claims) => self.cache_ttl.min(seconds_until_exp(claims)),
+                None => self.cache_ttl,
+            };
+            let verdict = CachedVerdict {
+                claims: claims.clone(),
+                expires_at: now + ttl,
+            };
+            self.cache
+                .lock()
+                .unwrap()
+                .insert(token.to_owned(), verdict, now);
+        }
+        claims.ok_or(AuthError::InvalidToken)
+    }
+
+    async fn verify_remotely(&self, token: &str) -> Result<Claims, AuthError> {
+        match &self.credentials {
+            Some(credentials) => self.introspect(token, credentials).await,
+            None => self.verify_token(token).await,
+        }
+    }
+
+    async fn introspect(
+        &self,
+        token: &str,
+        credentials: &ClientCredentials,
+    ) -> Result<Claims, AuthError> {
+        let response = self
+            .client
+            .post(format!("{}/introspect", self.auth_service_url))
+            .basic_auth(&credentials.client_id, Some(&credentials.client_secret))
+            .form(&[("token", token)])
+            .send()
+            .await
+            .map_err(|e| AuthError::Unavailable(e.to_string()))?;
+
+        if response.status() != reqwest::StatusCode::OK {
+            return Err(AuthError::Unavailable(format!(
+                "/introspect returned {}",
+                response.status()
+            )));
+        }
+        let body: IntrospectResponse = response
+            .json()
+            .await
+            .map_err(|e| AuthError::Unavailable(e.to_string()))?;
+        match body {
+            IntrospectResponse {
+                active: true,
+                sub: Some(sub),
+                exp: Some(exp),
+                scope,
+                roles,
+            } => Ok(Claims {
+                sub,
+                exp,
+                scope,
+                roles,
+            }),
+            _ => Err(AuthError::InvalidToken),
+        }
+    }
+
+    async fn verify_token(&self, token: &str) -> Result<Claims, AuthError> {
+        let response = self
+            .client
+            .post(format!("{}/verify-token", self.auth_service_url))
+            .json(&serde_json::json!({ "token": token }))
+            .send()
+            .await
+            .map_err(|e| AuthError::Unavailable(e.to_string()))?;
+
+        match response.status() {
+            // the body has the claims, personal access tokens aren't JWTs
+            reqwest::StatusCode::OK => response
+                .json::<Claims>()
+                .await
+                .map_err(|e| AuthError::Unavailable(e.to_string())),
+            // 403 and 423 are for tokens of suspended accounts
+            reqwest::StatusCode::UNAUTHORIZED
+            | reqwest::StatusCode::FORBIDDEN
+            | reqwest::StatusCode::LOCKED
+            | reqwest::StatusCode::BAD_REQUEST
+            | reqwest::StatusCode::UNPROCESSABLE_ENTITY => Err(AuthError::InvalidToken),
+            status => Err(AuthError::Unavailable(format!(
+                "/verify-token returned {}",
+                status
+            ))),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use jsonwebtoken::{EncodingKey, Header, encode};
+
+use super::*;
+
+const SECRET: &str = "secret";
+
+fn token_expiring_in(seconds: i64, secret: &str) -> String {
+    let claims = Claims {
+        sub: "test@example.com".to_owned(),
+        exp: (chrono::Utc::now().timestamp() + seconds) as usize,
+        scope: None,
+        roles: vec!["admin".to_owned()],
+    };
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .unwrap()
+}
+
+#[test]
+fn local_validator_accepts_valid_token() {
+    let validator = LocalValidator::new(SECRET);
+    let claims = validator.validate(&token_expiring_in(600, SECRET)).unwrap();
+    assert_eq!(claims.sub, "test@example.com");
+}
+
+#[test]
+fn local_validator_rejects_invalid_tokens() {
+    let validator = LocalValidator::new(SECRET);
+    let test_cases = [
+        token_expiring_in(600, "other secret"),
+        token_expiring_in(-600, SECRET),
+        "invalid".to_owned(),
+    ];
+    for tc in test_cases {
+        assert_eq!(
+            validator.validate(&tc),
+            Err(AuthError::InvalidToken),
+            "failed for: {}",
+            tc
+        );
+    }
+}
+
+#[test]
+fn scopes_are_matched_whole() {
+    let claims = Claims {
+        sub: "test@example.com".to_owned(),
+        exp: 0,
+        scope: Some("repo:read repo:write".to_owned()),
+        roles: Vec::new(),
+    };
+    assert!(claims.has_scope("repo:write"));
+    assert!(!claims.has_scope("repo"));
+}
+
+#[test]
+fn cache_expires_verdicts() {
+    let now = Instant::now();
+    let mut cache = TokenCache::new(10);
+    let verdict = CachedVerdict {
+        claims: None,
+        expires_at: now + Duration::from_secs(1),
+    };
+    cache.insert("token".to_owned(), verdict, now);
+    assert_eq!(cache.get("token", now), Some(None));
+    assert_eq!(cache.get("token", now + Duration::from_secs(2)), None);
+    assert_eq!(cache.get("other", now), None);
+}
+
+#[test]
+fn cache_is_bounded() {
+    let now = Instant::now();
+    let mut cache = TokenCache::new(2);
+    for token in ["a", "b", "c"] {
+        let verdict = CachedVerdict {
+            claims: None,
+            expires_at: now + Duration::from_secs(60),
+        };
+        cache.insert(token.to_owned(), verdict, now);
+    }
+    assert!(cache.entries.len() <= 2);
+    assert_eq!(cache.get("c", now), Some(None));
+}
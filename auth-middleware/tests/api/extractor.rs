@@ -0,0 +1,63 @@
+use crate::helpers::{JWT_SECRET, TestApp, get_token, get_token_with_roles};
+use auth_middleware::{AuthGuard, TokenValidator};
+
+#[tokio::test]
+async fn should_return_401_if_token_missing() {
+    let app = TestApp::new(AuthGuard::new(TokenValidator::local(JWT_SECRET))).await;
+
+    let response = app.get_whoami(None, None).await;
+    assert_eq!(response.status(), 401);
+}
+
+#[tokio::test]
+async fn should_accept_bearer_token() {
+    let app = TestApp::new(AuthGuard::new(TokenValidator::local(JWT_SECRET))).await;
+    let token = get_token("valid@email.com");
+
+    let response = app.get_whoami(Some(&token), None).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().await.unwrap(), "valid@email.com");
+}
+
+#[tokio::test]
+async fn should_accept_configured_cookie() {
+    let guard = AuthGuard::new(TokenValidator::local(JWT_SECRET)).with_cookie_name("__Host-jwt");
+    let app = TestApp::new(guard).await;
+    let token = get_token("valid@email.com");
+
+    let response = app
+        .get_whoami(None, Some(&format!("__Host-jwt={}", token)))
+        .await;
+    assert_eq!(response.status(), 200);
+
+    let response = app.get_whoami(None, Some(&format!("jwt={}", token))).await;
+    assert_eq!(response.status(), 401);
+}
+
+#[tokio::test]
+async fn should_return_401_if_invalid_token() {
+    let app = TestApp::new(AuthGuard::new(TokenValidator::local("other secret"))).await;
+    let token = get_token("valid@email.com");
+
+    let response = app.get_whoami(Some(&token), None).await;
+    assert_eq!(response.status(), 401);
+    let response = app.get_whoami(None, Some("jwt=invalid")).await;
+    assert_eq!(response.status(), 401);
+}
+
+#[tokio::test]
+async fn should_require_role() {
+    let app = TestApp::new(AuthGuard::new(TokenValidator::local(JWT_SECRET))).await;
+
+    let token = get_token_with_roles("admin@email.com", &["support", "admin"]);
+    let response = app.get_admin_whoami(Some(&token)).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().await.unwrap(), "admin@email.com");
+
+    let token = get_token_with_roles("valid@email.com", &["support"]);
+    let response = app.get_admin_whoami(Some(&token)).await;
+    assert_eq!(response.status(), 403);
+
+    let response = app.get_admin_whoami(None).await;
+    assert_eq!(response.status(), 401);
+}
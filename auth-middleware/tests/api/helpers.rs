@@ -0,0 +1,216 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use auth_middleware::{
+    AuthGuard, AuthenticatedUser, Claims, RequireRole, TokenValidator, require_role,
+};
+use axum::{
+    Form, Json, Router,
+    extract::State,
+    http::{HeaderMap, StatusCode, header::AUTHORIZATION},
+    middleware,
+    response::IntoResponse,
+    routing::get,
+    routing::post,
+};
+use jsonwebtoken::{EncodingKey, Header, encode};
+use serde::Deserialize;
+
+pub const JWT_SECRET: &str = "secret";
+// base64("app_service:app-service-secret")
+const CLIENT_AUTHORIZATION: &str = "Basic YXBwX3NlcnZpY2U6YXBwLXNlcnZpY2Utc2VjcmV0";
+pub const CLIENT_CREDENTIALS: (&str, &str) = ("app_service", "app-service-secret");
+
+pub fn get_token(email: &str) -> String {
+    get_token_with_roles(email, &[])
+}
+
+pub fn get_token_with_roles(email: &str, roles: &[&str]) -> String {
+    let claims = Claims {
+        sub: email.to_owned(),
+        exp: (chrono::Utc::now().timestamp() + 600) as usize,
+        scope: None,
+        roles: roles.iter().map(|role| role.to_string()).collect(),
+    };
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(JWT_SECRET.as_bytes()),
+    )
+    .unwrap()
+}
+
+async fn serve(router: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+    #[allow(clippy::let_underscore_future)]
+    let _ = tokio::spawn(async move { axum::serve(listener, router).await });
+    address
+}
+
+#[derive(Deserialize)]
+struct VerifyTokenRequest {
+    token: String,
+}
+
+#[derive(Clone)]
+struct FakeAuthState {
+    valid_token: String,
+    claims: Claims,
+    status_for_valid: StatusCode,
+    calls: Arc<AtomicUsize>,
+}
+
+/// Stand-in for the auth service `/verify-token`, accepting only one token.
+pub struct FakeAuthService {
+    pub address: String,
+    calls: Arc<AtomicUsize>,
+}
+
+impl FakeAuthService {
+    pub async fn new(valid_token: &str) -> Self {
+        Self::with_status(valid_token, StatusCode::OK).await
+    }
+
+    // Answers `status` instead of 200 for the valid token, e.g. to fake an outage.
+    pub async fn with_status(valid_token: &str, status: StatusCode) -> Self {
+        let claims = TokenValidator::local(JWT_SECRET)
+            .validate(valid_token)
+            .await
+            .expect("The valid token is not a JWT of the tests");
+        Self::start(valid_token, claims, status).await
+    }
+
+    // Accepts a token that isn't a JWT, such as a personal access token.
+    pub async fn with_claims(valid_token: &str, claims: Claims) -> Self {
+        Self::start(valid_token, claims, StatusCode::OK).await
+    }
+
+    async fn start(valid_token: &str, claims: Claims, status: StatusCode) -> Self {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let state = FakeAuthState {
+            valid_token: valid_token.to_owned(),
+            claims,
+            status_for_valid: status,
+            calls: calls.clone(),
+        };
+        let router = Router::new()
+            .route("/verify-token", post(verify_token))
+            .route("/introspect", post(introspect))
+            .with_state(state);
+        let address = serve(router).await;
+        Self { address, calls }
+    }
+
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+async fn verify_token(
+    State(state): State<FakeAuthState>,
+    Json(request): Json<VerifyTokenRequest>,
+) -> impl IntoResponse {
+    state.calls.fetch_add(1, Ordering::SeqCst);
+    if request.token != state.valid_token {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    if state.status_for_valid != StatusCode::OK {
+        return state.status_for_valid.into_response();
+    }
+    Json(state.claims).into_response()
+}
+
+async fn introspect(
+    State(state): State<FakeAuthState>,
+    headers: HeaderMap,
+    Form(request): Form<VerifyTokenRequest>,
+) -> impl IntoResponse {
+    state.calls.fetch_add(1, Ordering::SeqCst);
+    if headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        != Some(CLIENT_AUTHORIZATION)
+    {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    if request.token != state.valid_token {
+        return Json(serde_json::json!({ "active": false })).into_response();
+    }
+    Json(serde_json::json!({
+        "active": true,
+        "sub": "introspected@email.com",
+        "exp": chrono::Utc::now().timestamp() + 600,
+        "roles": ["admin"],
+    }))
+    .into_response()
+}
+
+async fn whoami(user: AuthenticatedUser) -> String {
+    user.email().to_owned()
+}
+
+async fn scope(user: AuthenticatedUser) -> String {
+    user.claims.scope.unwrap_or_default()
+}
+
+/// A downstream service with protected routes, `/whoami` and `/scope`, and one
+/// only for admins, `/admin/whoami`.
+pub struct TestApp {
+    pub address: String,
+    pub http_client: reqwest::Client,
+}
+
+impl TestApp {
+    pub async fn new(guard: AuthGuard) -> Self {
+        let admin =
+            Router::new()
+                .route("/whoami", get(whoami))
+                .layer(middleware::from_fn_with_state(
+                    (guard.clone(), RequireRole("admin")),
+                    require_role,
+                ));
+        let router = Router::new()
+            .route("/whoami", get(whoami))
+            .route("/scope", get(scope))
+            .nest("/admin", admin)
+            .with_state(guard);
+        let address = serve(router).await;
+        Self {
+            address,
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn get_whoami(
+        &self,
+        bearer: Option<&str>,
+        cookie: Option<&str>,
+    ) -> reqwest::Response {
+        self.get("/whoami", bearer, cookie).await
+    }
+
+    pub async fn get_scope(&self, bearer: Option<&str>) -> reqwest::Response {
+        self.get("/scope", bearer, None).await
+    }
+
+    pub async fn get_admin_whoami(&self, bearer: Option<&str>) -> reqwest::Response {
+        self.get("/admin/whoami", bearer, None).await
+    }
+
+    async fn get(
+        &self,
+        path: &str,
+        bearer: Option<&str>,
+        cookie: Option<&str>,
+    ) -> reqwest::Response {
+        let mut request = self.http_client.get(format!("{}{}", self.address, path));
+        if let Some(token) = bearer {
+            request = request.bearer_auth(token);
+        }
+        if let Some(cookie) = cookie {
+            request = request.header("Cookie", cookie);
+        }
+        request.send().await.expect("Failed to execute request.")
+    }
+}
@@ -0,0 +1,3 @@
+mod extractor;
+mod helpers;
+mod remote;
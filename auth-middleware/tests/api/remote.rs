@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+use crate::helpers::{
+    CLIENT_CREDENTIALS, FakeAuthService, TestApp, get_token, get_token_with_roles,
+};
+use auth_middleware::{AuthGuard, Claims, TokenValidator, validator::RemoteValidator};
+use axum::http::StatusCode;
+
+#[tokio::test]
+async fn should_validate_through_auth_service() {
+    let token = get_token("valid@email.com");
+    let auth_service = FakeAuthService::new(&token).await;
+    let app = TestApp::new(AuthGuard::new(TokenValidator::remote(
+        &auth_service.address,
+    )))
+    .await;
+
+    let response = app.get_whoami(None, Some(&format!("jwt={}", token))).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().await.unwrap(), "valid@email.com");
+
+    let response = app
+        .get_whoami(Some(&get_token("other@email.com")), None)
+        .await;
+    assert_eq!(response.status(), 401);
+}
+
+#[tokio::test]
+async fn should_read_roles_of_tokens_verified_by_auth_service() {
+    let token = get_token_with_roles("admin@email.com", &["admin"]);
+    let auth_service = FakeAuthService::new(&token).await;
+    let app = TestApp::new(AuthGuard::new(TokenValidator::remote(
+        &auth_service.address,
+    )))
+    .await;
+
+    let response = app.get_admin_whoami(Some(&token)).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().await.unwrap(), "admin@email.com");
+}
+
+#[tokio::test]
+async fn should_accept_personal_access_tokens_with_their_scopes() {
+    // not a JWT, only the auth service knows what it stands for
+    let token = "pat_0123456789abcdef.secret";
+    let claims = Claims {
+        sub: "scripted@email.com".to_owned(),
+        exp: (chrono::Utc::now().timestamp() + 600) as usize,
+        scope: Some("repo:read repo:write".to_owned()),
+        roles: Vec::new(),
+    };
+    let auth_service = FakeAuthService::with_claims(token, claims).await;
+    let app = TestApp::new(AuthGuard::new(TokenValidator::remote(
+        &auth_service.address,
+    )))
+    .await;
+
+    let response = app.get_whoami(Some(token), None).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().await.unwrap(), "scripted@email.com");
+    let response = app.get_scope(Some(token)).await;
+    assert_eq!(response.text().await.unwrap(), "repo:read repo:write");
+    let response = app.get_admin_whoami(Some(token)).await;
+    assert_eq!(response.status(), 403);
+}
+
+#[tokio::test]
+async fn should_cache_verdicts() {
+    let token = get_token("valid@email.com");
+    let auth_service = FakeAuthService::new(&token).await;
+    let app = TestApp::new(AuthGuard::new(TokenValidator::remote(
+        &auth_service.address,
+    )))
+    .await;
+
+    for _ in 0..3 {
+        let response = app.get_whoami(Some(&token), None).await;
+        assert_eq!(response.status(), 200);
+        let response = app.get_whoami(Some("invalid"), None).await;
+        assert_eq!(response.status(), 401);
+    }
+    assert_eq!(auth_service.calls(), 2);
+}
+
+#[tokio::test]
+async fn should_not_cache_with_zero_ttl() {
+    let token = get_token("valid@email.com");
+    let auth_service = FakeAuthService::new(&token).await;
+    let validator = RemoteValidator::new(&auth_service.address).with_cache_ttl(Duration::ZERO);
+    let app = TestApp::new(AuthGuard::new(TokenValidator::Remote(validator))).await;
+
+    for _ in 0..3 {
+        let response = app.get_whoami(Some(&token), None).await;
+        assert_eq!(response.status(), 200);
+    }
+    assert_eq!(auth_service.calls(), 3);
+}
+
+#[tokio::test]
+async fn should_return_401_for_tokens_of_suspended_accounts() {
+    let token = get_token("valid@email.com");
+    for status in [StatusCode::FORBIDDEN, StatusCode::LOCKED] {
+        let auth_service = FakeAuthService::with_status(&token, status).await;
+        let app = TestApp::new(AuthGuard::new(TokenValidator::remote(
+            &auth_service.address,
+        )))
+        .await;
+
+        let response = app.get_whoami(Some(&token), None).await;
+        assert_eq!(response.status(), 401, "Failed for status: {}", status);
+    }
+}
+
+#[tokio::test]
+async fn should_return_503_if_auth_service_fails() {
+    let token = get_token("valid@email.com");
+    let auth_service =
+        FakeAuthService::with_status(&token, StatusCode::INTERNAL_SERVER_ERROR).await;
+    let app = TestApp::new(AuthGuard::new(TokenValidator::remote(
+        &auth_service.address,
+    )))
+    .await;
+
+    let response = app.get_whoami(Some(&token), None).await;
+    assert_eq!(response.status(), 503);
+
+    // failures are not cached
+    let _ = app.get_whoami(Some(&token), None).await;
+    assert_eq!(auth_service.calls(), 2);
+}
+
+#[tokio::test]
+async fn should_use_introspection_with_client_credentials() {
+    let token = get_token("valid@email.com");
+    let auth_service = FakeAuthService::new(&token).await;
+    let (client_id, client_secret) = CLIENT_CREDENTIALS;
+    let validator = RemoteValidator::new(&auth_service.address)
+        .with_client_credentials(client_id, client_secret);
+    let app = TestApp::new(AuthGuard::new(TokenValidator::Remote(validator))).await;
+
+    // the fake introspection answers with its own subject
+    let response = app.get_whoami(Some(&token), None).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().await.unwrap(), "introspected@email.com");
+    let response = app.get_admin_whoami(Some(&token)).await;
+    assert_eq!(response.status(), 200);
+
+    let response = app.get_whoami(Some("invalid"), None).await;
+    assert_eq!(response.status(), 401);
+}
+
+#[tokio::test]
+async fn should_return_503_if_client_credentials_rejected() {
+    let token = get_token("valid@email.com");
+    let auth_service = FakeAuthService::new(&token).await;
+    let validator =
+        RemoteValidator::new(&auth_service.address).with_client_credentials("app_service", "wrong");
+    let app = TestApp::new(AuthGuard::new(TokenValidator::Remote(validator))).await;
+
+    let response = app.get_whoami(Some(&token), None).await;
+    assert_eq!(response.status(), 503);
+}
@@ -32,6 +32,23 @@ signupLoginLink.addEventListener("click", (e) => {
 
 // -----------------------------------------------------
 
+// Apps using the service as their identity provider send users here with a
+// return_to link back to /authorize. Only same-origin links are followed.
+function returnAfterLogin() {
+    const returnTo = new URLSearchParams(window.location.search).get("return_to");
+    if (!returnTo) {
+        return false;
+    }
+    const url = new URL(returnTo, window.location.href);
+    if (url.origin !== window.location.origin) {
+        return false;
+    }
+    window.location.assign(url.href);
+    return true;
+}
+
+// -----------------------------------------------------
+
 const loginForm = document.getElementById("login-form");
 const loginButton = document.getElementById("login-form-submit");
 const loginErrAlter = document.getElementById("login-err-alert");
@@ -41,22 +58,27 @@ loginButton.addEventListener("click", (e) => {
 
     const email = loginForm.email.value;
     const password = loginForm.password.value;
+    // accounts in an organization are logged into with its id
+    const organizationId = loginForm.organization_id.value || null;
 
     fetch('/auth/login', {
         method: 'POST',
         headers: {
             'Content-Type': 'application/json',
+            'X-CSRF-Token': getCsrfToken(),
         },
-        body: JSON.stringify({ email, password }),
+        body: JSON.stringify({ email, password, organizationId }),
     }).then(response => {
         if (response.status === 206) {
             TwoFAForm.email.value = email;
+            TwoFAForm.organization_id.value = organizationId || "";
             response.json().then(data => {
                 TwoFAForm.login_attempt_id.value = data.loginAttemptId;
             });
 
             loginForm.email.value = "";
             loginForm.password.value = "";
+            loginForm.organization_id.value = "";
 
             loginSection.style.display = "none";
             twoFASection.style.display = "block";
@@ -65,7 +87,11 @@ loginButton.addEventListener("click", (e) => {
         } else if (response.status === 200) {
             loginForm.email.value = "";
             loginForm.password.value = "";
+            loginForm.organization_id.value = "";
             loginErrAlter.style.display = "none";
+            if (returnAfterLogin()) {
+                return;
+            }
             alert("You have successfully logged in.");
         } else {
             response.json().then(data => {
@@ -81,6 +107,33 @@ loginButton.addEventListener("click", (e) => {
     });
 });
 
+const magicLink = document.getElementById("magic-link");
+
+magicLink.addEventListener("click", (e) => {
+    e.preventDefault();
+
+    const email = loginForm.email.value;
+    const organizationId = loginForm.organization_id.value || null;
+
+    fetch('/auth/login/magic-link', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ email, organizationId }),
+    }).then(response => {
+        response.json().then(data => {
+            if (response.status === 202) {
+                loginErrAlter.style.display = "none";
+                alert(data.message);
+            } else if (data.error) {
+                loginErrAlter.innerHTML = `<span><strong>Error: </strong>${data.error}</span>`;
+                loginErrAlter.style.display = "block";
+            }
+        });
+    });
+});
+
 const signupForm = document.getElementById("signup-form");
 const signupButton = document.getElementById("signup-form-submit");
 const signupErrAlter = document.getElementById("signup-err-alert");
@@ -96,6 +149,7 @@ signupButton.addEventListener("click", (e) => {
         method: 'POST',
         headers: {
             'Content-Type': 'application/json',
+            'X-CSRF-Token': getCsrfToken(),
         },
         body: JSON.stringify({ email, password, requires2FA }),
     }).then(response => {
@@ -131,20 +185,26 @@ TwoFAButton.addEventListener("click", (e) => {
 
     const email = TwoFAForm.email.value;
     const loginAttemptId = TwoFAForm.login_attempt_id.value;
+    const organizationId = TwoFAForm.organization_id.value || null;
     const TwoFACode = TwoFAForm.email_code.value;
 
     fetch('/auth/verify-2fa', {
         method: 'POST',
         headers: {
             'Content-Type': 'application/json',
+            'X-CSRF-Token': getCsrfToken(),
         },
-        body: JSON.stringify({ email, loginAttemptId, "2FACode": TwoFACode }),
+        body: JSON.stringify({ email, loginAttemptId, organizationId, "2FACode": TwoFACode }),
     }).then(response => {
         if (response.ok) {
             TwoFAForm.email.value = "";
             TwoFAForm.email_code.value = "";
             TwoFAForm.login_attempt_id.value = "";
+            TwoFAForm.organization_id.value = "";
             TwoFAErrAlter.style.display = "none";
+            if (returnAfterLogin()) {
+                return;
+            }
             alert("You have successfully logged in.");
             loginSection.style.display = "block";
             twoFASection.style.display = "none";
@@ -161,4 +221,68 @@ TwoFAButton.addEventListener("click", (e) => {
             });
         }
     });
-});
\ No newline at end of file
+});
+
+// Social logins of 2FA users come back with the login attempt in the
+// fragment, only the code is left to enter.
+const loginAttempt = new URLSearchParams(window.location.hash.slice(1));
+if (loginAttempt.has("login_attempt_id")) {
+    TwoFAForm.email.value = loginAttempt.get("email");
+    TwoFAForm.login_attempt_id.value = loginAttempt.get("login_attempt_id");
+    TwoFAForm.organization_id.value = loginAttempt.get("organization_id") || "";
+    history.replaceState(null, "", window.location.pathname + window.location.search);
+
+    loginSection.style.display = "none";
+    twoFASection.style.display = "block";
+    signupSection.style.display = "none";
+}
+
+// -----------------------------------------------------
+
+// Reset links carry their token in the fragment, it never reaches the server
+// until the new password is sent with it.
+const passwordResetSection = document.getElementById("password-reset-section");
+const passwordResetForm = document.getElementById("password-reset-form");
+const passwordResetButton = document.getElementById("password-reset-form-submit");
+const passwordResetErrAlert = document.getElementById("password-reset-err-alert");
+
+const passwordReset = new URLSearchParams(window.location.hash.slice(1));
+if (passwordReset.has("password_reset")) {
+    passwordResetForm.token.value = passwordReset.get("password_reset");
+    history.replaceState(null, "", window.location.pathname + window.location.search);
+
+    loginSection.style.display = "none";
+    twoFASection.style.display = "none";
+    signupSection.style.display = "none";
+    passwordResetSection.style.display = "block";
+}
+
+passwordResetButton.addEventListener("click", (e) => {
+    e.preventDefault();
+
+    const token = passwordResetForm.token.value;
+    const password = passwordResetForm.password.value;
+
+    fetch('/auth/password-reset', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ token, password }),
+    }).then(response => {
+        if (response.ok) {
+            passwordResetForm.reset();
+            passwordResetErrAlert.style.display = "none";
+            alert("Your password was changed, you can log in with it.");
+            passwordResetSection.style.display = "none";
+            loginSection.style.display = "block";
+        } else {
+            response.json().then(data => {
+                const fieldError = (data.errors || [])[0];
+                const error_msg = fieldError ? fieldError.message : data.error;
+                passwordResetErrAlert.innerHTML = `<span><strong>Error: </strong>${error_msg}</span>`;
+                passwordResetErrAlert.style.display = "block";
+            });
+        }
+    });
+});
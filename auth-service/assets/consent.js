@@ -0,0 +1,52 @@
+// The authorization request, as /authorize passed it on to this page.
+const request = Object.fromEntries(new URLSearchParams(window.location.search));
+
+const clientName = document.getElementById("client-name");
+const scopeList = document.getElementById("scope-list");
+const consentErrAlter = document.getElementById("consent-err-alert");
+
+const SCOPE_DESCRIPTIONS = {
+    openid: "Who you are",
+    email: "Your email address",
+    profile: "Your name and language",
+};
+
+for (const scope of (request.scope || "").split(" ")) {
+    if (SCOPE_DESCRIPTIONS[scope]) {
+        const item = document.createElement("li");
+        item.textContent = SCOPE_DESCRIPTIONS[scope];
+        scopeList.appendChild(item);
+    }
+}
+
+fetch(`/auth/oauth/clients/${encodeURIComponent(request.client_id || "")}`)
+    .then(response => response.ok ? response.json() : null)
+    .then(client => {
+        if (client) {
+            clientName.textContent = client.name;
+        }
+    });
+
+function decide(approve) {
+    fetch('/auth/authorize', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+            'X-CSRF-Token': getCsrfToken(),
+        },
+        body: JSON.stringify({ ...request, approve }),
+    }).then(response => {
+        response.json().then(data => {
+            if (response.ok) {
+                window.location.assign(data.redirectTo);
+                return;
+            }
+            // the description may echo the request, so it isn't parsed as HTML
+            consentErrAlter.textContent = `Error: ${data.error_description || data.error}`;
+            consentErrAlter.style.display = "block";
+        });
+    });
+}
+
+document.getElementById("consent-approve").addEventListener("click", () => decide(true));
+document.getElementById("consent-deny").addEventListener("click", () => decide(false));
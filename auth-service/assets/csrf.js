@@ -0,0 +1,9 @@
+// The auth service sets a csrf_token cookie which has to be echoed back in the
+// X-CSRF-Token header of cookie-authenticated POSTs such as logout. Shared by
+// the pages of the auth service and of the apps behind the same domain.
+function getCsrfToken() {
+    const cookie = document.cookie
+        .split("; ")
+        .find((row) => row.startsWith("csrf_token="));
+    return cookie ? cookie.split("=")[1] : "";
+}
@@ -0,0 +1,28 @@
+// The token of the link in the new sign-in alert.
+const token = new URLSearchParams(window.location.search).get("token") || "";
+
+const revokeButton = document.getElementById("revoke-confirm");
+const revokeErrAlert = document.getElementById("revoke-err-alert");
+const revokeDoneAlert = document.getElementById("revoke-done-alert");
+
+revokeButton.addEventListener("click", () => {
+    fetch('/auth/revoke-sessions', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ token }),
+    }).then(response => {
+        response.json().then(data => {
+            if (response.ok) {
+                revokeErrAlert.style.display = "none";
+                revokeDoneAlert.textContent = data.message;
+                revokeDoneAlert.style.display = "block";
+                revokeButton.disabled = true;
+                return;
+            }
+            revokeErrAlert.textContent = `Error: ${data.error}`;
+            revokeErrAlert.style.display = "block";
+        });
+    });
+});
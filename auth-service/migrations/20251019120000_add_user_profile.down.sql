@@ -0,0 +1,5 @@
+ALTER TABLE users
+   DROP COLUMN IF EXISTS email_verified,
+   DROP COLUMN IF EXISTS created_at,
+   DROP COLUMN IF EXISTS display_name,
+   DROP COLUMN IF EXISTS locale;
@@ -0,0 +1,5 @@
+ALTER TABLE users
+   ADD COLUMN IF NOT EXISTS email_verified BOOLEAN NOT NULL DEFAULT FALSE,
+   ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+   ADD COLUMN IF NOT EXISTS display_name TEXT,
+   ADD COLUMN IF NOT EXISTS locale TEXT;
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS roles(
+   name TEXT NOT NULL PRIMARY KEY,
+   description TEXT NOT NULL DEFAULT ''
+);
+
+CREATE TABLE IF NOT EXISTS user_roles(
+   user_email TEXT NOT NULL REFERENCES users(email) ON DELETE CASCADE,
+   role TEXT NOT NULL REFERENCES roles(name) ON DELETE CASCADE,
+   PRIMARY KEY (user_email, role)
+);
+
+INSERT INTO roles (name, description)
+VALUES ('admin', 'Manages users')
+ON CONFLICT (name) DO NOTHING;
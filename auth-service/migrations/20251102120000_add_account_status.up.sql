@@ -0,0 +1,3 @@
+ALTER TABLE users
+   ADD COLUMN IF NOT EXISTS status TEXT NOT NULL DEFAULT 'active'
+   CONSTRAINT users_status_check CHECK (status IN ('active', 'disabled'));
@@ -0,0 +1,5 @@
+UPDATE users SET status = 'disabled' WHERE status IN ('locked', 'pending_verification');
+
+ALTER TABLE users
+   DROP CONSTRAINT IF EXISTS users_status_check,
+   ADD CONSTRAINT users_status_check CHECK (status IN ('active', 'disabled'));
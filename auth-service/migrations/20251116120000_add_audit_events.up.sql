@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS audit_events (
+   id BIGSERIAL PRIMARY KEY,
+   event_type TEXT NOT NULL,
+   actor TEXT,
+   target TEXT,
+   ip TEXT,
+   user_agent TEXT,
+   outcome TEXT NOT NULL CONSTRAINT audit_events_outcome_check CHECK (outcome IN ('success', 'failure')),
+   occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- no foreign keys, events outlive their users and failed logins name unknown emails
+CREATE INDEX IF NOT EXISTS audit_events_actor_idx ON audit_events (actor, occurred_at DESC);
+CREATE INDEX IF NOT EXISTS audit_events_target_idx ON audit_events (target, occurred_at DESC);
+
+CREATE OR REPLACE FUNCTION audit_events_append_only() RETURNS trigger AS $$
+BEGIN
+   RAISE EXCEPTION 'audit_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_events_append_only
+   BEFORE UPDATE OR DELETE ON audit_events
+   FOR EACH ROW EXECUTE FUNCTION audit_events_append_only();
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS known_devices(
+   user_email TEXT NOT NULL REFERENCES users(email) ON DELETE CASCADE,
+   device_id TEXT NOT NULL,
+   user_agent TEXT,
+   ip TEXT,
+   last_seen TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+   PRIMARY KEY (user_email, device_id)
+);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS trusted_devices(
+   id TEXT PRIMARY KEY,
+   user_email TEXT NOT NULL REFERENCES users(email) ON DELETE CASCADE,
+   user_agent TEXT,
+   ip TEXT,
+   created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+   expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS trusted_devices_user_email_idx ON trusted_devices (user_email);
@@ -0,0 +1,27 @@
+CREATE TABLE IF NOT EXISTS oauth_clients(
+   client_id TEXT PRIMARY KEY,
+   name TEXT NOT NULL,
+   redirect_uris TEXT[] NOT NULL,
+   secret_hash TEXT,
+   created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS oauth_authorization_codes(
+   code TEXT PRIMARY KEY,
+   client_id TEXT NOT NULL REFERENCES oauth_clients(client_id) ON DELETE CASCADE,
+   user_email TEXT NOT NULL REFERENCES users(email) ON DELETE CASCADE,
+   redirect_uri TEXT NOT NULL,
+   scopes TEXT[] NOT NULL,
+   nonce TEXT,
+   code_challenge TEXT NOT NULL,
+   auth_time TIMESTAMPTZ NOT NULL,
+   expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS oauth_consents(
+   user_email TEXT NOT NULL REFERENCES users(email) ON DELETE CASCADE,
+   client_id TEXT NOT NULL REFERENCES oauth_clients(client_id) ON DELETE CASCADE,
+   scopes TEXT[] NOT NULL,
+   granted_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+   PRIMARY KEY (user_email, client_id)
+);
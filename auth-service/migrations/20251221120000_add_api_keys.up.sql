@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS api_keys(
+   id TEXT PRIMARY KEY,
+   name TEXT NOT NULL,
+   secret_hash TEXT NOT NULL,
+   scopes TEXT[] NOT NULL,
+   created_by TEXT,
+   created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+   expires_at TIMESTAMPTZ,
+   last_used_at TIMESTAMPTZ
+);
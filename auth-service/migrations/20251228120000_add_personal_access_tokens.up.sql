@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS personal_access_tokens(
+   id TEXT PRIMARY KEY,
+   user_email TEXT NOT NULL REFERENCES users(email) ON DELETE CASCADE,
+   name TEXT NOT NULL,
+   secret_hash TEXT NOT NULL,
+   scopes TEXT[] NOT NULL,
+   created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+   expires_at TIMESTAMPTZ NOT NULL,
+   last_used_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS personal_access_tokens_user_email_idx ON personal_access_tokens (user_email);
@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS organizations(
+   id TEXT PRIMARY KEY,
+   name TEXT NOT NULL,
+   owner_email TEXT NOT NULL,
+   require_2fa BOOLEAN NOT NULL DEFAULT FALSE,
+   min_password_length INTEGER NOT NULL DEFAULT 8,
+   created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS tenant_id TEXT REFERENCES organizations(id) ON DELETE SET NULL;
+CREATE INDEX IF NOT EXISTS users_tenant_id_idx ON users (tenant_id);
+
+CREATE TABLE IF NOT EXISTS invitations(
+   token_hash TEXT PRIMARY KEY,
+   email TEXT NOT NULL,
+   organization_id TEXT NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+   invited_by TEXT NOT NULL,
+   expires_at TIMESTAMPTZ NOT NULL
+);
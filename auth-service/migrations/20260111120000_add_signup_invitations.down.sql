@@ -0,0 +1,4 @@
+DELETE FROM invitations WHERE organization_id IS NULL;
+ALTER TABLE invitations DROP COLUMN IF EXISTS role;
+ALTER TABLE invitations ALTER COLUMN invited_by SET NOT NULL;
+ALTER TABLE invitations ALTER COLUMN organization_id SET NOT NULL;
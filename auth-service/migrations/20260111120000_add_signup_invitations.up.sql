@@ -0,0 +1,5 @@
+-- invitations also let people sign up when open registration is off
+ALTER TABLE invitations ALTER COLUMN organization_id DROP NOT NULL;
+ALTER TABLE invitations ADD COLUMN IF NOT EXISTS role TEXT;
+-- admins using the static API key have no email
+ALTER TABLE invitations ALTER COLUMN invited_by DROP NOT NULL;
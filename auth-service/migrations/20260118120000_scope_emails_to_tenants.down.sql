@@ -0,0 +1,60 @@
+-- emails are unique again, so only the account outside any organization is kept
+DELETE FROM users u
+WHERE u.tenant_id IS NOT NULL
+   AND EXISTS (SELECT 1 FROM users other WHERE other.email = u.email AND other.tenant_key < u.tenant_key);
+
+ALTER TABLE personal_access_tokens DROP CONSTRAINT IF EXISTS personal_access_tokens_user_fkey;
+DROP INDEX IF EXISTS personal_access_tokens_user_idx;
+ALTER TABLE personal_access_tokens DROP COLUMN IF EXISTS user_tenant;
+CREATE INDEX IF NOT EXISTS personal_access_tokens_user_email_idx ON personal_access_tokens (user_email);
+
+ALTER TABLE oauth_consents DROP CONSTRAINT IF EXISTS oauth_consents_user_fkey;
+ALTER TABLE oauth_consents DROP CONSTRAINT IF EXISTS oauth_consents_pkey;
+ALTER TABLE oauth_consents DROP COLUMN IF EXISTS user_tenant;
+ALTER TABLE oauth_consents ADD PRIMARY KEY (user_email, client_id);
+
+ALTER TABLE oauth_authorization_codes DROP CONSTRAINT IF EXISTS oauth_authorization_codes_user_fkey;
+ALTER TABLE oauth_authorization_codes DROP COLUMN IF EXISTS user_tenant;
+
+ALTER TABLE trusted_devices DROP CONSTRAINT IF EXISTS trusted_devices_user_fkey;
+DROP INDEX IF EXISTS trusted_devices_user_idx;
+ALTER TABLE trusted_devices DROP COLUMN IF EXISTS user_tenant;
+CREATE INDEX IF NOT EXISTS trusted_devices_user_email_idx ON trusted_devices (user_email);
+
+ALTER TABLE known_devices DROP CONSTRAINT IF EXISTS known_devices_user_fkey;
+ALTER TABLE known_devices DROP CONSTRAINT IF EXISTS known_devices_pkey;
+ALTER TABLE known_devices DROP COLUMN IF EXISTS user_tenant;
+ALTER TABLE known_devices ADD PRIMARY KEY (user_email, device_id);
+
+ALTER TABLE linked_identities DROP CONSTRAINT IF EXISTS linked_identities_user_fkey;
+DROP INDEX IF EXISTS linked_identities_user_idx;
+ALTER TABLE linked_identities DROP COLUMN IF EXISTS user_tenant;
+CREATE INDEX IF NOT EXISTS linked_identities_user_email_idx ON linked_identities (user_email);
+
+ALTER TABLE user_roles DROP CONSTRAINT IF EXISTS user_roles_user_fkey;
+ALTER TABLE user_roles DROP CONSTRAINT IF EXISTS user_roles_pkey;
+ALTER TABLE user_roles DROP COLUMN IF EXISTS user_tenant;
+ALTER TABLE user_roles ADD PRIMARY KEY (user_email, role);
+
+ALTER TABLE users
+   DROP CONSTRAINT IF EXISTS users_tenant_id_fkey,
+   ADD CONSTRAINT users_tenant_id_fkey
+      FOREIGN KEY (tenant_id) REFERENCES organizations(id) ON DELETE SET NULL;
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_pkey;
+ALTER TABLE users DROP COLUMN IF EXISTS tenant_key;
+ALTER TABLE users ADD PRIMARY KEY (email);
+
+ALTER TABLE user_roles ADD CONSTRAINT user_roles_user_email_fkey
+   FOREIGN KEY (user_email) REFERENCES users(email) ON DELETE CASCADE;
+ALTER TABLE linked_identities ADD CONSTRAINT linked_identities_user_email_fkey
+   FOREIGN KEY (user_email) REFERENCES users(email) ON DELETE CASCADE;
+ALTER TABLE known_devices ADD CONSTRAINT known_devices_user_email_fkey
+   FOREIGN KEY (user_email) REFERENCES users(email) ON DELETE CASCADE;
+ALTER TABLE trusted_devices ADD CONSTRAINT trusted_devices_user_email_fkey
+   FOREIGN KEY (user_email) REFERENCES users(email) ON DELETE CASCADE;
+ALTER TABLE oauth_authorization_codes ADD CONSTRAINT oauth_authorization_codes_user_email_fkey
+   FOREIGN KEY (user_email) REFERENCES users(email) ON DELETE CASCADE;
+ALTER TABLE oauth_consents ADD CONSTRAINT oauth_consents_user_email_fkey
+   FOREIGN KEY (user_email) REFERENCES users(email) ON DELETE CASCADE;
+ALTER TABLE personal_access_tokens ADD CONSTRAINT personal_access_tokens_user_email_fkey
+   FOREIGN KEY (user_email) REFERENCES users(email) ON DELETE CASCADE;
@@ -0,0 +1,69 @@
+-- Emails are unique within a tenant. Users outside any organization share the
+-- '' tenant, as a key can't be NULL.
+ALTER TABLE users
+   ADD COLUMN IF NOT EXISTS tenant_key TEXT GENERATED ALWAYS AS (COALESCE(tenant_id, '')) STORED;
+
+ALTER TABLE user_roles DROP CONSTRAINT IF EXISTS user_roles_user_email_fkey;
+ALTER TABLE linked_identities DROP CONSTRAINT IF EXISTS linked_identities_user_email_fkey;
+ALTER TABLE known_devices DROP CONSTRAINT IF EXISTS known_devices_user_email_fkey;
+ALTER TABLE trusted_devices DROP CONSTRAINT IF EXISTS trusted_devices_user_email_fkey;
+ALTER TABLE oauth_authorization_codes DROP CONSTRAINT IF EXISTS oauth_authorization_codes_user_email_fkey;
+ALTER TABLE oauth_consents DROP CONSTRAINT IF EXISTS oauth_consents_user_email_fkey;
+ALTER TABLE personal_access_tokens DROP CONSTRAINT IF EXISTS personal_access_tokens_user_email_fkey;
+
+ALTER TABLE users
+   DROP CONSTRAINT IF EXISTS users_pkey,
+   ADD PRIMARY KEY (tenant_key, email);
+-- members leave with their organization, they have no account elsewhere
+ALTER TABLE users
+   DROP CONSTRAINT IF EXISTS users_tenant_id_fkey,
+   ADD CONSTRAINT users_tenant_id_fkey
+      FOREIGN KEY (tenant_id) REFERENCES organizations(id) ON DELETE CASCADE;
+
+ALTER TABLE user_roles
+   ADD COLUMN IF NOT EXISTS user_tenant TEXT NOT NULL DEFAULT '',
+   DROP CONSTRAINT IF EXISTS user_roles_pkey,
+   ADD PRIMARY KEY (user_tenant, user_email, role),
+   ADD CONSTRAINT user_roles_user_fkey FOREIGN KEY (user_tenant, user_email)
+      REFERENCES users(tenant_key, email) ON DELETE CASCADE;
+
+ALTER TABLE linked_identities
+   ADD COLUMN IF NOT EXISTS user_tenant TEXT NOT NULL DEFAULT '',
+   ADD CONSTRAINT linked_identities_user_fkey FOREIGN KEY (user_tenant, user_email)
+      REFERENCES users(tenant_key, email) ON DELETE CASCADE;
+DROP INDEX IF EXISTS linked_identities_user_email_idx;
+CREATE INDEX IF NOT EXISTS linked_identities_user_idx ON linked_identities (user_tenant, user_email);
+
+ALTER TABLE known_devices
+   ADD COLUMN IF NOT EXISTS user_tenant TEXT NOT NULL DEFAULT '',
+   DROP CONSTRAINT IF EXISTS known_devices_pkey,
+   ADD PRIMARY KEY (user_tenant, user_email, device_id),
+   ADD CONSTRAINT known_devices_user_fkey FOREIGN KEY (user_tenant, user_email)
+      REFERENCES users(tenant_key, email) ON DELETE CASCADE;
+
+ALTER TABLE trusted_devices
+   ADD COLUMN IF NOT EXISTS user_tenant TEXT NOT NULL DEFAULT '',
+   ADD CONSTRAINT trusted_devices_user_fkey FOREIGN KEY (user_tenant, user_email)
+      REFERENCES users(tenant_key, email) ON DELETE CASCADE;
+DROP INDEX IF EXISTS trusted_devices_user_email_idx;
+CREATE INDEX IF NOT EXISTS trusted_devices_user_idx ON trusted_devices (user_tenant, user_email);
+
+ALTER TABLE oauth_authorization_codes
+   ADD COLUMN IF NOT EXISTS user_tenant TEXT NOT NULL DEFAULT '',
+   ADD CONSTRAINT oauth_authorization_codes_user_fkey FOREIGN KEY (user_tenant, user_email)
+      REFERENCES users(tenant_key, email) ON DELETE CASCADE;
+
+ALTER TABLE oauth_consents
+   ADD COLUMN IF NOT EXISTS user_tenant TEXT NOT NULL DEFAULT '',
+   DROP CONSTRAINT IF EXISTS oauth_consents_pkey,
+   ADD PRIMARY KEY (user_tenant, user_email, client_id),
+   ADD CONSTRAINT oauth_consents_user_fkey FOREIGN KEY (user_tenant, user_email)
+      REFERENCES users(tenant_key, email) ON DELETE CASCADE;
+
+ALTER TABLE personal_access_tokens
+   ADD COLUMN IF NOT EXISTS user_tenant TEXT NOT NULL DEFAULT '',
+   ADD CONSTRAINT personal_access_tokens_user_fkey FOREIGN KEY (user_tenant, user_email)
+      REFERENCES users(tenant_key, email) ON DELETE CASCADE;
+DROP INDEX IF EXISTS personal_access_tokens_user_email_idx;
+CREATE INDEX IF NOT EXISTS personal_access_tokens_user_idx
+   ON personal_access_tokens (user_tenant, user_email);
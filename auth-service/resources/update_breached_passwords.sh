@@ -0,0 +1,32 @@
+#!/bin/bash
+
+# The committed breached_passwords.txt is a hashed common-password denylist:
+# the uppercase SHA-1 of every line of common_passwords.txt, without counts.
+# This script replaces it with the candidates the Have I Been Pwned range API
+# reports, each with its breach count, for deployments that want the denylist
+# narrowed to known breaches. Only the first 5 characters of each SHA-1 leave
+# the machine (k-anonymity).
+set -euo pipefail
+
+cd "$(dirname "$0")"
+
+declare -A ranges
+
+while IFS= read -r password; do
+  # Skip blank lines
+  if [[ -z "$password" ]]; then
+    continue
+  fi
+  hash=$(printf '%s' "$password" | sha1sum | cut -d ' ' -f1 | tr 'a-f' 'A-F')
+  prefix=${hash:0:5}
+  if [[ -z "${ranges[$prefix]+set}" ]]; then
+    ranges[$prefix]=$(curl -fsS "https://api.pwnedpasswords.com/range/$prefix" | tr -d '\r')
+  fi
+  count=$(grep "^${hash:5}:" <<<"${ranges[$prefix]}" | cut -d ':' -f2 || true)
+  if [[ -n "$count" ]]; then
+    echo "$hash:$count"
+  fi
+done < common_passwords.txt | sort -u > breached_passwords.txt.new
+
+mv breached_passwords.txt.new breached_passwords.txt
+echo "$(wc -l < breached_passwords.txt) breached passwords"
@@ -1,28 +1,243 @@
+use color_eyre::eyre::{Context, Result, eyre};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-use crate::domain::{BannedTokenStore, EmailClient, TwoFACodeStore, UserStore};
+use crate::domain::{
+    AccessTokenStore, AuditEvent, AuditLog, BannedTokenStore, EmailClient, MagicLinkStore,
+    OAuthStore, OrganizationStore, PasswordPolicy, TrustedDeviceStore, TwoFACodeStore, UserStore,
+};
+use crate::services::data_stores::hashmap_access_token_store::HashMapAccessTokenStore;
+use crate::services::data_stores::hashmap_magic_link_store::HashMapMagicLinkStore;
+use crate::services::data_stores::hashmap_oauth_store::HashMapOAuthStore;
+use crate::services::data_stores::hashmap_organization_store::HashMapOrganizationStore;
+use crate::services::data_stores::hashmap_trusted_device_store::HashMapTrustedDeviceStore;
+use crate::services::data_stores::hashmap_two_fa_code_store::HashMapTwoFACodeStore;
+use crate::services::data_stores::hashmap_user_store::HashmapUserStore;
+use crate::services::data_stores::hashset_banned_token_store::HashSetBannedTokenStore;
+use crate::services::data_stores::postgres_access_token_store::PostgresAccessTokenStore;
+use crate::services::data_stores::postgres_audit_log::PostgresAuditLog;
+use crate::services::data_stores::postgres_oauth_store::PostgresOAuthStore;
+use crate::services::data_stores::postgres_organization_store::PostgresOrganizationStore;
+use crate::services::data_stores::postgres_trusted_device_store::PostgresTrustedDeviceStore;
+use crate::services::data_stores::postgres_user_store::PostgresUserStore;
+use crate::services::data_stores::redis_banned_token_store::RedisBannedTokenStore;
+use crate::services::data_stores::redis_magic_link_store::RedisMagicLinkStore;
+use crate::services::data_stores::redis_two_fa_code_store::RedisTwoFACodeStore;
+use crate::services::data_stores::store_backends::{
+    AnyAccessTokenStore, AnyAuditLog, AnyBannedTokenStore, AnyMagicLinkStore, AnyOAuthStore,
+    AnyOrganizationStore, AnyTrustedDeviceStore, AnyTwoFACodeStore, AnyUserStore,
+    TokenStoreBackend, UserStoreBackend,
+};
+use crate::services::data_stores::vec_audit_log::VecAuditLog;
+use crate::services::mock_mail_client::MockEmailClient;
+use crate::settings::Settings;
+use crate::utils::oidc::SigningKey;
+use crate::{get_postgres_pool, get_redis_client};
 
 #[derive(Clone)]
-pub struct AppState<T: UserStore, U: BannedTokenStore, V: TwoFACodeStore, W: EmailClient> {
+pub struct AppState<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+> {
     pub user_store: Arc<RwLock<T>>,
     pub banned_token_store: Arc<RwLock<U>>,
     pub two_fa_code_store: Arc<RwLock<V>>,
     pub email_client: Arc<W>,
+    pub audit_log: Arc<RwLock<X>>,
+    pub trusted_device_store: Arc<RwLock<Y>>,
+    pub oauth_store: Arc<RwLock<Z>>,
+    pub magic_link_store: Arc<RwLock<M>>,
+    pub access_token_store: Arc<RwLock<P>>,
+    pub organization_store: Arc<RwLock<O>>,
+    pub signing_key: Arc<SigningKey>,
+    pub password_policy: Arc<PasswordPolicy>,
+    pub settings: Arc<Settings>,
 }
 
-impl<T: UserStore, U: BannedTokenStore, V: TwoFACodeStore, W: EmailClient> AppState<T, U, V, W> {
+impl<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+> AppState<T, U, V, W, X, Y, Z, M, P, O>
+{
+    // one store per generic, like the struct itself
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         user_store: Arc<RwLock<T>>,
         banned_token_store: Arc<RwLock<U>>,
         two_fa_code_store: Arc<RwLock<V>>,
         email_client: Arc<W>,
+        audit_log: Arc<RwLock<X>>,
+        trusted_device_store: Arc<RwLock<Y>>,
+        oauth_store: Arc<RwLock<Z>>,
+        magic_link_store: Arc<RwLock<M>>,
+        access_token_store: Arc<RwLock<P>>,
+        organization_store: Arc<RwLock<O>>,
+        signing_key: Arc<SigningKey>,
+        password_policy: Arc<PasswordPolicy>,
+        settings: Arc<Settings>,
     ) -> Self {
         Self {
             user_store,
             banned_token_store,
             two_fa_code_store,
             email_client,
+            audit_log,
+            trusted_device_store,
+            oauth_store,
+            magic_link_store,
+            access_token_store,
+            organization_store,
+            signing_key,
+            password_policy,
+            settings,
         }
     }
+
+    /// Failing to record an event is logged but doesn't fail the request.
+    pub async fn record_audit_event(&self, event: AuditEvent) {
+        if let Err(e) = self.audit_log.write().await.record(event).await {
+            tracing::error!(error = ?e, "Failed to record audit event");
+        }
+    }
+}
+
+/// `AppState` whose stores are picked at runtime by [`AppStateBuilder`].
+pub type ConfiguredAppState = AppState<
+    AnyUserStore,
+    AnyBannedTokenStore,
+    AnyTwoFACodeStore,
+    MockEmailClient,
+    AnyAuditLog,
+    AnyTrustedDeviceStore,
+    AnyOAuthStore,
+    AnyMagicLinkStore,
+    AnyAccessTokenStore,
+    AnyOrganizationStore,
+>;
+
+/// Builds a [`ConfiguredAppState`], connecting only to the backends that were selected.
+pub struct AppStateBuilder {
+    settings: Settings,
+}
+
+impl AppStateBuilder {
+    pub fn new(settings: Settings) -> Self {
+        Self { settings }
+    }
+
+    #[tracing::instrument(name = "Building app state", skip_all)]
+    pub async fn build(self) -> Result<ConfiguredAppState> {
+        let settings = self.settings;
+        let (
+            user_store,
+            audit_log,
+            trusted_device_store,
+            oauth_store,
+            access_token_store,
+            organization_store,
+        ) = match settings.stores.user_store {
+            UserStoreBackend::Memory => {
+                let user_store = HashmapUserStore::default();
+                let organization_store = HashMapOrganizationStore::new(&user_store);
+                (
+                    AnyUserStore::Memory(user_store),
+                    AnyAuditLog::Memory(VecAuditLog::default()),
+                    AnyTrustedDeviceStore::Memory(HashMapTrustedDeviceStore::default()),
+                    AnyOAuthStore::Memory(HashMapOAuthStore::default()),
+                    AnyAccessTokenStore::Memory(HashMapAccessTokenStore::default()),
+                    AnyOrganizationStore::Memory(organization_store),
+                )
+            }
+            UserStoreBackend::Postgres => {
+                let pg_pool = get_postgres_pool(&settings.database)
+                    .await
+                    .wrap_err("Failed to create Postgres connection pool")?;
+                sqlx::migrate!()
+                    .run(&pg_pool)
+                    .await
+                    .wrap_err("Failed to run migrations")?;
+                (
+                    AnyUserStore::Postgres(PostgresUserStore::new(pg_pool.clone())),
+                    AnyAuditLog::Postgres(PostgresAuditLog::new(pg_pool.clone())),
+                    AnyTrustedDeviceStore::Postgres(PostgresTrustedDeviceStore::new(
+                        pg_pool.clone(),
+                    )),
+                    AnyOAuthStore::Postgres(PostgresOAuthStore::new(pg_pool.clone())),
+                    AnyAccessTokenStore::Postgres(PostgresAccessTokenStore::new(pg_pool.clone())),
+                    AnyOrganizationStore::Postgres(PostgresOrganizationStore::new(pg_pool)),
+                )
+            }
+        };
+
+        let (banned_token_store, two_fa_code_store, magic_link_store) =
+            match settings.stores.token_store {
+                TokenStoreBackend::Memory => (
+                    AnyBannedTokenStore::Memory(HashSetBannedTokenStore::default()),
+                    AnyTwoFACodeStore::Memory(HashMapTwoFACodeStore::default()),
+                    AnyMagicLinkStore::Memory(HashMapMagicLinkStore::new(
+                        settings.auth.magic_link_ttl_seconds,
+                    )),
+                ),
+                TokenStoreBackend::Redis => {
+                    let redis_conn = get_redis_client(settings.redis.host_name.clone())
+                        .wrap_err("Failed to get Redis client")?
+                        .get_connection()
+                        .wrap_err("Failed to get Redis connection")?;
+                    let redis_conn = Arc::new(RwLock::new(redis_conn));
+                    (
+                        AnyBannedTokenStore::Redis(RedisBannedTokenStore::new(
+                            redis_conn.clone(),
+                            settings.auth.token_ttl_seconds as u64,
+                        )),
+                        AnyTwoFACodeStore::Redis(RedisTwoFACodeStore::new(
+                            redis_conn.clone(),
+                            settings.auth.two_fa_code_ttl_seconds,
+                        )),
+                        AnyMagicLinkStore::Redis(RedisMagicLinkStore::new(
+                            redis_conn,
+                            settings.auth.magic_link_ttl_seconds,
+                        )),
+                    )
+                }
+            };
+
+        let signing_key = SigningKey::from_settings(&settings.oidc.signing_key)
+            .map_err(|e| eyre!(e))
+            .wrap_err("Failed to load the OIDC signing key")?;
+        let password_policy = PasswordPolicy::from_settings(&settings.auth.password)
+            .map_err(|e| eyre!(e))
+            .wrap_err("Failed to load the breached passwords")?;
+
+        Ok(AppState::new(
+            Arc::new(RwLock::new(user_store)),
+            Arc::new(RwLock::new(banned_token_store)),
+            Arc::new(RwLock::new(two_fa_code_store)),
+            Arc::new(MockEmailClient::default()),
+            Arc::new(RwLock::new(audit_log)),
+            Arc::new(RwLock::new(trusted_device_store)),
+            Arc::new(RwLock::new(oauth_store)),
+            Arc::new(RwLock::new(magic_link_store)),
+            Arc::new(RwLock::new(access_token_store)),
+            Arc::new(RwLock::new(organization_store)),
+            Arc::new(signing_key),
+            Arc::new(password_policy),
+            Arc::new(settings),
+        ))
+    }
 }
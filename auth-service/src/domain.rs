@@ -1,13 +1,37 @@
+pub mod api_key;
+pub mod audit;
 pub mod data_stores;
+pub mod device;
 pub mod email;
 pub mod email_client;
 pub mod error;
+pub mod invitation;
+pub mod oauth;
+pub mod organization;
 pub mod password;
+pub mod password_policy;
+pub mod password_strength;
+pub mod personal_access_token;
+pub mod profile;
+pub mod role;
+pub mod social;
 pub mod user;
 
+pub use crate::domain::api_key::*;
+pub use crate::domain::audit::*;
 pub use crate::domain::data_stores::*;
+pub use crate::domain::device::*;
 pub use crate::domain::email::*;
 pub use crate::domain::email_client::*;
 pub use crate::domain::error::*;
+pub use crate::domain::invitation::*;
+pub use crate::domain::oauth::*;
+pub use crate::domain::organization::*;
 pub use crate::domain::password::*;
+pub use crate::domain::password_policy::*;
+pub use crate::domain::password_strength::*;
+pub use crate::domain::personal_access_token::*;
+pub use crate::domain::profile::*;
+pub use crate::domain::role::*;
+pub use crate::domain::social::*;
 pub use crate::domain::user::*;
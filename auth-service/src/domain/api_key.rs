@@ -0,0 +1,112 @@
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use super::{FieldError, SUPPORTED_SCOPES, generate_secret, hash_secret};
+
+#[cfg(test)]
+mod tests;
+
+const API_KEY_ID_PREFIX: &str = "ak_";
+const MAX_SCOPE_LENGTH: usize = 64;
+const MAX_TTL_DAYS: i64 = 3650;
+
+/// A credential of a backend job or service, exchanged for service tokens
+/// with the client credentials grant. The key is `{id}.{secret}`, the id
+/// finds it, only a hash of the whole key is kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    pub secret_hash: String,
+    pub scopes: Vec<String>,
+    /// The admin who issued it, `None` for the admin API key.
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    /// A new key with the key itself to hand out, it can't be shown again.
+    pub fn issue(
+        name: String,
+        scopes: &[String],
+        ttl_days: Option<i64>,
+        created_by: Option<String>,
+    ) -> Result<(Self, String), FieldError> {
+        let name = name.trim().to_owned();
+        if name.is_empty() {
+            return Err(FieldError::new("name", "required", "The key name is empty"));
+        }
+        let scopes = parse_service_scopes(&scopes.join(" "))
+            .map_err(|e| FieldError::new("scopes", "invalid_scope", e))?;
+        if scopes.is_empty() {
+            return Err(FieldError::new(
+                "scopes",
+                "required",
+                "At least one scope is required",
+            ));
+        }
+        let expires_at = match ttl_days {
+            Some(days) if !(1..=MAX_TTL_DAYS).contains(&days) => {
+                return Err(FieldError::new(
+                    "expiresInDays",
+                    "out_of_range",
+                    format!("Keys expire in 1 to {MAX_TTL_DAYS} days"),
+                ));
+            }
+            Some(days) => Some(Utc::now() + Duration::days(days)),
+            None => None,
+        };
+
+        let id = format!("{API_KEY_ID_PREFIX}{}", Uuid::new_v4().simple());
+        let key = format!("{id}.{}", generate_secret());
+        let api_key = Self {
+            id,
+            name,
+            secret_hash: hash_secret(&key),
+            scopes,
+            created_by,
+            created_at: Utc::now(),
+            expires_at,
+            last_used_at: None,
+        };
+        Ok((api_key, key))
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= Utc::now())
+    }
+
+    /// Comparing hashes leaks nothing useful through timing.
+    pub fn verify(&self, key: &str) -> bool {
+        api_key_id(key) == Some(self.id.as_str()) && hash_secret(key) == self.secret_hash
+    }
+}
+
+/// The id part of a key, `None` when it isn't shaped like one.
+pub fn api_key_id(key: &str) -> Option<&str> {
+    key.split_once('.')
+        .map(|(id, _)| id)
+        .filter(|id| id.starts_with(API_KEY_ID_PREFIX))
+}
+
+/// Parses space-separated service scopes, e.g. `reports:read`. The scopes of
+/// OpenID Connect are for users and can't be given to services. The result
+/// is sorted and deduplicated.
+pub fn parse_service_scopes(scope: &str) -> Result<Vec<String>, String> {
+    let mut scopes: Vec<String> = scope.split_whitespace().map(str::to_owned).collect();
+    scopes.sort();
+    scopes.dedup();
+    for scope in &scopes {
+        let valid = scope.len() <= MAX_SCOPE_LENGTH
+            && scope
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, ':' | '.' | '_' | '-'));
+        if !valid || SUPPORTED_SCOPES.contains(&scope.as_str()) {
+            return Err(format!("Invalid service scope `{scope}`"));
+        }
+    }
+    Ok(scopes)
+}
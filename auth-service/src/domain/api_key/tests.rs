@@ -0,0 +1,71 @@
+use super::*;
+
+fn scopes(scopes: &[&str]) -> Vec<String> {
+    scopes.iter().map(|scope| scope.to_string()).collect()
+}
+
+#[test]
+fn test_issue_api_key() {
+    let (api_key, key) = ApiKey::issue(
+        " Nightly report ".to_owned(),
+        &scopes(&["reports:write", "reports:read", "reports:read"]),
+        Some(30),
+        Some("admin@example.com".to_owned()),
+    )
+    .unwrap();
+
+    assert_eq!(api_key.name, "Nightly report");
+    assert_eq!(api_key.scopes, scopes(&["reports:read", "reports:write"]));
+    assert!(api_key.id.starts_with("ak_"));
+    assert_eq!(api_key_id(&key), Some(api_key.id.as_str()));
+    assert_ne!(api_key.secret_hash, key);
+    assert!(api_key.expires_at.is_some());
+    assert!(!api_key.is_expired());
+    assert_eq!(api_key.last_used_at, None);
+}
+
+#[test]
+fn test_verify_api_key() {
+    let (api_key, key) = ApiKey::issue("job".to_owned(), &scopes(&["jobs"]), None, None).unwrap();
+    let (other, other_key) =
+        ApiKey::issue("job".to_owned(), &scopes(&["jobs"]), None, None).unwrap();
+
+    assert!(api_key.verify(&key));
+    assert!(!api_key.verify(&other_key));
+    assert!(!api_key.verify(&format!("{}.forged", api_key.id)));
+    assert!(!other.verify(&key));
+    assert_eq!(api_key.expires_at, None);
+}
+
+#[test]
+fn test_issue_rejects_invalid_keys() {
+    let issue = |name: &str, scope: &[&str], ttl_days| {
+        ApiKey::issue(name.to_owned(), &scopes(scope), ttl_days, None)
+    };
+
+    assert!(issue(" ", &["jobs"], None).is_err());
+    assert!(issue("job", &[], None).is_err());
+    assert!(issue("job", &["jobs"], Some(0)).is_err());
+    assert!(issue("job", &["jobs"], Some(MAX_TTL_DAYS + 1)).is_err());
+}
+
+#[test]
+fn test_expired_api_key() {
+    let (mut api_key, _) =
+        ApiKey::issue("job".to_owned(), &scopes(&["jobs"]), Some(1), None).unwrap();
+    api_key.expires_at = Some(Utc::now() - Duration::seconds(1));
+    assert!(api_key.is_expired());
+}
+
+#[test]
+fn test_parse_service_scopes() {
+    assert_eq!(
+        parse_service_scopes("b:read a.write  b:read"),
+        Ok(scopes(&["a.write", "b:read"]))
+    );
+    assert_eq!(parse_service_scopes(""), Ok(Vec::new()));
+    assert!(parse_service_scopes("openid").is_err());
+    assert!(parse_service_scopes("email").is_err());
+    assert!(parse_service_scopes("a/b").is_err());
+    assert!(parse_service_scopes(&"a".repeat(MAX_SCOPE_LENGTH + 1)).is_err());
+}
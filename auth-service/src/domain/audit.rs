@@ -0,0 +1,218 @@
+use std::str::FromStr;
+
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests;
+
+/// The security-relevant things users, admins and services do, recorded in
+/// the audit log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEventType {
+    Signup,
+    Login,
+    TwoFactorCodeSent,
+    TwoFactorVerified,
+    Logout,
+    TokenVerified,
+    TokenIntrospected,
+    ProfileUpdated,
+    RolesChanged,
+    StatusChanged,
+    TwoFactorReset,
+    TokensRevoked,
+    PasswordReset,
+    PasswordChanged,
+    NewDeviceSignIn,
+    DeviceTrusted,
+    TrustedDeviceForgotten,
+    ConsentGranted,
+    AccountLinked,
+    MagicLinkSent,
+    ApiKeyCreated,
+    ApiKeyRevoked,
+    AccessTokenCreated,
+    AccessTokenRevoked,
+    OrganizationCreated,
+    OrganizationSettingsChanged,
+    MemberInvited,
+    OrganizationJoined,
+    InvitationCreated,
+}
+
+impl AuditEventType {
+    pub const ALL: [Self; 29] = [
+        Self::Signup,
+        Self::Login,
+        Self::TwoFactorCodeSent,
+        Self::TwoFactorVerified,
+        Self::Logout,
+        Self::TokenVerified,
+        Self::TokenIntrospected,
+        Self::ProfileUpdated,
+        Self::RolesChanged,
+        Self::StatusChanged,
+        Self::TwoFactorReset,
+        Self::TokensRevoked,
+        Self::PasswordReset,
+        Self::PasswordChanged,
+        Self::NewDeviceSignIn,
+        Self::DeviceTrusted,
+        Self::TrustedDeviceForgotten,
+        Self::ConsentGranted,
+        Self::AccountLinked,
+        Self::MagicLinkSent,
+        Self::ApiKeyCreated,
+        Self::ApiKeyRevoked,
+        Self::AccessTokenCreated,
+        Self::AccessTokenRevoked,
+        Self::OrganizationCreated,
+        Self::OrganizationSettingsChanged,
+        Self::MemberInvited,
+        Self::OrganizationJoined,
+        Self::InvitationCreated,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Signup => "signup",
+            Self::Login => "login",
+            Self::TwoFactorCodeSent => "two_factor_code_sent",
+            Self::TwoFactorVerified => "two_factor_verified",
+            Self::Logout => "logout",
+            Self::TokenVerified => "token_verified",
+            Self::TokenIntrospected => "token_introspected",
+            Self::ProfileUpdated => "profile_updated",
+            Self::RolesChanged => "roles_changed",
+            Self::StatusChanged => "status_changed",
+            Self::TwoFactorReset => "two_factor_reset",
+            Self::TokensRevoked => "tokens_revoked",
+            Self::PasswordReset => "password_reset",
+            Self::PasswordChanged => "password_changed",
+            Self::NewDeviceSignIn => "new_device_sign_in",
+            Self::DeviceTrusted => "device_trusted",
+            Self::TrustedDeviceForgotten => "trusted_device_forgotten",
+            Self::ConsentGranted => "consent_granted",
+            Self::AccountLinked => "account_linked",
+            Self::MagicLinkSent => "magic_link_sent",
+            Self::ApiKeyCreated => "api_key_created",
+            Self::ApiKeyRevoked => "api_key_revoked",
+            Self::AccessTokenCreated => "access_token_created",
+            Self::AccessTokenRevoked => "access_token_revoked",
+            Self::OrganizationCreated => "organization_created",
+            Self::OrganizationSettingsChanged => "organization_settings_changed",
+            Self::MemberInvited => "member_invited",
+            Self::OrganizationJoined => "organization_joined",
+            Self::InvitationCreated => "invitation_created",
+        }
+    }
+}
+
+impl FromStr for AuditEventType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|event_type| event_type.as_str() == s)
+            .ok_or_else(|| format!("Unknown audit event type `{s}`"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+impl AuditOutcome {
+    /// Any 2xx response is a success, e.g. the 206 of a login waiting for 2FA.
+    pub fn from_status(status: StatusCode) -> Self {
+        if status.is_success() {
+            Self::Success
+        } else {
+            Self::Failure
+        }
+    }
+
+    pub fn from_result<T, E>(result: &Result<T, E>) -> Self {
+        if result.is_ok() {
+            Self::Success
+        } else {
+            Self::Failure
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Failure => "failure",
+        }
+    }
+}
+
+impl FromStr for AuditOutcome {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "success" => Ok(Self::Success),
+            "failure" => Ok(Self::Failure),
+            _ => Err(format!("Unknown audit outcome `{s}`")),
+        }
+    }
+}
+
+/// Where a request came from, as far as the service can tell.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientInfo {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+/// One entry of the audit log. The actor is who made the request, if known,
+/// e.g. the email of a login attempt or the admin changing a user, and the
+/// target the user an admin acted on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEvent {
+    pub event_type: AuditEventType,
+    pub actor: Option<String>,
+    pub target: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub outcome: AuditOutcome,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl AuditEvent {
+    pub fn new(event_type: AuditEventType, client: &ClientInfo, outcome: AuditOutcome) -> Self {
+        Self {
+            event_type,
+            actor: None,
+            target: None,
+            ip: client.ip.clone(),
+            user_agent: client.user_agent.clone(),
+            outcome,
+            occurred_at: Utc::now(),
+        }
+    }
+
+    pub fn with_actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = Some(actor.into());
+        self
+    }
+
+    pub fn with_target(mut self, target: impl Into<String>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    /// Whether the event is part of the history of `email`, as actor or target.
+    pub fn concerns(&self, email: &str) -> bool {
+        self.actor.as_deref() == Some(email) || self.target.as_deref() == Some(email)
+    }
+}
@@ -0,0 +1,48 @@
+use super::*;
+
+#[test]
+fn event_types_round_trip_through_str() {
+    for event_type in AuditEventType::ALL {
+        assert_eq!(event_type.as_str().parse(), Ok(event_type));
+        let json = serde_json::to_string(&event_type).unwrap();
+        assert_eq!(json, format!("\"{}\"", event_type.as_str()));
+    }
+    assert!("unknown".parse::<AuditEventType>().is_err());
+}
+
+#[test]
+fn outcome_follows_status() {
+    assert_eq!(
+        AuditOutcome::from_status(StatusCode::OK),
+        AuditOutcome::Success
+    );
+    assert_eq!(
+        AuditOutcome::from_status(StatusCode::PARTIAL_CONTENT),
+        AuditOutcome::Success
+    );
+    assert_eq!(
+        AuditOutcome::from_status(StatusCode::UNAUTHORIZED),
+        AuditOutcome::Failure
+    );
+    assert_eq!("failure".parse(), Ok(AuditOutcome::Failure));
+}
+
+#[test]
+fn event_concerns_actor_and_target() {
+    let client = ClientInfo {
+        ip: Some("127.0.0.1".to_owned()),
+        user_agent: None,
+    };
+    let event = AuditEvent::new(
+        AuditEventType::StatusChanged,
+        &client,
+        AuditOutcome::Success,
+    )
+    .with_actor("admin@example.com")
+    .with_target("user@example.com");
+
+    assert_eq!(event.ip.as_deref(), Some("127.0.0.1"));
+    assert!(event.concerns("admin@example.com"));
+    assert!(event.concerns("user@example.com"));
+    assert!(!event.concerns("other@example.com"));
+}
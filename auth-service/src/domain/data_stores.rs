@@ -1,7 +1,22 @@
 use validator::{Validate, ValidationError, ValidationErrors};
 
-use super::Email;
+use super::AccountStatus;
+use super::ApiKey;
+use super::AuditEvent;
+use super::AuthorizationCode;
+use super::Invitation;
+use super::KnownDevice;
+use super::OAuthClient;
+use super::Organization;
+use super::Password;
+use super::PersonalAccessToken;
+use super::Role;
+use super::TenantSettings;
+use super::TrustedDevice;
 use super::User;
+use super::UserKey;
+use super::UserProfile;
+use chrono::{DateTime, Utc};
 use color_eyre::eyre::Report;
 use rand::prelude::*;
 use thiserror::Error;
@@ -31,13 +46,84 @@ impl PartialEq for UserStoreError {
     }
 }
 
+/// A page of users ordered by email, optionally filtered by a
+/// case-insensitive search on the email and display name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UserQuery {
+    pub search: Option<String>,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+#[derive(Debug)]
+pub struct UserPage {
+    pub users: Vec<User>,
+    /// Number of users matching the search, across all pages.
+    pub total: usize,
+}
+
 #[async_trait::async_trait]
 pub trait UserStore: Send + Sync + Clone {
+    /// Fails if the email is taken in the tenant of the user.
     async fn add_user(&mut self, user: User) -> Result<(), UserStoreError>;
 
-    async fn get_user(&self, email: &str) -> Result<User, UserStoreError>;
+    async fn get_user(&self, user: &UserKey) -> Result<User, UserStoreError>;
+
+    async fn validate_user(&self, user: &UserKey, password: &str) -> Result<(), UserStoreError>;
+
+    /// Replaces the profile of a user, returning the updated user.
+    async fn update_profile(
+        &mut self,
+        user: &UserKey,
+        profile: UserProfile,
+    ) -> Result<User, UserStoreError>;
+
+    /// Replaces the roles of a user, returning the updated user.
+    async fn set_roles(&mut self, user: &UserKey, roles: Vec<Role>)
+    -> Result<User, UserStoreError>;
+
+    async fn list_users(&self, query: &UserQuery) -> Result<UserPage, UserStoreError>;
+
+    async fn set_password(
+        &mut self,
+        user: &UserKey,
+        password: Password,
+    ) -> Result<(), UserStoreError>;
+
+    async fn set_status(
+        &mut self,
+        user: &UserKey,
+        status: AccountStatus,
+    ) -> Result<User, UserStoreError>;
+
+    async fn set_requires_2fa(
+        &mut self,
+        user: &UserKey,
+        requires_2fa: bool,
+    ) -> Result<User, UserStoreError>;
+
+    async fn known_devices(&self, user: &UserKey) -> Result<Vec<KnownDevice>, UserStoreError>;
+
+    /// Adds a device, or refreshes the one with the same id.
+    async fn remember_device(
+        &mut self,
+        user: &UserKey,
+        device: KnownDevice,
+    ) -> Result<(), UserStoreError>;
+
+    async fn forget_devices(&mut self, user: &UserKey) -> Result<(), UserStoreError>;
+
+    /// The user an account at a social login provider is linked to.
+    async fn linked_user(&self, provider: &str, subject: &str) -> Result<User, UserStoreError>;
 
-    async fn validate_user(&self, email: &str, password: &str) -> Result<(), UserStoreError>;
+    /// Links an account at a social login provider to a user, replacing any
+    /// previous link of that account.
+    async fn link_identity(
+        &mut self,
+        user: &UserKey,
+        provider: &str,
+        subject: &str,
+    ) -> Result<(), UserStoreError>;
 }
 
 #[derive(Debug, Error)]
@@ -50,6 +136,17 @@ pub enum BannedTokenStoreError {
 pub trait BannedTokenStore: Send + Sync + Clone {
     async fn add_token(&mut self, token: String) -> Result<(), BannedTokenStoreError>;
     async fn contains_token(&self, token: &str) -> Result<bool, BannedTokenStoreError>;
+    /// Bans every token of a user issued at or before `issued_before`, a Unix
+    /// timestamp, e.g. to log the user out everywhere.
+    async fn ban_user_tokens(
+        &mut self,
+        user: &UserKey,
+        issued_before: i64,
+    ) -> Result<(), BannedTokenStoreError>;
+    async fn user_tokens_banned_before(
+        &self,
+        user: &UserKey,
+    ) -> Result<Option<i64>, BannedTokenStoreError>;
 }
 
 #[derive(Debug, Error)]
@@ -157,15 +254,315 @@ impl AsRef<str> for LoginAttemptId {
 pub trait TwoFACodeStore: Send + Sync + Clone {
     async fn add_code(
         &mut self,
-        email: Email,
+        user: UserKey,
         login_attempt_id: LoginAttemptId,
         code: TwoFACode,
     ) -> Result<(), TwoFACodeStoreError>;
 
-    async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError>;
+    async fn remove_code(&mut self, user: &UserKey) -> Result<(), TwoFACodeStoreError>;
 
     async fn get_code(
         &self,
-        email: &Email,
+        user: &UserKey,
     ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError>;
 }
+
+#[derive(Debug, Error)]
+pub enum TrustedDeviceStoreError {
+    #[error("Trusted device not found")]
+    DeviceNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for TrustedDeviceStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::DeviceNotFound, Self::DeviceNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+/// The [`TrustedDevice`]s of each user. Expired devices are never returned.
+#[async_trait::async_trait]
+pub trait TrustedDeviceStore: Send + Sync + Clone {
+    async fn add_device(
+        &mut self,
+        user: &UserKey,
+        device: TrustedDevice,
+    ) -> Result<(), TrustedDeviceStoreError>;
+
+    async fn get_device(
+        &self,
+        user: &UserKey,
+        id: &str,
+    ) -> Result<TrustedDevice, TrustedDeviceStoreError>;
+
+    /// The devices of a user, most recently trusted first.
+    async fn user_devices(
+        &self,
+        user: &UserKey,
+    ) -> Result<Vec<TrustedDevice>, TrustedDeviceStoreError>;
+
+    async fn remove_device(
+        &mut self,
+        user: &UserKey,
+        id: &str,
+    ) -> Result<(), TrustedDeviceStoreError>;
+
+    async fn remove_user_devices(&mut self, user: &UserKey) -> Result<(), TrustedDeviceStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum AccessTokenStoreError {
+    #[error("Personal access token not found")]
+    TokenNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for AccessTokenStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::TokenNotFound, Self::TokenNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+/// The [`PersonalAccessToken`]s of each user, expired ones included.
+#[async_trait::async_trait]
+pub trait AccessTokenStore: Send + Sync + Clone {
+    async fn add_token(&mut self, token: PersonalAccessToken) -> Result<(), AccessTokenStoreError>;
+
+    async fn get_token(&self, id: &str) -> Result<PersonalAccessToken, AccessTokenStoreError>;
+
+    /// The tokens of a user, newest first.
+    async fn user_tokens(
+        &self,
+        user: &UserKey,
+    ) -> Result<Vec<PersonalAccessToken>, AccessTokenStoreError>;
+
+    /// Removes a token of a user, tokens of other users are not found.
+    async fn remove_token(&mut self, user: &UserKey, id: &str)
+    -> Result<(), AccessTokenStoreError>;
+
+    async fn record_use(
+        &mut self,
+        id: &str,
+        used_at: DateTime<Utc>,
+    ) -> Result<(), AccessTokenStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum OrganizationStoreError {
+    #[error("Organization not found")]
+    OrganizationNotFound,
+    #[error("Invitation not found")]
+    InvitationNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for OrganizationStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::OrganizationNotFound, Self::OrganizationNotFound)
+                | (Self::InvitationNotFound, Self::InvitationNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+/// [`Organization`]s, which users are members of them, and the
+/// [`Invitation`]s to join one or to sign up.
+#[async_trait::async_trait]
+pub trait OrganizationStore: Send + Sync + Clone {
+    async fn add_organization(
+        &mut self,
+        organization: Organization,
+    ) -> Result<(), OrganizationStoreError>;
+
+    async fn get_organization(&self, id: &str) -> Result<Organization, OrganizationStoreError>;
+
+    /// Removes an organization along with its accounts and invitations.
+    async fn remove_organization(&mut self, id: &str) -> Result<(), OrganizationStoreError>;
+
+    /// Replaces the settings of an organization, returning the updated one.
+    async fn update_organization_settings(
+        &mut self,
+        id: &str,
+        settings: TenantSettings,
+    ) -> Result<Organization, OrganizationStoreError>;
+
+    /// The emails of the members of an organization, sorted.
+    async fn organization_members(&self, id: &str) -> Result<Vec<String>, OrganizationStoreError>;
+
+    async fn add_invitation(
+        &mut self,
+        invitation: Invitation,
+    ) -> Result<(), OrganizationStoreError>;
+
+    async fn get_invitation(&self, token_hash: &str) -> Result<Invitation, OrganizationStoreError>;
+
+    /// Removes an invitation so it can only be accepted once, expired ones
+    /// included.
+    async fn take_invitation(
+        &mut self,
+        token_hash: &str,
+    ) -> Result<Invitation, OrganizationStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum OAuthStoreError {
+    #[error("Client already exists")]
+    ClientAlreadyExists,
+    #[error("Client not found")]
+    ClientNotFound,
+    #[error("Authorization code not found")]
+    CodeNotFound,
+    #[error("API key not found")]
+    ApiKeyNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for OAuthStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::ClientAlreadyExists, Self::ClientAlreadyExists)
+                | (Self::ClientNotFound, Self::ClientNotFound)
+                | (Self::CodeNotFound, Self::CodeNotFound)
+                | (Self::ApiKeyNotFound, Self::ApiKeyNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+/// Registered [`OAuthClient`]s, the [`AuthorizationCode`]s issued to them,
+/// the scopes each user consented to give each client and the [`ApiKey`]s of
+/// services using the client credentials grant.
+#[async_trait::async_trait]
+pub trait OAuthStore: Send + Sync + Clone {
+    async fn add_client(&mut self, client: OAuthClient) -> Result<(), OAuthStoreError>;
+
+    async fn get_client(&self, client_id: &str) -> Result<OAuthClient, OAuthStoreError>;
+
+    async fn add_code(&mut self, code: AuthorizationCode) -> Result<(), OAuthStoreError>;
+
+    /// Removes and returns a code, so it can only be exchanged once. Expired
+    /// codes are not found.
+    async fn take_code(&mut self, code: &str) -> Result<AuthorizationCode, OAuthStoreError>;
+
+    /// The scopes `user` consented to give the client, empty if none.
+    async fn granted_scopes(
+        &self,
+        user: &UserKey,
+        client_id: &str,
+    ) -> Result<Vec<String>, OAuthStoreError>;
+
+    /// Adds `scopes` to the ones already granted.
+    async fn grant_consent(
+        &mut self,
+        user: &UserKey,
+        client_id: &str,
+        scopes: &[String],
+    ) -> Result<(), OAuthStoreError>;
+
+    async fn add_api_key(&mut self, api_key: ApiKey) -> Result<(), OAuthStoreError>;
+
+    /// Expired keys are returned too, so they can be told apart from revoked ones.
+    async fn get_api_key(&self, id: &str) -> Result<ApiKey, OAuthStoreError>;
+
+    /// All keys, most recently created first.
+    async fn api_keys(&self) -> Result<Vec<ApiKey>, OAuthStoreError>;
+
+    async fn remove_api_key(&mut self, id: &str) -> Result<(), OAuthStoreError>;
+
+    async fn record_api_key_use(
+        &mut self,
+        id: &str,
+        used_at: DateTime<Utc>,
+    ) -> Result<(), OAuthStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum MagicLinkStoreError {
+    #[error("Magic link not found")]
+    TokenNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for MagicLinkStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::TokenNotFound, Self::TokenNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+/// What an emailed link is for, a link is only accepted for its purpose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkPurpose {
+    SignIn,
+    PasswordReset,
+    RevokeSessions,
+}
+
+impl LinkPurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SignIn => "magic_link",
+            Self::PasswordReset => "password_reset",
+            Self::RevokeSessions => "revoke_sessions",
+        }
+    }
+}
+
+/// Pending emailed links, magic sign-in, password reset and sign-out ones,
+/// keyed by the hash of their token. Links expire after the TTL the store was built with.
+#[async_trait::async_trait]
+pub trait MagicLinkStore: Send + Sync + Clone {
+    async fn add_token(
+        &mut self,
+        purpose: LinkPurpose,
+        token_hash: &str,
+        user: UserKey,
+    ) -> Result<(), MagicLinkStoreError>;
+
+    /// Removes and returns the user a link was sent to, so it can only be
+    /// used once.
+    async fn take_token(
+        &mut self,
+        purpose: LinkPurpose,
+        token_hash: &str,
+    ) -> Result<UserKey, MagicLinkStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum AuditLogError {
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+/// Append-only record of [`AuditEvent`]s.
+#[async_trait::async_trait]
+pub trait AuditLog: Send + Sync + Clone {
+    async fn record(&mut self, event: AuditEvent) -> Result<(), AuditLogError>;
+
+    /// The events concerning a user, as actor or target, most recent first.
+    async fn user_events(
+        &self,
+        email: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<AuditEvent>, AuditLogError>;
+}
@@ -0,0 +1,95 @@
+use std::net::IpAddr;
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::domain::ClientInfo;
+
+#[cfg(test)]
+mod tests;
+
+/// A device a user logged in from, identified by the random id of its device
+/// cookie, or by its user agent and IP for clients without cookies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnownDevice {
+    pub device_id: String,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub last_seen: DateTime<Utc>,
+}
+
+impl KnownDevice {
+    pub fn new(device_id: String, client: &ClientInfo) -> Self {
+        Self {
+            device_id,
+            user_agent: client.user_agent.clone(),
+            ip: client.ip.clone(),
+            last_seen: Utc::now(),
+        }
+    }
+
+    pub fn matches(&self, device_id: Option<&str>, client: &ClientInfo) -> bool {
+        if device_id == Some(self.device_id.as_str()) {
+            return true;
+        }
+        self.user_agent.is_some()
+            && self.ip.is_some()
+            && self.user_agent == client.user_agent
+            && self.ip == client.ip
+    }
+}
+
+/// A browser a user chose to trust after a 2FA login, which then skips 2FA
+/// until it expires or the user forgets it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrustedDevice {
+    pub id: String,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl TrustedDevice {
+    pub fn new(client: &ClientInfo, ttl: Duration) -> Self {
+        let created_at = Utc::now();
+        Self {
+            id: generate_device_id(),
+            user_agent: client.user_agent.clone(),
+            ip: client.ip.clone(),
+            created_at,
+            expires_at: created_at + ttl,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
+
+/// Device ids come from a cookie, only the UUIDs we hand out are accepted.
+pub fn parse_device_id(value: &str) -> Option<String> {
+    Uuid::parse_str(value).ok().map(|uuid| uuid.to_string())
+}
+
+pub fn generate_device_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+/// The network of an IP, e.g. `203.0.113.x`, as an approximate location
+/// without revealing the full address.
+pub fn network_hint(ip: &str) -> Option<String> {
+    match ip.parse::<IpAddr>().ok()? {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            Some(format!("{a}.{b}.{c}.x"))
+        }
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            Some(format!(
+                "{:x}:{:x}:{:x}::/48",
+                segments[0], segments[1], segments[2]
+            ))
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use super::*;
+
+fn client(user_agent: Option<&str>, ip: Option<&str>) -> ClientInfo {
+    ClientInfo {
+        ip: ip.map(str::to_owned),
+        user_agent: user_agent.map(str::to_owned),
+    }
+}
+
+#[test]
+fn device_matches_cookie_or_user_agent_and_ip() {
+    let known = client(Some("Firefox"), Some("203.0.113.7"));
+    let device = KnownDevice::new("device-1".to_owned(), &known);
+
+    assert!(device.matches(Some("device-1"), &client(None, None)));
+    assert!(device.matches(None, &known));
+    assert!(device.matches(Some("device-2"), &known));
+    assert!(!device.matches(None, &client(Some("Firefox"), Some("203.0.113.8"))));
+    assert!(!device.matches(None, &client(Some("Chrome"), Some("203.0.113.7"))));
+}
+
+#[test]
+fn device_without_user_agent_only_matches_cookie() {
+    let unknown = client(None, None);
+    let device = KnownDevice::new("device-1".to_owned(), &unknown);
+
+    assert!(!device.matches(None, &unknown));
+    assert!(device.matches(Some("device-1"), &unknown));
+}
+
+#[test]
+fn only_uuid_device_ids_are_accepted() {
+    let device_id = generate_device_id();
+    assert_eq!(parse_device_id(&device_id), Some(device_id));
+    assert_eq!(parse_device_id("'; DROP TABLE users; --"), None);
+}
+
+#[test]
+fn network_hint_hides_host_part() {
+    assert_eq!(network_hint("203.0.113.7").as_deref(), Some("203.0.113.x"));
+    assert_eq!(
+        network_hint("2001:db8:85a3::8a2e:370:7334").as_deref(),
+        Some("2001:db8:85a3::/48")
+    );
+    assert_eq!(network_hint("not an ip"), None);
+}
+
+#[test]
+fn trusted_device_expires_after_ttl() {
+    let client = client(Some("Firefox"), Some("203.0.113.7"));
+    let device = TrustedDevice::new(&client, Duration::days(30));
+    assert!(parse_device_id(&device.id).is_some());
+    assert_eq!(device.user_agent.as_deref(), Some("Firefox"));
+    assert_eq!(device.expires_at - device.created_at, Duration::days(30));
+    assert!(!device.is_expired());
+
+    let expired = TrustedDevice::new(&client, Duration::zero());
+    assert!(expired.is_expired());
+}
@@ -1,14 +1,19 @@
 use axum::{
     Json,
-    http::StatusCode,
+    http::{HeaderValue, StatusCode, header},
     response::{IntoResponse, Response},
 };
+use axum_tracing_opentelemetry::tracing_opentelemetry_instrumentation_sdk::find_current_trace_id;
 use color_eyre::eyre::Report;
 use thiserror::Error;
+use uuid::Uuid;
 
 use serde::{Deserialize, Serialize};
+use validator::ValidationErrors;
 
-fn log_error_chain(e: &(dyn std::error::Error + 'static)) {
+use crate::domain::PasswordRule;
+
+fn error_report(e: &(dyn std::error::Error + 'static)) -> String {
     let mut report = format!("{:?}\n", e);
     let mut current = e.source();
     while let Some(cause) = current {
@@ -16,13 +21,79 @@ fn log_error_chain(e: &(dyn std::error::Error + 'static)) {
         report = format!("{}\n{}", report, str);
         current = cause.source();
     }
-    report = format!("{}", report);
-    tracing::error!(name:"error", "{}", report);
+    report
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct ErrorResponse {
     pub error: String,
+    /// What is wrong with each rejected field of the request.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+/// RFC 7807 body of an [`AuthAPIError`], sent instead of its [`ErrorResponse`]
+/// to clients accepting `application/problem+json`.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemDetails {
+    /// A `tag:` URI under the host of the public URL once sent, e.g.
+    /// `tag:auth.example.com,2026:problems/missing-token`.
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// `urn:uuid:` of this occurrence, also logged with the error.
+    pub instance: String,
+    /// OpenTelemetry trace of the request, when it is traced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+/// A rejected field of a request, named as in its JSON body.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct FieldError {
+    pub field: String,
+    /// Stable, e.g. `missing_uppercase`, for clients to show their own message.
+    pub code: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, code: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_owned(),
+            code: code.to_owned(),
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_email(field: &str) -> Self {
+        Self::new(field, "invalid_email", "Must be a valid email address.")
+    }
+
+    /// From a domain type checked with `validator`, whose custom validators
+    /// describe the failure in the error code.
+    pub fn from_validation(field: &str, code: &str, errors: &ValidationErrors) -> Self {
+        let message = errors
+            .field_errors()
+            .values()
+            .flat_map(|errors| errors.iter())
+            .next()
+            .map(|error| error.message.as_ref().unwrap_or(&error.code).to_string())
+            .unwrap_or_else(|| "Invalid value.".to_owned());
+        Self::new(field, code, message)
+    }
+}
+
+impl From<PasswordRule> for FieldError {
+    fn from(rule: PasswordRule) -> Self {
+        Self::new("password", rule.code(), rule.to_string())
+    }
 }
 
 #[derive(Error, Debug)]
@@ -31,20 +102,80 @@ pub enum AuthAPIError {
     UserAlreadyExists,
     #[error("Invalid credentials")]
     InvalidCredentials,
+    #[error("Invalid fields: {0:?}")]
+    InvalidFields(Vec<FieldError>),
+    #[error("Malformed request body: {0:?}")]
+    MalformedBody(Vec<FieldError>),
     #[error("Incorrect credentials")]
     IncorrectCredentials,
     #[error("Missing token")]
     MissingToken,
     #[error("Invalid token")]
     InvalidToken,
+    #[error("Invalid CSRF token")]
+    InvalidCsrfToken,
+    #[error("Invalid client credentials")]
+    InvalidClient,
+    #[error("Invalid input")]
+    InvalidInput,
+    #[error("Missing role {0}")]
+    MissingRole(String),
+    #[error("Missing scope {0}")]
+    MissingScope(String),
+    #[error("Invalid API key")]
+    InvalidApiKey,
+    #[error("Account disabled")]
+    AccountDisabled,
+    #[error("Account locked")]
+    AccountLocked,
+    #[error("Account pending verification")]
+    AccountPendingVerification,
+    #[error("User not found")]
+    UserNotFound,
+    #[error("Trusted device not found")]
+    TrustedDeviceNotFound,
+    #[error("Client not found")]
+    OAuthClientNotFound,
+    #[error("API key not found")]
+    ApiKeyNotFound,
+    #[error("Personal access token not found")]
+    AccessTokenNotFound,
+    #[error("Organization not found")]
+    OrganizationNotFound,
+    #[error("Not the owner of the organization")]
+    NotOrganizationOwner,
+    #[error("Already in the organization")]
+    AlreadyInOrganization,
+    #[error("Invalid invitation")]
+    InvalidInvitation,
+    #[error("Registration requires an invitation")]
+    RegistrationClosed,
+    #[error("Login provider not found")]
+    LoginProviderNotFound,
+    #[error("Invalid login state")]
+    InvalidLoginState,
+    #[error("Login with the provider failed")]
+    ProviderLoginFailed(#[source] Report),
+    #[error("Email not verified by the provider")]
+    ProviderEmailNotVerified,
+    #[error("Route not found")]
+    RouteNotFound,
+    #[error("Method not allowed")]
+    MethodNotAllowed,
     #[error("Unexpected error")]
     UnexpectedError(#[source] Report),
 }
 
-impl IntoResponse for AuthAPIError {
-    fn into_response(self) -> Response {
-        log_error_chain(&self);
-        let (status, error_message) = match self {
+impl From<FieldError> for AuthAPIError {
+    fn from(error: FieldError) -> Self {
+        AuthAPIError::InvalidFields(vec![error])
+    }
+}
+
+impl AuthAPIError {
+    /// The status of the response and its `error` message.
+    pub fn status(&self) -> (StatusCode, &'static str) {
+        match self {
             AuthAPIError::UserAlreadyExists => (StatusCode::CONFLICT, "User already exists"),
             AuthAPIError::InvalidCredentials => (StatusCode::BAD_REQUEST, "Invalid credentials"),
             AuthAPIError::UnexpectedError(_) => {
@@ -55,10 +186,248 @@ impl IntoResponse for AuthAPIError {
             }
             AuthAPIError::MissingToken => (StatusCode::BAD_REQUEST, "Invalid input"),
             AuthAPIError::InvalidToken => (StatusCode::UNAUTHORIZED, "JWT is not valid"),
+            AuthAPIError::InvalidCsrfToken => (StatusCode::FORBIDDEN, "Invalid CSRF token"),
+            AuthAPIError::InvalidInput => (StatusCode::BAD_REQUEST, "Invalid input"),
+            AuthAPIError::MissingRole(_) => (StatusCode::FORBIDDEN, "Insufficient role"),
+            AuthAPIError::MissingScope(_) => (StatusCode::FORBIDDEN, "Insufficient scope"),
+            AuthAPIError::InvalidApiKey => (StatusCode::UNAUTHORIZED, "Invalid API key"),
+            AuthAPIError::AccountDisabled => (StatusCode::FORBIDDEN, "Account disabled"),
+            AuthAPIError::AccountLocked => (StatusCode::LOCKED, "Account locked"),
+            AuthAPIError::AccountPendingVerification => {
+                (StatusCode::FORBIDDEN, "Account pending verification")
+            }
+            AuthAPIError::UserNotFound => (StatusCode::NOT_FOUND, "User not found"),
+            AuthAPIError::TrustedDeviceNotFound => {
+                (StatusCode::NOT_FOUND, "Trusted device not found")
+            }
+            AuthAPIError::OAuthClientNotFound => (StatusCode::NOT_FOUND, "Client not found"),
+            AuthAPIError::ApiKeyNotFound => (StatusCode::NOT_FOUND, "API key not found"),
+            AuthAPIError::AccessTokenNotFound => {
+                (StatusCode::NOT_FOUND, "Personal access token not found")
+            }
+            AuthAPIError::OrganizationNotFound => (StatusCode::NOT_FOUND, "Organization not found"),
+            AuthAPIError::NotOrganizationOwner => (
+                StatusCode::FORBIDDEN,
+                "Only the owner can manage the organization",
+            ),
+            AuthAPIError::AlreadyInOrganization => {
+                (StatusCode::CONFLICT, "Already a member of the organization")
+            }
+            AuthAPIError::InvalidInvitation => {
+                (StatusCode::BAD_REQUEST, "Invitation expired or invalid")
+            }
+            AuthAPIError::RegistrationClosed => {
+                (StatusCode::FORBIDDEN, "Registration requires an invitation")
+            }
+            AuthAPIError::LoginProviderNotFound => {
+                (StatusCode::NOT_FOUND, "Login provider not found")
+            }
+            AuthAPIError::InvalidLoginState => {
+                (StatusCode::BAD_REQUEST, "Login attempt expired or invalid")
+            }
+            AuthAPIError::ProviderLoginFailed(_) => {
+                (StatusCode::UNAUTHORIZED, "Login with the provider failed")
+            }
+            AuthAPIError::ProviderEmailNotVerified => (
+                StatusCode::FORBIDDEN,
+                "The provider has not verified this email",
+            ),
+            AuthAPIError::InvalidFields(_) => (StatusCode::BAD_REQUEST, "Invalid input"),
+            AuthAPIError::MalformedBody(_) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, "Malformed request body")
+            }
+            AuthAPIError::InvalidClient => (StatusCode::UNAUTHORIZED, "Invalid client credentials"),
+            AuthAPIError::RouteNotFound => (StatusCode::NOT_FOUND, "Not found"),
+            AuthAPIError::MethodNotAllowed => {
+                (StatusCode::METHOD_NOT_ALLOWED, "Method not allowed")
+            }
+        }
+    }
+
+    /// Last segment of the type URI of the problem, see [`ProblemDetails`].
+    pub fn problem_type(&self) -> &'static str {
+        match self {
+            AuthAPIError::UserAlreadyExists => "user-already-exists",
+            AuthAPIError::InvalidCredentials => "invalid-credentials",
+            AuthAPIError::InvalidFields(_) => "invalid-fields",
+            AuthAPIError::MalformedBody(_) => "malformed-body",
+            AuthAPIError::IncorrectCredentials => "incorrect-credentials",
+            AuthAPIError::MissingToken => "missing-token",
+            AuthAPIError::InvalidToken => "invalid-token",
+            AuthAPIError::InvalidCsrfToken => "invalid-csrf-token",
+            AuthAPIError::InvalidClient => "invalid-client",
+            AuthAPIError::InvalidInput => "invalid-input",
+            AuthAPIError::MissingRole(_) => "missing-role",
+            AuthAPIError::MissingScope(_) => "missing-scope",
+            AuthAPIError::InvalidApiKey => "invalid-api-key",
+            AuthAPIError::AccountDisabled => "account-disabled",
+            AuthAPIError::AccountLocked => "account-locked",
+            AuthAPIError::AccountPendingVerification => "account-pending-verification",
+            AuthAPIError::UserNotFound => "user-not-found",
+            AuthAPIError::TrustedDeviceNotFound => "trusted-device-not-found",
+            AuthAPIError::OAuthClientNotFound => "client-not-found",
+            AuthAPIError::ApiKeyNotFound => "api-key-not-found",
+            AuthAPIError::AccessTokenNotFound => "access-token-not-found",
+            AuthAPIError::OrganizationNotFound => "organization-not-found",
+            AuthAPIError::NotOrganizationOwner => "not-organization-owner",
+            AuthAPIError::AlreadyInOrganization => "already-in-organization",
+            AuthAPIError::InvalidInvitation => "invalid-invitation",
+            AuthAPIError::RegistrationClosed => "registration-closed",
+            AuthAPIError::LoginProviderNotFound => "login-provider-not-found",
+            AuthAPIError::InvalidLoginState => "invalid-login-state",
+            AuthAPIError::ProviderLoginFailed(_) => "provider-login-failed",
+            AuthAPIError::ProviderEmailNotVerified => "provider-email-not-verified",
+            AuthAPIError::RouteNotFound => "route-not-found",
+            AuthAPIError::MethodNotAllowed => "method-not-allowed",
+            AuthAPIError::UnexpectedError(_) => "unexpected-error",
+        }
+    }
+
+    /// The `error` body keeps the 400 it always had for a missing token, the
+    /// problem details say 401.
+    fn problem_status(&self) -> StatusCode {
+        match self {
+            AuthAPIError::MissingToken => StatusCode::UNAUTHORIZED,
+            _ => self.status().0,
+        }
+    }
+
+    fn problem_title(&self) -> &'static str {
+        match self {
+            // the `error` message of these predates their own type
+            AuthAPIError::MissingToken => "Missing token",
+            AuthAPIError::InvalidInput => "Invalid input",
+            _ => self.status().1,
+        }
+    }
+
+    fn problem_detail(&self) -> Option<String> {
+        let detail = match self {
+            AuthAPIError::InvalidFields(_) => "One or more fields have an invalid value.",
+            AuthAPIError::MalformedBody(_) => "The request body doesn't match the expected shape.",
+            AuthAPIError::MissingToken => "The request carries no token.",
+            AuthAPIError::MissingRole(role) => {
+                return Some(format!("The {role} role is required."));
+            }
+            AuthAPIError::MissingScope(scope) => {
+                return Some(format!("The {scope} scope is required."));
+            }
+            AuthAPIError::AccountLocked => "The account is temporarily locked.",
+            _ => return None,
         };
+        Some(detail.to_owned())
+    }
+}
+
+impl IntoResponse for AuthAPIError {
+    fn into_response(self) -> Response {
+        // identifies this occurrence in the logs as in the problem details
+        let instance = format!("urn:uuid:{}", Uuid::new_v4());
+        tracing::error!(name: "error", instance, "{}", error_report(&self));
+        let (status, error_message) = self.status();
+        let mut problem = ProblemDetails {
+            problem_type: self.problem_type().to_owned(),
+            title: self.problem_title().to_owned(),
+            status: self.problem_status().as_u16(),
+            detail: self.problem_detail(),
+            instance,
+            trace_id: find_current_trace_id(),
+            errors: Vec::new(),
+        };
+        let challenge = matches!(self, AuthAPIError::InvalidClient);
+        if let AuthAPIError::InvalidFields(errors) | AuthAPIError::MalformedBody(errors) = self {
+            problem.errors = errors;
+        }
         let body = Json(ErrorResponse {
             error: error_message.to_owned(),
+            errors: problem.errors.clone(),
         });
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        if challenge {
+            response.headers_mut().insert(
+                header::WWW_AUTHENTICATE,
+                HeaderValue::from_static("Basic realm=\"introspect\""),
+            );
+        }
+        response.extensions_mut().insert(problem);
+        response
+    }
+}
+
+/// RFC 6749 error body of the OAuth endpoints, also sent to clients as query
+/// parameters of their redirect URI.
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct OAuthErrorResponse {
+    pub error: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_description: Option<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum OAuthError {
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("Invalid client")]
+    InvalidClient,
+    #[error("Invalid grant: {0}")]
+    InvalidGrant(String),
+    #[error("Unsupported grant type")]
+    UnsupportedGrantType,
+    #[error("Unsupported response type")]
+    UnsupportedResponseType,
+    #[error("Invalid scope: {0}")]
+    InvalidScope(String),
+    #[error("Access denied")]
+    AccessDenied,
+    #[error("Login required")]
+    LoginRequired,
+    #[error("Consent required")]
+    ConsentRequired,
+    #[error("Server error")]
+    ServerError(#[source] Report),
+}
+
+impl OAuthError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            OAuthError::InvalidRequest(_) => "invalid_request",
+            OAuthError::InvalidClient => "invalid_client",
+            OAuthError::InvalidGrant(_) => "invalid_grant",
+            OAuthError::UnsupportedGrantType => "unsupported_grant_type",
+            OAuthError::UnsupportedResponseType => "unsupported_response_type",
+            OAuthError::InvalidScope(_) => "invalid_scope",
+            OAuthError::AccessDenied => "access_denied",
+            OAuthError::LoginRequired => "login_required",
+            OAuthError::ConsentRequired => "consent_required",
+            OAuthError::ServerError(_) => "server_error",
+        }
+    }
+
+    pub fn to_response(&self) -> OAuthErrorResponse {
+        let description = match self {
+            OAuthError::InvalidRequest(reason)
+            | OAuthError::InvalidGrant(reason)
+            | OAuthError::InvalidScope(reason) => Some(reason.clone()),
+            _ => None,
+        };
+        OAuthErrorResponse {
+            error: self.code().to_owned(),
+            error_description: description,
+        }
+    }
+}
+
+impl IntoResponse for OAuthError {
+    fn into_response(self) -> Response {
+        tracing::error!(name: "error", "{}", error_report(&self));
+        let body = Json(self.to_response());
+        match self {
+            OAuthError::InvalidClient => {
+                let challenge = [(header::WWW_AUTHENTICATE, "Basic realm=\"token\"")];
+                (StatusCode::UNAUTHORIZED, challenge, body).into_response()
+            }
+            OAuthError::ServerError(_) => (StatusCode::INTERNAL_SERVER_ERROR, body).into_response(),
+            _ => (StatusCode::BAD_REQUEST, body).into_response(),
+        }
     }
 }
@@ -0,0 +1,62 @@
+use chrono::{DateTime, Duration, Utc};
+
+use super::{Email, FieldError, Role, generate_secret, hash_secret};
+
+#[cfg(test)]
+mod tests;
+
+pub const DEFAULT_INVITATION_TTL_DAYS: i64 = 7;
+const MAX_INVITATION_TTL_DAYS: i64 = 30;
+
+/// An emailed invitation, either to sign up when open registration is off or
+/// to join an organization. Only a hash of its token is kept, the link is the
+/// one place the token exists.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Invitation {
+    pub token_hash: String,
+    pub email: Email,
+    /// Set for invitations to join an organization.
+    pub organization_id: Option<String>,
+    /// Given to the account created with the invitation.
+    pub role: Option<Role>,
+    /// None when an admin used the static API key.
+    pub invited_by: Option<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Invitation {
+    /// A new invitation with the token to send.
+    pub fn new(
+        email: Email,
+        invited_by: Option<String>,
+        ttl_days: i64,
+    ) -> Result<(Self, String), FieldError> {
+        if !(1..=MAX_INVITATION_TTL_DAYS).contains(&ttl_days) {
+            return Err(FieldError::new(
+                "expiresInDays",
+                "out_of_range",
+                format!("An invitation expires in 1 to {MAX_INVITATION_TTL_DAYS} days"),
+            ));
+        }
+        let token = generate_secret();
+        let invitation = Self {
+            token_hash: hash_secret(&token),
+            email,
+            organization_id: None,
+            role: None,
+            invited_by,
+            expires_at: Utc::now() + Duration::days(ttl_days),
+        };
+        Ok((invitation, token))
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+
+    /// Whether it lets `email` sign up, invitations to an organization are
+    /// accepted at its own route.
+    pub fn allows_signup(&self, email: &Email) -> bool {
+        self.organization_id.is_none() && !self.is_expired() && &self.email == email
+    }
+}
@@ -0,0 +1,50 @@
+use super::*;
+
+fn email(email: &str) -> Email {
+    Email::parse(email).unwrap()
+}
+
+fn invite(ttl_days: i64) -> Result<(Invitation, String), FieldError> {
+    Invitation::new(email("a@b.com"), Some("admin@b.com".to_owned()), ttl_days)
+}
+
+#[test]
+fn test_new_invitation() {
+    let (invitation, token) = invite(DEFAULT_INVITATION_TTL_DAYS).unwrap();
+    assert_eq!(invitation.token_hash, hash_secret(&token));
+    assert_eq!(invitation.organization_id, None);
+    assert_eq!(invitation.role, None);
+    assert!(!invitation.is_expired());
+
+    assert!(invite(0).is_err());
+    assert!(invite(MAX_INVITATION_TTL_DAYS + 1).is_err());
+}
+
+#[test]
+fn test_expired_invitation() {
+    let (invitation, _) = invite(1).unwrap();
+    let expired = Invitation {
+        expires_at: Utc::now() - Duration::seconds(1),
+        ..invitation
+    };
+    assert!(expired.is_expired());
+}
+
+#[test]
+fn test_invitation_allows_signup() {
+    let (invitation, _) = invite(1).unwrap();
+    assert!(invitation.allows_signup(&email("a@b.com")));
+    assert!(!invitation.allows_signup(&email("c@d.com")));
+
+    let to_organization = Invitation {
+        organization_id: Some("org".to_owned()),
+        ..invitation.clone()
+    };
+    assert!(!to_organization.allows_signup(&email("a@b.com")));
+
+    let expired = Invitation {
+        expires_at: Utc::now() - Duration::seconds(1),
+        ..invitation
+    };
+    assert!(!expired.allows_signup(&email("a@b.com")));
+}
@@ -0,0 +1,200 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Duration, Utc};
+use ring::digest::{SHA256, digest};
+use url::Url;
+use uuid::Uuid;
+
+use super::{FieldError, UserKey};
+
+#[cfg(test)]
+mod tests;
+
+pub const OPENID_SCOPE: &str = "openid";
+/// The scopes clients can ask for, `profile` adds the name and locale to the
+/// ID token and userinfo, `email` the email and whether it is verified.
+pub const SUPPORTED_SCOPES: [&str; 3] = [OPENID_SCOPE, "profile", "email"];
+
+const MIN_CODE_VERIFIER_LENGTH: usize = 43;
+const MAX_CODE_VERIFIER_LENGTH: usize = 128;
+
+/// A random, URL-safe secret, e.g. an authorization code or a client secret.
+pub fn generate_secret() -> String {
+    URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>())
+}
+
+/// Client secrets and sign-in tokens are random, a fast hash is enough to keep them out of the
+/// database.
+pub fn hash_secret(secret: &str) -> String {
+    URL_SAFE_NO_PAD.encode(digest(&SHA256, secret.as_bytes()))
+}
+
+/// An application using the service as its identity provider.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OAuthClient {
+    pub client_id: String,
+    pub name: String,
+    /// Exact URIs the user can be sent back to.
+    pub redirect_uris: Vec<String>,
+    /// Hash of the secret of confidential clients, public clients (SPAs,
+    /// mobile apps) rely on PKCE alone.
+    pub secret_hash: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl OAuthClient {
+    /// A new client with the secret to hand out, `None` for public clients.
+    pub fn register(
+        name: String,
+        redirect_uris: Vec<String>,
+        confidential: bool,
+    ) -> Result<(Self, Option<String>), FieldError> {
+        let name = name.trim().to_owned();
+        if name.is_empty() {
+            return Err(FieldError::new(
+                "name",
+                "required",
+                "The client name is empty",
+            ));
+        }
+        if redirect_uris.is_empty() {
+            return Err(FieldError::new(
+                "redirectUris",
+                "required",
+                "At least one redirect URI is required",
+            ));
+        }
+        for uri in &redirect_uris {
+            validate_redirect_uri(uri)
+                .map_err(|e| FieldError::new("redirectUris", "invalid_redirect_uri", e))?;
+        }
+
+        let secret = confidential.then(generate_secret);
+        let client = Self {
+            client_id: Uuid::new_v4().to_string(),
+            name,
+            redirect_uris,
+            secret_hash: secret.as_deref().map(hash_secret),
+            created_at: Utc::now(),
+        };
+        Ok((client, secret))
+    }
+
+    pub fn allows_redirect_uri(&self, uri: &str) -> bool {
+        self.redirect_uris.iter().any(|allowed| allowed == uri)
+    }
+
+    pub fn is_confidential(&self) -> bool {
+        self.secret_hash.is_some()
+    }
+
+    /// Comparing hashes leaks nothing useful through timing.
+    pub fn verify_secret(&self, secret: &str) -> bool {
+        self.secret_hash.as_deref() == Some(hash_secret(secret).as_str())
+    }
+}
+
+fn validate_redirect_uri(uri: &str) -> Result<(), String> {
+    let url = Url::parse(uri).map_err(|_| format!("Invalid redirect URI `{uri}`"))?;
+    if url.fragment().is_some() {
+        return Err(format!("Redirect URI `{uri}` has a fragment"));
+    }
+    // plain http only for local development
+    let local = matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"));
+    if url.scheme() != "https" && !(url.scheme() == "http" && local) {
+        return Err(format!("Redirect URI `{uri}` must use https"));
+    }
+    Ok(())
+}
+
+/// Parses a space-separated `scope`, which must ask for `openid` and only
+/// for supported scopes. The result is sorted and deduplicated.
+pub fn parse_scopes(scope: &str) -> Result<Vec<String>, String> {
+    let mut scopes: Vec<String> = scope.split_whitespace().map(str::to_owned).collect();
+    scopes.sort();
+    scopes.dedup();
+    if let Some(unknown) = scopes
+        .iter()
+        .find(|scope| !SUPPORTED_SCOPES.contains(&scope.as_str()))
+    {
+        return Err(format!("Unsupported scope `{unknown}`"));
+    }
+    if !scopes.iter().any(|scope| scope == OPENID_SCOPE) {
+        return Err(format!("The `{OPENID_SCOPE}` scope is required"));
+    }
+    Ok(scopes)
+}
+
+/// Checks a PKCE `code_verifier` against the `S256` challenge of the
+/// authorization request.
+pub fn verify_pkce(code_verifier: &str, code_challenge: &str) -> bool {
+    let valid_verifier = (MIN_CODE_VERIFIER_LENGTH..=MAX_CODE_VERIFIER_LENGTH)
+        .contains(&code_verifier.len())
+        && code_verifier
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~'));
+    valid_verifier && pkce_challenge(code_verifier) == code_challenge
+}
+
+/// The S256 challenge of a PKCE code verifier.
+pub fn pkce_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(digest(&SHA256, code_verifier.as_bytes()))
+}
+
+/// A single-use code handed to a client after the user authenticated and
+/// consented, exchanged at `/token` for the tokens.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthorizationCode {
+    pub code: String,
+    pub client_id: String,
+    pub user_email: String,
+    pub tenant_id: Option<String>,
+    pub redirect_uri: String,
+    pub scopes: Vec<String>,
+    pub nonce: Option<String>,
+    pub code_challenge: String,
+    /// When the user logged in, for the `auth_time` claim.
+    pub auth_time: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl AuthorizationCode {
+    pub fn user(&self) -> UserKey {
+        UserKey::new(self.tenant_id.as_deref(), &self.user_email)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
+
+/// What an authorization request asks for, once validated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthorizationGrant {
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub scopes: Vec<String>,
+    pub nonce: Option<String>,
+    pub code_challenge: String,
+}
+
+impl AuthorizationGrant {
+    pub fn issue_code(
+        self,
+        user: &UserKey,
+        auth_time: DateTime<Utc>,
+        ttl: Duration,
+    ) -> AuthorizationCode {
+        AuthorizationCode {
+            code: generate_secret(),
+            client_id: self.client_id,
+            user_email: user.email.clone(),
+            tenant_id: user.tenant_id.clone(),
+            redirect_uri: self.redirect_uri,
+            scopes: self.scopes,
+            nonce: self.nonce,
+            code_challenge: self.code_challenge,
+            auth_time,
+            expires_at: Utc::now() + ttl,
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use super::*;
+
+#[test]
+fn scopes_require_openid_and_known_values() {
+    assert_eq!(
+        parse_scopes("email openid email"),
+        Ok(vec!["email".to_owned(), "openid".to_owned()])
+    );
+    assert!(parse_scopes("email profile").is_err());
+    assert!(parse_scopes("openid admin").is_err());
+    assert!(parse_scopes("").is_err());
+}
+
+#[test]
+fn pkce_accepts_only_the_matching_verifier() {
+    // example of RFC 7636, appendix B
+    let verifier = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+    let challenge = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+    assert!(verify_pkce(verifier, challenge));
+    assert!(!verify_pkce(&verifier.replace('d', "e"), challenge));
+    assert!(!verify_pkce("too-short", challenge));
+}
+
+#[test]
+fn register_validates_redirect_uris() {
+    let uris = |uris: &[&str]| uris.iter().map(|uri| uri.to_string()).collect::<Vec<_>>();
+
+    assert!(
+        OAuthClient::register(
+            "App".to_owned(),
+            uris(&["https://app.example.com/cb"]),
+            false
+        )
+        .is_ok()
+    );
+    assert!(
+        OAuthClient::register("App".to_owned(), uris(&["http://localhost:8000/cb"]), false).is_ok()
+    );
+    assert!(
+        OAuthClient::register(
+            "App".to_owned(),
+            uris(&["http://app.example.com/cb"]),
+            false
+        )
+        .is_err()
+    );
+    assert!(
+        OAuthClient::register(
+            "App".to_owned(),
+            uris(&["https://app.example.com/cb#x"]),
+            false
+        )
+        .is_err()
+    );
+    assert!(OAuthClient::register("App".to_owned(), uris(&["not a uri"]), false).is_err());
+    assert!(OAuthClient::register("App".to_owned(), vec![], false).is_err());
+    assert!(
+        OAuthClient::register(" ".to_owned(), uris(&["https://app.example.com/cb"]), false)
+            .is_err()
+    );
+}
+
+#[test]
+fn confidential_clients_get_a_secret() {
+    let redirect_uris = vec!["https://app.example.com/cb".to_owned()];
+    let (client, secret) = OAuthClient::register("App".to_owned(), redirect_uris, true).unwrap();
+    let secret = secret.expect("No secret for a confidential client");
+
+    assert!(client.is_confidential());
+    assert!(client.verify_secret(&secret));
+    assert!(!client.verify_secret("wrong"));
+    assert!(client.allows_redirect_uri("https://app.example.com/cb"));
+    assert!(!client.allows_redirect_uri("https://app.example.com/cb/other"));
+
+    let redirect_uris = vec!["https://app.example.com/cb".to_owned()];
+    let (public, secret) = OAuthClient::register("App".to_owned(), redirect_uris, false).unwrap();
+    assert_eq!(secret, None);
+    assert!(!public.verify_secret(""));
+}
@@ -0,0 +1,100 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::{FieldError, PasswordRule};
+
+#[cfg(test)]
+mod tests;
+
+const MAX_NAME_LENGTH: usize = 100;
+/// The default password policy already asks for 8 characters.
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+const MAX_PASSWORD_LENGTH: usize = 128;
+
+/// Rules an organization sets for its members on top of the service's own.
+/// Of the password settings, only the minimum length can be raised per
+/// organization; the character classes, strength, maximum length and breach
+/// check stay those of `auth.password` for every account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TenantSettings {
+    /// Members are asked for a 2FA code at every login, whatever their own
+    /// setting.
+    pub require_2fa: bool,
+    /// Applies to passwords chosen after joining.
+    pub min_password_length: usize,
+}
+
+impl Default for TenantSettings {
+    fn default() -> Self {
+        Self {
+            require_2fa: false,
+            min_password_length: MIN_PASSWORD_LENGTH,
+        }
+    }
+}
+
+impl TenantSettings {
+    pub fn new(require_2fa: bool, min_password_length: usize) -> Result<Self, FieldError> {
+        if !(MIN_PASSWORD_LENGTH..=MAX_PASSWORD_LENGTH).contains(&min_password_length) {
+            return Err(FieldError::new(
+                "minPasswordLength",
+                "out_of_range",
+                format!(
+                    "The minimum password length is between {MIN_PASSWORD_LENGTH} and {MAX_PASSWORD_LENGTH}"
+                ),
+            ));
+        }
+        Ok(Self {
+            require_2fa,
+            min_password_length,
+        })
+    }
+
+    pub fn check_password(&self, password: &str) -> Result<(), PasswordRule> {
+        if password.chars().count() < self.min_password_length {
+            return Err(PasswordRule::TooShort(self.min_password_length));
+        }
+        Ok(())
+    }
+}
+
+/// A tenant, grouping accounts under its own settings. Each member has an
+/// account of their own in it, apart from their other accounts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Organization {
+    pub id: String,
+    pub name: String,
+    /// The user who created it, the only one who can invite members and
+    /// change its settings besides admins.
+    pub owner_email: String,
+    pub settings: TenantSettings,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Organization {
+    pub fn create(
+        name: String,
+        owner_email: String,
+        settings: TenantSettings,
+    ) -> Result<Self, FieldError> {
+        let name = name.trim().to_owned();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+            return Err(FieldError::new(
+                "name",
+                "invalid_length",
+                format!("The organization name has 1 to {MAX_NAME_LENGTH} characters"),
+            ));
+        }
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            owner_email,
+            settings,
+            created_at: Utc::now(),
+        })
+    }
+
+    pub fn is_owner(&self, email: &str) -> bool {
+        self.owner_email == email
+    }
+}
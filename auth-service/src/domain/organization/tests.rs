@@ -0,0 +1,39 @@
+use super::*;
+
+#[test]
+fn test_tenant_settings() {
+    assert_eq!(
+        TenantSettings::new(true, 12).unwrap().min_password_length,
+        12
+    );
+    assert!(TenantSettings::new(false, MIN_PASSWORD_LENGTH - 1).is_err());
+    assert!(TenantSettings::new(false, MAX_PASSWORD_LENGTH + 1).is_err());
+    assert_eq!(
+        TenantSettings::default(),
+        TenantSettings::new(false, MIN_PASSWORD_LENGTH).unwrap()
+    );
+}
+
+#[test]
+fn test_check_password_counts_characters() {
+    let settings = TenantSettings::new(false, 10).unwrap();
+    assert!(settings.check_password("Kx7!vTq2Lm").is_ok());
+    assert!(settings.check_password("Passwo1!").is_err());
+    // ten characters, more bytes
+    assert!(settings.check_password("Pässwörd1!").is_ok());
+    assert!(settings.check_password("Pässwö1!").is_err());
+}
+
+#[test]
+fn test_create_organization() {
+    let settings = TenantSettings::default();
+    let organization =
+        Organization::create(" Acme ".to_owned(), "a@b.com".to_owned(), settings.clone()).unwrap();
+    assert_eq!(organization.name, "Acme");
+    assert!(organization.is_owner("a@b.com"));
+    assert!(!organization.is_owner("c@d.com"));
+
+    assert!(Organization::create(" ".to_owned(), "a@b.com".to_owned(), settings.clone()).is_err());
+    let long_name = "a".repeat(MAX_NAME_LENGTH + 1);
+    assert!(Organization::create(long_name, "a@b.com".to_owned(), settings).is_err());
+}
@@ -1,44 +1,12 @@
-use validator::{Validate, ValidationError, ValidationErrors};
+use rand::prelude::*;
+
+use crate::domain::{PasswordPolicy, PasswordRule};
 
 #[cfg(test)]
 mod tests;
 
-fn validate_password_strength(password: &str) -> Result<(), ValidationError> {
-    if password.len() < 8 {
-        return Err(ValidationError::new(
-            "Password must be at least 8 characters long.",
-        ));
-    }
-    if !password.chars().any(|c| c.is_ascii_uppercase()) {
-        return Err(ValidationError::new(
-            "Password must contain at least one uppercase letter.",
-        ));
-    }
-    if !password.chars().any(|c| c.is_ascii_lowercase()) {
-        return Err(ValidationError::new(
-            "Password must contain at least one lowercase letter.",
-        ));
-    }
-    if !password.chars().any(|c| c.is_ascii_digit()) {
-        return Err(ValidationError::new(
-            "Password must contain at least one digit.",
-        ));
-    }
-    if !password
-        .chars()
-        .any(|c| "!@#$%^&*()_+=|[]{{}};:'\",.<>/?~-".contains(c))
-    {
-        return Err(ValidationError::new(
-            "Password must contain at least one special character.",
-        ));
-    }
-
-    Ok(())
-}
-
-#[derive(Debug, Validate, Clone, Eq, Hash, PartialEq)]
+#[derive(Debug, Clone, Eq, Hash, PartialEq)]
 pub struct Password {
-    #[validate(custom(function = "validate_password_strength"))]
     pub password: String,
 }
 
@@ -49,12 +17,11 @@ impl AsRef<str> for Password {
 }
 
 impl Password {
-    pub fn parse(password: &str) -> Result<Self, ValidationErrors> {
-        let password = Self {
+    pub fn parse(password: &str, policy: &PasswordPolicy) -> Result<Self, PasswordRule> {
+        policy.check(password)?;
+        Ok(Self {
             password: password.to_owned(),
-        };
-        password.validate()?;
-        Ok(password)
+        })
     }
 
     pub fn new_no_validation(password: String) -> Self {
@@ -63,4 +30,27 @@ impl Password {
         // database it has been validated already.
         Self { password }
     }
+
+    /// A random password passing the default policy, e.g. to lock out the
+    /// old password of an account reset by an admin.
+    pub fn generate() -> Self {
+        const CLASSES: [&[u8]; 4] = [
+            b"ABCDEFGHJKLMNPQRSTUVWXYZ",
+            b"abcdefghijkmnopqrstuvwxyz",
+            b"23456789",
+            b"!@#$%^&*-_=+?",
+        ];
+        let mut rng = rand::rng();
+        let alphabet = CLASSES.concat();
+        // one character of each class, then random ones
+        let mut password: Vec<u8> = CLASSES
+            .iter()
+            .filter_map(|class| class.choose(&mut rng).copied())
+            .collect();
+        password.extend((0..16).filter_map(|_| alphabet.choose(&mut rng).copied()));
+        password.shuffle(&mut rng);
+        Self {
+            password: String::from_utf8(password).expect("The alphabet is ASCII"),
+        }
+    }
 }
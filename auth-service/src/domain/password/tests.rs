@@ -1,4 +1,5 @@
 use super::*;
+use crate::domain::PasswordPolicy;
 
 // TODO: try
 // fake = "=2.3.0"
@@ -8,12 +9,12 @@ use super::*;
 #[test]
 fn valid_password() {
     let test_cases = [
-        "Password1!",
+        "Kx7!vTq2Lm",
         "LongPasswordWithNumb3rAndSpeci@lCharacter",
         "VeryStrongPassword!1",
     ];
     for tc in test_cases {
-        let result = Password::parse(tc);
+        let result = Password::parse(tc, &PasswordPolicy::default());
         assert!(result.is_ok(), "failed for test case: {}", tc);
     }
 }
@@ -28,7 +29,21 @@ fn invalid_password() {
         "123456",
     ];
     for tc in test_cases {
-        let result = Password::parse(tc);
+        let result = Password::parse(tc, &PasswordPolicy::default());
         assert!(result.is_err(), "failed for test case: {}", tc);
     }
 }
+
+#[test]
+fn generated_password_passes_validation() {
+    for _ in 0..100 {
+        let password = Password::generate();
+        assert_eq!(password.as_ref().len(), 20);
+        assert!(
+            Password::parse(password.as_ref(), &PasswordPolicy::default()).is_ok(),
+            "failed for: {}",
+            password.as_ref()
+        );
+    }
+    assert_ne!(Password::generate(), Password::generate());
+}
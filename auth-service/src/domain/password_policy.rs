@@ -0,0 +1,194 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+use ring::digest::{SHA1_FOR_LEGACY_USE_ONLY, digest};
+use thiserror::Error;
+
+use crate::domain::estimate_strength;
+use crate::settings::PasswordSettings;
+
+#[cfg(test)]
+mod tests;
+
+/// A hashed common-password denylist: the SHA-1 of every line of
+/// `common_passwords.txt`, so the check works offline.
+const BUNDLED_BREACHED_PASSWORDS: &str = include_str!("../../resources/breached_passwords.txt");
+/// As the Have I Been Pwned range API, which only ever sees these first
+/// characters of a hash.
+const RANGE_PREFIX_LENGTH: usize = 5;
+
+static BUNDLED: LazyLock<BreachedPasswords> = LazyLock::new(|| {
+    let mut breached = BreachedPasswords::default();
+    breached
+        .extend_from(BUNDLED_BREACHED_PASSWORDS)
+        .expect("The bundled breached passwords are valid");
+    breached
+});
+
+/// The first rule a password breaks, its code is sent back to the client.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PasswordRule {
+    #[error("Password must be at least {0} characters long.")]
+    TooShort(usize),
+    #[error("Password must be at most {0} characters long.")]
+    TooLong(usize),
+    #[error("Password must contain at least one uppercase letter.")]
+    MissingUppercase,
+    #[error("Password must contain at least one lowercase letter.")]
+    MissingLowercase,
+    #[error("Password must contain at least one digit.")]
+    MissingDigit,
+    #[error("Password must contain at least one special character.")]
+    MissingSpecial,
+    #[error("Password has appeared in a data breach.")]
+    Breached,
+    #[error("Password is too easy to guess.")]
+    TooWeak,
+}
+
+impl PasswordRule {
+    pub fn code(&self) -> &'static str {
+        match self {
+            PasswordRule::TooShort(_) => "too_short",
+            PasswordRule::TooLong(_) => "too_long",
+            PasswordRule::MissingUppercase => "missing_uppercase",
+            PasswordRule::MissingLowercase => "missing_lowercase",
+            PasswordRule::MissingDigit => "missing_digit",
+            PasswordRule::MissingSpecial => "missing_special",
+            PasswordRule::Breached => "breached",
+            PasswordRule::TooWeak => "too_weak",
+        }
+    }
+}
+
+/// Breached passwords by k-anonymity range: the SHA-1 suffixes sharing each
+/// 5 character prefix, so a lookup compares the password with its range only.
+#[derive(Debug, Clone, Default)]
+pub struct BreachedPasswords {
+    ranges: HashMap<String, HashSet<String>>,
+}
+
+impl BreachedPasswords {
+    /// Adds a dataset of uppercase SHA-1 hashes, one per line optionally
+    /// followed by `:count`.
+    pub fn extend_from(&mut self, dataset: &str) -> Result<(), String> {
+        for (number, line) in dataset.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let hash = line.split_once(':').map_or(line, |(hash, _)| hash);
+            if hash.len() != 40 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("Line {} is not a SHA-1 hash", number + 1));
+            }
+            let hash = hash.to_ascii_uppercase();
+            let (prefix, suffix) = hash.split_at(RANGE_PREFIX_LENGTH);
+            self.ranges
+                .entry(prefix.to_owned())
+                .or_default()
+                .insert(suffix.to_owned());
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, password: &str) -> bool {
+        let hash = hex_upper(digest(&SHA1_FOR_LEGACY_USE_ONLY, password.as_bytes()).as_ref());
+        let (prefix, suffix) = hash.split_at(RANGE_PREFIX_LENGTH);
+        self.ranges
+            .get(prefix)
+            .is_some_and(|range| range.contains(suffix))
+    }
+
+    pub fn len(&self) -> usize {
+        self.ranges.values().map(HashSet::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+}
+
+fn hex_upper(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02X}")).collect()
+}
+
+/// The rules of `auth.password`, checked on the passwords users choose.
+/// Lengths and character classes are counted in Unicode characters.
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    settings: PasswordSettings,
+    breached: BreachedPasswords,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            settings: PasswordSettings::default(),
+            breached: BUNDLED.clone(),
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Reads `breached_passwords_file` on top of the bundled dataset.
+    pub fn from_settings(settings: &PasswordSettings) -> Result<Self, String> {
+        let mut breached = BreachedPasswords::default();
+        if settings.check_breached {
+            breached = BUNDLED.clone();
+            if !settings.breached_passwords_file.is_empty() {
+                let path = &settings.breached_passwords_file;
+                let dataset = std::fs::read_to_string(path)
+                    .map_err(|e| format!("Failed to read {path}: {e}"))?;
+                breached
+                    .extend_from(&dataset)
+                    .map_err(|e| format!("{path}: {e}"))?;
+            }
+        }
+        Ok(Self {
+            settings: settings.clone(),
+            breached,
+        })
+    }
+
+    pub fn check(&self, password: &str) -> Result<(), PasswordRule> {
+        let settings = &self.settings;
+        let length = password.chars().count();
+        if length < settings.min_length {
+            return Err(PasswordRule::TooShort(settings.min_length));
+        }
+        if length > settings.max_length {
+            return Err(PasswordRule::TooLong(settings.max_length));
+        }
+        let passphrase = settings.passphrase_length > 0 && length >= settings.passphrase_length;
+        if !passphrase {
+            self.check_character_classes(password)?;
+        }
+        if settings.check_breached && self.breached.contains(password) {
+            return Err(PasswordRule::Breached);
+        }
+        if estimate_strength(password).score < settings.min_strength {
+            return Err(PasswordRule::TooWeak);
+        }
+        Ok(())
+    }
+
+    fn check_character_classes(&self, password: &str) -> Result<(), PasswordRule> {
+        let settings = &self.settings;
+        // letters of scripts without case, e.g. kana, count as both
+        let caseless = |c: char| c.is_alphabetic() && !c.is_uppercase() && !c.is_lowercase();
+        let has = |class: &dyn Fn(char) -> bool| password.chars().any(class);
+        if settings.require_uppercase && !has(&|c| c.is_uppercase() || caseless(c)) {
+            return Err(PasswordRule::MissingUppercase);
+        }
+        if settings.require_lowercase && !has(&|c| c.is_lowercase() || caseless(c)) {
+            return Err(PasswordRule::MissingLowercase);
+        }
+        if settings.require_digit && !has(&char::is_numeric) {
+            return Err(PasswordRule::MissingDigit);
+        }
+        if settings.require_special && !has(&|c| !c.is_alphanumeric() && !c.is_whitespace()) {
+            return Err(PasswordRule::MissingSpecial);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+use super::*;
+
+fn policy(settings: PasswordSettings) -> PasswordPolicy {
+    PasswordPolicy::from_settings(&settings).unwrap()
+}
+
+#[test]
+fn default_policy_reports_the_failing_rule() {
+    let policy = PasswordPolicy::default();
+    let test_cases = [
+        ("Small1!", PasswordRule::TooShort(8)),
+        ("no_capital_1", PasswordRule::MissingUppercase),
+        ("NO_LOWER_1", PasswordRule::MissingLowercase),
+        ("NoNumber!", PasswordRule::MissingDigit),
+        ("NoSpecial1", PasswordRule::MissingSpecial),
+        ("P@ssw0rd1", PasswordRule::Breached),
+        ("Password1!", PasswordRule::Breached),
+        ("Summer2024!", PasswordRule::Breached),
+    ];
+    for (password, rule) in test_cases {
+        assert_eq!(policy.check(password), Err(rule), "failed for: {password}");
+    }
+    assert!(policy.check(&"Aa1!".repeat(33)).is_err());
+    assert!(policy.check("Kx7!vTq2Lm").is_ok());
+}
+
+#[test]
+fn lengths_and_classes_count_unicode_characters() {
+    let policy = PasswordPolicy::default();
+    // 8 characters but 10 bytes
+    assert!(policy.check("Pässwö1!").is_ok());
+    assert!(policy.check("Ünïcödé9¿").is_ok());
+    // kana have no case
+    assert!(policy.check("パスワードです9!").is_ok());
+    assert_eq!(
+        policy.check("пароль1!"),
+        Err(PasswordRule::MissingUppercase)
+    );
+}
+
+#[test]
+fn long_passphrases_skip_the_character_classes() {
+    let policy = PasswordPolicy::default();
+    assert!(policy.check("ein langer satz über schnee").is_ok());
+    assert!(
+        policy
+            .check("とても長くて覚えやすい日本語のパスフレーズです")
+            .is_ok()
+    );
+    // still estimated
+    assert_eq!(
+        policy.check("aaaaaaaaaaaaaaaaaaaaaaaa"),
+        Err(PasswordRule::TooWeak)
+    );
+
+    let policy = self::policy(PasswordSettings {
+        passphrase_length: 0,
+        ..Default::default()
+    });
+    assert_eq!(
+        policy.check("ein langer satz über schnee"),
+        Err(PasswordRule::MissingUppercase)
+    );
+}
+
+#[test]
+fn rules_follow_the_settings() {
+    let policy = policy(PasswordSettings {
+        min_length: 4,
+        max_length: 12,
+        require_uppercase: false,
+        require_special: false,
+        min_strength: 0,
+        check_breached: false,
+        ..Default::default()
+    });
+    assert!(policy.check("abc1").is_ok());
+    assert!(policy.check("p@ssw0rd1").is_ok());
+    assert_eq!(
+        policy.check("abcdefghijk12"),
+        Err(PasswordRule::TooLong(12))
+    );
+    assert_eq!(policy.check("abcd"), Err(PasswordRule::MissingDigit));
+
+    let policy = self::policy(PasswordSettings {
+        min_strength: 4,
+        check_breached: false,
+        ..Default::default()
+    });
+    assert_eq!(policy.check("Password1!"), Err(PasswordRule::TooWeak));
+    assert!(policy.check("xK9#mQ2$vL7!wZ").is_ok());
+}
+
+#[test]
+fn breached_passwords_are_looked_up_by_range() {
+    let mut breached = BreachedPasswords::default();
+    // SHA-1 of "hunter2", in lowercase and with a count
+    let dataset = "f3bbbd66a63d4bf1747940578ec3d0103530e21d:1234\n\n";
+    breached.extend_from(dataset).unwrap();
+    assert_eq!(breached.len(), 1);
+    assert!(breached.contains("hunter2"));
+    assert!(!breached.contains("hunter3"));
+
+    assert!(breached.extend_from("not a hash").is_err());
+    assert!(!BUNDLED.is_empty());
+    assert!(BUNDLED.contains("123456"));
+}
+
+#[test]
+fn breached_passwords_file_is_loaded() {
+    let path = std::env::temp_dir().join(format!("breached-{}.txt", uuid::Uuid::new_v4()));
+    std::fs::write(&path, "F3BBBD66A63D4BF1747940578EC3D0103530E21D:3\n").unwrap();
+    let policy = policy(PasswordSettings {
+        min_length: 7,
+        require_uppercase: false,
+        require_special: false,
+        min_strength: 0,
+        breached_passwords_file: path.to_string_lossy().into_owned(),
+        ..Default::default()
+    });
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(policy.check("hunter2"), Err(PasswordRule::Breached));
+    assert_eq!(policy.check("trustno1"), Err(PasswordRule::Breached));
+
+    let missing = PasswordSettings {
+        breached_passwords_file: "/nonexistent/breached.txt".to_owned(),
+        ..Default::default()
+    };
+    assert!(PasswordPolicy::from_settings(&missing).is_err());
+}
@@ -0,0 +1,399 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+#[cfg(test)]
+mod tests;
+
+/// Common passwords and words, most common first, in lowercase. Leetspeak
+/// variants are found by folding the candidates, e.g. `p@$$w0rd`.
+const COMMON_WORDS: &[&str] = &[
+    "123456",
+    "password",
+    "12345678",
+    "qwerty",
+    "123456789",
+    "12345",
+    "1234",
+    "111111",
+    "1234567",
+    "dragon",
+    "123123",
+    "baseball",
+    "abc123",
+    "football",
+    "monkey",
+    "letmein",
+    "696969",
+    "shadow",
+    "master",
+    "666666",
+    "qwertyuiop",
+    "123321",
+    "mustang",
+    "1234567890",
+    "michael",
+    "654321",
+    "superman",
+    "1qaz2wsx",
+    "7777777",
+    "121212",
+    "000000",
+    "qazwsx",
+    "123qwe",
+    "killer",
+    "trustno1",
+    "jordan",
+    "jennifer",
+    "zxcvbnm",
+    "asdfgh",
+    "hunter",
+    "buster",
+    "soccer",
+    "harley",
+    "batman",
+    "andrew",
+    "tigger",
+    "sunshine",
+    "iloveyou",
+    "fuckyou",
+    "2000",
+    "charlie",
+    "robert",
+    "thomas",
+    "hockey",
+    "ranger",
+    "daniel",
+    "starwars",
+    "klaster",
+    "112233",
+    "george",
+    "computer",
+    "michelle",
+    "jessica",
+    "pepper",
+    "1111",
+    "zxcvbn",
+    "555555",
+    "11111111",
+    "131313",
+    "freedom",
+    "777777",
+    "pass",
+    "maggie",
+    "159753",
+    "aaaaaa",
+    "ginger",
+    "princess",
+    "joshua",
+    "cheese",
+    "amanda",
+    "summer",
+    "love",
+    "ashley",
+    "nicole",
+    "chelsea",
+    "biteme",
+    "matthew",
+    "access",
+    "yankees",
+    "987654321",
+    "dallas",
+    "austin",
+    "thunder",
+    "taylor",
+    "matrix",
+    "admin",
+    "welcome",
+    "login",
+    "secret",
+    "hello",
+    "winter",
+    "spring",
+    "autumn",
+    "test",
+    "guest",
+    "changeme",
+    "default",
+    "root",
+    "user",
+    "qwerty123",
+    "letmein1",
+    "password1",
+    "password123",
+    "welcome1",
+    "admin123",
+    "iloveyou1",
+    "monkey1",
+    "dragon1",
+    "abcd1234",
+    "aa123456",
+    "qwe123",
+    "azerty",
+    "qwertz",
+    "company",
+    "secure",
+    "strong",
+    "long",
+    "new",
+    "my",
+    "super",
+    "magic",
+    "lovely",
+    "angel",
+    "flower",
+    "happy",
+    "family",
+    "friend",
+    "money",
+    "orange",
+    "banana",
+    "apple",
+    "purple",
+    "silver",
+    "golden",
+    "diamond",
+    "internet",
+    "service",
+    "server",
+    "system",
+    "office",
+    "london",
+    "paris",
+    "berlin",
+];
+
+/// Rows of common keyboard layouts, runs along them are guessed early.
+const KEYBOARD_ROWS: [&str; 7] = [
+    "1234567890",
+    "qwertyuiop",
+    "asdfghjkl",
+    "zxcvbnm",
+    "azertyuiop",
+    "qwertzuiop",
+    "yxcvbnm",
+];
+
+/// As zxcvbn, a pattern inside a longer password costs at least this many
+/// guesses, the attacker doesn't know where it starts.
+const MIN_SUBMATCH_GUESSES: f64 = 50.0;
+const MIN_YEAR: u32 = 1900;
+const MAX_YEAR: u32 = 2099;
+
+/// The rows both ways.
+static KEYBOARD_RUNS: LazyLock<Vec<String>> = LazyLock::new(|| {
+    KEYBOARD_ROWS
+        .iter()
+        .flat_map(|row| [row.to_string(), row.chars().rev().collect()])
+        .collect()
+});
+
+static WORD_RANKS: LazyLock<HashMap<&'static str, usize>> = LazyLock::new(|| {
+    let mut ranks = HashMap::new();
+    for (rank, word) in COMMON_WORDS.iter().enumerate() {
+        ranks.entry(*word).or_insert(rank + 1);
+    }
+    ranks
+});
+
+static MAX_WORD_LENGTH: LazyLock<usize> = LazyLock::new(|| {
+    COMMON_WORDS
+        .iter()
+        .map(|word| word.chars().count())
+        .max()
+        .unwrap_or(0)
+});
+
+/// A zxcvbn-style estimate of how many guesses finding a password takes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PasswordStrength {
+    pub guesses_log10: f64,
+    /// From 0, guessed right away, to 4, out of reach of offline attacks.
+    pub score: u8,
+}
+
+impl PasswordStrength {
+    fn from_guesses_log10(guesses_log10: f64) -> Self {
+        let score = match guesses_log10 {
+            g if g < 3.0 => 0,
+            g if g < 6.0 => 1,
+            g if g < 8.0 => 2,
+            g if g < 10.0 => 3,
+            _ => 4,
+        };
+        Self {
+            guesses_log10,
+            score,
+        }
+    }
+}
+
+/// Splits the password into the cheapest patterns an attacker would try:
+/// common words, repeats, sequences, keyboard runs and years, the remaining
+/// characters being guessed one by one.
+pub fn estimate_strength(password: &str) -> PasswordStrength {
+    let chars: Vec<char> = password.chars().collect();
+    let folded: Vec<char> = chars.iter().map(|c| fold(*c)).collect();
+    let n = chars.len();
+
+    // cheapest (log10 guesses, pattern count) of the first i characters
+    let mut best = vec![(f64::INFINITY, 0usize); n + 1];
+    best[0] = (0.0, 0);
+    for start in 0..n {
+        let (prefix_guesses, prefix_patterns) = best[start];
+        let mut lowered = String::new();
+        let mut word = String::new();
+        let mut run = Run::default();
+        for end in start + 1..=n {
+            lowered.extend(chars[end - 1].to_lowercase());
+            word.push(folded[end - 1]);
+            run.extend(&chars[start..end], &lowered);
+            let guesses = if end - start == 1 {
+                Some(cardinality(chars[start]))
+            } else {
+                [
+                    run.repeat
+                        .then(|| cardinality(chars[start]) * (end - start) as f64),
+                    run.sequence
+                        .then(|| sequence_guesses(chars[start], end - start)),
+                    run.keyboard.then(|| 40.0 * (end - start) as f64),
+                    word_guesses(&chars[start..end], &lowered, &word),
+                    year_guesses(&lowered),
+                ]
+                .into_iter()
+                .flatten()
+                .reduce(f64::min)
+                .map(|guesses| {
+                    if end - start == n {
+                        guesses
+                    } else {
+                        guesses.max(MIN_SUBMATCH_GUESSES)
+                    }
+                })
+            };
+            if let Some(guesses) = guesses {
+                let candidate = prefix_guesses + guesses.log10();
+                if candidate < best[end].0 {
+                    best[end] = (candidate, prefix_patterns + 1);
+                }
+            }
+            if !run.any() && end - start >= *MAX_WORD_LENGTH {
+                break;
+            }
+        }
+    }
+
+    let (guesses_log10, patterns) = best[n];
+    // the order of the patterns is unknown too
+    let ordering: f64 = (1..=patterns).map(|k| (k as f64).log10()).sum();
+    PasswordStrength::from_guesses_log10(guesses_log10 + ordering)
+}
+
+/// Which patterns the characters seen so far still follow.
+struct Run {
+    repeat: bool,
+    sequence: bool,
+    keyboard: bool,
+}
+
+impl Default for Run {
+    fn default() -> Self {
+        Self {
+            repeat: true,
+            sequence: true,
+            keyboard: true,
+        }
+    }
+}
+
+impl Run {
+    fn extend(&mut self, chars: &[char], lowered: &str) {
+        let len = chars.len();
+        if len < 2 {
+            return;
+        }
+        let (last, before) = (chars[len - 1], chars[len - 2]);
+        self.repeat &= lowered.chars().all(|c| lowered.starts_with(c));
+        let step = |a: char, b: char| b as i64 - a as i64;
+        self.sequence &= matches!(step(chars[0], chars[1]), 1 | -1)
+            && step(before, last) == step(chars[0], chars[1]);
+        self.keyboard &= KEYBOARD_RUNS.iter().any(|row| row.contains(lowered));
+    }
+
+    fn any(&self) -> bool {
+        self.repeat || self.sequence || self.keyboard
+    }
+}
+
+fn fold(c: char) -> char {
+    match c {
+        '4' | '@' => 'a',
+        '3' => 'e',
+        '1' | '!' | '|' => 'i',
+        '0' => 'o',
+        '$' | '5' => 's',
+        '7' => 't',
+        _ => c.to_lowercase().next().unwrap_or(c),
+    }
+}
+
+/// How many characters an attacker tries at each position.
+fn cardinality(c: char) -> f64 {
+    if c.is_ascii_digit() {
+        10.0
+    } else if c.is_ascii_alphabetic() {
+        26.0
+    } else if c.is_ascii() {
+        33.0
+    } else {
+        100.0
+    }
+}
+
+fn sequence_guesses(first: char, len: usize) -> f64 {
+    let start = if matches!(first, 'a' | 'A' | 'z' | 'Z' | '0' | '1') {
+        4.0
+    } else {
+        cardinality(first)
+    };
+    start * len as f64
+}
+
+fn word_guesses(chars: &[char], lowered: &str, folded: &str) -> Option<f64> {
+    let (rank, leet) = match WORD_RANKS.get(lowered) {
+        Some(rank) => (*rank, 0),
+        None => {
+            let rank = WORD_RANKS.get(folded)?;
+            let substituted = lowered.chars().zip(folded.chars()).filter(|(a, b)| a != b);
+            (*rank, substituted.count())
+        }
+    };
+    Some(rank as f64 * case_variations(chars) * 2f64.powi(leet as i32))
+}
+
+/// As zxcvbn, capitalizing the first or every letter doubles the guesses,
+/// other mixes count every way of picking the uppercase letters.
+fn case_variations(chars: &[char]) -> f64 {
+    let upper = chars.iter().filter(|c| c.is_uppercase()).count();
+    let lower = chars.iter().filter(|c| c.is_lowercase()).count();
+    if upper == 0 {
+        return 1.0;
+    }
+    let first_only = upper == 1 && chars[0].is_uppercase();
+    if lower == 0 || first_only {
+        return 2.0;
+    }
+    (1..=upper.min(lower))
+        .map(|k| binomial(upper + lower, k))
+        .sum()
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    (1..=k).fold(1.0, |acc, i| acc * (n + 1 - i) as f64 / i as f64)
+}
+
+fn year_guesses(lowered: &str) -> Option<f64> {
+    let year: u32 = lowered.parse().ok().filter(|_| lowered.len() == 4)?;
+    (MIN_YEAR..=MAX_YEAR)
+        .contains(&year)
+        .then(|| f64::from(MAX_YEAR - MIN_YEAR + 1))
+}
@@ -0,0 +1,51 @@
+use super::*;
+
+#[test]
+fn common_passwords_are_weak() {
+    let test_cases = [
+        "password",
+        "P@ssw0rd",
+        "123456",
+        "qwerty123",
+        "aaaaaaaaaaaa",
+        "abcdefghijkl",
+        "asdfghjkl",
+        "1990",
+    ];
+    for tc in test_cases {
+        let strength = estimate_strength(tc);
+        assert_eq!(strength.score, 0, "failed for: {tc} ({strength:?})");
+    }
+}
+
+#[test]
+fn random_and_long_passwords_are_strong() {
+    let test_cases = [
+        "xK9#mQ2$vL7!",
+        "correct horse battery staple",
+        "ein langer Satz über Schnee",
+        "長い日本語のパスフレーズです",
+    ];
+    for tc in test_cases {
+        let strength = estimate_strength(tc);
+        assert_eq!(strength.score, 4, "failed for: {tc} ({strength:?})");
+    }
+}
+
+#[test]
+fn patterns_lower_the_estimate() {
+    let random = estimate_strength("Xq7!Rm2#");
+    for tc in ["Summer2024!", "Qwerty1!", "Aaaaaa1!"] {
+        let strength = estimate_strength(tc);
+        assert!(
+            strength.guesses_log10 < random.guesses_log10,
+            "failed for: {tc} ({strength:?})"
+        );
+    }
+    assert_eq!(estimate_strength("Password1!").score, 1);
+}
+
+#[test]
+fn empty_password_is_weakest() {
+    assert_eq!(estimate_strength("").score, 0);
+}
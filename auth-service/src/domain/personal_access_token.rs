@@ -0,0 +1,104 @@
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use super::{FieldError, UserKey, generate_secret, hash_secret, parse_service_scopes};
+
+#[cfg(test)]
+mod tests;
+
+const TOKEN_ID_PREFIX: &str = "pat_";
+const MAX_TTL_DAYS: i64 = 365;
+
+/// A long-lived token a user creates for their scripts, accepted by
+/// `/verify-token` with the scopes it was given. The token is `{id}.{secret}`,
+/// the id finds it, only a hash of the whole token is kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersonalAccessToken {
+    pub id: String,
+    pub user_email: String,
+    pub tenant_id: Option<String>,
+    pub name: String,
+    pub secret_hash: String,
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl PersonalAccessToken {
+    /// A new token with the token itself to hand out, it can't be shown again.
+    pub fn issue(
+        user: UserKey,
+        name: String,
+        scopes: &[String],
+        ttl_days: i64,
+    ) -> Result<(Self, String), FieldError> {
+        let name = name.trim().to_owned();
+        if name.is_empty() {
+            return Err(FieldError::new(
+                "name",
+                "required",
+                "The token name is empty",
+            ));
+        }
+        let scopes = parse_service_scopes(&scopes.join(" "))
+            .map_err(|e| FieldError::new("scopes", "invalid_scope", e))?;
+        if scopes.is_empty() {
+            return Err(FieldError::new(
+                "scopes",
+                "required",
+                "At least one scope is required",
+            ));
+        }
+        if !(1..=MAX_TTL_DAYS).contains(&ttl_days) {
+            return Err(FieldError::new(
+                "expiresInDays",
+                "out_of_range",
+                format!("Tokens expire in 1 to {MAX_TTL_DAYS} days"),
+            ));
+        }
+
+        let id = format!("{TOKEN_ID_PREFIX}{}", Uuid::new_v4().simple());
+        let token = format!("{id}.{}", generate_secret());
+        let created_at = Utc::now();
+        let personal_access_token = Self {
+            id,
+            user_email: user.email,
+            tenant_id: user.tenant_id,
+            name,
+            secret_hash: hash_secret(&token),
+            scopes,
+            created_at,
+            expires_at: created_at + Duration::days(ttl_days),
+            last_used_at: None,
+        };
+        Ok((personal_access_token, token))
+    }
+
+    pub fn user(&self) -> UserKey {
+        UserKey::new(self.tenant_id.as_deref(), &self.user_email)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+
+    /// Comparing hashes leaks nothing useful through timing.
+    pub fn verify(&self, token: &str) -> bool {
+        personal_access_token_id(token) == Some(self.id.as_str())
+            && hash_secret(token) == self.secret_hash
+    }
+}
+
+/// The id part of a personal access token, `None` when it isn't shaped like
+/// one, e.g. for a JWT.
+pub fn personal_access_token_id(token: &str) -> Option<&str> {
+    token
+        .split_once('.')
+        .map(|(id, _)| id)
+        .filter(|id| id.starts_with(TOKEN_ID_PREFIX))
+}
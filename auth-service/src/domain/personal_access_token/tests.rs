@@ -0,0 +1,86 @@
+use super::*;
+
+fn scopes(scopes: &[&str]) -> Vec<String> {
+    scopes.iter().map(|scope| scope.to_string()).collect()
+}
+
+fn issue(
+    name: &str,
+    scope: &[&str],
+    ttl_days: i64,
+) -> Result<(PersonalAccessToken, String), FieldError> {
+    PersonalAccessToken::issue(
+        UserKey::new(None, "user@example.com"),
+        name.to_owned(),
+        &scopes(scope),
+        ttl_days,
+    )
+}
+
+#[test]
+fn test_issue_personal_access_token() {
+    let (pat, token) = issue(" Deploy script ", &["repo:write", "repo:read"], 30).unwrap();
+
+    assert_eq!(pat.name, "Deploy script");
+    assert_eq!(pat.user_email, "user@example.com");
+    assert_eq!(pat.scopes, scopes(&["repo:read", "repo:write"]));
+    assert!(pat.id.starts_with("pat_"));
+    assert_eq!(personal_access_token_id(&token), Some(pat.id.as_str()));
+    assert_ne!(pat.secret_hash, token);
+    assert_eq!(pat.expires_at - pat.created_at, Duration::days(30));
+    assert!(!pat.is_expired());
+    assert!(pat.has_scope("repo:read"));
+    assert!(!pat.has_scope("repo"));
+}
+
+#[test]
+fn test_verify_personal_access_token() {
+    let (pat, token) = issue("script", &["repo"], 1).unwrap();
+    let (other, other_token) = issue("script", &["repo"], 1).unwrap();
+
+    assert!(pat.verify(&token));
+    assert!(!pat.verify(&other_token));
+    assert!(!pat.verify(&format!("{}.forged", pat.id)));
+    assert!(!other.verify(&token));
+}
+
+#[test]
+fn test_issue_rejects_invalid_tokens() {
+    let rejected = |result: Result<_, FieldError>| {
+        let error = result.unwrap_err();
+        (error.field, error.code)
+    };
+    let field = |field: &str, code: &str| (field.to_owned(), code.to_owned());
+    assert_eq!(
+        rejected(issue(" ", &["repo"], 1)),
+        field("name", "required")
+    );
+    assert_eq!(
+        rejected(issue("script", &[], 1)),
+        field("scopes", "required")
+    );
+    assert_eq!(
+        rejected(issue("script", &["openid"], 1)),
+        field("scopes", "invalid_scope")
+    );
+    assert_eq!(
+        rejected(issue("script", &["repo"], 0)),
+        field("expiresInDays", "out_of_range")
+    );
+    assert!(issue("script", &["repo"], MAX_TTL_DAYS + 1).is_err());
+}
+
+#[test]
+fn test_expired_personal_access_token() {
+    let (mut pat, _) = issue("script", &["repo"], 1).unwrap();
+    pat.expires_at = Utc::now() - Duration::seconds(1);
+    assert!(pat.is_expired());
+}
+
+#[test]
+fn test_personal_access_token_id() {
+    assert_eq!(personal_access_token_id("pat_abc.secret"), Some("pat_abc"));
+    assert_eq!(personal_access_token_id("ak_abc.secret"), None);
+    assert_eq!(personal_access_token_id("eyJhbGciOi.eyJzdWIi.sig"), None);
+    assert_eq!(personal_access_token_id("pat_abc"), None);
+}
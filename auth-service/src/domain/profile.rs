@@ -0,0 +1,103 @@
+use validator::{Validate, ValidationError, ValidationErrors};
+
+#[cfg(test)]
+mod tests;
+
+fn validate_display_name(name: &str) -> Result<(), ValidationError> {
+    if name.trim() != name || name.is_empty() {
+        return Err(ValidationError::new(
+            "Display name must not be empty or start/end with spaces.",
+        ));
+    }
+    if name.chars().count() > 64 {
+        return Err(ValidationError::new(
+            "Display name must be at most 64 characters long.",
+        ));
+    }
+    if name.chars().any(char::is_control) {
+        return Err(ValidationError::new(
+            "Display name must not contain control characters.",
+        ));
+    }
+    Ok(())
+}
+
+// BCP 47 shaped, e.g. "en", "pt-BR" or "zh-Hant-TW", without checking the
+// subtags against the registry.
+fn validate_locale(locale: &str) -> Result<(), ValidationError> {
+    let mut subtags = locale.split('-');
+    let language = subtags.next().unwrap_or_default();
+    let valid_language =
+        (2..=3).contains(&language.len()) && language.chars().all(|c| c.is_ascii_alphabetic());
+    let valid_subtags = subtags.all(|subtag| {
+        (2..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+    });
+    if !valid_language || !valid_subtags {
+        return Err(ValidationError::new(
+            "Locale must be a language tag such as en or pt-BR.",
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Validate, Clone, PartialEq)]
+pub struct DisplayName {
+    #[validate(custom(function = "validate_display_name"))]
+    pub display_name: String,
+}
+
+impl AsRef<str> for DisplayName {
+    fn as_ref(&self) -> &str {
+        self.display_name.as_str()
+    }
+}
+
+impl DisplayName {
+    pub fn parse(display_name: &str) -> Result<Self, ValidationErrors> {
+        let display_name = Self {
+            display_name: display_name.to_owned(),
+        };
+        display_name.validate()?;
+        Ok(display_name)
+    }
+
+    pub fn new_no_validation(display_name: String) -> Self {
+        // Use with care, since there is no validation, e.g. when reading
+        // from the database, where it has been validated already.
+        Self { display_name }
+    }
+}
+
+#[derive(Debug, Validate, Clone, PartialEq)]
+pub struct Locale {
+    #[validate(custom(function = "validate_locale"))]
+    pub locale: String,
+}
+
+impl AsRef<str> for Locale {
+    fn as_ref(&self) -> &str {
+        self.locale.as_str()
+    }
+}
+
+impl Locale {
+    pub fn parse(locale: &str) -> Result<Self, ValidationErrors> {
+        let locale = Self {
+            locale: locale.to_owned(),
+        };
+        locale.validate()?;
+        Ok(locale)
+    }
+
+    pub fn new_no_validation(locale: String) -> Self {
+        // Use with care, see DisplayName::new_no_validation.
+        Self { locale }
+    }
+}
+
+/// The fields of a user that the user can change through `PATCH /me`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UserProfile {
+    pub display_name: Option<DisplayName>,
+    pub locale: Option<Locale>,
+}
@@ -0,0 +1,41 @@
+use super::*;
+
+#[test]
+fn valid_display_name() {
+    let test_cases = ["Gabriel", "Ana Maria", "李小龍", &"a".repeat(64)];
+    for tc in test_cases {
+        assert!(DisplayName::parse(tc).is_ok(), "failed for: {}", tc);
+    }
+}
+
+#[test]
+fn invalid_display_name() {
+    let test_cases = ["", " Gabriel", "Gabriel ", "Gabr\niel", &"a".repeat(65)];
+    for tc in test_cases {
+        assert!(DisplayName::parse(tc).is_err(), "failed for: {}", tc);
+    }
+}
+
+#[test]
+fn valid_locale() {
+    let test_cases = ["en", "pt-BR", "zh-Hant-TW", "es-419"];
+    for tc in test_cases {
+        assert!(Locale::parse(tc).is_ok(), "failed for: {}", tc);
+    }
+}
+
+#[test]
+fn invalid_locale() {
+    let test_cases = [
+        "",
+        "e",
+        "english",
+        "pt_BR",
+        "pt-",
+        "en-B",
+        "en-US-toolongsubtag",
+    ];
+    for tc in test_cases {
+        assert!(Locale::parse(tc).is_err(), "failed for: {}", tc);
+    }
+}
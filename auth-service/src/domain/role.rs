@@ -0,0 +1,54 @@
+use validator::{Validate, ValidationError, ValidationErrors};
+
+#[cfg(test)]
+mod tests;
+
+/// The role allowed to manage other users.
+pub const ADMIN_ROLE: &str = "admin";
+
+// Roles travel in the JWT, so they are kept short and URL safe,
+// e.g. "admin" or "billing-reader".
+fn validate_role(role: &str) -> Result<(), ValidationError> {
+    let valid_chars = role
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    let starts_with_letter = role.starts_with(|c: char| c.is_ascii_lowercase());
+    if !(1..=32).contains(&role.len()) || !valid_chars || !starts_with_letter {
+        return Err(ValidationError::new(
+            "Role must be 1 to 32 lowercase letters, digits, '-' or '_', starting with a letter.",
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Validate, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Role {
+    #[validate(custom(function = "validate_role"))]
+    pub role: String,
+}
+
+impl AsRef<str> for Role {
+    fn as_ref(&self) -> &str {
+        self.role.as_str()
+    }
+}
+
+impl Role {
+    pub fn parse(role: &str) -> Result<Self, ValidationErrors> {
+        let role = Self {
+            role: role.to_owned(),
+        };
+        role.validate()?;
+        Ok(role)
+    }
+
+    pub fn new_no_validation(role: String) -> Self {
+        // Use with care, e.g. when reading from the database, where it has
+        // been validated already.
+        Self { role }
+    }
+
+    pub fn admin() -> Self {
+        Self::new_no_validation(ADMIN_ROLE.to_owned())
+    }
+}
@@ -0,0 +1,25 @@
+use super::*;
+
+#[test]
+fn valid_role() {
+    let test_cases = ["admin", "billing-reader", "support_2", &"a".repeat(32)];
+    for tc in test_cases {
+        assert!(Role::parse(tc).is_ok(), "failed for: {}", tc);
+    }
+}
+
+#[test]
+fn invalid_role() {
+    let test_cases = [
+        "",
+        "Admin",
+        "2fa-admin",
+        "-admin",
+        "admin role",
+        "admin:write",
+        &"a".repeat(33),
+    ];
+    for tc in test_cases {
+        assert!(Role::parse(tc).is_err(), "failed for: {}", tc);
+    }
+}
@@ -0,0 +1,66 @@
+use std::str::FromStr;
+
+#[cfg(test)]
+mod tests;
+
+const GOOGLE_ISSUER: &str = "https://accounts.google.com";
+const OIDC_SCOPES: [&str; 3] = ["openid", "email", "profile"];
+const GITHUB_SCOPES: [&str; 2] = ["read:user", "user:email"];
+
+/// How users of an external provider sign in: Google and generic providers
+/// speak OpenID Connect, GitHub only OAuth 2.0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocialProviderKind {
+    Google,
+    Github,
+    Oidc,
+}
+
+impl SocialProviderKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Google => "google",
+            Self::Github => "github",
+            Self::Oidc => "oidc",
+        }
+    }
+
+    /// The issuer of providers that have a well-known one.
+    pub fn default_issuer(&self) -> Option<&'static str> {
+        match self {
+            Self::Google => Some(GOOGLE_ISSUER),
+            Self::Github | Self::Oidc => None,
+        }
+    }
+
+    pub fn default_scopes(&self) -> &'static [&'static str] {
+        match self {
+            Self::Github => &GITHUB_SCOPES,
+            Self::Google | Self::Oidc => &OIDC_SCOPES,
+        }
+    }
+}
+
+impl FromStr for SocialProviderKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "google" => Ok(Self::Google),
+            "github" => Ok(Self::Github),
+            "oidc" => Ok(Self::Oidc),
+            _ => Err(format!(
+                "Unknown provider kind `{s}`, expected one of: google, github, oidc"
+            )),
+        }
+    }
+}
+
+/// Who a provider says signed in. `subject` is the provider's own, stable,
+/// id of the account, which keeps it linked when its email changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SocialIdentity {
+    pub subject: String,
+    pub email: String,
+    pub email_verified: bool,
+}
@@ -0,0 +1,28 @@
+use super::*;
+
+#[test]
+fn test_parse_provider_kind() {
+    assert_eq!("google".parse(), Ok(SocialProviderKind::Google));
+    assert_eq!(" GitHub ".parse(), Ok(SocialProviderKind::Github));
+    assert_eq!("oidc".parse(), Ok(SocialProviderKind::Oidc));
+    assert!("facebook".parse::<SocialProviderKind>().is_err());
+}
+
+#[test]
+fn test_provider_kind_defaults() {
+    assert_eq!(
+        SocialProviderKind::Google.default_issuer(),
+        Some("https://accounts.google.com")
+    );
+    assert_eq!(SocialProviderKind::Oidc.default_issuer(), None);
+    assert!(
+        SocialProviderKind::Oidc
+            .default_scopes()
+            .contains(&"openid")
+    );
+    assert!(
+        !SocialProviderKind::Github
+            .default_scopes()
+            .contains(&"openid")
+    );
+}
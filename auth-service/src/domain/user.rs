@@ -1,11 +1,92 @@
-use validator::ValidationErrors;
+use std::str::FromStr;
 
-use crate::domain::{Email, Password};
+use crate::domain::{
+    AuthAPIError, Email, Password, PasswordPolicy, PasswordRule, Role, UserProfile,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests;
 
 #[derive(Debug)]
 pub enum ParseErrors {
     InvalidEmail,
-    InvalidPassword,
+    InvalidPassword(PasswordRule),
+}
+
+/// Only active accounts can log in or use their tokens. `Disabled` is set by
+/// an admin, `Locked` is a temporary hold, e.g. after suspicious activity,
+/// and `PendingVerification` waits for the user to confirm their email.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountStatus {
+    #[default]
+    Active,
+    Disabled,
+    Locked,
+    PendingVerification,
+}
+
+impl AccountStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Disabled => "disabled",
+            Self::Locked => "locked",
+            Self::PendingVerification => "pending_verification",
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        *self == Self::Active
+    }
+
+    /// The error telling the user why their account can't be used.
+    pub fn ensure_active(&self) -> Result<(), AuthAPIError> {
+        match self {
+            Self::Active => Ok(()),
+            Self::Disabled => Err(AuthAPIError::AccountDisabled),
+            Self::Locked => Err(AuthAPIError::AccountLocked),
+            Self::PendingVerification => Err(AuthAPIError::AccountPendingVerification),
+        }
+    }
+}
+
+impl FromStr for AccountStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "active" => Ok(Self::Active),
+            "disabled" => Ok(Self::Disabled),
+            "locked" => Ok(Self::Locked),
+            "pending_verification" => Ok(Self::PendingVerification),
+            _ => Err(format!("Unknown account status `{s}`")),
+        }
+    }
+}
+
+/// Finds a user. Emails are only unique within a tenant, users outside any
+/// organization sharing the `None` one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UserKey {
+    pub tenant_id: Option<String>,
+    pub email: String,
+}
+
+impl UserKey {
+    pub fn new(tenant_id: Option<&str>, email: &str) -> Self {
+        Self {
+            tenant_id: tenant_id.map(ToOwned::to_owned),
+            email: email.to_owned(),
+        }
+    }
+
+    /// The tenant where it is part of a stored key, which can't be missing.
+    pub fn tenant_key(&self) -> &str {
+        self.tenant_id.as_deref().unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -13,6 +94,13 @@ pub struct User {
     email: Email,
     password: Password,
     requires_2fa: bool,
+    email_verified: bool,
+    created_at: DateTime<Utc>,
+    profile: UserProfile,
+    roles: Vec<Role>,
+    status: AccountStatus,
+    /// The organization the user belongs to, if any.
+    tenant_id: Option<String>,
 }
 
 impl User {
@@ -21,9 +109,47 @@ impl User {
             email,
             password,
             requires_2fa,
+            email_verified: false,
+            created_at: Utc::now(),
+            profile: UserProfile::default(),
+            roles: Vec::new(),
+            status: AccountStatus::Active,
+            tenant_id: None,
         }
     }
 
+    /// For stores restoring the fields that are not set at signup.
+    pub fn with_details(
+        mut self,
+        email_verified: bool,
+        created_at: DateTime<Utc>,
+        profile: UserProfile,
+    ) -> Self {
+        self.email_verified = email_verified;
+        self.created_at = created_at;
+        self.profile = profile;
+        self
+    }
+
+    pub fn with_roles(mut self, roles: Vec<Role>) -> Self {
+        self.set_roles(roles);
+        self
+    }
+
+    pub fn with_status(mut self, status: AccountStatus) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn with_tenant(mut self, tenant_id: Option<String>) -> Self {
+        self.tenant_id = tenant_id;
+        self
+    }
+
+    pub fn key(&self) -> UserKey {
+        UserKey::new(self.tenant_id(), self.email_str())
+    }
+
     pub fn email_str(&self) -> &str {
         self.email.as_ref()
     }
@@ -44,22 +170,74 @@ impl User {
         self.requires_2fa
     }
 
+    pub fn set_requires_2fa(&mut self, requires_2fa: bool) {
+        self.requires_2fa = requires_2fa;
+    }
+
+    pub fn set_password(&mut self, password: Password) {
+        self.password = password;
+    }
+
+    pub fn status(&self) -> AccountStatus {
+        self.status
+    }
+
+    pub fn set_status(&mut self, status: AccountStatus) {
+        self.status = status;
+    }
+
+    pub fn tenant_id(&self) -> Option<&str> {
+        self.tenant_id.as_deref()
+    }
+
+    pub fn email_verified(&self) -> bool {
+        self.email_verified
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    pub fn profile(&self) -> &UserProfile {
+        &self.profile
+    }
+
+    pub fn set_profile(&mut self, profile: UserProfile) {
+        self.profile = profile;
+    }
+
+    /// Sorted and without duplicates.
+    pub fn roles(&self) -> &[Role] {
+        &self.roles
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r.as_ref() == role)
+    }
+
+    pub fn set_roles(&mut self, mut roles: Vec<Role>) {
+        roles.sort();
+        roles.dedup();
+        self.roles = roles;
+    }
+
     pub fn parse(
         email: String,
         password: String,
         requires_2fa: bool,
-    ) -> Result<Self, ValidationErrors> {
-        let email = Email::parse(&email)?;
-        let password = Password::parse(&password)?;
+        policy: &PasswordPolicy,
+    ) -> Result<Self, ParseErrors> {
+        let email = Email::parse(&email).map_err(|_| ParseErrors::InvalidEmail)?;
+        let password = Password::parse(&password, policy).map_err(ParseErrors::InvalidPassword)?;
 
         Ok(Self::new(email, password, requires_2fa))
     }
 
     pub fn new_no_validation(email: String, password: String, requires_2fa: bool) -> Self {
-        Self {
-            email: Email::new_no_validation(email),
-            password: Password::new_no_validation(password),
+        Self::new(
+            Email::new_no_validation(email),
+            Password::new_no_validation(password),
             requires_2fa,
-        }
+        )
     }
 }
@@ -0,0 +1,44 @@
+use super::*;
+
+#[test]
+fn account_status_round_trips_through_str() {
+    let statuses = [
+        AccountStatus::Active,
+        AccountStatus::Disabled,
+        AccountStatus::Locked,
+        AccountStatus::PendingVerification,
+    ];
+    for status in statuses {
+        assert_eq!(status.as_str().parse(), Ok(status));
+        let json = serde_json::to_string(&status).unwrap();
+        assert_eq!(json, format!("\"{}\"", status.as_str()));
+    }
+    assert!("suspended".parse::<AccountStatus>().is_err());
+}
+
+#[test]
+fn only_active_accounts_pass() {
+    assert!(AccountStatus::Active.ensure_active().is_ok());
+    assert!(matches!(
+        AccountStatus::Disabled.ensure_active(),
+        Err(AuthAPIError::AccountDisabled)
+    ));
+    assert!(matches!(
+        AccountStatus::Locked.ensure_active(),
+        Err(AuthAPIError::AccountLocked)
+    ));
+    assert!(matches!(
+        AccountStatus::PendingVerification.ensure_active(),
+        Err(AuthAPIError::AccountPendingVerification)
+    ));
+}
+
+#[test]
+fn new_users_are_active() {
+    let user = User::new_no_validation(
+        "test@example.com".to_owned(),
+        "Kx7!vTq2Lm".to_owned(),
+        false,
+    );
+    assert_eq!(user.status(), AccountStatus::Active);
+}
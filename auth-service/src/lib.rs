@@ -1,12 +1,30 @@
-use crate::domain::{BannedTokenStore, EmailClient, TwoFACodeStore, UserStore};
-use axum::{Router, http::Method, routing::post, serve::Serve};
+// Handlers are generic over every store of `AppState`, one type parameter each.
+#![allow(clippy::type_complexity)]
+
+use crate::domain::{
+    AccessTokenStore, AuditLog, BannedTokenStore, EmailClient, MagicLinkStore, OAuthStore,
+    OrganizationStore, TrustedDeviceStore, TwoFACodeStore, UserStore,
+};
+use axum::{
+    Router,
+    extract::{ConnectInfo, connect_info::IntoMakeServiceWithConnectInfo},
+    handler::HandlerWithoutStateExt,
+    middleware::AddExtension,
+    routing::{delete, get, post, put},
+    serve::Serve,
+};
 use redis::Client;
 use redis::RedisResult;
+use settings::DatabaseSettings;
 use sqlx::{PgPool, postgres::PgPoolOptions};
 use std::error::Error;
-use tower_http::{cors::CorsLayer, services::ServeDir};
-use utils::constants::DROPLET_IP;
+use std::net::SocketAddr;
+use tower_http::services::ServeDir;
 
+use crate::utils::cors::cors_layer;
+use crate::utils::csrf::{csrf_cookie_middleware, require_csrf_token};
+use crate::utils::extractors::require_admin;
+use crate::utils::problem::{method_not_allowed, problem_details_middleware, route_not_found};
 use crate::utils::tracing::metrics_middleware;
 use axum::middleware;
 use axum_otel_metrics::HttpMetricsLayerBuilder;
@@ -16,14 +34,18 @@ pub mod app_state;
 pub mod domain;
 pub mod routes;
 pub mod services;
+pub mod settings;
 pub mod utils;
 
 use crate::app_state::AppState;
 
 use routes::*;
 
-pub async fn get_postgres_pool(url: &str) -> Result<PgPool, sqlx::Error> {
-    PgPoolOptions::new().max_connections(5).connect(url).await
+pub async fn get_postgres_pool(settings: &DatabaseSettings) -> Result<PgPool, sqlx::Error> {
+    PgPoolOptions::new()
+        .max_connections(settings.max_connections)
+        .connect(&settings.url)
+        .await
 }
 
 pub fn get_redis_client(redis_hostname: String) -> RedisResult<Client> {
@@ -32,7 +54,10 @@ pub fn get_redis_client(redis_hostname: String) -> RedisResult<Client> {
 }
 
 pub struct Application {
-    server: Serve<Router, Router>,
+    server: Serve<
+        IntoMakeServiceWithConnectInfo<Router, SocketAddr>,
+        AddExtension<Router, ConnectInfo<SocketAddr>>,
+    >,
     pub address: String,
 }
 
@@ -42,38 +67,174 @@ impl Application {
         U: BannedTokenStore + 'static,
         V: TwoFACodeStore + 'static,
         W: EmailClient + 'static,
+        X: AuditLog + 'static,
+        Y: TrustedDeviceStore + 'static,
+        Z: OAuthStore + 'static,
+        M: MagicLinkStore + 'static,
+        P: AccessTokenStore + 'static,
+        O: OrganizationStore + 'static,
     >(
-        app_state: AppState<T, U, V, W>,
-        address: &str,
+        app_state: AppState<T, U, V, W, X, Y, Z, M, P, O>,
     ) -> Result<Self, Box<dyn Error>> {
-        let listener = tokio::net::TcpListener::bind(address).await?;
+        let settings = app_state.settings.clone();
+        let listener = tokio::net::TcpListener::bind(&settings.application.address).await?;
         let address = listener.local_addr()?.to_string();
 
-        let allowed_origins = [
-            "http://localhost:8000".parse()?,
-            format!("http://{}:8000", DROPLET_IP.as_str()).parse()?,
-        ];
+        let cors = cors_layer(&settings.cors);
 
-        let cors = CorsLayer::new()
-            .allow_methods([Method::GET, Method::POST])
-            .allow_credentials(true)
-            .allow_origin(allowed_origins);
+        let admin = Router::new()
+            .route("/users", get(list_users))
+            .route("/users/:email", get(get_user))
+            .route("/users/:email/roles", put(set_user_roles))
+            .route("/users/:email/status", put(set_user_status))
+            .route("/users/:email/disable", post(disable_user))
+            .route("/users/:email/enable", post(enable_user))
+            .route("/users/:email/reset-2fa", post(reset_2fa))
+            .route("/users/:email/revoke-tokens", post(revoke_tokens))
+            .route("/users/:email/password-reset", post(reset_password))
+            .route("/oauth-clients", post(register_oauth_client))
+            .route("/api-keys", get(list_api_keys).post(create_api_key))
+            .route("/api-keys/:id", delete(revoke_api_key))
+            .route("/invitations", post(create_invitation))
+            .route_layer(middleware::from_fn_with_state(
+                settings.clone(),
+                require_csrf_token,
+            ))
+            .route_layer(middleware::from_fn_with_state(
+                app_state.clone(),
+                require_admin,
+            ));
 
         let router = Router::new()
-            .nest_service("/", ServeDir::new("assets"))
+            .nest_service(
+                "/",
+                ServeDir::new("assets")
+                    .call_fallback_on_method_not_allowed(true)
+                    .not_found_service(route_not_found.into_service()),
+            )
             .route("/signup", post(signup))
             .route("/login", post(login))
+            .route("/login/magic-link", post(request_magic_link))
+            .route("/login/magic-link/callback", get(magic_link_callback))
+            .route("/login/:provider", get(social_login))
+            .route("/callback/:provider", get(social_callback))
             .route("/verify-2fa", post(verify_2fa))
-            .route("/logout", post(logout))
+            .route(
+                "/logout",
+                post(logout).layer(middleware::from_fn_with_state(
+                    settings.clone(),
+                    require_csrf_token,
+                )),
+            )
+            .route(
+                "/me",
+                get(get_me)
+                    .patch(patch_me)
+                    .layer(middleware::from_fn_with_state(
+                        settings.clone(),
+                        require_csrf_token,
+                    )),
+            )
+            .route("/me/activity", get(get_my_activity))
+            .route(
+                "/me/trusted-devices",
+                get(get_trusted_devices)
+                    .delete(forget_trusted_devices)
+                    .layer(middleware::from_fn_with_state(
+                        settings.clone(),
+                        require_csrf_token,
+                    )),
+            )
+            .route(
+                "/me/trusted-devices/:id",
+                delete(forget_trusted_device).layer(middleware::from_fn_with_state(
+                    settings.clone(),
+                    require_csrf_token,
+                )),
+            )
+            .route(
+                "/tokens",
+                get(list_access_tokens).post(create_access_token).layer(
+                    middleware::from_fn_with_state(settings.clone(), require_csrf_token),
+                ),
+            )
+            .route(
+                "/tokens/:id",
+                delete(revoke_access_token).layer(middleware::from_fn_with_state(
+                    settings.clone(),
+                    require_csrf_token,
+                )),
+            )
+            .route(
+                "/orgs",
+                post(create_organization).layer(middleware::from_fn_with_state(
+                    settings.clone(),
+                    require_csrf_token,
+                )),
+            )
+            .route("/orgs/invitations/accept", post(accept_invitation))
+            .route("/orgs/:id", get(get_organization))
+            .route(
+                "/orgs/:id/settings",
+                put(update_organization_settings).layer(middleware::from_fn_with_state(
+                    settings.clone(),
+                    require_csrf_token,
+                )),
+            )
+            .route(
+                "/orgs/:id/invitations",
+                post(invite_member).layer(middleware::from_fn_with_state(
+                    settings.clone(),
+                    require_csrf_token,
+                )),
+            )
+            .route("/password-reset", post(complete_password_reset))
             .route("/verify-token", post(verify_token))
+            .route("/introspect", post(introspect))
+            .route(
+                "/revoke-sessions",
+                get(revoke_sessions_page).post(revoke_sessions),
+            )
+            .route("/csrf-token", get(csrf_token))
+            .route(
+                "/authorize",
+                get(authorize)
+                    .post(authorize_decision)
+                    .layer(middleware::from_fn_with_state(
+                        settings.clone(),
+                        require_csrf_token,
+                    )),
+            )
+            .route("/token", post(token))
+            .route("/userinfo", get(userinfo))
+            .route("/oauth/clients/:client_id", get(get_oauth_client))
+            .route(
+                "/.well-known/openid-configuration",
+                get(openid_configuration),
+            )
+            .route("/.well-known/jwks.json", get(jwks))
+            .nest("/admin", admin)
+            .method_not_allowed_fallback(method_not_allowed)
             .with_state(app_state.clone())
+            .layer(middleware::from_fn_with_state(
+                settings.clone(),
+                csrf_cookie_middleware,
+            ))
+            .layer(middleware::from_fn_with_state(
+                settings.clone(),
+                problem_details_middleware,
+            ))
             .layer(cors)
             .layer(OtelAxumLayer::default())
-            .layer(OtelInResponseLayer::default())
+            .layer(OtelInResponseLayer)
             .layer(HttpMetricsLayerBuilder::new().build())
             .layer(middleware::from_fn(metrics_middleware));
 
-        let server = axum::serve(listener, router);
+        // the client address is recorded in the audit log
+        let server = axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        );
 
         Ok(Application { server, address })
     }
@@ -1,12 +1,40 @@
+mod access_tokens;
+mod admin;
+mod api_keys;
+mod csrf_token;
+mod introspect;
+mod invitations;
 mod login;
 mod logout;
+mod magic_link;
+mod me;
+mod oauth;
+mod organizations;
+mod password_reset;
+mod revoke_sessions;
 mod signup;
+mod social_login;
+mod trusted_devices;
 mod verify_2fa;
 mod verify_token;
 
 // re-export items from sub-modules
+pub use access_tokens::*;
+pub use admin::*;
+pub use api_keys::*;
+pub use csrf_token::*;
+pub use introspect::*;
+pub use invitations::*;
 pub use login::*;
 pub use logout::*;
+pub use magic_link::*;
+pub use me::*;
+pub use oauth::*;
+pub use organizations::*;
+pub use password_reset::*;
+pub use revoke_sessions::*;
 pub use signup::*;
+pub use social_login::*;
+pub use trusted_devices::*;
 pub use verify_2fa::*;
 pub use verify_token::*;
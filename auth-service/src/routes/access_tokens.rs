@@ -0,0 +1,212 @@
+use crate::app_state::AppState;
+use crate::domain::data_stores::{
+    AccessTokenStore, AccessTokenStoreError, BannedTokenStore, MagicLinkStore, OAuthStore,
+    OrganizationStore, TrustedDeviceStore, TwoFACodeStore, UserStore,
+};
+use crate::domain::{
+    AuditEvent, AuditEventType, AuditLog, AuditOutcome, AuthAPIError, ClientInfo, EmailClient,
+    PersonalAccessToken,
+};
+use crate::utils::extractors::{AuthenticatedUser, JsonBody, PathParams};
+use axum::{Json, extract::State, http::StatusCode};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct CreateAccessTokenRequest {
+    pub name: String,
+    pub scopes: Vec<String>,
+    #[serde(rename = "expiresInDays")]
+    pub expires_in_days: i64,
+}
+
+/// A personal access token as seen by its user, never with its secret.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccessTokenResponse {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: DateTime<Utc>,
+    #[serde(rename = "lastUsedAt")]
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl From<PersonalAccessToken> for AccessTokenResponse {
+    fn from(access_token: PersonalAccessToken) -> Self {
+        Self {
+            id: access_token.id,
+            name: access_token.name,
+            scopes: access_token.scopes,
+            created_at: access_token.created_at,
+            expires_at: access_token.expires_at,
+            last_used_at: access_token.last_used_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreatedAccessTokenResponse {
+    #[serde(flatten)]
+    pub access_token: AccessTokenResponse,
+    /// Only shown once, it is stored hashed.
+    pub token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccessTokenListResponse {
+    pub tokens: Vec<AccessTokenResponse>,
+}
+
+fn map_access_token_store_error(e: AccessTokenStoreError) -> AuthAPIError {
+    match e {
+        AccessTokenStoreError::TokenNotFound => AuthAPIError::AccessTokenNotFound,
+        e => AuthAPIError::UnexpectedError(e.into()),
+    }
+}
+
+#[tracing::instrument(name = "Create personal access token", skip_all)]
+pub async fn create_access_token<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    client: ClientInfo,
+    user: AuthenticatedUser,
+    JsonBody(request): JsonBody<CreateAccessTokenRequest>,
+) -> Result<(StatusCode, Json<CreatedAccessTokenResponse>), AuthAPIError> {
+    let (access_token, token) = PersonalAccessToken::issue(
+        user.key(),
+        request.name,
+        &request.scopes,
+        request.expires_in_days,
+    )?;
+
+    let id = access_token.id.clone();
+    let result = state
+        .access_token_store
+        .write()
+        .await
+        .add_token(access_token.clone())
+        .await
+        .map_err(map_access_token_store_error);
+    record_access_token_event(
+        &state,
+        &client,
+        &user,
+        AuditEventType::AccessTokenCreated,
+        &id,
+        &result,
+    )
+    .await;
+    result?;
+
+    let response = CreatedAccessTokenResponse {
+        access_token: access_token.into(),
+        token,
+    };
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+#[tracing::instrument(name = "List personal access tokens", skip_all)]
+pub async fn list_access_tokens<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    user: AuthenticatedUser,
+) -> Result<Json<AccessTokenListResponse>, AuthAPIError> {
+    let tokens = state
+        .access_token_store
+        .read()
+        .await
+        .user_tokens(&user.key())
+        .await
+        .map_err(map_access_token_store_error)?;
+    Ok(Json(AccessTokenListResponse {
+        tokens: tokens.into_iter().map(AccessTokenResponse::from).collect(),
+    }))
+}
+
+/// Revokes a token of the user, `/verify-token` refuses it at once.
+#[tracing::instrument(name = "Revoke personal access token", skip_all)]
+pub async fn revoke_access_token<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    client: ClientInfo,
+    user: AuthenticatedUser,
+    PathParams(id): PathParams<String>,
+) -> Result<StatusCode, AuthAPIError> {
+    let result = state
+        .access_token_store
+        .write()
+        .await
+        .remove_token(&user.key(), &id)
+        .await
+        .map(|()| StatusCode::NO_CONTENT)
+        .map_err(map_access_token_store_error);
+    record_access_token_event(
+        &state,
+        &client,
+        &user,
+        AuditEventType::AccessTokenRevoked,
+        &id,
+        &result,
+    )
+    .await;
+    result
+}
+
+async fn record_access_token_event<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+    R,
+>(
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    client: &ClientInfo,
+    user: &AuthenticatedUser,
+    event_type: AuditEventType,
+    id: &str,
+    result: &Result<R, AuthAPIError>,
+) {
+    let event = AuditEvent::new(event_type, client, AuditOutcome::from_result(result))
+        .with_actor(user.email.as_ref())
+        .with_target(id);
+    state.record_audit_event(event).await;
+}
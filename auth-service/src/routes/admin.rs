@@ -0,0 +1,544 @@
+use crate::app_state::AppState;
+use crate::domain::data_stores::{
+    AccessTokenStore, BannedTokenStore, MagicLinkStore, OAuthStore, OrganizationStore,
+    TrustedDeviceStore, TwoFACodeStore, UserQuery, UserStore, UserStoreError,
+};
+use crate::domain::{
+    AccountStatus, AuditEvent, AuditEventType, AuditLog, AuditOutcome, AuthAPIError, ClientInfo,
+    Email, EmailClient, FieldError, Password, Role, User, UserKey,
+};
+use crate::routes::{MeResponse, send_password_reset_link};
+use crate::utils::extractors::{Admin, JsonBody, PathParams, QueryParams};
+use crate::utils::pagination::Page;
+use axum::{Extension, Json, extract::State, http::StatusCode};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+/// A user as seen by admins, the `/me` fields plus the account status.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminUserResponse {
+    #[serde(flatten)]
+    pub user: MeResponse,
+    pub status: AccountStatus,
+}
+
+impl From<&User> for AdminUserResponse {
+    fn from(user: &User) -> Self {
+        Self {
+            user: MeResponse::from(user),
+            status: user.status(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ListUsersQuery {
+    pub search: Option<String>,
+    pub page: Option<usize>,
+    #[serde(rename = "perPage")]
+    pub per_page: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserListResponse {
+    pub users: Vec<AdminUserResponse>,
+    pub page: usize,
+    #[serde(rename = "perPage")]
+    pub per_page: usize,
+    pub total: usize,
+}
+
+/// Which account of an email a route acts on, emails being only unique
+/// within an organization: the one outside any unless `organizationId` is set.
+#[derive(Deserialize)]
+pub struct AccountQuery {
+    #[serde(rename = "organizationId")]
+    pub organization_id: Option<String>,
+}
+
+impl AccountQuery {
+    fn user(&self, email: &str) -> UserKey {
+        UserKey::new(self.organization_id.as_deref(), email)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SetRolesRequest {
+    pub roles: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SetStatusRequest {
+    pub status: AccountStatus,
+}
+
+fn map_user_store_error(e: UserStoreError) -> AuthAPIError {
+    match e {
+        UserStoreError::UserNotFound | UserStoreError::InvalidCredentials => {
+            AuthAPIError::UserNotFound
+        }
+        e => AuthAPIError::UnexpectedError(e.into()),
+    }
+}
+
+// Logs the user out everywhere, tokens issued from now on are not affected.
+pub(crate) async fn ban_user_tokens<U: BannedTokenStore>(
+    banned_token_store: &tokio::sync::RwLock<U>,
+    user: &UserKey,
+) -> Result<(), AuthAPIError> {
+    banned_token_store
+        .write()
+        .await
+        .ban_user_tokens(user, Utc::now().timestamp())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))
+}
+
+// Makes every browser of the user ask for a 2FA code again.
+pub(crate) async fn remove_trusted_devices<Y: TrustedDeviceStore>(
+    trusted_device_store: &tokio::sync::RwLock<Y>,
+    user: &UserKey,
+) -> Result<(), AuthAPIError> {
+    trusted_device_store
+        .write()
+        .await
+        .remove_user_devices(user)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))
+}
+
+// Records an admin action on `target`, a user's email or an API key id,
+// whatever its outcome.
+pub(crate) async fn record_admin_action<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+    R,
+>(
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    admin: &Admin,
+    client: &ClientInfo,
+    event_type: AuditEventType,
+    target: &str,
+    result: &Result<R, AuthAPIError>,
+) {
+    let mut event =
+        AuditEvent::new(event_type, client, AuditOutcome::from_result(result)).with_target(target);
+    if let Some(admin_email) = &admin.email {
+        event = event.with_actor(admin_email.as_ref());
+    }
+    state.record_audit_event(event).await;
+}
+
+#[tracing::instrument(name = "Admin List Users", skip_all)]
+pub async fn list_users<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    QueryParams(query): QueryParams<ListUsersQuery>,
+) -> Result<Json<UserListResponse>, AuthAPIError> {
+    let page = Page::parse(query.page, query.per_page)?;
+    let search = query
+        .search
+        .map(|search| search.trim().to_owned())
+        .filter(|search| !search.is_empty());
+
+    let user_query = UserQuery {
+        search,
+        offset: page.offset(),
+        limit: page.per_page,
+    };
+    let users = state
+        .user_store
+        .read()
+        .await
+        .list_users(&user_query)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok(Json(UserListResponse {
+        users: users.users.iter().map(AdminUserResponse::from).collect(),
+        page: page.page,
+        per_page: page.per_page,
+        total: users.total,
+    }))
+}
+
+#[tracing::instrument(name = "Admin Get User", skip_all)]
+pub async fn get_user<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    PathParams(email): PathParams<String>,
+    QueryParams(query): QueryParams<AccountQuery>,
+) -> Result<Json<AdminUserResponse>, AuthAPIError> {
+    let user = state
+        .user_store
+        .read()
+        .await
+        .get_user(&query.user(&email))
+        .await
+        .map_err(map_user_store_error)?;
+    Ok(Json(AdminUserResponse::from(&user)))
+}
+
+#[tracing::instrument(name = "Admin Set Roles", skip_all)]
+pub async fn set_user_roles<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    Extension(admin): Extension<Admin>,
+    client: ClientInfo,
+    PathParams(email): PathParams<String>,
+    QueryParams(query): QueryParams<AccountQuery>,
+    JsonBody(request): JsonBody<SetRolesRequest>,
+) -> Result<Json<AdminUserResponse>, AuthAPIError> {
+    let result = async {
+        let roles = request
+            .roles
+            .iter()
+            .map(|role| Role::parse(role))
+            .collect::<Result<Vec<Role>, _>>()
+            .map_err(|e| FieldError::from_validation("roles", "invalid_role", &e))?;
+        let user = state
+            .user_store
+            .write()
+            .await
+            .set_roles(&query.user(&email), roles)
+            .await
+            .map_err(map_user_store_error)?;
+        Ok::<_, AuthAPIError>(Json(AdminUserResponse::from(&user)))
+    }
+    .await;
+
+    record_admin_action(
+        &state,
+        &admin,
+        &client,
+        AuditEventType::RolesChanged,
+        &email,
+        &result,
+    )
+    .await;
+    result
+}
+
+#[tracing::instrument(name = "Admin Disable User", skip_all)]
+pub async fn disable_user<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    Extension(admin): Extension<Admin>,
+    client: ClientInfo,
+    PathParams(email): PathParams<String>,
+    QueryParams(query): QueryParams<AccountQuery>,
+) -> Result<Json<AdminUserResponse>, AuthAPIError> {
+    let result = async {
+        let user = state
+            .user_store
+            .write()
+            .await
+            .set_status(&query.user(&email), AccountStatus::Disabled)
+            .await
+            .map_err(map_user_store_error)?;
+        ban_user_tokens(&state.banned_token_store, &user.key()).await?;
+        Ok::<_, AuthAPIError>(Json(AdminUserResponse::from(&user)))
+    }
+    .await;
+
+    record_admin_action(
+        &state,
+        &admin,
+        &client,
+        AuditEventType::StatusChanged,
+        &email,
+        &result,
+    )
+    .await;
+    result
+}
+
+#[tracing::instrument(name = "Admin Enable User", skip_all)]
+pub async fn enable_user<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    Extension(admin): Extension<Admin>,
+    client: ClientInfo,
+    PathParams(email): PathParams<String>,
+    QueryParams(query): QueryParams<AccountQuery>,
+) -> Result<Json<AdminUserResponse>, AuthAPIError> {
+    let result = async {
+        let user = state
+            .user_store
+            .write()
+            .await
+            .set_status(&query.user(&email), AccountStatus::Active)
+            .await
+            .map_err(map_user_store_error)?;
+        Ok::<_, AuthAPIError>(Json(AdminUserResponse::from(&user)))
+    }
+    .await;
+
+    record_admin_action(
+        &state,
+        &admin,
+        &client,
+        AuditEventType::StatusChanged,
+        &email,
+        &result,
+    )
+    .await;
+    result
+}
+
+/// Sets any account status, tokens of accounts that can't be used anymore are
+/// revoked.
+#[tracing::instrument(name = "Admin Set Status", skip_all)]
+pub async fn set_user_status<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    Extension(admin): Extension<Admin>,
+    client: ClientInfo,
+    PathParams(email): PathParams<String>,
+    QueryParams(query): QueryParams<AccountQuery>,
+    JsonBody(request): JsonBody<SetStatusRequest>,
+) -> Result<Json<AdminUserResponse>, AuthAPIError> {
+    let result = async {
+        let user = state
+            .user_store
+            .write()
+            .await
+            .set_status(&query.user(&email), request.status)
+            .await
+            .map_err(map_user_store_error)?;
+        if !user.status().is_active() {
+            ban_user_tokens(&state.banned_token_store, &user.key()).await?;
+        }
+        Ok::<_, AuthAPIError>(Json(AdminUserResponse::from(&user)))
+    }
+    .await;
+
+    record_admin_action(
+        &state,
+        &admin,
+        &client,
+        AuditEventType::StatusChanged,
+        &email,
+        &result,
+    )
+    .await;
+    result
+}
+
+/// Turns 2FA off, e.g. for users who lost access to their codes, they can
+/// turn it back on themselves.
+#[tracing::instrument(name = "Admin Reset 2FA", skip_all)]
+pub async fn reset_2fa<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    Extension(admin): Extension<Admin>,
+    client: ClientInfo,
+    PathParams(email): PathParams<String>,
+    QueryParams(query): QueryParams<AccountQuery>,
+) -> Result<Json<AdminUserResponse>, AuthAPIError> {
+    let result = async {
+        let user = state
+            .user_store
+            .write()
+            .await
+            .set_requires_2fa(&query.user(&email), false)
+            .await
+            .map_err(map_user_store_error)?;
+
+        let key = user.key();
+        let mut two_fa_code_store = state.two_fa_code_store.write().await;
+        if two_fa_code_store.get_code(&key).await.is_ok() {
+            two_fa_code_store
+                .remove_code(&key)
+                .await
+                .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+        }
+        remove_trusted_devices(&state.trusted_device_store, &key).await?;
+        Ok::<_, AuthAPIError>(Json(AdminUserResponse::from(&user)))
+    }
+    .await;
+
+    record_admin_action(
+        &state,
+        &admin,
+        &client,
+        AuditEventType::TwoFactorReset,
+        &email,
+        &result,
+    )
+    .await;
+    result
+}
+
+#[tracing::instrument(name = "Admin Revoke Tokens", skip_all)]
+pub async fn revoke_tokens<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    Extension(admin): Extension<Admin>,
+    client: ClientInfo,
+    PathParams(email): PathParams<String>,
+    QueryParams(query): QueryParams<AccountQuery>,
+) -> Result<StatusCode, AuthAPIError> {
+    let result = async {
+        let user = state
+            .user_store
+            .read()
+            .await
+            .get_user(&query.user(&email))
+            .await
+            .map_err(map_user_store_error)?;
+        ban_user_tokens(&state.banned_token_store, &user.key()).await?;
+        Ok::<_, AuthAPIError>(StatusCode::OK)
+    }
+    .await;
+
+    record_admin_action(
+        &state,
+        &admin,
+        &client,
+        AuditEventType::TokensRevoked,
+        &email,
+        &result,
+    )
+    .await;
+    result
+}
+
+/// Replaces the password with a random one nobody knows, logs the user out
+/// everywhere and emails them a link to choose a new password.
+#[tracing::instrument(name = "Admin Reset Password", skip_all)]
+pub async fn reset_password<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    Extension(admin): Extension<Admin>,
+    client: ClientInfo,
+    PathParams(email): PathParams<String>,
+    QueryParams(query): QueryParams<AccountQuery>,
+) -> Result<StatusCode, AuthAPIError> {
+    let result = async {
+        let email = Email::parse(&email).map_err(|_| AuthAPIError::UserNotFound)?;
+        let user = query.user(email.as_ref());
+        let password = Password::generate();
+        state
+            .user_store
+            .write()
+            .await
+            .set_password(&user, password.clone())
+            .await
+            .map_err(map_user_store_error)?;
+        ban_user_tokens(&state.banned_token_store, &user).await?;
+        remove_trusted_devices(&state.trusted_device_store, &user).await?;
+        send_password_reset_link(&email, user.tenant_id.as_deref(), &state).await?;
+        Ok::<_, AuthAPIError>(StatusCode::OK)
+    }
+    .await;
+
+    record_admin_action(
+        &state,
+        &admin,
+        &client,
+        AuditEventType::PasswordReset,
+        &email,
+        &result,
+    )
+    .await;
+    result
+}
@@ -0,0 +1,179 @@
+use crate::app_state::AppState;
+use crate::domain::data_stores::{
+    AccessTokenStore, BannedTokenStore, MagicLinkStore, OAuthStore, OAuthStoreError,
+    OrganizationStore, TrustedDeviceStore, TwoFACodeStore, UserStore,
+};
+use crate::domain::{ApiKey, AuditEventType, AuditLog, AuthAPIError, ClientInfo, EmailClient};
+use crate::routes::admin::record_admin_action;
+use crate::utils::extractors::{Admin, JsonBody, PathParams};
+use axum::{Extension, Json, extract::State, http::StatusCode};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<String>,
+    /// Keys without it never expire.
+    #[serde(rename = "expiresInDays")]
+    pub expires_in_days: Option<i64>,
+}
+
+/// An API key as seen by admins, never with its secret.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiKeyResponse {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+    #[serde(rename = "createdBy")]
+    pub created_by: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(rename = "lastUsedAt")]
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(api_key: ApiKey) -> Self {
+        Self {
+            id: api_key.id,
+            name: api_key.name,
+            scopes: api_key.scopes,
+            created_by: api_key.created_by,
+            created_at: api_key.created_at,
+            expires_at: api_key.expires_at,
+            last_used_at: api_key.last_used_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreatedApiKeyResponse {
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+    /// Only shown once, it is stored hashed.
+    pub key: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiKeyListResponse {
+    #[serde(rename = "apiKeys")]
+    pub api_keys: Vec<ApiKeyResponse>,
+}
+
+#[tracing::instrument(name = "Create API key", skip_all)]
+pub async fn create_api_key<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    Extension(admin): Extension<Admin>,
+    client: ClientInfo,
+    JsonBody(request): JsonBody<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<CreatedApiKeyResponse>), AuthAPIError> {
+    let created_by = admin.email.as_ref().map(|email| email.as_ref().to_owned());
+    let (api_key, key) = ApiKey::issue(
+        request.name,
+        &request.scopes,
+        request.expires_in_days,
+        created_by,
+    )?;
+
+    let id = api_key.id.clone();
+    let result = state
+        .oauth_store
+        .write()
+        .await
+        .add_api_key(api_key.clone())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()));
+    record_admin_action(
+        &state,
+        &admin,
+        &client,
+        AuditEventType::ApiKeyCreated,
+        &id,
+        &result,
+    )
+    .await;
+    result?;
+
+    let response = CreatedApiKeyResponse {
+        api_key: api_key.into(),
+        key,
+    };
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+#[tracing::instrument(name = "List API keys", skip_all)]
+pub async fn list_api_keys<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+) -> Result<Json<ApiKeyListResponse>, AuthAPIError> {
+    let api_keys = state
+        .oauth_store
+        .read()
+        .await
+        .api_keys()
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    Ok(Json(ApiKeyListResponse {
+        api_keys: api_keys.into_iter().map(ApiKeyResponse::from).collect(),
+    }))
+}
+
+/// Revoking a key also makes the service tokens issued for it inactive.
+#[tracing::instrument(name = "Revoke API key", skip_all)]
+pub async fn revoke_api_key<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    Extension(admin): Extension<Admin>,
+    client: ClientInfo,
+    PathParams(id): PathParams<String>,
+) -> Result<StatusCode, AuthAPIError> {
+    let result = match state.oauth_store.write().await.remove_api_key(&id).await {
+        Ok(()) => Ok(StatusCode::NO_CONTENT),
+        Err(OAuthStoreError::ApiKeyNotFound) => Err(AuthAPIError::ApiKeyNotFound),
+        Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+    record_admin_action(
+        &state,
+        &admin,
+        &client,
+        AuditEventType::ApiKeyRevoked,
+        &id,
+        &result,
+    )
+    .await;
+    result
+}
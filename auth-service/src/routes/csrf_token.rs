@@ -0,0 +1,19 @@
+use axum::{Extension, Json, response::IntoResponse};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::csrf::CsrfToken;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CsrfTokenResponse {
+    #[serde(rename = "csrfToken")]
+    pub csrf_token: String,
+}
+
+// For front-ends that cannot read the csrf_token cookie themselves, the
+// cookie is set by the CSRF middleware when it is missing.
+#[tracing::instrument(name = "CSRF Token", skip_all)]
+pub async fn csrf_token(
+    Extension(CsrfToken(csrf_token)): Extension<CsrfToken>,
+) -> impl IntoResponse {
+    Json(CsrfTokenResponse { csrf_token })
+}
@@ -0,0 +1,108 @@
+use crate::app_state::AppState;
+use crate::domain::data_stores::{
+    AccessTokenStore, BannedTokenStore, MagicLinkStore, OAuthStore, OrganizationStore,
+    TrustedDeviceStore, TwoFACodeStore, UserStore,
+};
+use crate::domain::error::AuthAPIError;
+use crate::domain::{AuditEvent, AuditEventType, AuditLog, AuditOutcome, ClientInfo, EmailClient};
+use crate::utils::auth::TokenKind;
+use crate::utils::extractors::{
+    AuthenticatedClient, FormBody, ensure_subject_active, validate_active_token,
+};
+use axum::{Json, extract::State, response::IntoResponse};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct IntrospectRequest {
+    pub token: String,
+    /// Accepted for RFC 7662 compatibility, only access tokens exist.
+    pub token_type_hint: Option<String>,
+}
+
+/// RFC 7662 response, inactive tokens only get `{"active": false}`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IntrospectResponse {
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iat: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+    /// The API key of service tokens.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+}
+
+#[tracing::instrument(name = "Introspect", skip_all, fields(client_id = %client.client_id))]
+pub async fn introspect<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    client: AuthenticatedClient,
+    client_info: ClientInfo,
+    FormBody(request): FormBody<IntrospectRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    // like /verify-token, only inactive tokens are audited
+    let event = AuditEvent::new(
+        AuditEventType::TokenIntrospected,
+        &client_info,
+        AuditOutcome::Failure,
+    )
+    .with_actor(client.client_id);
+    let claims = match validate_active_token(
+        &request.token,
+        &state.banned_token_store,
+        &state.settings.auth,
+    )
+    .await
+    {
+        Ok(claims) => claims,
+        Err(AuthAPIError::InvalidToken) => {
+            state.record_audit_event(event).await;
+            return Ok(Json(IntrospectResponse::default()));
+        }
+        Err(e) => return Err(e),
+    };
+    // tokens of suspended accounts and revoked API keys are inactive too
+    match ensure_subject_active(&claims, &state.user_store, &state.oauth_store).await {
+        Ok(()) => {}
+        Err(e @ AuthAPIError::UnexpectedError(_)) => return Err(e),
+        Err(_) => {
+            state
+                .record_audit_event(event.with_target(claims.sub))
+                .await;
+            return Ok(Json(IntrospectResponse::default()));
+        }
+    }
+
+    let client_id = (claims.kind == TokenKind::Service).then(|| claims.sub.clone());
+    Ok(Json(IntrospectResponse {
+        active: true,
+        sub: Some(claims.sub),
+        exp: Some(claims.exp),
+        iat: Some(claims.iat),
+        scope: claims.scope,
+        jti: Some(claims.jti).filter(|jti| !jti.is_empty()),
+        token_type: Some("Bearer".to_owned()),
+        client_id,
+        roles: claims.roles,
+    }))
+}
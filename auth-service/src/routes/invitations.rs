@@ -0,0 +1,129 @@
+use crate::app_state::AppState;
+use crate::domain::data_stores::{
+    AccessTokenStore, BannedTokenStore, MagicLinkStore, OAuthStore, OrganizationStore,
+    TrustedDeviceStore, TwoFACodeStore, UserStore, UserStoreError,
+};
+use crate::domain::{
+    AuditEventType, AuditLog, AuthAPIError, ClientInfo, DEFAULT_INVITATION_TTL_DAYS, Email,
+    EmailClient, FieldError, Invitation, Role, UserKey,
+};
+use crate::routes::admin::record_admin_action;
+use crate::utils::extractors::{Admin, JsonBody};
+use axum::{Extension, Json, extract::State, http::StatusCode};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct CreateInvitationRequest {
+    pub email: String,
+    pub role: Option<String>,
+    #[serde(default = "default_expires_in_days", rename = "expiresInDays")]
+    pub expires_in_days: i64,
+}
+
+fn default_expires_in_days() -> i64 {
+    DEFAULT_INVITATION_TTL_DAYS
+}
+
+/// An invitation as seen by whoever sent it, never with its token.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InvitationResponse {
+    pub email: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: DateTime<Utc>,
+}
+
+impl From<Invitation> for InvitationResponse {
+    fn from(invitation: Invitation) -> Self {
+        Self {
+            email: invitation.email.as_ref().to_owned(),
+            role: invitation.role.map(|role| role.as_ref().to_owned()),
+            expires_at: invitation.expires_at,
+        }
+    }
+}
+
+/// Invites someone to sign up, the only way in when open registration is off.
+/// The emailed link carries the token `/signup` takes as `inviteToken`.
+#[tracing::instrument(name = "Create invitation", skip_all)]
+pub async fn create_invitation<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    Extension(admin): Extension<Admin>,
+    client: ClientInfo,
+    JsonBody(request): JsonBody<CreateInvitationRequest>,
+) -> Result<(StatusCode, Json<InvitationResponse>), AuthAPIError> {
+    let email = Email::parse(&request.email).map_err(|_| FieldError::invalid_email("email"))?;
+    let role = request
+        .role
+        .as_deref()
+        .map(Role::parse)
+        .transpose()
+        .map_err(|e| FieldError::from_validation("role", "invalid_role", &e))?;
+    let invited_by = admin.email.as_ref().map(|email| email.as_ref().to_owned());
+    let (mut invitation, token) =
+        Invitation::new(email.clone(), invited_by, request.expires_in_days)?;
+    invitation.role = role;
+
+    let result = async {
+        match state
+            .user_store
+            .read()
+            .await
+            .get_user(&UserKey::new(None, email.as_ref()))
+            .await
+        {
+            Ok(_) => return Err(AuthAPIError::UserAlreadyExists),
+            Err(UserStoreError::UserNotFound) => {}
+            Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+        }
+        state
+            .organization_store
+            .write()
+            .await
+            .add_invitation(invitation.clone())
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+        let link = state
+            .settings
+            .application
+            .public_url(&format!("/?invite={token}"));
+        let content = format!(
+            "You are invited to create an account: {link}\n\n\
+             The link expires in {} days. \
+             If you didn't expect it, you can ignore this email.",
+            request.expires_in_days,
+        );
+        state
+            .email_client
+            .send_email(&email, "You are invited", &content)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))
+    }
+    .await;
+    record_admin_action(
+        &state,
+        &admin,
+        &client,
+        AuditEventType::InvitationCreated,
+        email.as_ref(),
+        &result,
+    )
+    .await;
+    result?;
+
+    Ok((StatusCode::CREATED, Json(invitation.into())))
+}
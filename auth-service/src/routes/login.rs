@@ -1,17 +1,52 @@
 use crate::app_state::AppState;
-use crate::domain::data_stores::{BannedTokenStore, TwoFACodeStore, UserStore};
-use crate::domain::{AuthAPIError, Email, EmailClient, LoginAttemptId, TwoFACode};
-use crate::utils::auth::generate_auth_cookie;
+use crate::domain::data_stores::{
+    AccessTokenStore, BannedTokenStore, LinkPurpose, MagicLinkStore, OAuthStore, OrganizationStore,
+    OrganizationStoreError, TrustedDeviceStore, TrustedDeviceStoreError, TwoFACodeStore, UserStore,
+};
+use crate::domain::{
+    AuditEvent, AuditEventType, AuditLog, AuditOutcome, AuthAPIError, ClientInfo, Email,
+    EmailClient, KnownDevice, LoginAttemptId, Role, TwoFACode, User, UserKey, generate_device_id,
+    generate_secret, hash_secret, network_hint, parse_device_id,
+};
+use crate::settings::{ApplicationSettings, AuthSettings};
+use crate::utils::auth::{
+    create_auth_cookie, create_device_cookie, generate_auth_token, validate_trusted_device_token,
+};
+use crate::utils::extractors::JsonBody;
 use axum::{
     Json, body::Body, extract::State, http::StatusCode, response::IntoResponse, response::Response,
 };
 use axum_extra::extract::CookieJar;
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// How the JWT is handed to the client once the login is complete. Clients
+/// that can't use cookies, e.g. mobile and CLI ones, ask for it in the body
+/// and send it back in an `Authorization: Bearer` header.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenDelivery {
+    #[default]
+    Cookie,
+    Body,
+}
 
 #[derive(Deserialize)]
 pub struct LoginRequest {
     pub email: String,
     pub password: String,
+    /// The account of the email in this organization, the one outside any
+    /// when missing.
+    #[serde(default, rename = "organizationId")]
+    pub organization_id: Option<String>,
+    #[serde(default, rename = "tokenDelivery")]
+    pub token_delivery: TokenDelivery,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokenResponse {
+    pub token: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -28,71 +63,392 @@ pub enum LoginResponse {
     TwoFactorAuth(TwoFactorAuthResponse),
 }
 
-#[tracing::instrument(name = "No 2FA", skip_all)]
-async fn handle_no_2fa(email: &Email, jar: CookieJar) -> (CookieJar, Response<Body>) {
-    let auth_cookie = match generate_auth_cookie(email) {
-        Ok(cookie) => cookie,
+// Also used by verify_2fa, which completes the login of 2FA users.
+pub(crate) fn issue_auth_token(
+    email: &Email,
+    roles: &[Role],
+    tenant: Option<&str>,
+    settings: &AuthSettings,
+    token_delivery: TokenDelivery,
+    jar: CookieJar,
+) -> (CookieJar, Response<Body>) {
+    let token = match generate_auth_token(email, roles, tenant, settings) {
+        Ok(token) => token,
         Err(_) => return (jar, AuthAPIError::IncorrectCredentials.into_response()),
     };
 
-    let updated_jar = jar.add(auth_cookie);
-
-    (updated_jar, StatusCode::OK.into_response())
+    match token_delivery {
+        TokenDelivery::Cookie => {
+            let updated_jar = jar.add(create_auth_cookie(token, settings));
+            (updated_jar, StatusCode::OK.into_response())
+        }
+        TokenDelivery::Body => (jar, Json(TokenResponse { token }).into_response()),
+    }
 }
 
-#[tracing::instrument(name = "Handle 2FA", skip_all)]
-async fn handle_2fa<T: UserStore, U: BannedTokenStore, V: TwoFACodeStore, W: EmailClient>(
-    email: &Email,
-    state: &AppState<T, U, V, W>,
+/// Remembers the device a user is logging in from, telling them by email when
+/// it is a new one. Failures are logged, they shouldn't block the login.
+#[tracing::instrument(name = "Track device", skip_all)]
+pub(crate) async fn track_device<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    user: &User,
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    client: &ClientInfo,
     jar: CookieJar,
-) -> (CookieJar, Response<Body>) {
-    let login_attempt_id = LoginAttemptId::default();
-    let two_fa_code = TwoFACode::default();
+) -> CookieJar {
+    let cookie_settings = &state.settings.auth.cookie;
+    let cookie_id = jar
+        .get(&cookie_settings.device_cookie_name())
+        .and_then(|cookie| parse_device_id(cookie.value()));
 
-    let body = TwoFactorAuthResponse {
-        message: "2FA required".to_owned(),
-        login_attempt_id: login_attempt_id.as_ref().to_owned(),
+    let known_devices = match state
+        .user_store
+        .read()
+        .await
+        .known_devices(&user.key())
+        .await
+    {
+        Ok(devices) => devices,
+        Err(e) => {
+            tracing::error!(error = ?e, "Failed to load known devices");
+            return jar;
+        }
     };
+    let known = known_devices
+        .iter()
+        .find(|device| device.matches(cookie_id.as_deref(), client));
+    let device_id = known
+        .map(|device| device.device_id.clone())
+        .or(cookie_id)
+        .unwrap_or_else(generate_device_id);
+    // the very first device isn't news to anyone
+    let is_new_device = known.is_none() && !known_devices.is_empty();
 
     if let Err(e) = state
-        .two_fa_code_store
+        .user_store
         .write()
         .await
-        .add_code(email.clone(), login_attempt_id.clone(), two_fa_code.clone())
+        .remember_device(&user.key(), KnownDevice::new(device_id.clone(), client))
         .await
     {
-        return (jar, AuthAPIError::UnexpectedError(e.into()).into_response());
+        tracing::error!(error = ?e, "Failed to remember device");
     }
 
-    if let Err(e) = state
+    if is_new_device {
+        let result = send_new_device_alert(user, state, client).await;
+        if let Err(e) = &result {
+            tracing::error!(error = ?e, "Failed to send new sign-in alert");
+        }
+        let event = AuditEvent::new(
+            AuditEventType::NewDeviceSignIn,
+            client,
+            AuditOutcome::from_result(&result),
+        )
+        .with_actor(user.email_str());
+        state.record_audit_event(event).await;
+    }
+
+    jar.add(create_device_cookie(device_id, cookie_settings))
+}
+
+async fn send_new_device_alert<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    user: &User,
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    client: &ClientInfo,
+) -> Result<(), AuthAPIError> {
+    let email = user.email();
+    // single-use and short-lived like a password reset link, only its hash is
+    // stored
+    let token = generate_secret();
+    state
+        .magic_link_store
+        .write()
+        .await
+        .add_token(
+            LinkPurpose::RevokeSessions,
+            &hash_secret(&token),
+            user.key(),
+        )
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    let revoke_url = state
+        .settings
+        .application
+        .public_url(&format!("/revoke-sessions?token={token}"));
+    let location = client
+        .ip
+        .as_deref()
+        .and_then(network_hint)
+        .unwrap_or_else(|| "unknown".to_owned());
+    let minutes = state.settings.auth.magic_link_ttl_seconds.div_ceil(60);
+    let content = format!(
+        "There was a new sign-in to your account.\n\n\
+         Time: {time}\n\
+         Device: {device}\n\
+         Network: {location}\n\n\
+         If this wasn't you, sign out everywhere: {revoke_url}\n\n\
+         The link works once and expires in {minutes} minutes.",
+        time = Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+        device = client.user_agent.as_deref().unwrap_or("unknown"),
+    );
+    state
         .email_client
-        .send_email(&email, "2FA code", &two_fa_code.as_ref())
+        .send_email(&email, "New sign-in to your account", &content)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))
+}
+
+/// Whether the login of `user` needs a 2FA code, because of their own setting
+/// or because their organization asks it of every member.
+pub(crate) async fn requires_2fa<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    user: &User,
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+) -> Result<bool, AuthAPIError> {
+    let Some(tenant_id) = user.tenant_id() else {
+        return Ok(user.requires_2fa());
+    };
+    match state
+        .organization_store
+        .read()
+        .await
+        .get_organization(tenant_id)
         .await
     {
-        return (jar, AuthAPIError::UnexpectedError(e.into()).into_response());
+        Ok(organization) => Ok(user.requires_2fa() || organization.settings.require_2fa),
+        Err(OrganizationStoreError::OrganizationNotFound) => Ok(user.requires_2fa()),
+        Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
     }
+}
 
-    let body = Json(body);
+/// Whether the request carries a trusted-device cookie of `user` that is
+/// still in the store, letting the login skip 2FA.
+#[tracing::instrument(name = "Check trusted device", skip_all)]
+pub(crate) async fn is_trusted_device<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    user: &User,
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    jar: &CookieJar,
+) -> bool {
+    let Some(cookie) = jar.get(&state.settings.auth.cookie.trusted_device_cookie_name()) else {
+        return false;
+    };
+    let claims = match validate_trusted_device_token(cookie.value(), &state.settings.auth) {
+        Ok(claims) if claims.user() == user.key() => claims,
+        _ => return false,
+    };
+    match state
+        .trusted_device_store
+        .read()
+        .await
+        .get_device(&user.key(), &claims.jti)
+        .await
+    {
+        Ok(_) => true,
+        Err(TrustedDeviceStoreError::DeviceNotFound) => false,
+        Err(e) => {
+            tracing::error!(error = ?e, "Failed to check trusted device");
+            false
+        }
+    }
+}
+
+/// Emails a 2FA code to the user, returning the attempt it completes.
+#[tracing::instrument(name = "Start 2FA", skip_all)]
+pub(crate) async fn start_2fa<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    user: &User,
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+) -> Result<LoginAttemptId, AuthAPIError> {
+    let login_attempt_id = LoginAttemptId::default();
+    let two_fa_code = TwoFACode::default();
+
+    state
+        .two_fa_code_store
+        .write()
+        .await
+        .add_code(user.key(), login_attempt_id.clone(), two_fa_code.clone())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    state
+        .email_client
+        .send_email(&user.email(), "2FA code", two_fa_code.as_ref())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok(login_attempt_id)
+}
+
+/// The login page, opened on its 2FA form, for logins that end with a
+/// redirect. The attempt is in the fragment, which browsers don't send to
+/// servers.
+pub(crate) fn two_fa_location(
+    application: &ApplicationSettings,
+    user: &User,
+    login_attempt_id: &LoginAttemptId,
+    return_to: Option<&str>,
+) -> Result<String, url::ParseError> {
+    let mut url = Url::parse(&application.public_url("/"))?;
+    if let Some(return_to) = return_to {
+        url.query_pairs_mut().append_pair("return_to", return_to);
+    }
+    let mut fragment = url::form_urlencoded::Serializer::new(String::new());
+    fragment
+        .append_pair("login_attempt_id", login_attempt_id.as_ref())
+        .append_pair("email", user.email_str());
+    if let Some(tenant_id) = user.tenant_id() {
+        fragment.append_pair("organization_id", tenant_id);
+    }
+    let fragment = fragment.finish();
+    url.set_fragment(Some(&fragment));
+    Ok(url.into())
+}
+
+#[tracing::instrument(name = "Handle 2FA", skip_all)]
+async fn handle_2fa<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    user: &User,
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    jar: CookieJar,
+) -> (CookieJar, Response<Body>) {
+    let login_attempt_id = match start_2fa(user, state).await {
+        Ok(login_attempt_id) => login_attempt_id,
+        Err(e) => return (jar, e.into_response()),
+    };
+
+    let body = Json(TwoFactorAuthResponse {
+        message: "2FA required".to_owned(),
+        login_attempt_id: login_attempt_id.as_ref().to_owned(),
+    });
     let response = (StatusCode::PARTIAL_CONTENT, body).into_response();
     (jar, response)
 }
 
 #[tracing::instrument(name = "Login", skip_all)]
-pub async fn login<T: UserStore, U: BannedTokenStore, V: TwoFACodeStore, W: EmailClient>(
-    State(state): State<AppState<T, U, V, W>>,
+pub async fn login<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    client: ClientInfo,
     jar: CookieJar,
-    Json(request): Json<LoginRequest>,
+    JsonBody(request): JsonBody<LoginRequest>,
+) -> (CookieJar, Response<Body>) {
+    let (jar, response) = authenticate(&state, &client, jar, &request).await;
+
+    let status = response.status();
+    // the attempted email, even when it doesn't belong to anyone
+    let actor = Email::parse(&request.email).ok();
+    let mut events = vec![AuditEventType::Login];
+    if status == StatusCode::PARTIAL_CONTENT {
+        events.push(AuditEventType::TwoFactorCodeSent);
+    }
+    for event_type in events {
+        let mut event = AuditEvent::new(event_type, &client, AuditOutcome::from_status(status));
+        if let Some(actor) = &actor {
+            event = event.with_actor(actor.as_ref());
+        }
+        state.record_audit_event(event).await;
+    }
+    (jar, response)
+}
+
+async fn authenticate<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    client: &ClientInfo,
+    jar: CookieJar,
+    request: &LoginRequest,
 ) -> (CookieJar, Response<Body>) {
     // requires_2fa is always false because here we are just checking if it is a valid email and password.
     // and the parse method in User does that.
+    let key = UserKey::new(request.organization_id.as_deref(), &request.email);
     let user_store = state.user_store.read().await;
-    let validation = user_store
-        .validate_user(&request.email, &request.password)
-        .await;
+    let validation = user_store.validate_user(&key, &request.password).await;
     if validation.is_err() {
         return (jar, AuthAPIError::IncorrectCredentials.into_response());
     }
-    let user = match user_store.get_user(&request.email).await {
+    let user = match user_store.get_user(&key).await {
         Ok(stored_user) => stored_user,
         Err(_) => {
             // it should happen because user_store.validate_user() already makes sure that
@@ -101,9 +457,27 @@ pub async fn login<T: UserStore, U: BannedTokenStore, V: TwoFACodeStore, W: Emai
             return (jar, AuthAPIError::IncorrectCredentials.into_response());
         }
     };
-    if user.requires_2fa() {
-        handle_2fa(&user.email(), &state, jar).await
+    // only told once the password is known to be right
+    // released before tracking the device, which writes to the store
+    drop(user_store);
+    if let Err(e) = user.status().ensure_active() {
+        return (jar, e.into_response());
+    }
+    let requires_2fa = match requires_2fa(&user, state).await {
+        Ok(requires_2fa) => requires_2fa,
+        Err(e) => return (jar, e.into_response()),
+    };
+    if requires_2fa && !is_trusted_device(&user, state, &jar).await {
+        handle_2fa(&user, state, jar).await
     } else {
-        handle_no_2fa(&user.email(), jar).await
+        let jar = track_device(&user, state, client, jar).await;
+        issue_auth_token(
+            &user.email(),
+            user.roles(),
+            user.tenant_id(),
+            &state.settings.auth,
+            request.token_delivery,
+            jar,
+        )
     }
 }
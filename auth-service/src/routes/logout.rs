@@ -1,42 +1,53 @@
 use crate::app_state::AppState;
-use crate::domain::EmailClient;
-use crate::domain::data_stores::{BannedTokenStore, TwoFACodeStore, UserStore};
+use crate::domain::data_stores::{
+    AccessTokenStore, BannedTokenStore, MagicLinkStore, OAuthStore, OrganizationStore,
+    TrustedDeviceStore, TwoFACodeStore, UserStore,
+};
 use crate::domain::error::AuthAPIError;
-use crate::utils::auth::validate_token;
-use crate::utils::constants::JWT_COOKIE_NAME;
+use crate::domain::{AuditEvent, AuditEventType, AuditLog, AuditOutcome, ClientInfo, EmailClient};
+use crate::utils::auth::auth_removal_cookie;
+use crate::utils::extractors::{AuthenticatedUser, TokenSource};
 use axum::{extract::State, http::StatusCode, response::IntoResponse};
-use axum_extra::extract::cookie::{Cookie, CookieJar};
+use axum_extra::extract::cookie::CookieJar;
 
 #[tracing::instrument(name = "Logout", skip_all)]
-pub async fn logout<T: UserStore, U: BannedTokenStore, V: TwoFACodeStore, W: EmailClient>(
-    State(state): State<AppState<T, U, V, W>>,
+pub async fn logout<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    client: ClientInfo,
     jar: CookieJar,
+    user: AuthenticatedUser,
 ) -> (CookieJar, impl IntoResponse) {
-    let cookie = match jar.get(JWT_COOKIE_NAME) {
-        Some(cookie) => cookie,
-        None => return (jar, AuthAPIError::MissingToken.into_response()),
-    };
-    let token = cookie.value().to_owned();
-    let _ = match validate_token(&token).await {
-        Ok(_) => {
-            match state
-                .banned_token_store
-                .write()
-                .await
-                .add_token(token)
-                .await
-            {
-                Ok(_) => {}
-                Err(e) => return (jar, AuthAPIError::UnexpectedError(e.into()).into_response()),
-            }
-        }
-        Err(_) => return (jar, AuthAPIError::InvalidToken.into_response()),
-    };
+    let result = state
+        .banned_token_store
+        .write()
+        .await
+        .add_token(user.token)
+        .await;
+    let event = AuditEvent::new(
+        AuditEventType::Logout,
+        &client,
+        AuditOutcome::from_result(&result),
+    )
+    .with_actor(user.email.as_ref());
+    state.record_audit_event(event).await;
+    if let Err(e) = result {
+        return (jar, AuthAPIError::UnexpectedError(e.into()).into_response());
+    }
 
-    let cookie_to_remove = Cookie::build((JWT_COOKIE_NAME, ""))
-        .path("/")
-        .http_only(true);
-
-    let updated_jar = jar.remove(cookie_to_remove);
+    let updated_jar = match user.source {
+        TokenSource::Cookie => jar.remove(auth_removal_cookie(&state.settings.auth.cookie)),
+        TokenSource::Bearer => jar,
+    };
     (updated_jar, StatusCode::OK.into_response())
 }
@@ -0,0 +1,212 @@
+use crate::app_state::AppState;
+use crate::domain::data_stores::{
+    AccessTokenStore, BannedTokenStore, LinkPurpose, MagicLinkStore, MagicLinkStoreError,
+    OAuthStore, OrganizationStore, TrustedDeviceStore, TwoFACodeStore, UserStore,
+};
+use crate::domain::{
+    AuditEvent, AuditEventType, AuditLog, AuditOutcome, AuthAPIError, ClientInfo, Email,
+    EmailClient, FieldError, UserKey, UserStoreError, generate_secret, hash_secret,
+};
+use crate::routes::login::{
+    is_trusted_device, requires_2fa, start_2fa, track_device, two_fa_location,
+};
+use crate::utils::auth::generate_auth_cookie;
+use crate::utils::extractors::{JsonBody, QueryParams};
+use axum::{Json, extract::State, http::StatusCode, response::Redirect};
+use axum_extra::extract::CookieJar;
+use color_eyre::eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct MagicLinkRequest {
+    pub email: String,
+    /// The account of the email in this organization, the one outside any
+    /// when missing.
+    #[serde(default, rename = "organizationId")]
+    pub organization_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MagicLinkResponse {
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct MagicLinkCallbackQuery {
+    pub token: String,
+}
+
+/// Emails a single-use sign-in link. The response is the same whether the
+/// email belongs to a user or not, so it can't be used to find accounts.
+#[tracing::instrument(name = "Request magic link", skip_all)]
+pub async fn request_magic_link<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    client: ClientInfo,
+    JsonBody(request): JsonBody<MagicLinkRequest>,
+) -> Result<(StatusCode, Json<MagicLinkResponse>), AuthAPIError> {
+    let email = Email::parse(&request.email).map_err(|_| FieldError::invalid_email("email"))?;
+
+    let user = UserKey::new(request.organization_id.as_deref(), email.as_ref());
+    let result = send_magic_link(&email, &user, &state).await;
+    let event = AuditEvent::new(
+        AuditEventType::MagicLinkSent,
+        &client,
+        AuditOutcome::from_result(&result),
+    )
+    .with_actor(email.as_ref());
+    state.record_audit_event(event).await;
+    // unknown and inactive users get the same answer as everyone else
+    if let Err(AuthAPIError::UnexpectedError(e)) = result {
+        return Err(AuthAPIError::UnexpectedError(e));
+    }
+
+    let response = Json(MagicLinkResponse {
+        message: "If the email belongs to an account, a sign-in link was sent to it".to_owned(),
+    });
+    Ok((StatusCode::ACCEPTED, response))
+}
+
+async fn send_magic_link<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    email: &Email,
+    key: &UserKey,
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+) -> Result<(), AuthAPIError> {
+    let user = match state.user_store.read().await.get_user(key).await {
+        Ok(user) => user,
+        Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::UserNotFound),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+    user.status().ensure_active()?;
+
+    // only the hash is stored, the link is the one place the token exists
+    let token = generate_secret();
+    state
+        .magic_link_store
+        .write()
+        .await
+        .add_token(LinkPurpose::SignIn, &hash_secret(&token), key.clone())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let link = state
+        .settings
+        .application
+        .public_url(&format!("/login/magic-link/callback?token={token}"));
+    let minutes = state.settings.auth.magic_link_ttl_seconds.div_ceil(60);
+    let content = format!(
+        "Sign in to your account: {link}\n\n\
+         The link works once and expires in {minutes} minutes. \
+         If you didn't ask for it, you can ignore this email."
+    );
+    state
+        .email_client
+        .send_email(email, "Your sign-in link", &content)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))
+}
+
+/// Where the emailed link leads. The link is used up, then the user is logged
+/// in, or sent to the 2FA form if they need it.
+#[tracing::instrument(name = "Magic link callback", skip_all)]
+pub async fn magic_link_callback<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    client: ClientInfo,
+    jar: CookieJar,
+    QueryParams(query): QueryParams<MagicLinkCallbackQuery>,
+) -> Result<(CookieJar, Redirect), AuthAPIError> {
+    let key = match state
+        .magic_link_store
+        .write()
+        .await
+        .take_token(LinkPurpose::SignIn, &hash_secret(&query.token))
+        .await
+    {
+        Ok(key) => Some(key),
+        Err(MagicLinkStoreError::TokenNotFound) => None,
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+
+    let result = async {
+        let key = key.as_ref().ok_or(AuthAPIError::InvalidLoginState)?;
+        let user = match state.user_store.read().await.get_user(key).await {
+            Ok(user) => user,
+            // deleted since the link was sent
+            Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::InvalidLoginState),
+            Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+        };
+        user.status().ensure_active()?;
+        let login_attempt_id = if requires_2fa(&user, &state).await?
+            && !is_trusted_device(&user, &state, &jar).await
+        {
+            Some(start_2fa(&user, &state).await?)
+        } else {
+            None
+        };
+        Ok((user, login_attempt_id))
+    }
+    .await;
+
+    let mut events = vec![AuditEventType::Login];
+    if matches!(&result, Ok((_, Some(_)))) {
+        events.push(AuditEventType::TwoFactorCodeSent);
+    }
+    for event_type in events {
+        let mut event = AuditEvent::new(event_type, &client, AuditOutcome::from_result(&result));
+        if let Some(key) = &key {
+            event = event.with_actor(key.email.as_str());
+        }
+        state.record_audit_event(event).await;
+    }
+    let (user, login_attempt_id) = result?;
+    let settings = &state.settings;
+    if let Some(login_attempt_id) = login_attempt_id {
+        let location = two_fa_location(&settings.application, &user, &login_attempt_id, None)
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+        return Ok((jar, Redirect::to(&location)));
+    }
+    let jar = track_device(&user, &state, &client, jar).await;
+    let cookie = generate_auth_cookie(
+        &user.email(),
+        user.roles(),
+        user.tenant_id(),
+        &settings.auth,
+    )
+    .map_err(|_| AuthAPIError::UnexpectedError(eyre!("Failed to create auth token")))?;
+    Ok((
+        jar.add(cookie),
+        Redirect::to(&settings.application.public_url("/")),
+    ))
+}
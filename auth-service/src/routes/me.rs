@@ -0,0 +1,251 @@
+use crate::app_state::AppState;
+use crate::domain::data_stores::{
+    AccessTokenStore, BannedTokenStore, MagicLinkStore, OAuthStore, OrganizationStore,
+    TrustedDeviceStore, TwoFACodeStore, UserStore,
+};
+use crate::domain::{
+    AuditEvent, AuditEventType, AuditLog, AuditOutcome, AuthAPIError, ClientInfo, DisplayName,
+    EmailClient, FieldError, Locale, User, UserStoreError,
+};
+use crate::utils::extractors::{AuthenticatedUser, JsonBody, QueryParams};
+use crate::utils::pagination::Page;
+use axum::{Json, extract::State};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MeResponse {
+    pub email: String,
+    #[serde(rename = "requires2FA")]
+    pub requires_2fa: bool,
+    #[serde(rename = "emailVerified")]
+    pub email_verified: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "displayName")]
+    pub display_name: Option<String>,
+    pub locale: Option<String>,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default, rename = "organizationId")]
+    pub organization_id: Option<String>,
+}
+
+impl From<&User> for MeResponse {
+    fn from(user: &User) -> Self {
+        let profile = user.profile();
+        Self {
+            email: user.email_str().to_owned(),
+            requires_2fa: user.requires_2fa(),
+            email_verified: user.email_verified(),
+            created_at: user.created_at(),
+            display_name: profile
+                .display_name
+                .as_ref()
+                .map(|name| name.as_ref().to_owned()),
+            locale: profile
+                .locale
+                .as_ref()
+                .map(|locale| locale.as_ref().to_owned()),
+            roles: user
+                .roles()
+                .iter()
+                .map(|role| role.as_ref().to_owned())
+                .collect(),
+            organization_id: user.tenant_id().map(str::to_owned),
+        }
+    }
+}
+
+// Tells a missing field (None, left unchanged) from an explicit null
+// (Some(None), cleared).
+fn deserialize_some<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateMeRequest {
+    #[serde(default, rename = "displayName", deserialize_with = "deserialize_some")]
+    pub display_name: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub locale: Option<Option<String>>,
+}
+
+fn map_user_store_error(e: UserStoreError) -> AuthAPIError {
+    match e {
+        // the token outlived its user
+        UserStoreError::UserNotFound => AuthAPIError::InvalidToken,
+        e => AuthAPIError::UnexpectedError(e.into()),
+    }
+}
+
+#[tracing::instrument(name = "Get Me", skip_all)]
+pub async fn get_me<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    user: AuthenticatedUser,
+) -> Result<Json<MeResponse>, AuthAPIError> {
+    let user = state
+        .user_store
+        .read()
+        .await
+        .get_user(&user.key())
+        .await
+        .map_err(map_user_store_error)?;
+    Ok(Json(MeResponse::from(&user)))
+}
+
+#[tracing::instrument(name = "Update Me", skip_all)]
+pub async fn patch_me<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    client: ClientInfo,
+    user: AuthenticatedUser,
+    JsonBody(request): JsonBody<UpdateMeRequest>,
+) -> Result<Json<MeResponse>, AuthAPIError> {
+    let result = async {
+        let mut user_store = state.user_store.write().await;
+        let mut profile = user_store
+            .get_user(&user.key())
+            .await
+            .map_err(map_user_store_error)?
+            .profile()
+            .clone();
+
+        if let Some(display_name) = request.display_name {
+            profile.display_name = display_name
+                .map(|name| DisplayName::parse(&name))
+                .transpose()
+                .map_err(|e| {
+                    FieldError::from_validation("displayName", "invalid_display_name", &e)
+                })?;
+        }
+        if let Some(locale) = request.locale {
+            profile.locale = locale
+                .map(|locale| Locale::parse(&locale))
+                .transpose()
+                .map_err(|e| FieldError::from_validation("locale", "invalid_locale", &e))?;
+        }
+
+        let user = user_store
+            .update_profile(&user.key(), profile)
+            .await
+            .map_err(map_user_store_error)?;
+        Ok::<_, AuthAPIError>(Json(MeResponse::from(&user)))
+    }
+    .await;
+
+    let event = AuditEvent::new(
+        AuditEventType::ProfileUpdated,
+        &client,
+        AuditOutcome::from_result(&result),
+    )
+    .with_actor(user.email.as_ref());
+    state.record_audit_event(event).await;
+    result
+}
+
+#[derive(Deserialize)]
+pub struct ActivityQuery {
+    pub page: Option<usize>,
+    #[serde(rename = "perPage")]
+    pub per_page: Option<usize>,
+}
+
+/// An audit event as shown to the user it concerns. Where the request came
+/// from is only shown for the user's own requests, not e.g. for an admin's.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActivityEvent {
+    #[serde(rename = "eventType")]
+    pub event_type: AuditEventType,
+    pub outcome: AuditOutcome,
+    pub ip: Option<String>,
+    #[serde(rename = "userAgent")]
+    pub user_agent: Option<String>,
+    #[serde(rename = "occurredAt")]
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl ActivityEvent {
+    fn for_user(event: AuditEvent, email: &str) -> Self {
+        let own_request = event.actor.as_deref() == Some(email);
+        Self {
+            event_type: event.event_type,
+            outcome: event.outcome,
+            ip: event.ip.filter(|_| own_request),
+            user_agent: event.user_agent.filter(|_| own_request),
+            occurred_at: event.occurred_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActivityResponse {
+    pub events: Vec<ActivityEvent>,
+    pub page: usize,
+    #[serde(rename = "perPage")]
+    pub per_page: usize,
+}
+
+/// The audit events of the user, most recent first.
+#[tracing::instrument(name = "Get My Activity", skip_all)]
+pub async fn get_my_activity<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    user: AuthenticatedUser,
+    QueryParams(query): QueryParams<ActivityQuery>,
+) -> Result<Json<ActivityResponse>, AuthAPIError> {
+    let page = Page::parse(query.page, query.per_page)?;
+    let email = user.email.as_ref();
+    let events = state
+        .audit_log
+        .read()
+        .await
+        .user_events(email, page.offset(), page.per_page)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok(Json(ActivityResponse {
+        events: events
+            .into_iter()
+            .map(|event| ActivityEvent::for_user(event, email))
+            .collect(),
+        page: page.page,
+        per_page: page.per_page,
+    }))
+}
@@ -0,0 +1,773 @@
+use crate::app_state::AppState;
+use crate::domain::data_stores::{
+    AccessTokenStore, BannedTokenStore, MagicLinkStore, OAuthStore, OAuthStoreError,
+    OrganizationStore, TrustedDeviceStore, TwoFACodeStore, UserStore,
+};
+use crate::domain::{
+    AuditEvent, AuditEventType, AuditLog, AuditOutcome, AuthAPIError, AuthorizationGrant,
+    ClientInfo, Email, EmailClient, OAuthClient, OAuthError, SUPPORTED_SCOPES, UserStoreError,
+    parse_scopes, parse_service_scopes, verify_pkce,
+};
+use crate::utils::auth::{generate_access_token, generate_service_token, issued_and_expiry};
+use crate::utils::extractors::{
+    AccessTokenUser, AuthenticatedUser, JsonBody, PathParams, basic_credentials,
+    ensure_account_active,
+};
+use crate::utils::oidc::{IdTokenClaims, UserInfo};
+use axum::{
+    Form, Json,
+    extract::{
+        Query, RawQuery, State,
+        rejection::{FormRejection, QueryRejection},
+    },
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Redirect, Response},
+};
+use chrono::{DateTime, Duration, Utc};
+use color_eyre::eyre::eyre;
+use jsonwebtoken::jwk::JwkSet;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+const CODE_RESPONSE_TYPE: &str = "code";
+const AUTHORIZATION_CODE_GRANT_TYPE: &str = "authorization_code";
+const CLIENT_CREDENTIALS_GRANT_TYPE: &str = "client_credentials";
+const PKCE_METHOD: &str = "S256";
+// base64url of a SHA-256 digest
+const CODE_CHALLENGE_LENGTH: usize = 43;
+
+/// Parameters of an authorization request, as sent by the client to
+/// `GET /authorize` and passed on by the consent page to `POST /authorize`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthorizeRequest {
+    pub response_type: String,
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub state: Option<String>,
+    pub nonce: Option<String>,
+    pub code_challenge: String,
+    pub code_challenge_method: String,
+    /// `none` to fail instead of asking the user anything, `consent` to ask
+    /// again for scopes already granted.
+    pub prompt: Option<String>,
+}
+
+impl AuthorizeRequest {
+    fn prompt_is(&self, prompt: &str) -> bool {
+        self.prompt.as_deref() == Some(prompt)
+    }
+
+    /// Where to send the user back with `params` and the request's `state`.
+    fn redirect_uri_with(&self, params: &[(&str, &str)]) -> String {
+        // registered redirect URIs are valid URLs
+        let mut url = Url::parse(&self.redirect_uri).expect("Invalid redirect URI");
+        {
+            let mut query = url.query_pairs_mut();
+            query.extend_pairs(params);
+            if let Some(state) = &self.state {
+                query.append_pair("state", state);
+            }
+        }
+        url.into()
+    }
+
+    fn error_redirect_uri(&self, error: OAuthError) -> String {
+        let response = error.to_response();
+        let mut params = vec![("error", response.error.as_str())];
+        if let Some(description) = &response.error_description {
+            params.push(("error_description", description));
+        }
+        self.redirect_uri_with(&params)
+    }
+}
+
+/// The user's answer on the consent page.
+#[derive(Deserialize)]
+pub struct AuthorizeDecision {
+    #[serde(flatten)]
+    pub request: AuthorizeRequest,
+    pub approve: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuthorizeResponse {
+    #[serde(rename = "redirectTo")]
+    pub redirect_to: String,
+}
+
+#[derive(Deserialize)]
+pub struct TokenRequest {
+    pub grant_type: String,
+    #[serde(default)]
+    pub code: String,
+    #[serde(default)]
+    pub redirect_uri: String,
+    #[serde(default)]
+    pub code_verifier: String,
+    /// Public clients, and confidential ones not using HTTP Basic, send
+    /// their credentials in the form.
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    /// Scopes a service asks for with the client credentials grant, all the
+    /// scopes of its API key when missing.
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OAuthTokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+    pub id_token: String,
+    pub scope: String,
+}
+
+/// Token of the client credentials grant, there is no user to identify.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServiceTokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+    pub scope: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenIdConfiguration {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub userinfo_endpoint: String,
+    pub jwks_uri: String,
+    pub response_types_supported: Vec<String>,
+    pub grant_types_supported: Vec<String>,
+    pub subject_types_supported: Vec<String>,
+    pub id_token_signing_alg_values_supported: Vec<String>,
+    pub scopes_supported: Vec<String>,
+    pub token_endpoint_auth_methods_supported: Vec<String>,
+    pub code_challenge_methods_supported: Vec<String>,
+    pub claims_supported: Vec<String>,
+}
+
+/// What the consent page shows about a client.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OAuthClientResponse {
+    #[serde(rename = "clientId")]
+    pub client_id: String,
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct RegisterOAuthClientRequest {
+    pub name: String,
+    #[serde(rename = "redirectUris")]
+    pub redirect_uris: Vec<String>,
+    /// Confidential clients, e.g. server-side apps, get a secret.
+    #[serde(default)]
+    pub confidential: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegisteredOAuthClientResponse {
+    #[serde(rename = "clientId")]
+    pub client_id: String,
+    pub name: String,
+    #[serde(rename = "redirectUris")]
+    pub redirect_uris: Vec<String>,
+    /// Only shown once, it is stored hashed.
+    #[serde(rename = "clientSecret", skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+}
+
+fn server_error(e: impl Into<color_eyre::eyre::Report>) -> OAuthError {
+    OAuthError::ServerError(e.into())
+}
+
+// Failures before the redirect URI is known to belong to the client are
+// returned to the browser, anything later goes back to the client.
+async fn validate_client<Z: OAuthStore>(
+    oauth_store: &tokio::sync::RwLock<Z>,
+    request: &AuthorizeRequest,
+) -> Result<OAuthClient, OAuthError> {
+    let client = match oauth_store
+        .read()
+        .await
+        .get_client(&request.client_id)
+        .await
+    {
+        Ok(client) => client,
+        Err(OAuthStoreError::ClientNotFound) => {
+            return Err(OAuthError::InvalidRequest("Unknown client_id".to_owned()));
+        }
+        Err(e) => return Err(server_error(e)),
+    };
+    if !client.allows_redirect_uri(&request.redirect_uri) {
+        return Err(OAuthError::InvalidRequest(
+            "redirect_uri is not registered for the client".to_owned(),
+        ));
+    }
+    Ok(client)
+}
+
+fn validate_grant(request: &AuthorizeRequest) -> Result<AuthorizationGrant, OAuthError> {
+    if request.response_type != CODE_RESPONSE_TYPE {
+        return Err(OAuthError::UnsupportedResponseType);
+    }
+    let scopes = parse_scopes(&request.scope).map_err(OAuthError::InvalidScope)?;
+    if request.code_challenge_method != PKCE_METHOD
+        || request.code_challenge.len() != CODE_CHALLENGE_LENGTH
+    {
+        return Err(OAuthError::InvalidRequest(
+            "A PKCE code_challenge with the S256 method is required".to_owned(),
+        ));
+    }
+    Ok(AuthorizationGrant {
+        client_id: request.client_id.clone(),
+        redirect_uri: request.redirect_uri.clone(),
+        scopes,
+        nonce: request.nonce.clone().filter(|nonce| !nonce.is_empty()),
+        code_challenge: request.code_challenge.clone(),
+    })
+}
+
+// Issues a code for the grant, returning where to send the user with it.
+async fn issue_code<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    user: &AuthenticatedUser,
+    request: &AuthorizeRequest,
+    grant: AuthorizationGrant,
+) -> Result<String, OAuthError> {
+    // the session token was issued when the user logged in
+    let auth_time = DateTime::from_timestamp(user.claims.iat as i64, 0).unwrap_or_else(Utc::now);
+    let ttl = Duration::seconds(state.settings.oidc.code_ttl_seconds);
+    let code = grant.issue_code(&user.key(), auth_time, ttl);
+    let redirect_uri = request.redirect_uri_with(&[("code", &code.code)]);
+    state
+        .oauth_store
+        .write()
+        .await
+        .add_code(code)
+        .await
+        .map_err(server_error)?;
+    Ok(redirect_uri)
+}
+
+/// Start of the authorization code flow. Users are sent to the login page
+/// first if needed, then to the consent page for scopes they haven't granted
+/// the client yet, and finally back to the client with a code.
+#[tracing::instrument(name = "Authorize", skip_all)]
+pub async fn authorize<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    user: Option<AuthenticatedUser>,
+    RawQuery(raw_query): RawQuery,
+    request: Result<Query<AuthorizeRequest>, QueryRejection>,
+) -> Result<Redirect, OAuthError> {
+    // the RFC 6749 error body, rather than axum's plain text
+    let Query(request) = request.map_err(|e| OAuthError::InvalidRequest(e.body_text()))?;
+    let client = validate_client(&state.oauth_store, &request).await?;
+    let grant = match validate_grant(&request) {
+        Ok(grant) => grant,
+        Err(e) => return Ok(Redirect::to(&request.error_redirect_uri(e))),
+    };
+    let raw_query = raw_query.unwrap_or_default();
+    let application = &state.settings.application;
+
+    let Some(user) = user else {
+        if request.prompt_is("none") {
+            return Ok(Redirect::to(
+                &request.error_redirect_uri(OAuthError::LoginRequired),
+            ));
+        }
+        // the login page comes back here once the user is logged in
+        let return_to = application.public_url(&format!("/authorize?{raw_query}"));
+        let login =
+            Url::parse_with_params(&application.public_url("/"), [("return_to", return_to)])
+                .map_err(server_error)?;
+        return Ok(Redirect::to(login.as_str()));
+    };
+    match ensure_account_active(&user.key(), &state.user_store).await {
+        Ok(()) => {}
+        Err(AuthAPIError::UnexpectedError(e)) => return Err(OAuthError::ServerError(e)),
+        Err(_) => {
+            return Ok(Redirect::to(
+                &request.error_redirect_uri(OAuthError::AccessDenied),
+            ));
+        }
+    }
+
+    let granted = state
+        .oauth_store
+        .read()
+        .await
+        .granted_scopes(&user.key(), &client.client_id)
+        .await
+        .map_err(server_error)?;
+    let consented = grant.scopes.iter().all(|scope| granted.contains(scope));
+    if consented && !request.prompt_is("consent") {
+        let redirect_uri = issue_code(&state, &user, &request, grant).await?;
+        return Ok(Redirect::to(&redirect_uri));
+    }
+    if request.prompt_is("none") {
+        return Ok(Redirect::to(
+            &request.error_redirect_uri(OAuthError::ConsentRequired),
+        ));
+    }
+    Ok(Redirect::to(
+        &application.public_url(&format!("/consent.html?{raw_query}")),
+    ))
+}
+
+/// Records the answer of the consent page, returning where to send the user.
+#[tracing::instrument(name = "Authorize decision", skip_all)]
+pub async fn authorize_decision<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    user: AuthenticatedUser,
+    client_info: ClientInfo,
+    JsonBody(decision): JsonBody<AuthorizeDecision>,
+) -> Result<Json<AuthorizeResponse>, OAuthError> {
+    let request = decision.request;
+    let client = validate_client(&state.oauth_store, &request).await?;
+    let redirect_to = match validate_grant(&request) {
+        Err(e) => request.error_redirect_uri(e),
+        Ok(_) if !decision.approve => request.error_redirect_uri(OAuthError::AccessDenied),
+        Ok(grant) => {
+            match ensure_account_active(&user.key(), &state.user_store).await {
+                Ok(()) => {}
+                Err(AuthAPIError::UnexpectedError(e)) => return Err(OAuthError::ServerError(e)),
+                Err(_) => {
+                    return Ok(Json(AuthorizeResponse {
+                        redirect_to: request.error_redirect_uri(OAuthError::AccessDenied),
+                    }));
+                }
+            }
+            let result = state
+                .oauth_store
+                .write()
+                .await
+                .grant_consent(&user.key(), &client.client_id, &grant.scopes)
+                .await;
+            let event = AuditEvent::new(
+                AuditEventType::ConsentGranted,
+                &client_info,
+                AuditOutcome::from_result(&result),
+            )
+            .with_actor(user.email.as_ref());
+            state.record_audit_event(event).await;
+            result.map_err(server_error)?;
+            issue_code(&state, &user, &request, grant).await?
+        }
+    };
+    Ok(Json(AuthorizeResponse { redirect_to }))
+}
+
+// The client from HTTP Basic or the form, checking the secret of
+// confidential clients. Public clients are bound to the code by PKCE.
+async fn authenticate_client<Z: OAuthStore>(
+    oauth_store: &tokio::sync::RwLock<Z>,
+    headers: &HeaderMap,
+    request: &TokenRequest,
+) -> Result<OAuthClient, OAuthError> {
+    let (client_id, client_secret) = match basic_credentials(headers) {
+        Some((client_id, client_secret)) => (client_id, Some(client_secret)),
+        None => (
+            request.client_id.clone().ok_or(OAuthError::InvalidClient)?,
+            request.client_secret.clone(),
+        ),
+    };
+    let client = match oauth_store.read().await.get_client(&client_id).await {
+        Ok(client) => client,
+        Err(OAuthStoreError::ClientNotFound) => return Err(OAuthError::InvalidClient),
+        Err(e) => return Err(server_error(e)),
+    };
+    if client.is_confidential()
+        && !client_secret.is_some_and(|secret| client.verify_secret(&secret))
+    {
+        return Err(OAuthError::InvalidClient);
+    }
+    Ok(client)
+}
+
+/// Exchanges an authorization code for an access token and an ID token, or
+/// the API key of a service for a service token.
+#[tracing::instrument(name = "Token", skip_all)]
+pub async fn token<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    headers: HeaderMap,
+    request: Result<Form<TokenRequest>, FormRejection>,
+) -> Result<Response, OAuthError> {
+    let Form(request) = request.map_err(|e| OAuthError::InvalidRequest(e.body_text()))?;
+    let response = match request.grant_type.as_str() {
+        AUTHORIZATION_CODE_GRANT_TYPE => exchange_code(&state, &headers, &request)
+            .await?
+            .into_response(),
+        CLIENT_CREDENTIALS_GRANT_TYPE => client_credentials(&state, &headers, &request)
+            .await?
+            .into_response(),
+        _ => return Err(OAuthError::UnsupportedGrantType),
+    };
+    let headers = [
+        (header::CACHE_CONTROL, "no-store"),
+        (header::PRAGMA, "no-cache"),
+    ];
+    Ok((headers, response).into_response())
+}
+
+async fn exchange_code<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    headers: &HeaderMap,
+    request: &TokenRequest,
+) -> Result<Json<OAuthTokenResponse>, OAuthError> {
+    let client = authenticate_client(&state.oauth_store, headers, request).await?;
+    let code = match state
+        .oauth_store
+        .write()
+        .await
+        .take_code(&request.code)
+        .await
+    {
+        Ok(code) => code,
+        Err(OAuthStoreError::CodeNotFound) => {
+            return Err(OAuthError::InvalidGrant(
+                "Unknown, expired or used code".to_owned(),
+            ));
+        }
+        Err(e) => return Err(server_error(e)),
+    };
+    if code.client_id != client.client_id || code.redirect_uri != request.redirect_uri {
+        return Err(OAuthError::InvalidGrant(
+            "The code was issued to another client or redirect_uri".to_owned(),
+        ));
+    }
+    if !verify_pkce(&request.code_verifier, &code.code_challenge) {
+        return Err(OAuthError::InvalidGrant(
+            "code_verifier doesn't match the code_challenge".to_owned(),
+        ));
+    }
+
+    let user = match state.user_store.read().await.get_user(&code.user()).await {
+        Ok(user) => user,
+        Err(UserStoreError::UserNotFound) => {
+            return Err(OAuthError::InvalidGrant(
+                "The user no longer exists".to_owned(),
+            ));
+        }
+        Err(e) => return Err(server_error(e)),
+    };
+    if user.status().ensure_active().is_err() {
+        return Err(OAuthError::InvalidGrant(
+            "The account is not active".to_owned(),
+        ));
+    }
+
+    let auth_settings = &state.settings.auth;
+    let email = Email::parse(&code.user_email).map_err(|e| server_error(eyre!(e)))?;
+    let access_token = generate_access_token(
+        &email,
+        &code.scopes,
+        code.tenant_id.as_deref(),
+        auth_settings,
+    )
+    .map_err(|_| server_error(eyre!("Failed to create access token")))?;
+    let (iat, exp) = issued_and_expiry(auth_settings.token_ttl_seconds)
+        .map_err(|_| server_error(eyre!("Failed to create ID token")))?;
+    let claims = IdTokenClaims {
+        iss: state.settings.application.public_url(""),
+        aud: client.client_id,
+        exp,
+        iat,
+        auth_time: code.auth_time.timestamp() as usize,
+        nonce: code.nonce,
+        user: UserInfo::new(&user, &code.scopes),
+    };
+    let id_token = state.signing_key.sign(&claims).map_err(server_error)?;
+
+    Ok(Json(OAuthTokenResponse {
+        access_token,
+        token_type: "Bearer".to_owned(),
+        expires_in: auth_settings.token_ttl_seconds,
+        id_token,
+        scope: code.scopes.join(" "),
+    }))
+}
+
+// The client credentials grant: an API key, as HTTP Basic or in the form, for
+// a token limited to the key's scopes.
+async fn client_credentials<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    headers: &HeaderMap,
+    request: &TokenRequest,
+) -> Result<Json<ServiceTokenResponse>, OAuthError> {
+    let (client_id, key) = match basic_credentials(headers) {
+        Some(credentials) => credentials,
+        None => match (&request.client_id, &request.client_secret) {
+            (Some(client_id), Some(key)) => (client_id.clone(), key.clone()),
+            _ => return Err(OAuthError::InvalidClient),
+        },
+    };
+    let api_key = match state.oauth_store.read().await.get_api_key(&client_id).await {
+        Ok(api_key) => api_key,
+        Err(OAuthStoreError::ApiKeyNotFound) => return Err(OAuthError::InvalidClient),
+        Err(e) => return Err(server_error(e)),
+    };
+    if api_key.is_expired() || !api_key.verify(&key) {
+        return Err(OAuthError::InvalidClient);
+    }
+
+    let scopes = match request.scope.as_deref() {
+        Some(scope) => parse_service_scopes(scope).map_err(OAuthError::InvalidScope)?,
+        None => Vec::new(),
+    };
+    if let Some(unknown) = scopes.iter().find(|scope| !api_key.scopes.contains(scope)) {
+        return Err(OAuthError::InvalidScope(format!(
+            "The API key doesn't have the `{unknown}` scope"
+        )));
+    }
+    let scopes = if scopes.is_empty() {
+        api_key.scopes
+    } else {
+        scopes
+    };
+
+    let now = Utc::now();
+    state
+        .oauth_store
+        .write()
+        .await
+        .record_api_key_use(&api_key.id, now)
+        .await
+        .map_err(server_error)?;
+    let auth_settings = &state.settings.auth;
+    let access_token = generate_service_token(&api_key.id, &scopes, auth_settings)
+        .map_err(|_| server_error(eyre!("Failed to create service token")))?;
+    Ok(Json(ServiceTokenResponse {
+        access_token,
+        token_type: "Bearer".to_owned(),
+        expires_in: auth_settings.token_ttl_seconds,
+        scope: scopes.join(" "),
+    }))
+}
+
+/// Claims about the user of an access token, limited to its scopes.
+#[tracing::instrument(name = "Userinfo", skip_all)]
+pub async fn userinfo<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    user: AccessTokenUser,
+) -> Result<Json<UserInfo>, AuthAPIError> {
+    let stored_user = match state.user_store.read().await.get_user(&user.key()).await {
+        Ok(user) => user,
+        // the token outlived its user
+        Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::InvalidToken),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+    stored_user.status().ensure_active()?;
+    Ok(Json(UserInfo::new(&stored_user, &user.scopes)))
+}
+
+#[tracing::instrument(name = "OpenID configuration", skip_all)]
+pub async fn openid_configuration<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+) -> Json<OpenIdConfiguration> {
+    let application = &state.settings.application;
+    let strings = |values: &[&str]| values.iter().map(|value| value.to_string()).collect();
+    Json(OpenIdConfiguration {
+        issuer: application.public_url(""),
+        authorization_endpoint: application.public_url("/authorize"),
+        token_endpoint: application.public_url("/token"),
+        userinfo_endpoint: application.public_url("/userinfo"),
+        jwks_uri: application.public_url("/.well-known/jwks.json"),
+        response_types_supported: strings(&[CODE_RESPONSE_TYPE]),
+        grant_types_supported: strings(&[
+            AUTHORIZATION_CODE_GRANT_TYPE,
+            CLIENT_CREDENTIALS_GRANT_TYPE,
+        ]),
+        subject_types_supported: strings(&["public"]),
+        id_token_signing_alg_values_supported: strings(&["ES256"]),
+        scopes_supported: strings(&SUPPORTED_SCOPES),
+        token_endpoint_auth_methods_supported: strings(&[
+            "client_secret_basic",
+            "client_secret_post",
+            "none",
+        ]),
+        code_challenge_methods_supported: strings(&[PKCE_METHOD]),
+        claims_supported: strings(&[
+            "iss",
+            "sub",
+            "aud",
+            "exp",
+            "iat",
+            "auth_time",
+            "nonce",
+            "email",
+            "email_verified",
+            "name",
+            "locale",
+        ]),
+    })
+}
+
+#[tracing::instrument(name = "JWKS", skip_all)]
+pub async fn jwks<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+) -> Json<JwkSet> {
+    Json(state.signing_key.jwks())
+}
+
+#[tracing::instrument(name = "Get OAuth client", skip_all)]
+pub async fn get_oauth_client<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    PathParams(client_id): PathParams<String>,
+) -> Result<Json<OAuthClientResponse>, AuthAPIError> {
+    match state.oauth_store.read().await.get_client(&client_id).await {
+        Ok(client) => Ok(Json(OAuthClientResponse {
+            client_id: client.client_id,
+            name: client.name,
+        })),
+        Err(OAuthStoreError::ClientNotFound) => Err(AuthAPIError::OAuthClientNotFound),
+        Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+}
+
+#[tracing::instrument(name = "Register OAuth client", skip_all)]
+pub async fn register_oauth_client<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    JsonBody(request): JsonBody<RegisterOAuthClientRequest>,
+) -> Result<(StatusCode, Json<RegisteredOAuthClientResponse>), AuthAPIError> {
+    let (client, client_secret) =
+        OAuthClient::register(request.name, request.redirect_uris, request.confidential)?;
+    let response = RegisteredOAuthClientResponse {
+        client_id: client.client_id.clone(),
+        name: client.name.clone(),
+        redirect_uris: client.redirect_uris.clone(),
+        client_secret,
+    };
+    state
+        .oauth_store
+        .write()
+        .await
+        .add_client(client)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    Ok((StatusCode::CREATED, Json(response)))
+}
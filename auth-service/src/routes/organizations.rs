@@ -0,0 +1,558 @@
+use crate::app_state::AppState;
+use crate::domain::data_stores::{
+    AccessTokenStore, BannedTokenStore, MagicLinkStore, OAuthStore, OrganizationStore,
+    OrganizationStoreError, TrustedDeviceStore, TwoFACodeStore, UserStore, UserStoreError,
+};
+use crate::domain::{
+    ADMIN_ROLE, AuditEvent, AuditEventType, AuditLog, AuditOutcome, AuthAPIError, ClientInfo,
+    DEFAULT_INVITATION_TTL_DAYS, Email, EmailClient, FieldError, Invitation, Organization,
+    Password, TenantSettings, User, UserKey, UserProfile, hash_secret,
+};
+use crate::routes::{InvitationResponse, MeResponse};
+use crate::utils::extractors::{AuthenticatedUser, JsonBody, PathParams};
+use axum::{Json, extract::State, http::StatusCode};
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::eyre;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrganizationSettings {
+    #[serde(rename = "require2FA")]
+    pub require_2fa: bool,
+    #[serde(rename = "minPasswordLength")]
+    pub min_password_length: usize,
+}
+
+impl From<TenantSettings> for OrganizationSettings {
+    fn from(settings: TenantSettings) -> Self {
+        Self {
+            require_2fa: settings.require_2fa,
+            min_password_length: settings.min_password_length,
+        }
+    }
+}
+
+impl TryFrom<OrganizationSettings> for TenantSettings {
+    type Error = AuthAPIError;
+
+    fn try_from(settings: OrganizationSettings) -> Result<Self, Self::Error> {
+        Ok(TenantSettings::new(
+            settings.require_2fa,
+            settings.min_password_length,
+        )?)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateOrganizationRequest {
+    pub name: String,
+    /// Of the account of the owner in the organization.
+    pub password: String,
+    #[serde(default, rename = "require2FA")]
+    pub require_2fa: bool,
+    #[serde(rename = "minPasswordLength")]
+    pub min_password_length: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrganizationResponse {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "ownerEmail")]
+    pub owner_email: String,
+    pub settings: OrganizationSettings,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    pub members: Vec<String>,
+}
+
+impl OrganizationResponse {
+    fn new(organization: Organization, members: Vec<String>) -> Self {
+        Self {
+            id: organization.id,
+            name: organization.name,
+            owner_email: organization.owner_email,
+            settings: organization.settings.into(),
+            created_at: organization.created_at,
+            members,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct InviteMemberRequest {
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct AcceptInvitationRequest {
+    pub token: String,
+    pub password: String,
+}
+
+fn map_user_store_error(e: UserStoreError) -> AuthAPIError {
+    match e {
+        // the session outlived its user
+        UserStoreError::UserNotFound => AuthAPIError::InvalidToken,
+        e => AuthAPIError::UnexpectedError(e.into()),
+    }
+}
+
+fn map_organization_store_error(e: OrganizationStoreError) -> AuthAPIError {
+    match e {
+        OrganizationStoreError::OrganizationNotFound => AuthAPIError::OrganizationNotFound,
+        e => AuthAPIError::UnexpectedError(e.into()),
+    }
+}
+
+/// Creates an organization owned by the caller, who becomes its first member
+/// with a new account in it, logged into with the organization's id.
+#[tracing::instrument(name = "Create organization", skip_all)]
+pub async fn create_organization<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    client: ClientInfo,
+    user: AuthenticatedUser,
+    JsonBody(request): JsonBody<CreateOrganizationRequest>,
+) -> Result<(StatusCode, Json<OrganizationResponse>), AuthAPIError> {
+    let settings = OrganizationSettings {
+        require_2fa: request.require_2fa,
+        min_password_length: request
+            .min_password_length
+            .unwrap_or(TenantSettings::default().min_password_length),
+    };
+    let organization = Organization::create(
+        request.name,
+        user.email.as_ref().to_owned(),
+        settings.try_into()?,
+    )?;
+    let password =
+        Password::parse(&request.password, &state.password_policy).map_err(FieldError::from)?;
+    organization
+        .settings
+        .check_password(password.as_ref())
+        .map_err(FieldError::from)?;
+
+    let result = async {
+        let creator = state
+            .user_store
+            .read()
+            .await
+            .get_user(&user.key())
+            .await
+            .map_err(map_user_store_error)?;
+        let owner = User::new(creator.email(), password, creator.requires_2fa())
+            .with_details(
+                creator.email_verified(),
+                Utc::now(),
+                creator.profile().clone(),
+            )
+            .with_tenant(Some(organization.id.clone()));
+        add_owned_organization(
+            &state.user_store,
+            &state.organization_store,
+            organization.clone(),
+            owner,
+        )
+        .await
+    }
+    .await;
+    record_organization_event(
+        &state,
+        &client,
+        &user.email,
+        AuditEventType::OrganizationCreated,
+        &organization.id,
+        &result,
+    )
+    .await;
+    result?;
+
+    let members = vec![user.email.as_ref().to_owned()];
+    let response = OrganizationResponse::new(organization, members);
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// Only members and admins see an organization, it is not found for others.
+#[tracing::instrument(name = "Get organization", skip_all)]
+pub async fn get_organization<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    user: AuthenticatedUser,
+    PathParams(id): PathParams<String>,
+) -> Result<Json<OrganizationResponse>, AuthAPIError> {
+    let member = state
+        .user_store
+        .read()
+        .await
+        .get_user(&user.key())
+        .await
+        .map_err(map_user_store_error)?;
+    if member.tenant_id() != Some(id.as_str()) && !user.claims.has_role(ADMIN_ROLE) {
+        return Err(AuthAPIError::OrganizationNotFound);
+    }
+    let organization_store = state.organization_store.read().await;
+    let organization = organization_store
+        .get_organization(&id)
+        .await
+        .map_err(map_organization_store_error)?;
+    let members = organization_store
+        .organization_members(&id)
+        .await
+        .map_err(map_organization_store_error)?;
+    Ok(Json(OrganizationResponse::new(organization, members)))
+}
+
+#[tracing::instrument(name = "Update organization settings", skip_all)]
+pub async fn update_organization_settings<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    client: ClientInfo,
+    user: AuthenticatedUser,
+    PathParams(id): PathParams<String>,
+    JsonBody(request): JsonBody<OrganizationSettings>,
+) -> Result<Json<OrganizationSettings>, AuthAPIError> {
+    let settings: TenantSettings = request.try_into()?;
+    let result = async {
+        owned_organization(&state, &user, &id).await?;
+        state
+            .organization_store
+            .write()
+            .await
+            .update_organization_settings(&id, settings)
+            .await
+            .map_err(map_organization_store_error)
+    }
+    .await;
+    record_organization_event(
+        &state,
+        &client,
+        &user.email,
+        AuditEventType::OrganizationSettingsChanged,
+        &id,
+        &result,
+    )
+    .await;
+    Ok(Json(result?.settings.into()))
+}
+
+/// Emails a link to join the organization, accepted at
+/// `/orgs/invitations/accept`.
+#[tracing::instrument(name = "Invite organization member", skip_all)]
+pub async fn invite_member<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    client: ClientInfo,
+    user: AuthenticatedUser,
+    PathParams(id): PathParams<String>,
+    JsonBody(request): JsonBody<InviteMemberRequest>,
+) -> Result<(StatusCode, Json<InvitationResponse>), AuthAPIError> {
+    let email = Email::parse(&request.email).map_err(|_| FieldError::invalid_email("email"))?;
+    let result = async {
+        let organization = owned_organization(&state, &user, &id).await?;
+        let (mut invitation, token) = Invitation::new(
+            email.clone(),
+            Some(user.email.as_ref().to_owned()),
+            DEFAULT_INVITATION_TTL_DAYS,
+        )
+        .map_err(|e| AuthAPIError::UnexpectedError(eyre!(e.message)))?;
+        invitation.organization_id = Some(id.clone());
+        state
+            .organization_store
+            .write()
+            .await
+            .add_invitation(invitation.clone())
+            .await
+            .map_err(map_organization_store_error)?;
+
+        let link = state
+            .settings
+            .application
+            .public_url(&format!("/?org_invite={token}"));
+        let content = format!(
+            "{} invited you to join {}: {link}\n\n\
+             The link expires in {DEFAULT_INVITATION_TTL_DAYS} days. \
+             If you don't know them, you can ignore this email.",
+            user.email.as_ref(),
+            organization.name,
+        );
+        state
+            .email_client
+            .send_email(&email, "You are invited to an organization", &content)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+        Ok::<_, AuthAPIError>(invitation)
+    }
+    .await;
+    let event = AuditEvent::new(
+        AuditEventType::MemberInvited,
+        &client,
+        AuditOutcome::from_result(&result),
+    )
+    .with_actor(user.email.as_ref())
+    .with_target(email.as_ref());
+    state.record_audit_event(event).await;
+
+    let response = InvitationResponse::from(result?);
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// Joins the organization of an invitation with a new account, as emails
+/// are only unique within an organization. Its password must follow the
+/// password rules of the organization.
+#[tracing::instrument(name = "Accept organization invitation", skip_all)]
+pub async fn accept_invitation<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    client: ClientInfo,
+    JsonBody(request): JsonBody<AcceptInvitationRequest>,
+) -> Result<(StatusCode, Json<MeResponse>), AuthAPIError> {
+    let token_hash = hash_secret(&request.token);
+    let invitation = match state
+        .organization_store
+        .read()
+        .await
+        .get_invitation(&token_hash)
+        .await
+    {
+        Ok(invitation) if !invitation.is_expired() => invitation,
+        Ok(_) | Err(OrganizationStoreError::InvitationNotFound) => {
+            return Err(AuthAPIError::InvalidInvitation);
+        }
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+    // invitations to sign up are used at /signup
+    let Some(organization_id) = invitation.organization_id.clone() else {
+        return Err(AuthAPIError::InvalidInvitation);
+    };
+
+    let result = join_organization(
+        &state,
+        &client,
+        &invitation,
+        &organization_id,
+        request.password,
+    )
+    .await;
+    let event = AuditEvent::new(
+        AuditEventType::OrganizationJoined,
+        &client,
+        AuditOutcome::from_result(&result),
+    )
+    .with_actor(invitation.email.as_ref())
+    .with_target(&organization_id);
+    state.record_audit_event(event).await;
+
+    let user = result?;
+    Ok((StatusCode::CREATED, Json(MeResponse::from(&user))))
+}
+
+/// Adds an organization with its owner's account, removing the organization
+/// again when the account can't be added so none is left without its owner.
+async fn add_owned_organization<T: UserStore, O: OrganizationStore>(
+    user_store: &RwLock<T>,
+    organization_store: &RwLock<O>,
+    organization: Organization,
+    owner: User,
+) -> Result<(), AuthAPIError> {
+    let id = organization.id.clone();
+    let mut organization_store = organization_store.write().await;
+    organization_store
+        .add_organization(organization)
+        .await
+        .map_err(map_organization_store_error)?;
+    let error = match user_store.write().await.add_user(owner).await {
+        Ok(()) => return Ok(()),
+        Err(UserStoreError::UserAlreadyExists) => AuthAPIError::AlreadyInOrganization,
+        Err(e) => AuthAPIError::UnexpectedError(e.into()),
+    };
+    if let Err(e) = organization_store.remove_organization(&id).await {
+        tracing::error!(error = ?e, "Failed to remove organization without owner");
+    }
+    Err(error)
+}
+
+async fn join_organization<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    client: &ClientInfo,
+    invitation: &Invitation,
+    organization_id: &str,
+    password: String,
+) -> Result<User, AuthAPIError> {
+    let email = invitation.email.as_ref();
+    let mut organization_store = state.organization_store.write().await;
+    let organization = match organization_store.get_organization(organization_id).await {
+        Ok(organization) => organization,
+        Err(OrganizationStoreError::OrganizationNotFound) => {
+            return Err(AuthAPIError::InvalidInvitation);
+        }
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+    let password = Password::parse(&password, &state.password_policy).map_err(FieldError::from)?;
+    organization
+        .settings
+        .check_password(password.as_ref())
+        .map_err(FieldError::from)?;
+
+    // held until the account is added, so two invitations can't both add it
+    let mut user_store = state.user_store.write().await;
+    match user_store
+        .get_user(&UserKey::new(Some(&organization.id), email))
+        .await
+    {
+        Ok(_) => return Err(AuthAPIError::AlreadyInOrganization),
+        Err(UserStoreError::UserNotFound) => {}
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+
+    // used up only once accepted, so a rejected password doesn't lose it
+    match organization_store
+        .take_invitation(&invitation.token_hash)
+        .await
+    {
+        Ok(_) => {}
+        Err(OrganizationStoreError::InvitationNotFound) => {
+            return Err(AuthAPIError::InvalidInvitation);
+        }
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+    // the invitation reached them by email
+    let user = User::new(invitation.email.clone(), password, false)
+        .with_details(true, Utc::now(), UserProfile::default())
+        .with_tenant(Some(organization.id));
+    user_store
+        .add_user(user.clone())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    drop(user_store);
+    drop(organization_store);
+
+    let event =
+        AuditEvent::new(AuditEventType::Signup, client, AuditOutcome::Success).with_actor(email);
+    state.record_audit_event(event).await;
+    Ok(user)
+}
+
+/// The organization `id`, if `user` is its owner, logged into their account in
+/// it, or an admin.
+async fn owned_organization<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    user: &AuthenticatedUser,
+    id: &str,
+) -> Result<Organization, AuthAPIError> {
+    let organization = state
+        .organization_store
+        .read()
+        .await
+        .get_organization(id)
+        .await
+        .map_err(map_organization_store_error)?;
+    let owner =
+        user.claims.tenant.as_deref() == Some(id) && organization.is_owner(user.email.as_ref());
+    if !owner && !user.claims.has_role(ADMIN_ROLE) {
+        return Err(AuthAPIError::NotOrganizationOwner);
+    }
+    Ok(organization)
+}
+
+async fn record_organization_event<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+    R,
+>(
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    client: &ClientInfo,
+    actor: &Email,
+    event_type: AuditEventType,
+    id: &str,
+    result: &Result<R, AuthAPIError>,
+) {
+    let event = AuditEvent::new(event_type, client, AuditOutcome::from_result(result))
+        .with_actor(actor.as_ref())
+        .with_target(id);
+    state.record_audit_event(event).await;
+}
@@ -0,0 +1,81 @@
+use super::*;
+use crate::domain::PasswordPolicy;
+use crate::services::data_stores::{
+    hashmap_organization_store::HashMapOrganizationStore, hashmap_user_store::HashmapUserStore,
+};
+
+fn owner(organization: &Organization) -> User {
+    User::parse(
+        "owner@email.com".to_owned(),
+        "Kx7!vTq2Lm".to_owned(),
+        false,
+        &PasswordPolicy::default(),
+    )
+    .unwrap()
+    .with_tenant(Some(organization.id.clone()))
+}
+
+#[tokio::test]
+async fn should_add_the_organization_with_its_owner() {
+    let user_store = HashmapUserStore::default();
+    let organization_store = RwLock::new(HashMapOrganizationStore::new(&user_store));
+    let user_store = RwLock::new(user_store);
+    let organization = Organization::create(
+        "Acme".to_owned(),
+        "owner@email.com".to_owned(),
+        TenantSettings::default(),
+    )
+    .unwrap();
+
+    add_owned_organization(
+        &user_store,
+        &organization_store,
+        organization.clone(),
+        owner(&organization),
+    )
+    .await
+    .unwrap();
+    let members = organization_store
+        .read()
+        .await
+        .organization_members(&organization.id)
+        .await
+        .unwrap();
+    assert_eq!(members, vec!["owner@email.com".to_owned()]);
+}
+
+#[tokio::test]
+async fn should_remove_the_organization_when_the_owner_account_exists() {
+    let user_store = HashmapUserStore::default();
+    let organization_store = RwLock::new(HashMapOrganizationStore::new(&user_store));
+    let user_store = RwLock::new(user_store);
+    let organization = Organization::create(
+        "Acme".to_owned(),
+        "owner@email.com".to_owned(),
+        TenantSettings::default(),
+    )
+    .unwrap();
+    user_store
+        .write()
+        .await
+        .add_user(owner(&organization))
+        .await
+        .unwrap();
+
+    let result = add_owned_organization(
+        &user_store,
+        &organization_store,
+        organization.clone(),
+        owner(&organization),
+    )
+    .await;
+    assert!(matches!(result, Err(AuthAPIError::AlreadyInOrganization)));
+    assert!(matches!(
+        organization_store
+            .read()
+            .await
+            .get_organization(&organization.id)
+            .await,
+        Err(OrganizationStoreError::OrganizationNotFound)
+    ));
+}
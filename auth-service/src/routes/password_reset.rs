@@ -0,0 +1,145 @@
+use crate::app_state::AppState;
+use crate::domain::data_stores::{
+    AccessTokenStore, BannedTokenStore, LinkPurpose, MagicLinkStore, MagicLinkStoreError,
+    OAuthStore, OrganizationStore, TrustedDeviceStore, TwoFACodeStore, UserStore,
+};
+use crate::domain::{
+    AuditEvent, AuditEventType, AuditLog, AuditOutcome, AuthAPIError, ClientInfo, Email,
+    EmailClient, FieldError, Password, UserKey, UserStoreError, generate_secret, hash_secret,
+};
+use crate::routes::{ban_user_tokens, remove_trusted_devices};
+use crate::utils::extractors::JsonBody;
+use axum::{extract::State, http::StatusCode};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct PasswordResetRequest {
+    pub token: String,
+    pub password: String,
+}
+
+/// Emails a single-use link to choose a new password. Only the hash of its
+/// token is stored, and the token is in the fragment of the link so it
+/// doesn't reach server logs.
+pub(crate) async fn send_password_reset_link<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    email: &Email,
+    tenant_id: Option<&str>,
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+) -> Result<(), AuthAPIError> {
+    let token = generate_secret();
+    state
+        .magic_link_store
+        .write()
+        .await
+        .add_token(
+            LinkPurpose::PasswordReset,
+            &hash_secret(&token),
+            UserKey::new(tenant_id, email.as_ref()),
+        )
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let link = state
+        .settings
+        .application
+        .public_url(&format!("/#password_reset={token}"));
+    let minutes = state.settings.auth.magic_link_ttl_seconds.div_ceil(60);
+    let content = format!(
+        "An administrator reset your password. Choose a new one: {link}\n\n\
+         The link works once and expires in {minutes} minutes."
+    );
+    state
+        .email_client
+        .send_email(email, "Password reset", &content)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))
+}
+
+/// Sets the password chosen from a reset link. The link is only used up by
+/// passwords following the policy of the service, so a rejected one can be
+/// fixed and sent again.
+#[tracing::instrument(name = "Password reset", skip_all)]
+pub async fn complete_password_reset<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    client: ClientInfo,
+    JsonBody(request): JsonBody<PasswordResetRequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    let password =
+        Password::parse(&request.password, &state.password_policy).map_err(FieldError::from)?;
+    let user = match state
+        .magic_link_store
+        .write()
+        .await
+        .take_token(LinkPurpose::PasswordReset, &hash_secret(&request.token))
+        .await
+    {
+        Ok(user) => user,
+        Err(MagicLinkStoreError::TokenNotFound) => return Err(AuthAPIError::InvalidToken),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+
+    let result = async {
+        let account = match state.user_store.read().await.get_user(&user).await {
+            Ok(account) => account,
+            // deleted since the link was sent
+            Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::InvalidToken),
+            Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+        };
+        if let Some(tenant_id) = account.tenant_id() {
+            let organization = state
+                .organization_store
+                .read()
+                .await
+                .get_organization(tenant_id)
+                .await
+                .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+            organization
+                .settings
+                .check_password(password.as_ref())
+                .map_err(FieldError::from)?;
+        }
+        state
+            .user_store
+            .write()
+            .await
+            .set_password(&user, password)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+        // sessions opened with the old password end with it
+        ban_user_tokens(&state.banned_token_store, &user).await?;
+        remove_trusted_devices(&state.trusted_device_store, &user).await?;
+        Ok(StatusCode::OK)
+    }
+    .await;
+
+    let event = AuditEvent::new(
+        AuditEventType::PasswordChanged,
+        &client,
+        AuditOutcome::from_result(&result),
+    )
+    .with_actor(user.email.as_str());
+    state.record_audit_event(event).await;
+    result
+}
@@ -0,0 +1,88 @@
+use crate::app_state::AppState;
+use crate::domain::data_stores::{
+    AccessTokenStore, BannedTokenStore, LinkPurpose, MagicLinkStore, MagicLinkStoreError,
+    OAuthStore, OrganizationStore, TrustedDeviceStore, TwoFACodeStore, UserStore,
+};
+use crate::domain::{
+    AuditEvent, AuditEventType, AuditLog, AuditOutcome, AuthAPIError, ClientInfo, EmailClient,
+    hash_secret,
+};
+use crate::routes::admin::{ban_user_tokens, remove_trusted_devices};
+use crate::utils::extractors::JsonBody;
+use axum::{Json, extract::State, response::Html};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct RevokeSessionsRequest {
+    pub token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RevokeSessionsResponse {
+    pub message: String,
+}
+
+/// Target of the link in new sign-in alerts. It only asks for a confirmation,
+/// link previews and mail scanners follow links and must not sign anyone out.
+pub async fn revoke_sessions_page() -> Html<&'static str> {
+    Html(include_str!("../../assets/revoke-sessions.html"))
+}
+
+/// Sent by the confirmation page with the token of the link: logs the user
+/// out everywhere and forgets their devices, so the next login from any of
+/// them is reported and asks for a 2FA code. The link is used up.
+#[tracing::instrument(name = "Revoke Sessions", skip_all)]
+pub async fn revoke_sessions<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    client: ClientInfo,
+    JsonBody(request): JsonBody<RevokeSessionsRequest>,
+) -> Result<Json<RevokeSessionsResponse>, AuthAPIError> {
+    let user = match state
+        .magic_link_store
+        .write()
+        .await
+        .take_token(LinkPurpose::RevokeSessions, &hash_secret(&request.token))
+        .await
+    {
+        Ok(user) => user,
+        Err(MagicLinkStoreError::TokenNotFound) => return Err(AuthAPIError::InvalidToken),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+
+    let result = async {
+        ban_user_tokens(&state.banned_token_store, &user).await?;
+        remove_trusted_devices(&state.trusted_device_store, &user).await?;
+        state
+            .user_store
+            .write()
+            .await
+            .forget_devices(&user)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))
+    }
+    .await;
+
+    let event = AuditEvent::new(
+        AuditEventType::TokensRevoked,
+        &client,
+        AuditOutcome::from_result(&result),
+    )
+    .with_actor(user.email.as_str());
+    state.record_audit_event(event).await;
+    result?;
+
+    Ok(Json(RevokeSessionsResponse {
+        message: "All sessions were signed out".to_owned(),
+    }))
+}
@@ -1,11 +1,23 @@
 use crate::domain::data_stores::UserStore;
-use crate::domain::{AuthAPIError, BannedTokenStore, EmailClient, TwoFACodeStore};
-use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use crate::domain::{
+    AccessTokenStore, AuditEvent, AuditEventType, AuditLog, AuditOutcome, AuthAPIError,
+    BannedTokenStore, ClientInfo, Email, EmailClient, FieldError, Invitation, MagicLinkStore,
+    OAuthStore, OrganizationStore, OrganizationStoreError, Password, TrustedDeviceStore,
+    TwoFACodeStore, UserProfile, hash_secret,
+};
+use crate::utils::extractors::JsonBody;
+use axum::{
+    Json,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 
 use crate::app_state::AppState;
-use crate::domain::User;
 use crate::domain::UserStoreError;
+use crate::domain::{User, UserKey};
 
 #[derive(Deserialize)]
 pub struct SignupRequest {
@@ -13,6 +25,9 @@ pub struct SignupRequest {
     pub password: String,
     #[serde(rename = "requires2FA")]
     pub requires_2fa: bool,
+    /// From the link of an invitation, required when open registration is off.
+    #[serde(default, rename = "inviteToken")]
+    pub invite_token: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
@@ -21,18 +36,96 @@ pub struct SignupResponse {
 }
 
 #[tracing::instrument(name = "Signup", skip_all)]
-pub async fn signup<T: UserStore, U: BannedTokenStore, V: TwoFACodeStore, W: EmailClient>(
-    State(state): State<AppState<T, U, V, W>>,
-    Json(request): Json<SignupRequest>,
-) -> impl IntoResponse {
-    let user = match User::parse(request.email, request.password, request.requires_2fa) {
-        Ok(user) => user,
-        Err(_) => {
-            return AuthAPIError::InvalidCredentials.into_response();
+pub async fn signup<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    client: ClientInfo,
+    JsonBody(request): JsonBody<SignupRequest>,
+) -> Response {
+    let actor = Email::parse(&request.email).ok();
+    let response = create_user(&state, request).await;
+
+    let mut event = AuditEvent::new(
+        AuditEventType::Signup,
+        &client,
+        AuditOutcome::from_status(response.status()),
+    );
+    if let Some(actor) = actor {
+        event = event.with_actor(actor.as_ref());
+    }
+    state.record_audit_event(event).await;
+    response
+}
+
+async fn create_user<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    request: SignupRequest,
+) -> Response {
+    let email = Email::parse(&request.email).map_err(|_| FieldError::invalid_email("email"));
+    let password =
+        Password::parse(&request.password, &state.password_policy).map_err(FieldError::from);
+    let user = match (email, password) {
+        (Ok(email), Ok(password)) => User::new(email, password, request.requires_2fa),
+        (email, password) => {
+            let errors = email.err().into_iter().chain(password.err()).collect();
+            return AuthAPIError::InvalidFields(errors).into_response();
+        }
+    };
+    let invitation = match &request.invite_token {
+        Some(token) => match state
+            .organization_store
+            .read()
+            .await
+            .get_invitation(&hash_secret(token))
+            .await
+        {
+            Ok(invitation) if invitation.allows_signup(&user.email()) => Some(invitation),
+            Ok(_) | Err(OrganizationStoreError::InvitationNotFound) => {
+                return AuthAPIError::InvalidInvitation.into_response();
+            }
+            Err(e) => return AuthAPIError::UnexpectedError(e.into()).into_response(),
+        },
+        None if !state.settings.auth.open_registration => {
+            return AuthAPIError::RegistrationClosed.into_response();
         }
+        None => None,
     };
-    let mut user_store = state.user_store.write().await;
-    match user_store.add_user(user).await {
+    let user = match &invitation {
+        // the invitation reached them by email
+        Some(_) => user.with_details(true, Utc::now(), UserProfile::default()),
+        None => user,
+    };
+    let email = user.email();
+    let result = state.user_store.write().await.add_user(user).await;
+    let accepted = match (&result, invitation) {
+        (Ok(()), Some(invitation)) => accept_invitation(state, &email, invitation).await,
+        _ => Ok(()),
+    };
+    if let Err(e) = accepted {
+        return e.into_response();
+    }
+    match result {
         Ok(_) => {
             let response = Json(SignupResponse {
                 message: "User created successfully!".to_owned(),
@@ -44,8 +137,47 @@ pub async fn signup<T: UserStore, U: BannedTokenStore, V: TwoFACodeStore, W: Ema
             UserStoreError::InvalidCredentials => AuthAPIError::InvalidCredentials.into_response(),
             UserStoreError::UnexpectedError(e) => AuthAPIError::UnexpectedError(e).into_response(),
             UserStoreError::UserNotFound => {
-                panic!("user not found should not happen inside add_user")
+                panic!("{e} should not happen inside add_user")
             }
         },
     }
 }
+
+// Gives the new user the role of their invitation and uses it up.
+async fn accept_invitation<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    email: &Email,
+    invitation: Invitation,
+) -> Result<(), AuthAPIError> {
+    if let Some(role) = invitation.role {
+        state
+            .user_store
+            .write()
+            .await
+            .set_roles(&UserKey::new(None, email.as_ref()), vec![role])
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    }
+    match state
+        .organization_store
+        .write()
+        .await
+        .take_invitation(&invitation.token_hash)
+        .await
+    {
+        // the email can only sign up once anyway
+        Ok(_) | Err(OrganizationStoreError::InvitationNotFound) => Ok(()),
+        Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+}
@@ -0,0 +1,317 @@
+use crate::app_state::AppState;
+use crate::domain::data_stores::{
+    AccessTokenStore, BannedTokenStore, MagicLinkStore, OAuthStore, OrganizationStore,
+    TrustedDeviceStore, TwoFACodeStore, UserStore,
+};
+use crate::domain::{
+    AuditEvent, AuditEventType, AuditLog, AuditOutcome, AuthAPIError, ClientInfo, Email,
+    EmailClient, Password, SocialIdentity, User, UserKey, UserProfile, UserStoreError,
+    generate_secret, pkce_challenge,
+};
+use crate::routes::login::{
+    is_trusted_device, requires_2fa, start_2fa, track_device, two_fa_location,
+};
+use crate::services::social_login::{SocialLoginError, SocialLoginProvider};
+use crate::settings::{ApplicationSettings, SocialProviderSettings};
+use crate::utils::auth::{
+    SocialLoginClaims, constant_time_eq, create_auth_cookie, create_social_login_cookie,
+    generate_auth_token, generate_social_login_token, social_login_removal_cookie,
+    validate_social_login_token,
+};
+use crate::utils::extractors::{PathParams, QueryParams};
+use axum::{extract::State, response::Redirect};
+use axum_extra::extract::CookieJar;
+use chrono::Utc;
+use color_eyre::eyre::eyre;
+use serde::Deserialize;
+use std::collections::HashMap;
+use url::Url;
+
+#[derive(Deserialize)]
+pub struct SocialLoginQuery {
+    /// Where to send the user once logged in, a URL of the service.
+    pub return_to: Option<String>,
+}
+
+/// What the provider sends back: a code, or an error when the user didn't
+/// let the service in.
+#[derive(Deserialize)]
+pub struct SocialCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+fn provider_settings<'a>(
+    providers: &'a HashMap<String, SocialProviderSettings>,
+    name: &str,
+) -> Result<&'a SocialProviderSettings, AuthAPIError> {
+    providers
+        .get(name)
+        .ok_or(AuthAPIError::LoginProviderNotFound)
+}
+
+fn callback_uri(application: &ApplicationSettings, provider: &str) -> String {
+    application.public_url(&format!("/callback/{provider}"))
+}
+
+// Only URLs of the service itself, the login must not hand users to anyone else.
+fn is_own_url(url: &str, application: &ApplicationSettings) -> bool {
+    match (Url::parse(url), Url::parse(&application.public_url("/"))) {
+        (Ok(url), Ok(public_url)) => url.origin() == public_url.origin(),
+        _ => false,
+    }
+}
+
+/// Starts a login with an external provider, sending the user there with a
+/// state, nonce and PKCE challenge kept in a short-lived cookie.
+#[tracing::instrument(name = "Social Login", skip_all)]
+pub async fn social_login<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    jar: CookieJar,
+    PathParams(provider): PathParams<String>,
+    QueryParams(query): QueryParams<SocialLoginQuery>,
+) -> Result<(CookieJar, Redirect), AuthAPIError> {
+    let settings = &state.settings;
+    let provider_settings = provider_settings(&settings.social.providers, &provider)?;
+    let return_to = query
+        .return_to
+        .filter(|url| is_own_url(url, &settings.application));
+
+    let login_state = generate_secret();
+    let nonce = generate_secret();
+    let code_verifier = generate_secret();
+    let url = SocialLoginProvider::new(
+        provider_settings,
+        callback_uri(&settings.application, &provider),
+    )
+    .authorization_url(&login_state, &nonce, &pkce_challenge(&code_verifier))
+    .await
+    .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let token = generate_social_login_token(
+        &provider,
+        login_state,
+        nonce,
+        code_verifier,
+        return_to,
+        &settings.auth,
+    )
+    .map_err(|_| AuthAPIError::UnexpectedError(eyre!("Failed to create social login token")))?;
+    let jar = jar.add(create_social_login_cookie(token, &settings.auth));
+    Ok((jar, Redirect::to(&url)))
+}
+
+/// Where the provider sends the user back. Accounts are linked to the user
+/// with the same email the first time, if the provider verified it, and a
+/// user is created when there is none.
+#[tracing::instrument(name = "Social Callback", skip_all)]
+pub async fn social_callback<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    client: ClientInfo,
+    jar: CookieJar,
+    PathParams(provider): PathParams<String>,
+    QueryParams(query): QueryParams<SocialCallbackQuery>,
+) -> (CookieJar, Result<Redirect, AuthAPIError>) {
+    let auth_settings = &state.settings.auth;
+    let claims = jar
+        .get(&auth_settings.cookie.social_login_cookie_name())
+        .and_then(|cookie| validate_social_login_token(cookie.value(), auth_settings).ok());
+    // single use, whatever the outcome
+    let jar = jar.remove(social_login_removal_cookie(&auth_settings.cookie));
+
+    match complete_social_login(&state, &client, jar.clone(), &provider, claims, query).await {
+        Ok((jar, redirect)) => (jar, Ok(redirect)),
+        Err(e) => (jar, Err(e)),
+    }
+}
+
+async fn complete_social_login<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    client: &ClientInfo,
+    jar: CookieJar,
+    provider: &str,
+    claims: Option<SocialLoginClaims>,
+    query: SocialCallbackQuery,
+) -> Result<(CookieJar, Redirect), AuthAPIError> {
+    let settings = &state.settings;
+    let provider_settings = provider_settings(&settings.social.providers, provider)?;
+
+    let identified = async {
+        let claims = claims
+            .filter(|claims| claims.provider == provider)
+            .filter(|claims| {
+                query
+                    .state
+                    .as_deref()
+                    .is_some_and(|state| constant_time_eq(state, &claims.state))
+            })
+            .ok_or(AuthAPIError::InvalidLoginState)?;
+        if let Some(error) = &query.error {
+            return Err(AuthAPIError::ProviderLoginFailed(eyre!(
+                "The provider returned {error}"
+            )));
+        }
+        let code = query.code.as_deref().ok_or(AuthAPIError::InvalidInput)?;
+
+        let identity = SocialLoginProvider::new(
+            provider_settings,
+            callback_uri(&settings.application, provider),
+        )
+        .exchange_code(code, &claims.code_verifier, &claims.nonce)
+        .await
+        .map_err(|e| match e {
+            SocialLoginError::Unreachable(_) => AuthAPIError::UnexpectedError(e.into()),
+            e => AuthAPIError::ProviderLoginFailed(e.into()),
+        })?;
+        let user = find_or_link_user(state, client, provider, identity).await?;
+        Ok((user, claims.return_to))
+    }
+    .await;
+
+    let actor = identified.as_ref().ok().map(|(user, _)| user.email());
+    let result = async {
+        let (user, return_to) = identified?;
+        user.status().ensure_active()?;
+        let login_attempt_id =
+            if requires_2fa(&user, state).await? && !is_trusted_device(&user, state, &jar).await {
+                Some(start_2fa(&user, state).await?)
+            } else {
+                None
+            };
+        Ok::<_, AuthAPIError>((user, return_to, login_attempt_id))
+    }
+    .await;
+
+    let mut events = vec![AuditEventType::Login];
+    if matches!(&result, Ok((_, _, Some(_)))) {
+        events.push(AuditEventType::TwoFactorCodeSent);
+    }
+    for event_type in events {
+        let mut event = AuditEvent::new(event_type, client, AuditOutcome::from_result(&result));
+        if let Some(actor) = &actor {
+            event = event.with_actor(actor.as_ref());
+        }
+        state.record_audit_event(event).await;
+    }
+    let (user, return_to, login_attempt_id) = result?;
+    let application = &settings.application;
+    if let Some(login_attempt_id) = login_attempt_id {
+        let location = two_fa_location(application, &user, &login_attempt_id, return_to.as_deref())
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+        return Ok((jar, Redirect::to(&location)));
+    }
+    let jar = track_device(&user, state, client, jar).await;
+    let token = generate_auth_token(
+        &user.email(),
+        user.roles(),
+        user.tenant_id(),
+        &settings.auth,
+    )
+    .map_err(|_| AuthAPIError::UnexpectedError(eyre!("Failed to create auth token")))?;
+    let jar = jar.add(create_auth_cookie(token, &settings.auth));
+    let return_to = return_to.unwrap_or_else(|| application.public_url("/"));
+    Ok((jar, Redirect::to(&return_to)))
+}
+
+async fn find_or_link_user<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    client: &ClientInfo,
+    provider: &str,
+    identity: SocialIdentity,
+) -> Result<User, AuthAPIError> {
+    let unexpected = |e: UserStoreError| AuthAPIError::UnexpectedError(e.into());
+    let mut user_store = state.user_store.write().await;
+    match user_store.linked_user(provider, &identity.subject).await {
+        Ok(user) => return Ok(user),
+        Err(UserStoreError::UserNotFound) => {}
+        Err(e) => return Err(unexpected(e)),
+    }
+
+    // an unverified email could be anyone's, including an existing user's
+    if !identity.email_verified {
+        return Err(AuthAPIError::ProviderEmailNotVerified);
+    }
+    let email = Email::parse(&identity.email).map_err(|_| {
+        AuthAPIError::ProviderLoginFailed(eyre!("The provider returned an invalid email"))
+    })?;
+    // new links are to the account outside any organization
+    let key = UserKey::new(None, email.as_ref());
+    let user = match user_store.get_user(&key).await {
+        Ok(user) => user,
+        Err(UserStoreError::UserNotFound) => {
+            if !state.settings.auth.open_registration {
+                return Err(AuthAPIError::RegistrationClosed);
+            }
+            // no password the user knows of, they can only log in with the provider
+            let user = User::new(email.clone(), Password::generate(), false).with_details(
+                true,
+                Utc::now(),
+                UserProfile::default(),
+            );
+            user_store
+                .add_user(user.clone())
+                .await
+                .map_err(unexpected)?;
+            let event = AuditEvent::new(AuditEventType::Signup, client, AuditOutcome::Success)
+                .with_actor(email.as_ref());
+            state.record_audit_event(event).await;
+            user
+        }
+        Err(e) => return Err(unexpected(e)),
+    };
+    user_store
+        .link_identity(&key, provider, &identity.subject)
+        .await
+        .map_err(unexpected)?;
+    drop(user_store);
+
+    let event = AuditEvent::new(AuditEventType::AccountLinked, client, AuditOutcome::Success)
+        .with_actor(email.as_ref());
+    state.record_audit_event(event).await;
+    Ok(user)
+}
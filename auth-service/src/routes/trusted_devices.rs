@@ -0,0 +1,197 @@
+use crate::app_state::AppState;
+use crate::domain::data_stores::{
+    AccessTokenStore, BannedTokenStore, MagicLinkStore, OAuthStore, OrganizationStore,
+    TrustedDeviceStore, TrustedDeviceStoreError, TwoFACodeStore, UserStore,
+};
+use crate::domain::{
+    AuditEvent, AuditEventType, AuditLog, AuditOutcome, AuthAPIError, ClientInfo, EmailClient,
+    TrustedDevice,
+};
+use crate::settings::AuthSettings;
+use crate::utils::auth::{trusted_device_removal_cookie, validate_trusted_device_token};
+use crate::utils::extractors::{AuthenticatedUser, PathParams};
+use axum::{Json, extract::State, http::StatusCode};
+use axum_extra::extract::CookieJar;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrustedDeviceResponse {
+    pub id: String,
+    #[serde(rename = "userAgent")]
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: DateTime<Utc>,
+    /// Whether this is the browser making the request.
+    pub current: bool,
+}
+
+impl TrustedDeviceResponse {
+    fn new(device: TrustedDevice, current_id: Option<&str>) -> Self {
+        Self {
+            current: current_id == Some(device.id.as_str()),
+            id: device.id,
+            user_agent: device.user_agent,
+            ip: device.ip,
+            created_at: device.created_at,
+            expires_at: device.expires_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrustedDevicesResponse {
+    pub devices: Vec<TrustedDeviceResponse>,
+}
+
+// The id of the trusted device of the request's browser, if any.
+fn current_device_id(jar: &CookieJar, settings: &AuthSettings) -> Option<String> {
+    let cookie = jar.get(&settings.cookie.trusted_device_cookie_name())?;
+    validate_trusted_device_token(cookie.value(), settings)
+        .ok()
+        .map(|claims| claims.jti)
+}
+
+fn map_trusted_device_store_error(e: TrustedDeviceStoreError) -> AuthAPIError {
+    match e {
+        TrustedDeviceStoreError::DeviceNotFound => AuthAPIError::TrustedDeviceNotFound,
+        e => AuthAPIError::UnexpectedError(e.into()),
+    }
+}
+
+#[tracing::instrument(name = "Get Trusted Devices", skip_all)]
+pub async fn get_trusted_devices<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    jar: CookieJar,
+    user: AuthenticatedUser,
+) -> Result<Json<TrustedDevicesResponse>, AuthAPIError> {
+    let current_id = current_device_id(&jar, &state.settings.auth);
+    let devices = state
+        .trusted_device_store
+        .read()
+        .await
+        .user_devices(&user.key())
+        .await
+        .map_err(map_trusted_device_store_error)?;
+
+    Ok(Json(TrustedDevicesResponse {
+        devices: devices
+            .into_iter()
+            .map(|device| TrustedDeviceResponse::new(device, current_id.as_deref()))
+            .collect(),
+    }))
+}
+
+/// Forgets a trusted device, its next login asks for a 2FA code again.
+#[tracing::instrument(name = "Forget Trusted Device", skip_all)]
+pub async fn forget_trusted_device<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    client: ClientInfo,
+    jar: CookieJar,
+    user: AuthenticatedUser,
+    PathParams(id): PathParams<String>,
+) -> Result<(CookieJar, StatusCode), AuthAPIError> {
+    let result = state
+        .trusted_device_store
+        .write()
+        .await
+        .remove_device(&user.key(), &id)
+        .await
+        .map_err(map_trusted_device_store_error);
+
+    record_forget_event(&state, &client, &user, &result).await;
+    result?;
+
+    let jar = if current_device_id(&jar, &state.settings.auth).as_deref() == Some(id.as_str()) {
+        jar.remove(trusted_device_removal_cookie(&state.settings.auth.cookie))
+    } else {
+        jar
+    };
+    Ok((jar, StatusCode::NO_CONTENT))
+}
+
+/// Forgets every trusted device of the user.
+#[tracing::instrument(name = "Forget Trusted Devices", skip_all)]
+pub async fn forget_trusted_devices<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    client: ClientInfo,
+    jar: CookieJar,
+    user: AuthenticatedUser,
+) -> Result<(CookieJar, StatusCode), AuthAPIError> {
+    let result = state
+        .trusted_device_store
+        .write()
+        .await
+        .remove_user_devices(&user.key())
+        .await
+        .map_err(map_trusted_device_store_error);
+
+    record_forget_event(&state, &client, &user, &result).await;
+    result?;
+
+    let jar = jar.remove(trusted_device_removal_cookie(&state.settings.auth.cookie));
+    Ok((jar, StatusCode::NO_CONTENT))
+}
+
+async fn record_forget_event<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+    R,
+>(
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    client: &ClientInfo,
+    user: &AuthenticatedUser,
+    result: &Result<R, AuthAPIError>,
+) {
+    let event = AuditEvent::new(
+        AuditEventType::TrustedDeviceForgotten,
+        client,
+        AuditOutcome::from_result(result),
+    )
+    .with_actor(user.email.as_ref());
+    state.record_audit_event(event).await;
+}
@@ -1,14 +1,22 @@
-use crate::domain::EmailClient;
-use crate::domain::data_stores::{BannedTokenStore, TwoFACodeStore, UserStore};
-use crate::utils::auth::generate_auth_cookie;
+use crate::domain::data_stores::{
+    AccessTokenStore, BannedTokenStore, MagicLinkStore, OAuthStore, OrganizationStore,
+    TrustedDeviceStore, TwoFACodeStore, UserStore,
+};
+use crate::domain::{AuditLog, EmailClient, TrustedDevice, User, UserKey};
+use crate::routes::login::{TokenDelivery, issue_auth_token, track_device};
+use crate::utils::extractors::JsonBody;
 use crate::{
     app_state::AppState,
-    domain::{AuthAPIError, Email, LoginAttemptId, TwoFACode},
-};
-use axum::{
-    Json, body::Body, extract::State, http::StatusCode, response::IntoResponse, response::Response,
+    domain::{
+        AuditEvent, AuditEventType, AuditOutcome, AuthAPIError, ClientInfo, Email, LoginAttemptId,
+        TwoFACode,
+    },
+    utils::auth::{create_trusted_device_cookie, generate_trusted_device_token},
 };
+use axum::{body::Body, extract::State, response::IntoResponse, response::Response};
 use axum_extra::extract::CookieJar;
+use chrono::Duration;
+use color_eyre::eyre::eyre;
 use serde::Deserialize;
 
 #[derive(Deserialize)]
@@ -18,13 +26,64 @@ pub struct Verify2FARequest {
     pub login_attempt_id: String,
     #[serde(rename = "2FACode")]
     pub twofa_code: String,
+    /// The organization of the account that logged in, as sent to `/login`.
+    #[serde(default, rename = "organizationId")]
+    pub organization_id: Option<String>,
+    #[serde(default, rename = "tokenDelivery")]
+    pub token_delivery: TokenDelivery,
+    /// Skips 2FA on the next logins from this browser.
+    #[serde(default, rename = "rememberDevice")]
+    pub remember_device: bool,
 }
 
 #[tracing::instrument(name = "Verify 2FA", skip_all)]
-pub async fn verify_2fa<T: UserStore, U: BannedTokenStore, V: TwoFACodeStore, W: EmailClient>(
-    State(state): State<AppState<T, U, V, W>>,
+pub async fn verify_2fa<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    client: ClientInfo,
+    jar: CookieJar,
+    JsonBody(request): JsonBody<Verify2FARequest>,
+) -> (CookieJar, Response<Body>) {
+    let (jar, response) = complete_login(&state, &client, jar, &request).await;
+
+    let mut event = AuditEvent::new(
+        AuditEventType::TwoFactorVerified,
+        &client,
+        AuditOutcome::from_status(response.status()),
+    );
+    if let Ok(email) = Email::parse(&request.email) {
+        event = event.with_actor(email.as_ref());
+    }
+    state.record_audit_event(event).await;
+    (jar, response)
+}
+
+async fn complete_login<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    client: &ClientInfo,
     jar: CookieJar,
-    Json(request): Json<Verify2FARequest>,
+    request: &Verify2FARequest,
 ) -> (CookieJar, Response<Body>) {
     let email = match Email::parse(request.email.as_str()) {
         Ok(email) => email,
@@ -46,8 +105,9 @@ pub async fn verify_2fa<T: UserStore, U: BannedTokenStore, V: TwoFACodeStore, W:
         }
     };
 
+    let key = UserKey::new(request.organization_id.as_deref(), email.as_ref());
     let (login_attempt_id_store, twofa_code_store) =
-        match state.two_fa_code_store.read().await.get_code(&email).await {
+        match state.two_fa_code_store.read().await.get_code(&key).await {
             Ok(get_code_results) => get_code_results,
             Err(_) => {
                 return (jar, AuthAPIError::IncorrectCredentials.into_response());
@@ -63,18 +123,90 @@ pub async fn verify_2fa<T: UserStore, U: BannedTokenStore, V: TwoFACodeStore, W:
         .two_fa_code_store
         .write()
         .await
-        .remove_code(&email)
+        .remove_code(&key)
         .await
     {
         return (jar, AuthAPIError::UnexpectedError(e.into()).into_response());
     }
 
-    let auth_cookie = match generate_auth_cookie(&email) {
-        Ok(cookie) => cookie,
+    // roles are read after the 2FA step so the token carries the current ones
+    let user = match state.user_store.read().await.get_user(&key).await {
+        Ok(user) => user,
         Err(_) => return (jar, AuthAPIError::IncorrectCredentials.into_response()),
     };
+    if let Err(e) = user.status().ensure_active() {
+        return (jar, e.into_response());
+    }
+
+    let mut jar = track_device(&user, state, client, jar).await;
+    if request.remember_device {
+        jar = trust_device(&user, state, client, jar).await;
+    }
+    issue_auth_token(
+        &email,
+        user.roles(),
+        user.tenant_id(),
+        &state.settings.auth,
+        request.token_delivery,
+        jar,
+    )
+}
 
-    let updated_jar = jar.add(auth_cookie);
+/// Sets the trusted-device cookie. Failures are logged, the login succeeds
+/// with the 2FA step still required next time.
+#[tracing::instrument(name = "Trust device", skip_all)]
+async fn trust_device<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    user: &User,
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    client: &ClientInfo,
+    jar: CookieJar,
+) -> CookieJar {
+    let ttl = Duration::days(state.settings.auth.trusted_device_ttl_days);
+    let device = TrustedDevice::new(client, ttl);
+    let result = async {
+        let token = generate_trusted_device_token(
+            &user.email(),
+            user.tenant_id(),
+            &device.id,
+            &state.settings.auth,
+        )
+        .map_err(|_| {
+            AuthAPIError::UnexpectedError(eyre!("Failed to create trusted-device token"))
+        })?;
+        state
+            .trusted_device_store
+            .write()
+            .await
+            .add_device(&user.key(), device)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+        Ok::<_, AuthAPIError>(token)
+    }
+    .await;
 
-    (updated_jar, StatusCode::OK.into_response())
+    let event = AuditEvent::new(
+        AuditEventType::DeviceTrusted,
+        client,
+        AuditOutcome::from_result(&result),
+    )
+    .with_actor(user.email_str());
+    state.record_audit_event(event).await;
+    match result {
+        Ok(token) => jar.add(create_trusted_device_cookie(token, &state.settings.auth)),
+        Err(e) => {
+            tracing::error!(error = ?e, "Failed to trust device");
+            jar
+        }
+    }
 }
@@ -1,34 +1,172 @@
 use crate::app_state::AppState;
-use crate::domain::EmailClient;
-use crate::domain::data_stores::{BannedTokenStore, TwoFACodeStore, UserStore};
-use crate::domain::error::AuthAPIError;
-use crate::utils::auth::validate_token;
-use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
-use serde::Deserialize;
+use crate::domain::AuthAPIError;
+use crate::domain::data_stores::{
+    AccessTokenStore, BannedTokenStore, MagicLinkStore, OAuthStore, OrganizationStore,
+    TrustedDeviceStore, TwoFACodeStore, UserStore,
+};
+use crate::domain::{
+    AuditEvent, AuditEventType, AuditLog, AuditOutcome, ClientInfo, EmailClient,
+    personal_access_token_id,
+};
+use crate::utils::auth::Claims;
+use crate::utils::extractors::{
+    JsonBody, ensure_subject_active, validate_active_token, validate_personal_access_token,
+};
+use axum::{Json, extract::State};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize)]
 pub struct VerifyTokenRequest {
     pub token: String,
+    /// When set, the token is only accepted if it carries this role.
+    #[serde(default, rename = "requiredRole")]
+    pub required_role: Option<String>,
+    /// When set, the token is only accepted if it carries this scope.
+    #[serde(default, rename = "requiredScope")]
+    pub required_scope: Option<String>,
 }
 
+/// What a valid token stands for, so services don't need to decode it,
+/// personal access tokens aren't JWTs.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyTokenResponse {
+    pub sub: String,
+    pub exp: usize,
+    /// Space-separated scopes, user sessions have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+}
+
+impl From<&Claims> for VerifyTokenResponse {
+    fn from(claims: &Claims) -> Self {
+        Self {
+            sub: claims.sub.clone(),
+            exp: claims.exp,
+            scope: claims.scope.clone(),
+            roles: claims.roles.clone(),
+        }
+    }
+}
+
+/// Accepts user, service and personal access tokens. Only rejected tokens are
+/// audited, services verify every request.
 #[tracing::instrument(name = "Verify Token", skip_all)]
-pub async fn verify_token<T: UserStore, U: BannedTokenStore, V: TwoFACodeStore, W: EmailClient>(
-    State(state): State<AppState<T, U, V, W>>,
-    Json(request): Json<VerifyTokenRequest>,
-) -> impl IntoResponse {
-    let verification = state
-        .banned_token_store
-        .read()
+pub async fn verify_token<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    client: ClientInfo,
+    JsonBody(request): JsonBody<VerifyTokenRequest>,
+) -> Result<Json<VerifyTokenResponse>, AuthAPIError> {
+    let event = AuditEvent::new(
+        AuditEventType::TokenVerified,
+        &client,
+        AuditOutcome::Failure,
+    );
+    if personal_access_token_id(&request.token).is_some() {
+        return verify_personal_access_token(&state, event, request).await;
+    }
+    let claims = match validate_active_token(
+        &request.token,
+        &state.banned_token_store,
+        &state.settings.auth,
+    )
+    .await
+    {
+        Ok(claims) => claims,
+        Err(e) => {
+            state.record_audit_event(event).await;
+            return Err(e);
+        }
+    };
+    let result = match ensure_subject_active(&claims, &state.user_store, &state.oauth_store).await {
+        Ok(()) => match (request.required_role, request.required_scope) {
+            (Some(role), _) if !claims.has_role(&role) => Err(AuthAPIError::MissingRole(role)),
+            (_, Some(scope)) if !claims.has_scope(&scope) => Err(AuthAPIError::MissingScope(scope)),
+            _ => Ok(Json(VerifyTokenResponse::from(&claims))),
+        },
+        Err(e) => Err(e),
+    };
+    if result.is_err() {
+        state
+            .record_audit_event(event.with_target(claims.sub))
+            .await;
+    }
+    result
+}
+
+/// Personal access tokens act for their user within their scopes, they carry
+/// no roles.
+async fn verify_personal_access_token<
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+>(
+    state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    event: AuditEvent,
+    request: VerifyTokenRequest,
+) -> Result<Json<VerifyTokenResponse>, AuthAPIError> {
+    let access_token = match validate_personal_access_token(
+        &request.token,
+        &state.access_token_store,
+        &state.banned_token_store,
+        &state.user_store,
+    )
+    .await
+    {
+        Ok(access_token) => access_token,
+        Err(e) => {
+            state.record_audit_event(event).await;
+            return Err(e);
+        }
+    };
+    let result = match (request.required_role, request.required_scope) {
+        (Some(role), _) => Err(AuthAPIError::MissingRole(role)),
+        (_, Some(scope)) if !access_token.has_scope(&scope) => {
+            Err(AuthAPIError::MissingScope(scope))
+        }
+        _ => Ok(Json(VerifyTokenResponse {
+            sub: access_token.user_email.clone(),
+            exp: access_token.expires_at.timestamp() as usize,
+            scope: Some(access_token.scopes.join(" ")),
+            roles: Vec::new(),
+        })),
+    };
+    if result.is_err() {
+        state
+            .record_audit_event(event.with_target(access_token.user_email))
+            .await;
+        return result;
+    }
+
+    // the last use is only shown to the user, the token was verified anyway
+    let recorded = state
+        .access_token_store
+        .write()
         .await
-        .contains_token(&request.token)
+        .record_use(&access_token.id, Utc::now())
         .await;
-    match verification {
-        Ok(true) => return AuthAPIError::InvalidToken.into_response(),
-        Ok(false) => {}
-        Err(e) => return AuthAPIError::UnexpectedError(e.into()).into_response(),
-    }
-    match validate_token(&request.token).await {
-        Ok(_) => StatusCode::OK.into_response(),
-        Err(_) => AuthAPIError::InvalidToken.into_response(),
+    if let Err(e) = recorded {
+        tracing::error!(error = ?e, "Failed to record personal access token use");
     }
+    result
 }
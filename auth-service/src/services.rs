@@ -1,2 +1,3 @@
 pub mod data_stores;
 pub mod mock_mail_client;
+pub mod social_login;
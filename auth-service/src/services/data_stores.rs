@@ -1,6 +1,19 @@
+pub mod hashmap_access_token_store;
+pub mod hashmap_magic_link_store;
+pub mod hashmap_oauth_store;
+pub mod hashmap_organization_store;
+pub mod hashmap_trusted_device_store;
 pub mod hashmap_two_fa_code_store;
 pub mod hashmap_user_store;
 pub mod hashset_banned_token_store;
+pub mod postgres_access_token_store;
+pub mod postgres_audit_log;
+pub mod postgres_oauth_store;
+pub mod postgres_organization_store;
+pub mod postgres_trusted_device_store;
 pub mod postgres_user_store;
 pub mod redis_banned_token_store;
+pub mod redis_magic_link_store;
 pub mod redis_two_fa_code_store;
+pub mod store_backends;
+pub mod vec_audit_log;
@@ -0,0 +1,71 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::domain::data_stores::{AccessTokenStore, AccessTokenStoreError};
+use crate::domain::{PersonalAccessToken, UserKey};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Debug, Default)]
+pub struct HashMapAccessTokenStore {
+    tokens: HashMap<String, PersonalAccessToken>,
+}
+
+#[async_trait::async_trait]
+impl AccessTokenStore for HashMapAccessTokenStore {
+    async fn add_token(&mut self, token: PersonalAccessToken) -> Result<(), AccessTokenStoreError> {
+        self.tokens.insert(token.id.clone(), token);
+        Ok(())
+    }
+
+    async fn get_token(&self, id: &str) -> Result<PersonalAccessToken, AccessTokenStoreError> {
+        self.tokens
+            .get(id)
+            .cloned()
+            .ok_or(AccessTokenStoreError::TokenNotFound)
+    }
+
+    async fn user_tokens(
+        &self,
+        user: &UserKey,
+    ) -> Result<Vec<PersonalAccessToken>, AccessTokenStoreError> {
+        let mut tokens: Vec<PersonalAccessToken> = self
+            .tokens
+            .values()
+            .filter(|token| token.user() == *user)
+            .cloned()
+            .collect();
+        tokens.sort_by_key(|token| Reverse(token.created_at));
+        Ok(tokens)
+    }
+
+    async fn remove_token(
+        &mut self,
+        user: &UserKey,
+        id: &str,
+    ) -> Result<(), AccessTokenStoreError> {
+        match self.tokens.get(id) {
+            Some(token) if token.user() == *user => {
+                self.tokens.remove(id);
+                Ok(())
+            }
+            _ => Err(AccessTokenStoreError::TokenNotFound),
+        }
+    }
+
+    async fn record_use(
+        &mut self,
+        id: &str,
+        used_at: DateTime<Utc>,
+    ) -> Result<(), AccessTokenStoreError> {
+        let token = self
+            .tokens
+            .get_mut(id)
+            .ok_or(AccessTokenStoreError::TokenNotFound)?;
+        token.last_used_at = Some(used_at);
+        Ok(())
+    }
+}
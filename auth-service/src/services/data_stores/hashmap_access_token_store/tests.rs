@@ -0,0 +1,69 @@
+use super::*;
+
+fn user() -> UserKey {
+    UserKey::new(None, "email@email.com")
+}
+
+fn token(user: UserKey) -> PersonalAccessToken {
+    let (token, _) =
+        PersonalAccessToken::issue(user, "script".to_owned(), &["repo".to_owned()], 1).unwrap();
+    token
+}
+
+#[tokio::test]
+async fn test_add_and_list_tokens() {
+    let mut store = HashMapAccessTokenStore::default();
+    let token = token(user());
+    store.add_token(token.clone()).await.unwrap();
+    store
+        .add_token(self::token(UserKey::new(None, "other@email.com")))
+        .await
+        .unwrap();
+
+    assert_eq!(store.get_token(&token.id).await, Ok(token.clone()));
+    store
+        .add_token(self::token(UserKey::new(Some("org"), "email@email.com")))
+        .await
+        .unwrap();
+
+    assert_eq!(store.user_tokens(&user()).await.unwrap(), [token]);
+    assert_eq!(
+        store.get_token("unknown").await,
+        Err(AccessTokenStoreError::TokenNotFound)
+    );
+}
+
+#[tokio::test]
+async fn test_record_use() {
+    let mut store = HashMapAccessTokenStore::default();
+    let token = token(user());
+    store.add_token(token.clone()).await.unwrap();
+
+    let used_at = Utc::now();
+    store.record_use(&token.id, used_at).await.unwrap();
+    let used = store.get_token(&token.id).await.unwrap();
+    assert_eq!(used.last_used_at, Some(used_at));
+    assert_eq!(
+        store.record_use("unknown", used_at).await,
+        Err(AccessTokenStoreError::TokenNotFound)
+    );
+}
+
+#[tokio::test]
+async fn test_only_the_owner_removes_a_token() {
+    let mut store = HashMapAccessTokenStore::default();
+    let token = token(user());
+    store.add_token(token.clone()).await.unwrap();
+
+    assert_eq!(
+        store
+            .remove_token(&UserKey::new(None, "other@email.com"), &token.id)
+            .await,
+        Err(AccessTokenStoreError::TokenNotFound)
+    );
+    store.remove_token(&user(), &token.id).await.unwrap();
+    assert_eq!(
+        store.get_token(&token.id).await,
+        Err(AccessTokenStoreError::TokenNotFound)
+    );
+}
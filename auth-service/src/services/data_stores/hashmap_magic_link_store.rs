@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::domain::{
+    UserKey,
+    data_stores::{LinkPurpose, MagicLinkStore, MagicLinkStoreError},
+};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Debug)]
+pub struct HashMapMagicLinkStore {
+    tokens: HashMap<(LinkPurpose, String), (UserKey, DateTime<Utc>)>,
+    ttl_seconds: u64,
+}
+
+impl HashMapMagicLinkStore {
+    pub fn new(ttl_seconds: u64) -> Self {
+        Self {
+            tokens: HashMap::new(),
+            ttl_seconds,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl MagicLinkStore for HashMapMagicLinkStore {
+    async fn add_token(
+        &mut self,
+        purpose: LinkPurpose,
+        token_hash: &str,
+        user: UserKey,
+    ) -> Result<(), MagicLinkStoreError> {
+        let now = Utc::now();
+        self.tokens.retain(|_, (_, expires_at)| *expires_at > now);
+        let expires_at = now + Duration::seconds(self.ttl_seconds as i64);
+        self.tokens
+            .insert((purpose, token_hash.to_owned()), (user, expires_at));
+        Ok(())
+    }
+
+    async fn take_token(
+        &mut self,
+        purpose: LinkPurpose,
+        token_hash: &str,
+    ) -> Result<UserKey, MagicLinkStoreError> {
+        self.tokens
+            .remove(&(purpose, token_hash.to_owned()))
+            .filter(|(_, expires_at)| *expires_at > Utc::now())
+            .map(|(user, _)| user)
+            .ok_or(MagicLinkStoreError::TokenNotFound)
+    }
+}
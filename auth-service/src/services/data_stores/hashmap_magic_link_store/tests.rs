@@ -0,0 +1,69 @@
+use super::*;
+
+fn user() -> UserKey {
+    UserKey::new(None, "test@example.com")
+}
+
+#[tokio::test]
+async fn test_token_can_only_be_taken_once() {
+    let mut store = HashMapMagicLinkStore::new(600);
+    store
+        .add_token(LinkPurpose::SignIn, "hash", user())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store.take_token(LinkPurpose::SignIn, "hash").await,
+        Ok(user())
+    );
+    assert_eq!(
+        store.take_token(LinkPurpose::SignIn, "hash").await,
+        Err(MagicLinkStoreError::TokenNotFound)
+    );
+}
+
+#[tokio::test]
+async fn test_unknown_token_is_not_found() {
+    let mut store = HashMapMagicLinkStore::new(600);
+    store
+        .add_token(LinkPurpose::SignIn, "hash", user())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store.take_token(LinkPurpose::SignIn, "other").await,
+        Err(MagicLinkStoreError::TokenNotFound)
+    );
+}
+
+#[tokio::test]
+async fn test_expired_token_is_not_found() {
+    let mut store = HashMapMagicLinkStore::new(0);
+    store
+        .add_token(LinkPurpose::SignIn, "hash", user())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store.take_token(LinkPurpose::SignIn, "hash").await,
+        Err(MagicLinkStoreError::TokenNotFound)
+    );
+}
+
+#[tokio::test]
+async fn test_token_is_only_taken_for_its_purpose() {
+    let mut store = HashMapMagicLinkStore::new(600);
+    store
+        .add_token(LinkPurpose::PasswordReset, "hash", user())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store.take_token(LinkPurpose::SignIn, "hash").await,
+        Err(MagicLinkStoreError::TokenNotFound)
+    );
+    assert_eq!(
+        store.take_token(LinkPurpose::PasswordReset, "hash").await,
+        Ok(user())
+    );
+}
@@ -0,0 +1,115 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::domain::data_stores::{OAuthStore, OAuthStoreError};
+use crate::domain::{ApiKey, AuthorizationCode, OAuthClient, UserKey};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Debug, Default)]
+pub struct HashMapOAuthStore {
+    clients: HashMap<String, OAuthClient>,
+    codes: HashMap<String, AuthorizationCode>,
+    consents: HashMap<(UserKey, String), Vec<String>>,
+    api_keys: HashMap<String, ApiKey>,
+}
+
+#[async_trait::async_trait]
+impl OAuthStore for HashMapOAuthStore {
+    async fn add_client(&mut self, client: OAuthClient) -> Result<(), OAuthStoreError> {
+        if self.clients.contains_key(&client.client_id) {
+            return Err(OAuthStoreError::ClientAlreadyExists);
+        }
+        self.clients.insert(client.client_id.clone(), client);
+        Ok(())
+    }
+
+    async fn get_client(&self, client_id: &str) -> Result<OAuthClient, OAuthStoreError> {
+        self.clients
+            .get(client_id)
+            .cloned()
+            .ok_or(OAuthStoreError::ClientNotFound)
+    }
+
+    async fn add_code(&mut self, code: AuthorizationCode) -> Result<(), OAuthStoreError> {
+        self.codes.retain(|_, code| !code.is_expired());
+        self.codes.insert(code.code.clone(), code);
+        Ok(())
+    }
+
+    async fn take_code(&mut self, code: &str) -> Result<AuthorizationCode, OAuthStoreError> {
+        self.codes
+            .remove(code)
+            .filter(|code| !code.is_expired())
+            .ok_or(OAuthStoreError::CodeNotFound)
+    }
+
+    async fn granted_scopes(
+        &self,
+        user: &UserKey,
+        client_id: &str,
+    ) -> Result<Vec<String>, OAuthStoreError> {
+        Ok(self
+            .consents
+            .get(&(user.clone(), client_id.to_owned()))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn grant_consent(
+        &mut self,
+        user: &UserKey,
+        client_id: &str,
+        scopes: &[String],
+    ) -> Result<(), OAuthStoreError> {
+        let granted = self
+            .consents
+            .entry((user.clone(), client_id.to_owned()))
+            .or_default();
+        granted.extend_from_slice(scopes);
+        granted.sort();
+        granted.dedup();
+        Ok(())
+    }
+
+    async fn add_api_key(&mut self, api_key: ApiKey) -> Result<(), OAuthStoreError> {
+        self.api_keys.insert(api_key.id.clone(), api_key);
+        Ok(())
+    }
+
+    async fn get_api_key(&self, id: &str) -> Result<ApiKey, OAuthStoreError> {
+        self.api_keys
+            .get(id)
+            .cloned()
+            .ok_or(OAuthStoreError::ApiKeyNotFound)
+    }
+
+    async fn api_keys(&self) -> Result<Vec<ApiKey>, OAuthStoreError> {
+        let mut api_keys: Vec<_> = self.api_keys.values().cloned().collect();
+        api_keys.sort_by_key(|api_key| Reverse(api_key.created_at));
+        Ok(api_keys)
+    }
+
+    async fn remove_api_key(&mut self, id: &str) -> Result<(), OAuthStoreError> {
+        self.api_keys
+            .remove(id)
+            .map(|_| ())
+            .ok_or(OAuthStoreError::ApiKeyNotFound)
+    }
+
+    async fn record_api_key_use(
+        &mut self,
+        id: &str,
+        used_at: DateTime<Utc>,
+    ) -> Result<(), OAuthStoreError> {
+        let api_key = self
+            .api_keys
+            .get_mut(id)
+            .ok_or(OAuthStoreError::ApiKeyNotFound)?;
+        api_key.last_used_at = Some(used_at);
+        Ok(())
+    }
+}
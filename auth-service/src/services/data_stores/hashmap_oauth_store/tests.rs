@@ -0,0 +1,143 @@
+use chrono::{Duration, Utc};
+
+use super::*;
+use crate::domain::AuthorizationGrant;
+
+fn user() -> UserKey {
+    UserKey::new(None, "test@example.com")
+}
+
+fn client() -> OAuthClient {
+    let redirect_uris = vec!["https://app.example.com/callback".to_owned()];
+    OAuthClient::register("App".to_owned(), redirect_uris, false)
+        .unwrap()
+        .0
+}
+
+fn code(client_id: &str, ttl: Duration) -> AuthorizationCode {
+    AuthorizationGrant {
+        client_id: client_id.to_owned(),
+        redirect_uri: "https://app.example.com/callback".to_owned(),
+        scopes: vec!["openid".to_owned()],
+        nonce: None,
+        code_challenge: "challenge".to_owned(),
+    }
+    .issue_code(&user(), Utc::now(), ttl)
+}
+
+#[tokio::test]
+async fn test_add_and_get_client() {
+    let mut store = HashMapOAuthStore::default();
+    let client = client();
+    store.add_client(client.clone()).await.unwrap();
+
+    assert_eq!(
+        store.get_client(&client.client_id).await,
+        Ok(client.clone())
+    );
+    assert_eq!(
+        store.add_client(client).await,
+        Err(OAuthStoreError::ClientAlreadyExists)
+    );
+    assert_eq!(
+        store.get_client("unknown").await,
+        Err(OAuthStoreError::ClientNotFound)
+    );
+}
+
+#[tokio::test]
+async fn test_codes_are_single_use() {
+    let mut store = HashMapOAuthStore::default();
+    let code = code("client", Duration::seconds(60));
+    store.add_code(code.clone()).await.unwrap();
+
+    assert_eq!(store.take_code(&code.code).await, Ok(code.clone()));
+    assert_eq!(
+        store.take_code(&code.code).await,
+        Err(OAuthStoreError::CodeNotFound)
+    );
+}
+
+#[tokio::test]
+async fn test_expired_codes_are_not_found() {
+    let mut store = HashMapOAuthStore::default();
+    let code = code("client", Duration::zero());
+    store.add_code(code.clone()).await.unwrap();
+
+    assert_eq!(
+        store.take_code(&code.code).await,
+        Err(OAuthStoreError::CodeNotFound)
+    );
+}
+
+#[tokio::test]
+async fn test_consent_adds_to_granted_scopes() {
+    let mut store = HashMapOAuthStore::default();
+    assert!(
+        store
+            .granted_scopes(&user(), "client")
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let scopes = |scopes: &[&str]| scopes.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    store
+        .grant_consent(&user(), "client", &scopes(&["openid", "email"]))
+        .await
+        .unwrap();
+    store
+        .grant_consent(&user(), "client", &scopes(&["openid", "profile"]))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store.granted_scopes(&user(), "client").await.unwrap(),
+        scopes(&["email", "openid", "profile"])
+    );
+    assert!(
+        store
+            .granted_scopes(&user(), "other")
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_api_keys() {
+    let mut store = HashMapOAuthStore::default();
+    let scopes = vec!["jobs".to_owned()];
+    let (first, _) = ApiKey::issue("first".to_owned(), &scopes, None, None).unwrap();
+    let (mut second, _) = ApiKey::issue("second".to_owned(), &scopes, None, None).unwrap();
+    second.created_at = first.created_at + Duration::seconds(1);
+    store.add_api_key(first.clone()).await.unwrap();
+    store.add_api_key(second.clone()).await.unwrap();
+
+    assert_eq!(store.get_api_key(&first.id).await, Ok(first.clone()));
+    assert_eq!(
+        store.api_keys().await,
+        Ok(vec![second.clone(), first.clone()])
+    );
+
+    let used_at = Utc::now();
+    store.record_api_key_use(&first.id, used_at).await.unwrap();
+    assert_eq!(
+        store.get_api_key(&first.id).await.unwrap().last_used_at,
+        Some(used_at)
+    );
+
+    store.remove_api_key(&first.id).await.unwrap();
+    assert_eq!(
+        store.get_api_key(&first.id).await,
+        Err(OAuthStoreError::ApiKeyNotFound)
+    );
+    assert_eq!(
+        store.remove_api_key(&first.id).await,
+        Err(OAuthStoreError::ApiKeyNotFound)
+    );
+    assert_eq!(
+        store.record_api_key_use(&first.id, used_at).await,
+        Err(OAuthStoreError::ApiKeyNotFound)
+    );
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use crate::domain::data_stores::{OrganizationStore, OrganizationStoreError};
+use crate::domain::{Invitation, Organization, TenantSettings};
+
+use super::hashmap_user_store::{HashmapUserStore, SharedUsers};
+
+#[cfg(test)]
+mod tests;
+
+/// Members are the users of a [`HashmapUserStore`] whose tenant is the
+/// organization, so both stores see the same users.
+#[derive(Clone)]
+pub struct HashMapOrganizationStore {
+    users: SharedUsers,
+    organizations: HashMap<String, Organization>,
+    // token hash to invitation
+    invitations: HashMap<String, Invitation>,
+}
+
+impl HashMapOrganizationStore {
+    pub fn new(user_store: &HashmapUserStore) -> Self {
+        Self {
+            users: user_store.shared_users(),
+            organizations: HashMap::new(),
+            invitations: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl OrganizationStore for HashMapOrganizationStore {
+    async fn add_organization(
+        &mut self,
+        organization: Organization,
+    ) -> Result<(), OrganizationStoreError> {
+        self.organizations
+            .insert(organization.id.clone(), organization);
+        Ok(())
+    }
+
+    async fn get_organization(&self, id: &str) -> Result<Organization, OrganizationStoreError> {
+        self.organizations
+            .get(id)
+            .cloned()
+            .ok_or(OrganizationStoreError::OrganizationNotFound)
+    }
+
+    async fn remove_organization(&mut self, id: &str) -> Result<(), OrganizationStoreError> {
+        self.organizations
+            .remove(id)
+            .ok_or(OrganizationStoreError::OrganizationNotFound)?;
+        self.users
+            .write()
+            .await
+            .retain(|_, user| user.tenant_id() != Some(id));
+        self.invitations
+            .retain(|_, invitation| invitation.organization_id.as_deref() != Some(id));
+        Ok(())
+    }
+
+    async fn update_organization_settings(
+        &mut self,
+        id: &str,
+        settings: TenantSettings,
+    ) -> Result<Organization, OrganizationStoreError> {
+        let organization = self
+            .organizations
+            .get_mut(id)
+            .ok_or(OrganizationStoreError::OrganizationNotFound)?;
+        organization.settings = settings;
+        Ok(organization.clone())
+    }
+    async fn organization_members(&self, id: &str) -> Result<Vec<String>, OrganizationStoreError> {
+        self.get_organization(id).await?;
+        let mut members: Vec<String> = self
+            .users
+            .read()
+            .await
+            .values()
+            .filter(|user| user.tenant_id() == Some(id))
+            .map(|user| user.email_str().to_owned())
+            .collect();
+        members.sort();
+        Ok(members)
+    }
+
+    async fn add_invitation(
+        &mut self,
+        invitation: Invitation,
+    ) -> Result<(), OrganizationStoreError> {
+        if let Some(id) = &invitation.organization_id {
+            self.get_organization(id).await?;
+        }
+        self.invitations
+            .insert(invitation.token_hash.clone(), invitation);
+        Ok(())
+    }
+
+    async fn get_invitation(&self, token_hash: &str) -> Result<Invitation, OrganizationStoreError> {
+        self.invitations
+            .get(token_hash)
+            .cloned()
+            .ok_or(OrganizationStoreError::InvitationNotFound)
+    }
+
+    async fn take_invitation(
+        &mut self,
+        token_hash: &str,
+    ) -> Result<Invitation, OrganizationStoreError> {
+        self.invitations
+            .remove(token_hash)
+            .ok_or(OrganizationStoreError::InvitationNotFound)
+    }
+}
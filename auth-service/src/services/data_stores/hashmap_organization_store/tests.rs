@@ -0,0 +1,154 @@
+use super::*;
+use crate::domain::data_stores::{UserStore, UserStoreError};
+use crate::domain::{Email, PasswordPolicy, User, UserKey};
+
+// The organization store shares the users of the returned user store.
+async fn stores() -> (HashmapUserStore, HashMapOrganizationStore) {
+    let mut user_store = HashmapUserStore::default();
+    let user = User::parse(
+        "email@email.com".to_owned(),
+        "Kx7!vTq2Lm".to_owned(),
+        false,
+        &PasswordPolicy::default(),
+    )
+    .unwrap();
+    user_store.add_user(user).await.unwrap();
+    let store = HashMapOrganizationStore::new(&user_store);
+    (user_store, store)
+}
+
+fn organization() -> Organization {
+    Organization::create(
+        "Acme".to_owned(),
+        "email@email.com".to_owned(),
+        TenantSettings::default(),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_organizations() {
+    let (mut user_store, mut store) = stores().await;
+    let organization = organization();
+
+    store.add_organization(organization.clone()).await.unwrap();
+    assert_eq!(
+        store.organization_members(&organization.id).await.unwrap(),
+        Vec::<String>::new()
+    );
+    // the same email, with an account of its own in the organization
+    let member = User::parse(
+        "email@email.com".to_owned(),
+        "Rw4#nYs8Pe".to_owned(),
+        false,
+        &PasswordPolicy::default(),
+    )
+    .unwrap()
+    .with_tenant(Some(organization.id.clone()));
+    user_store.add_user(member).await.unwrap();
+    assert_eq!(
+        store.organization_members(&organization.id).await.unwrap(),
+        vec!["email@email.com".to_owned()]
+    );
+    assert_eq!(
+        store.organization_members("unknown").await.unwrap_err(),
+        OrganizationStoreError::OrganizationNotFound
+    );
+
+    let settings = TenantSettings::new(true, 12).unwrap();
+    let updated = store
+        .update_organization_settings(&organization.id, settings.clone())
+        .await
+        .unwrap();
+    assert_eq!(updated.settings, settings);
+    assert_eq!(
+        store.get_organization(&organization.id).await.unwrap(),
+        updated
+    );
+}
+
+#[tokio::test]
+async fn test_invitations() {
+    let (_, mut store) = stores().await;
+    let organization = organization();
+    let email = Email::parse("new@email.com").unwrap();
+    let (mut invitation, _) =
+        Invitation::new(email, Some("email@email.com".to_owned()), 1).unwrap();
+    invitation.organization_id = Some(organization.id.clone());
+
+    assert_eq!(
+        store.add_invitation(invitation.clone()).await.unwrap_err(),
+        OrganizationStoreError::OrganizationNotFound
+    );
+    store.add_organization(organization).await.unwrap();
+    store.add_invitation(invitation.clone()).await.unwrap();
+    assert_eq!(
+        store.get_invitation(&invitation.token_hash).await.unwrap(),
+        invitation
+    );
+    assert_eq!(
+        store.take_invitation(&invitation.token_hash).await.unwrap(),
+        invitation
+    );
+    assert_eq!(
+        store
+            .take_invitation(&invitation.token_hash)
+            .await
+            .unwrap_err(),
+        OrganizationStoreError::InvitationNotFound
+    );
+}
+
+#[tokio::test]
+async fn test_remove_organization() {
+    let (mut user_store, mut store) = stores().await;
+    let organization = organization();
+    store.add_organization(organization.clone()).await.unwrap();
+    let member = User::parse(
+        "email@email.com".to_owned(),
+        "Rw4#nYs8Pe".to_owned(),
+        false,
+        &PasswordPolicy::default(),
+    )
+    .unwrap()
+    .with_tenant(Some(organization.id.clone()));
+    user_store.add_user(member).await.unwrap();
+    let email = Email::parse("new@email.com").unwrap();
+    let (mut invitation, _) =
+        Invitation::new(email, Some("email@email.com".to_owned()), 1).unwrap();
+    invitation.organization_id = Some(organization.id.clone());
+    store.add_invitation(invitation.clone()).await.unwrap();
+
+    store.remove_organization(&organization.id).await.unwrap();
+    assert_eq!(
+        store.get_organization(&organization.id).await.unwrap_err(),
+        OrganizationStoreError::OrganizationNotFound
+    );
+    assert_eq!(
+        store
+            .get_invitation(&invitation.token_hash)
+            .await
+            .unwrap_err(),
+        OrganizationStoreError::InvitationNotFound
+    );
+    // its accounts go with it, the one outside it stays
+    let email = "email@email.com";
+    assert_eq!(
+        user_store
+            .get_user(&UserKey::new(Some(&organization.id), email))
+            .await
+            .unwrap_err(),
+        UserStoreError::UserNotFound
+    );
+    user_store
+        .get_user(&UserKey::new(None, email))
+        .await
+        .unwrap();
+    assert_eq!(
+        store
+            .remove_organization(&organization.id)
+            .await
+            .unwrap_err(),
+        OrganizationStoreError::OrganizationNotFound
+    );
+}
@@ -0,0 +1,81 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use crate::domain::data_stores::{TrustedDeviceStore, TrustedDeviceStoreError};
+use crate::domain::{TrustedDevice, UserKey};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Debug, Default)]
+pub struct HashMapTrustedDeviceStore {
+    devices: HashMap<UserKey, Vec<TrustedDevice>>,
+}
+
+#[async_trait::async_trait]
+impl TrustedDeviceStore for HashMapTrustedDeviceStore {
+    async fn add_device(
+        &mut self,
+        user: &UserKey,
+        device: TrustedDevice,
+    ) -> Result<(), TrustedDeviceStoreError> {
+        let devices = self.devices.entry(user.clone()).or_default();
+        devices.retain(|trusted| !trusted.is_expired());
+        devices.push(device);
+        Ok(())
+    }
+
+    async fn get_device(
+        &self,
+        user: &UserKey,
+        id: &str,
+    ) -> Result<TrustedDevice, TrustedDeviceStoreError> {
+        self.devices
+            .get(user)
+            .and_then(|devices| {
+                devices
+                    .iter()
+                    .find(|device| device.id == id && !device.is_expired())
+            })
+            .cloned()
+            .ok_or(TrustedDeviceStoreError::DeviceNotFound)
+    }
+
+    async fn user_devices(
+        &self,
+        user: &UserKey,
+    ) -> Result<Vec<TrustedDevice>, TrustedDeviceStoreError> {
+        let mut devices: Vec<_> = self
+            .devices
+            .get(user)
+            .into_iter()
+            .flatten()
+            .filter(|device| !device.is_expired())
+            .cloned()
+            .collect();
+        devices.sort_by_key(|device| Reverse(device.created_at));
+        Ok(devices)
+    }
+
+    async fn remove_device(
+        &mut self,
+        user: &UserKey,
+        id: &str,
+    ) -> Result<(), TrustedDeviceStoreError> {
+        let devices = self
+            .devices
+            .get_mut(user)
+            .ok_or(TrustedDeviceStoreError::DeviceNotFound)?;
+        let count = devices.len();
+        devices.retain(|device| device.id != id);
+        if devices.len() == count {
+            return Err(TrustedDeviceStoreError::DeviceNotFound);
+        }
+        Ok(())
+    }
+
+    async fn remove_user_devices(&mut self, user: &UserKey) -> Result<(), TrustedDeviceStoreError> {
+        self.devices.remove(user);
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+use chrono::Duration;
+
+use super::*;
+use crate::domain::ClientInfo;
+
+fn user() -> UserKey {
+    UserKey::new(None, "test@example.com")
+}
+
+fn device(ttl: Duration) -> TrustedDevice {
+    TrustedDevice::new(&ClientInfo::default(), ttl)
+}
+
+#[tokio::test]
+async fn test_add_and_get_device() {
+    let mut store = HashMapTrustedDeviceStore::default();
+    let trusted = device(Duration::days(30));
+    store.add_device(&user(), trusted.clone()).await.unwrap();
+
+    assert_eq!(
+        store.get_device(&user(), &trusted.id).await,
+        Ok(trusted.clone())
+    );
+    assert_eq!(
+        store
+            .get_device(&UserKey::new(None, "other@example.com"), &trusted.id)
+            .await,
+        Err(TrustedDeviceStoreError::DeviceNotFound)
+    );
+    assert_eq!(
+        store
+            .get_device(&UserKey::new(Some("org"), "test@example.com"), &trusted.id)
+            .await,
+        Err(TrustedDeviceStoreError::DeviceNotFound)
+    );
+}
+
+#[tokio::test]
+async fn test_expired_devices_are_not_returned() {
+    let mut store = HashMapTrustedDeviceStore::default();
+    let expired = device(Duration::zero());
+    store.add_device(&user(), expired.clone()).await.unwrap();
+
+    assert_eq!(
+        store.get_device(&user(), &expired.id).await,
+        Err(TrustedDeviceStoreError::DeviceNotFound)
+    );
+    assert_eq!(store.user_devices(&user()).await.unwrap(), []);
+}
+
+#[tokio::test]
+async fn test_remove_devices() {
+    let mut store = HashMapTrustedDeviceStore::default();
+    let first = device(Duration::days(30));
+    let second = device(Duration::days(30));
+    store.add_device(&user(), first.clone()).await.unwrap();
+    store.add_device(&user(), second.clone()).await.unwrap();
+
+    store.remove_device(&user(), &first.id).await.unwrap();
+    assert_eq!(
+        store.remove_device(&user(), &first.id).await,
+        Err(TrustedDeviceStoreError::DeviceNotFound)
+    );
+    assert_eq!(store.user_devices(&user()).await.unwrap(), [second]);
+
+    store.remove_user_devices(&user()).await.unwrap();
+    assert_eq!(store.user_devices(&user()).await.unwrap(), []);
+}
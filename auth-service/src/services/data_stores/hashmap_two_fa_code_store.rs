@@ -5,8 +5,8 @@ use thiserror::Error;
 mod tests;
 
 use crate::domain::{
+    UserKey,
     data_stores::{LoginAttemptId, TwoFACode, TwoFACodeStore, TwoFACodeStoreError},
-    email::Email,
 };
 
 #[derive(Debug, Error)]
@@ -21,18 +21,18 @@ pub enum HashMapTwoFACodeStoreError {
 
 #[derive(Default, Clone, Debug)]
 pub struct HashMapTwoFACodeStore {
-    codes: HashMap<Email, (LoginAttemptId, TwoFACode)>,
+    codes: HashMap<UserKey, (LoginAttemptId, TwoFACode)>,
 }
 
 #[async_trait::async_trait]
 impl TwoFACodeStore for HashMapTwoFACodeStore {
     async fn add_code(
         &mut self,
-        email: Email,
+        user: UserKey,
         login_attempt_id: LoginAttemptId,
         code: TwoFACode,
     ) -> Result<(), TwoFACodeStoreError> {
-        let value = self.codes.insert(email, (login_attempt_id, code));
+        let value = self.codes.insert(user, (login_attempt_id, code));
         if value.is_some() {
             (return Err(TwoFACodeStoreError::UnexpectedError(
                 HashMapTwoFACodeStoreError::InsertError.into(),
@@ -41,8 +41,8 @@ impl TwoFACodeStore for HashMapTwoFACodeStore {
         Ok(())
     }
 
-    async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError> {
-        let value = self.codes.remove(email);
+    async fn remove_code(&mut self, user: &UserKey) -> Result<(), TwoFACodeStoreError> {
+        let value = self.codes.remove(user);
         if value.is_none() {
             return Err(TwoFACodeStoreError::UnexpectedError(
                 HashMapTwoFACodeStoreError::RemoveError.into(),
@@ -53,9 +53,9 @@ impl TwoFACodeStore for HashMapTwoFACodeStore {
 
     async fn get_code(
         &self,
-        email: &Email,
+        user: &UserKey,
     ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
-        match self.codes.get(email) {
+        match self.codes.get(user) {
             Some(value) => Ok(value.clone()),
             None => Err(TwoFACodeStoreError::UnexpectedError(
                 HashMapTwoFACodeStoreError::GetError.into(),
@@ -6,17 +6,17 @@ use super::*;
 async fn test_add_code() {
     let mut store = HashMapTwoFACodeStore::default();
 
-    let email = Email::parse("email@email.com").unwrap();
+    let user = UserKey::new(None, "user@user.com");
     let login_attempt_id = LoginAttemptId::parse(Uuid::new_v4().to_string()).unwrap();
     let code = TwoFACode::default();
     store
-        .add_code(email.clone(), login_attempt_id.clone(), code.clone())
+        .add_code(user.clone(), login_attempt_id.clone(), code.clone())
         .await
         .unwrap();
     assert_eq!(store.codes.len(), 1);
-    assert!(store.codes.contains_key(&email));
+    assert!(store.codes.contains_key(&user));
     assert_eq!(
-        store.codes.get(&email),
+        store.codes.get(&user),
         Some((login_attempt_id, code)).as_ref()
     )
 }
@@ -25,16 +25,16 @@ async fn test_add_code() {
 async fn test_add_code_fail() {
     let mut store = HashMapTwoFACodeStore::default();
 
-    let email = Email::parse("email@email.com").unwrap();
+    let user = UserKey::new(None, "user@user.com");
     let login_attempt_id = LoginAttemptId::parse(Uuid::new_v4().to_string()).unwrap();
     let code = TwoFACode::default();
     store
-        .add_code(email.clone(), login_attempt_id.clone(), code.clone())
+        .add_code(user.clone(), login_attempt_id.clone(), code.clone())
         .await
         .unwrap();
 
     let result = store
-        .add_code(email.clone(), login_attempt_id.clone(), code.clone())
+        .add_code(user.clone(), login_attempt_id.clone(), code.clone())
         .await;
 
     assert!(result.is_err());
@@ -50,15 +50,15 @@ async fn test_add_code_fail() {
 async fn test_remove_code() {
     let mut store = HashMapTwoFACodeStore::default();
 
-    let email = Email::parse("email@email.com").unwrap();
+    let user = UserKey::new(None, "user@user.com");
     let login_attempt_id = LoginAttemptId::parse(Uuid::new_v4().to_string()).unwrap();
     let code = TwoFACode::default();
     store
-        .add_code(email.clone(), login_attempt_id.clone(), code.clone())
+        .add_code(user.clone(), login_attempt_id.clone(), code.clone())
         .await
         .unwrap();
     assert_eq!(store.codes.len(), 1);
-    store.remove_code(&email).await.unwrap();
+    store.remove_code(&user).await.unwrap();
     assert_eq!(store.codes.len(), 0);
 }
 
@@ -66,8 +66,8 @@ async fn test_remove_code() {
 async fn test_remove_fail() {
     let mut store = HashMapTwoFACodeStore::default();
 
-    let email = Email::parse("email@email.com").unwrap();
-    let result = store.remove_code(&email).await;
+    let user = UserKey::new(None, "user@user.com");
+    let result = store.remove_code(&user).await;
     assert!(result.is_err());
     assert_eq!(
         result.err(),
@@ -81,26 +81,26 @@ async fn test_remove_fail() {
 async fn test_get_code() {
     let mut store = HashMapTwoFACodeStore::default();
 
-    let email = Email::parse("email@email.com").unwrap();
+    let user = UserKey::new(None, "user@user.com");
     let login_attempt_id = LoginAttemptId::parse(Uuid::new_v4().to_string()).unwrap();
     let code = TwoFACode::default();
     store
-        .add_code(email.clone(), login_attempt_id.clone(), code.clone())
+        .add_code(user.clone(), login_attempt_id.clone(), code.clone())
         .await
         .unwrap();
-    let value = store.get_code(&email).await.unwrap();
+    let value = store.get_code(&user).await.unwrap();
     assert_eq!(value.0, login_attempt_id);
     assert_eq!(value.1, code);
     assert_eq!(store.codes.len(), 1);
-    assert!(store.codes.contains_key(&email));
+    assert!(store.codes.contains_key(&user));
 }
 
 #[tokio::test]
 async fn test_get_code_fail() {
     let store = HashMapTwoFACodeStore::default();
 
-    let email = Email::parse("email@email.com").unwrap();
-    let result = store.get_code(&email).await;
+    let user = UserKey::new(None, "user@user.com");
+    let result = store.get_code(&user).await;
     assert!(result.is_err());
     assert_eq!(
         result.err(),
@@ -1,43 +1,188 @@
-use crate::domain::data_stores::{UserStore, UserStoreError};
+use crate::domain::data_stores::{UserPage, UserQuery, UserStore, UserStoreError};
 use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
 
-use crate::domain::{Email, User};
+use crate::domain::{AccountStatus, KnownDevice, Password, Role, User, UserKey, UserProfile};
 
 #[cfg(test)]
 mod tests;
 
+// shared with the organization store, like the tables of a database
+pub(super) type SharedUsers = Arc<RwLock<HashMap<UserKey, User>>>;
+
 #[derive(Clone, Default)]
 pub struct HashmapUserStore {
-    users: HashMap<Email, User>,
+    users: SharedUsers,
+    devices: HashMap<UserKey, Vec<KnownDevice>>,
+    // (provider, subject) to user
+    identities: HashMap<(String, String), UserKey>,
+}
+
+impl HashmapUserStore {
+    pub(super) fn shared_users(&self) -> SharedUsers {
+        self.users.clone()
+    }
+
+    async fn update_user(
+        &self,
+        key: &UserKey,
+        update: impl FnOnce(&mut User) + Send,
+    ) -> Result<User, UserStoreError> {
+        let mut users = self.users.write().await;
+        let user = users.get_mut(key).ok_or(UserStoreError::UserNotFound)?;
+        update(user);
+        Ok(user.clone())
+    }
+}
+
+fn matches_search(user: &User, search: &str) -> bool {
+    let search = search.to_lowercase();
+    let display_name = user
+        .profile()
+        .display_name
+        .as_ref()
+        .map(|name| name.as_ref().to_lowercase());
+    user.email_str().to_lowercase().contains(&search)
+        || display_name.is_some_and(|name| name.contains(&search))
 }
 
 #[async_trait::async_trait]
 impl UserStore for HashmapUserStore {
     async fn add_user(&mut self, user: User) -> Result<(), UserStoreError> {
-        let email = user.email();
+        let key = user.key();
 
-        if self.users.contains_key(&email) {
+        let mut users = self.users.write().await;
+        if users.contains_key(&key) {
             return Err(UserStoreError::UserAlreadyExists);
         }
-        self.users.insert(email, user);
+        users.insert(key, user);
         Ok(())
     }
 
-    async fn get_user(&self, email: &str) -> Result<User, UserStoreError> {
-        let email = Email {
-            email: email.to_owned(),
-        };
+    async fn get_user(&self, user: &UserKey) -> Result<User, UserStoreError> {
         self.users
-            .get(&email)
+            .read()
+            .await
+            .get(user)
             .ok_or(UserStoreError::UserNotFound)
             .cloned()
     }
 
-    async fn validate_user(&self, email: &str, password: &str) -> Result<(), UserStoreError> {
-        let user = self.get_user(email).await?;
+    async fn validate_user(&self, user: &UserKey, password: &str) -> Result<(), UserStoreError> {
+        let user = self.get_user(user).await?;
         if user.password_str() != password {
             return Err(UserStoreError::InvalidCredentials);
         }
         Ok(())
     }
+
+    async fn update_profile(
+        &mut self,
+        user: &UserKey,
+        profile: UserProfile,
+    ) -> Result<User, UserStoreError> {
+        self.update_user(user, |user| user.set_profile(profile))
+            .await
+    }
+
+    async fn set_roles(
+        &mut self,
+        user: &UserKey,
+        roles: Vec<Role>,
+    ) -> Result<User, UserStoreError> {
+        self.update_user(user, |user| user.set_roles(roles)).await
+    }
+
+    async fn list_users(&self, query: &UserQuery) -> Result<UserPage, UserStoreError> {
+        let users = self.users.read().await;
+        let mut users: Vec<&User> = users
+            .values()
+            .filter(|user| {
+                query
+                    .search
+                    .as_deref()
+                    .is_none_or(|search| matches_search(user, search))
+            })
+            .collect();
+        users.sort_by(|a, b| (a.email_str(), a.tenant_id()).cmp(&(b.email_str(), b.tenant_id())));
+        Ok(UserPage {
+            total: users.len(),
+            users: users
+                .into_iter()
+                .skip(query.offset)
+                .take(query.limit)
+                .cloned()
+                .collect(),
+        })
+    }
+
+    async fn set_password(
+        &mut self,
+        user: &UserKey,
+        password: Password,
+    ) -> Result<(), UserStoreError> {
+        self.update_user(user, |user| user.set_password(password))
+            .await?;
+        Ok(())
+    }
+
+    async fn set_status(
+        &mut self,
+        user: &UserKey,
+        status: AccountStatus,
+    ) -> Result<User, UserStoreError> {
+        self.update_user(user, |user| user.set_status(status)).await
+    }
+
+    async fn set_requires_2fa(
+        &mut self,
+        user: &UserKey,
+        requires_2fa: bool,
+    ) -> Result<User, UserStoreError> {
+        self.update_user(user, |user| user.set_requires_2fa(requires_2fa))
+            .await
+    }
+
+    async fn known_devices(&self, user: &UserKey) -> Result<Vec<KnownDevice>, UserStoreError> {
+        Ok(self.devices.get(user).cloned().unwrap_or_default())
+    }
+
+    async fn remember_device(
+        &mut self,
+        user: &UserKey,
+        device: KnownDevice,
+    ) -> Result<(), UserStoreError> {
+        self.get_user(user).await?;
+        let devices = self.devices.entry(user.clone()).or_default();
+        devices.retain(|known| known.device_id != device.device_id);
+        devices.push(device);
+        Ok(())
+    }
+
+    async fn forget_devices(&mut self, user: &UserKey) -> Result<(), UserStoreError> {
+        self.get_user(user).await?;
+        self.devices.remove(user);
+        Ok(())
+    }
+
+    async fn linked_user(&self, provider: &str, subject: &str) -> Result<User, UserStoreError> {
+        let user = self
+            .identities
+            .get(&(provider.to_owned(), subject.to_owned()))
+            .ok_or(UserStoreError::UserNotFound)?;
+        self.get_user(user).await
+    }
+
+    async fn link_identity(
+        &mut self,
+        user: &UserKey,
+        provider: &str,
+        subject: &str,
+    ) -> Result<(), UserStoreError> {
+        self.get_user(user).await?;
+        self.identities
+            .insert((provider.to_owned(), subject.to_owned()), user.clone());
+        Ok(())
+    }
 }
@@ -1,9 +1,23 @@
 use super::*;
+use crate::domain::data_stores::UserQuery;
 use crate::domain::data_stores::UserStore;
+use crate::domain::{
+    AccountStatus, ClientInfo, DisplayName, KnownDevice, Locale, Password, PasswordPolicy, Role,
+};
+
+fn key(email: &str) -> UserKey {
+    UserKey::new(None, email)
+}
 
 async fn get_filled_hashmap_user_store() -> HashmapUserStore {
     let mut store = HashmapUserStore::default();
-    let user = User::parse("email@email.com".to_owned(), "Password1!".to_owned(), false).unwrap();
+    let user = User::parse(
+        "email@email.com".to_owned(),
+        "Kx7!vTq2Lm".to_owned(),
+        false,
+        &PasswordPolicy::default(),
+    )
+    .unwrap();
     store.add_user(user).await.unwrap();
     store
 }
@@ -12,43 +26,287 @@ async fn get_filled_hashmap_user_store() -> HashmapUserStore {
 async fn test_add_user() {
     let mut store = HashmapUserStore::default();
 
-    let user = User::parse("email@email.com".to_owned(), "Password1!".to_owned(), false).unwrap();
+    let user = User::parse(
+        "email@email.com".to_owned(),
+        "Kx7!vTq2Lm".to_owned(),
+        false,
+        &PasswordPolicy::default(),
+    )
+    .unwrap();
     store.add_user(user).await.unwrap();
-    let email = Email {
-        email: "email@email.com".to_owned(),
-    };
-    assert!(store.users.contains_key(&email));
+    assert!(
+        store
+            .users
+            .read()
+            .await
+            .contains_key(&key("email@email.com"))
+    );
 }
 
 #[tokio::test]
 async fn test_get_user() {
     let store = get_filled_hashmap_user_store().await;
 
-    let user = store.get_user("email@email.com").await;
+    let user = store.get_user(&key("email@email.com")).await;
     assert!(user.is_ok());
     let user = user.unwrap();
     assert_eq!(user.email_str(), "email@email.com");
-    assert_eq!(user.password_str(), "Password1!");
+    assert_eq!(user.password_str(), "Kx7!vTq2Lm");
     assert!(!user.requires_2fa());
 
-    let user = store.get_user("wrong_email@email.com").await;
+    let user = store.get_user(&key("wrong_email@email.com")).await;
     assert!(user.is_err());
     assert_eq!(user.unwrap_err(), UserStoreError::UserNotFound)
 }
 
+#[tokio::test]
+async fn test_emails_are_unique_within_a_tenant() {
+    let mut store = get_filled_hashmap_user_store().await;
+    let user = User::parse(
+        "email@email.com".to_owned(),
+        "Rw4#nYs8Pe".to_owned(),
+        false,
+        &PasswordPolicy::default(),
+    )
+    .unwrap()
+    .with_tenant(Some("acme".to_owned()));
+    store.add_user(user.clone()).await.unwrap();
+    assert_eq!(
+        store.add_user(user).await.unwrap_err(),
+        UserStoreError::UserAlreadyExists
+    );
+
+    let member = UserKey::new(Some("acme"), "email@email.com");
+    assert!(store.validate_user(&member, "Rw4#nYs8Pe").await.is_ok());
+    assert_eq!(
+        store
+            .validate_user(&key("email@email.com"), "Rw4#nYs8Pe")
+            .await
+            .unwrap_err(),
+        UserStoreError::InvalidCredentials
+    );
+    assert_eq!(
+        store
+            .get_user(&UserKey::new(Some("other"), "email@email.com"))
+            .await
+            .unwrap_err(),
+        UserStoreError::UserNotFound
+    );
+}
+
 #[tokio::test]
 async fn test_validate_user() {
     let store = get_filled_hashmap_user_store().await;
-    let user = store.validate_user("email@email.com", "Password1!").await;
+    let user = store
+        .validate_user(&key("email@email.com"), "Kx7!vTq2Lm")
+        .await;
     assert!(user.is_ok());
     let user = store
-        .validate_user("email@email.com", "wrong_password")
+        .validate_user(&key("email@email.com"), "wrong_password")
         .await;
     assert!(user.is_err());
     assert_eq!(user.unwrap_err(), UserStoreError::InvalidCredentials);
     let user = store
-        .validate_user("wrong_email@email.com", "password")
+        .validate_user(&key("wrong_email@email.com"), "password")
         .await;
     assert!(user.is_err());
     assert_eq!(user.unwrap_err(), UserStoreError::UserNotFound);
 }
+
+#[tokio::test]
+async fn test_update_profile() {
+    let mut store = get_filled_hashmap_user_store().await;
+    let profile = UserProfile {
+        display_name: Some(DisplayName::parse("Gabriel").unwrap()),
+        locale: Some(Locale::parse("pt-BR").unwrap()),
+    };
+
+    let user = store
+        .update_profile(&key("email@email.com"), profile.clone())
+        .await
+        .unwrap();
+    assert_eq!(user.profile(), &profile);
+    let user = store.get_user(&key("email@email.com")).await.unwrap();
+    assert_eq!(user.profile(), &profile);
+
+    let result = store
+        .update_profile(&key("wrong_email@email.com"), UserProfile::default())
+        .await;
+    assert_eq!(result.unwrap_err(), UserStoreError::UserNotFound);
+}
+
+#[tokio::test]
+async fn test_set_roles() {
+    let mut store = get_filled_hashmap_user_store().await;
+    let roles = vec![
+        Role::parse("support").unwrap(),
+        Role::admin(),
+        Role::admin(),
+    ];
+
+    let user = store
+        .set_roles(&key("email@email.com"), roles)
+        .await
+        .unwrap();
+    assert_eq!(
+        user.roles(),
+        [Role::admin(), Role::parse("support").unwrap()]
+    );
+    let user = store.get_user(&key("email@email.com")).await.unwrap();
+    assert!(user.has_role("admin"));
+
+    let user = store
+        .set_roles(&key("email@email.com"), vec![])
+        .await
+        .unwrap();
+    assert!(user.roles().is_empty());
+
+    let result = store.set_roles(&key("wrong_email@email.com"), vec![]).await;
+    assert_eq!(result.unwrap_err(), UserStoreError::UserNotFound);
+}
+
+#[tokio::test]
+async fn test_list_users() {
+    let mut store = get_filled_hashmap_user_store().await;
+    for email in ["carol@email.com", "alice@email.com", "bob@other.com"] {
+        let user = User::parse(
+            email.to_owned(),
+            "Kx7!vTq2Lm".to_owned(),
+            false,
+            &PasswordPolicy::default(),
+        )
+        .unwrap();
+        store.add_user(user).await.unwrap();
+    }
+    let profile = UserProfile {
+        display_name: Some(DisplayName::parse("Robert").unwrap()),
+        locale: None,
+    };
+    store
+        .update_profile(&key("bob@other.com"), profile)
+        .await
+        .unwrap();
+
+    let query = UserQuery {
+        search: None,
+        offset: 1,
+        limit: 2,
+    };
+    let page = store.list_users(&query).await.unwrap();
+    assert_eq!(page.total, 4);
+    let emails: Vec<&str> = page.users.iter().map(User::email_str).collect();
+    assert_eq!(emails, ["bob@other.com", "carol@email.com"]);
+
+    let query = UserQuery {
+        search: Some("ROB".to_owned()),
+        offset: 0,
+        limit: 10,
+    };
+    let page = store.list_users(&query).await.unwrap();
+    assert_eq!(page.total, 1);
+    assert_eq!(page.users[0].email_str(), "bob@other.com");
+}
+
+#[tokio::test]
+async fn test_set_password_status_and_2fa() {
+    let mut store = get_filled_hashmap_user_store().await;
+
+    let password = Password::parse("Nv2%gFh7Ub4^", &PasswordPolicy::default()).unwrap();
+    store
+        .set_password(&key("email@email.com"), password)
+        .await
+        .unwrap();
+    assert!(
+        store
+            .validate_user(&key("email@email.com"), "Nv2%gFh7Ub4^")
+            .await
+            .is_ok()
+    );
+
+    let user = store
+        .set_status(&key("email@email.com"), AccountStatus::Disabled)
+        .await
+        .unwrap();
+    assert_eq!(user.status(), AccountStatus::Disabled);
+
+    let user = store
+        .set_requires_2fa(&key("email@email.com"), true)
+        .await
+        .unwrap();
+    assert!(user.requires_2fa());
+
+    let result = store
+        .set_status(&key("wrong_email@email.com"), AccountStatus::Active)
+        .await;
+    assert_eq!(result.unwrap_err(), UserStoreError::UserNotFound);
+}
+
+#[tokio::test]
+async fn test_remember_and_forget_devices() {
+    let mut store = get_filled_hashmap_user_store().await;
+    let client = ClientInfo {
+        ip: Some("127.0.0.1".to_owned()),
+        user_agent: Some("Firefox".to_owned()),
+    };
+    assert!(
+        store
+            .known_devices(&key("email@email.com"))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let device = KnownDevice::new("device-1".to_owned(), &client);
+    store
+        .remember_device(&key("email@email.com"), device.clone())
+        .await
+        .unwrap();
+    // same id, refreshed rather than added
+    store
+        .remember_device(&key("email@email.com"), device.clone())
+        .await
+        .unwrap();
+    assert_eq!(
+        store.known_devices(&key("email@email.com")).await.unwrap(),
+        std::slice::from_ref(&device)
+    );
+    assert_eq!(
+        store
+            .remember_device(&key("wrong_email@email.com"), device)
+            .await,
+        Err(UserStoreError::UserNotFound)
+    );
+
+    store.forget_devices(&key("email@email.com")).await.unwrap();
+    assert!(
+        store
+            .known_devices(&key("email@email.com"))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_link_identity() {
+    let mut store = get_filled_hashmap_user_store().await;
+    assert_eq!(
+        store.linked_user("google", "123").await.unwrap_err(),
+        UserStoreError::UserNotFound
+    );
+    assert_eq!(
+        store
+            .link_identity(&key("wrong_email@email.com"), "google", "123")
+            .await,
+        Err(UserStoreError::UserNotFound)
+    );
+
+    store
+        .link_identity(&key("email@email.com"), "google", "123")
+        .await
+        .unwrap();
+    let user = store.linked_user("google", "123").await.unwrap();
+    assert_eq!(user.email_str(), "email@email.com");
+    // subjects are only unique within a provider
+    assert!(store.linked_user("github", "123").await.is_err());
+}
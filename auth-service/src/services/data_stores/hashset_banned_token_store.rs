@@ -1,5 +1,8 @@
-use crate::domain::data_stores::{BannedTokenStore, BannedTokenStoreError};
-use std::collections::HashSet;
+use crate::domain::{
+    UserKey,
+    data_stores::{BannedTokenStore, BannedTokenStoreError},
+};
+use std::collections::{HashMap, HashSet};
 
 #[cfg(test)]
 mod tests;
@@ -7,6 +10,7 @@ mod tests;
 #[derive(Clone, Debug, Default)]
 pub struct HashSetBannedTokenStore {
     pub tokens: HashSet<String>,
+    pub user_bans: HashMap<UserKey, i64>,
 }
 
 #[async_trait::async_trait]
@@ -19,4 +23,18 @@ impl BannedTokenStore for HashSetBannedTokenStore {
         let result = self.tokens.contains(token);
         Ok(result)
     }
+    async fn ban_user_tokens(
+        &mut self,
+        user: &UserKey,
+        issued_before: i64,
+    ) -> Result<(), BannedTokenStoreError> {
+        self.user_bans.insert(user.clone(), issued_before);
+        Ok(())
+    }
+    async fn user_tokens_banned_before(
+        &self,
+        user: &UserKey,
+    ) -> Result<Option<i64>, BannedTokenStoreError> {
+        Ok(self.user_bans.get(user).copied())
+    }
 }
@@ -18,3 +18,33 @@ async fn is_valid() {
     assert!(store.contains_token(token).await.unwrap());
     assert!(!store.contains_token("something.else").await.unwrap());
 }
+
+#[tokio::test]
+async fn ban_user_tokens() {
+    let mut store = HashSetBannedTokenStore::default();
+    assert_eq!(
+        store
+            .user_tokens_banned_before(&UserKey::new(None, "test@example.com"))
+            .await
+            .unwrap(),
+        None
+    );
+    store
+        .ban_user_tokens(&UserKey::new(None, "test@example.com"), 1_700_000_000)
+        .await
+        .unwrap();
+    assert_eq!(
+        store
+            .user_tokens_banned_before(&UserKey::new(None, "test@example.com"))
+            .await
+            .unwrap(),
+        Some(1_700_000_000)
+    );
+    assert_eq!(
+        store
+            .user_tokens_banned_before(&UserKey::new(None, "other@example.com"))
+            .await
+            .unwrap(),
+        None
+    );
+}
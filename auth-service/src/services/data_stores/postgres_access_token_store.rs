@@ -0,0 +1,126 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::domain::data_stores::{AccessTokenStore, AccessTokenStoreError};
+use crate::domain::{PersonalAccessToken, UserKey};
+
+#[derive(Clone)]
+pub struct PostgresAccessTokenStore {
+    pool: PgPool,
+}
+
+impl PostgresAccessTokenStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl AccessTokenStore for PostgresAccessTokenStore {
+    #[tracing::instrument(name = "Adding personal access token to PostgreSQL", skip_all)]
+    async fn add_token(&mut self, token: PersonalAccessToken) -> Result<(), AccessTokenStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO personal_access_tokens
+                (id, user_tenant, user_email, name, secret_hash, scopes, created_at, expires_at,
+                last_used_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+            token.id,
+            token.tenant_id.as_deref().unwrap_or_default(),
+            token.user_email,
+            token.name,
+            token.secret_hash,
+            &token.scopes,
+            token.created_at,
+            token.expires_at,
+            token.last_used_at,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AccessTokenStoreError::UnexpectedError(e.into()))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving personal access token from PostgreSQL", skip_all)]
+    async fn get_token(&self, id: &str) -> Result<PersonalAccessToken, AccessTokenStoreError> {
+        sqlx::query_as!(
+            PersonalAccessToken,
+            r#"
+            SELECT id, user_email, NULLIF(user_tenant, '') AS tenant_id, name, secret_hash, scopes,
+                created_at, expires_at, last_used_at
+            FROM personal_access_tokens WHERE id = $1
+            "#,
+            id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AccessTokenStoreError::UnexpectedError(e.into()))?
+        .ok_or(AccessTokenStoreError::TokenNotFound)
+    }
+
+    #[tracing::instrument(name = "Listing personal access tokens from PostgreSQL", skip_all)]
+    async fn user_tokens(
+        &self,
+        user: &UserKey,
+    ) -> Result<Vec<PersonalAccessToken>, AccessTokenStoreError> {
+        sqlx::query_as!(
+            PersonalAccessToken,
+            r#"
+            SELECT id, user_email, NULLIF(user_tenant, '') AS tenant_id, name, secret_hash, scopes,
+                created_at, expires_at, last_used_at
+            FROM personal_access_tokens WHERE user_tenant = $1 AND user_email = $2
+            ORDER BY created_at DESC
+            "#,
+            user.tenant_key(),
+            user.email,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AccessTokenStoreError::UnexpectedError(e.into()))
+    }
+
+    #[tracing::instrument(name = "Removing personal access token from PostgreSQL", skip_all)]
+    async fn remove_token(
+        &mut self,
+        user: &UserKey,
+        id: &str,
+    ) -> Result<(), AccessTokenStoreError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM personal_access_tokens
+            WHERE id = $1 AND user_tenant = $2 AND user_email = $3
+            "#,
+            id,
+            user.tenant_key(),
+            user.email,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AccessTokenStoreError::UnexpectedError(e.into()))?;
+        if result.rows_affected() == 0 {
+            return Err(AccessTokenStoreError::TokenNotFound);
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Recording personal access token use in PostgreSQL", skip_all)]
+    async fn record_use(
+        &mut self,
+        id: &str,
+        used_at: DateTime<Utc>,
+    ) -> Result<(), AccessTokenStoreError> {
+        let result = sqlx::query!(
+            "UPDATE personal_access_tokens SET last_used_at = $2 WHERE id = $1",
+            id,
+            used_at,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AccessTokenStoreError::UnexpectedError(e.into()))?;
+        if result.rows_affected() == 0 {
+            return Err(AccessTokenStoreError::TokenNotFound);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use color_eyre::eyre::eyre;
+use sqlx::PgPool;
+
+use crate::domain::{
+    AuditEvent, AuditEventType, AuditOutcome,
+    data_stores::{AuditLog, AuditLogError},
+};
+
+#[derive(Clone)]
+pub struct PostgresAuditLog {
+    pool: PgPool,
+}
+
+impl PostgresAuditLog {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl AuditLog for PostgresAuditLog {
+    #[tracing::instrument(name = "Recording audit event in PostgreSQL", skip_all)]
+    async fn record(&mut self, event: AuditEvent) -> Result<(), AuditLogError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO audit_events (event_type, actor, target, ip, user_agent, outcome, occurred_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            event.event_type.as_str(),
+            event.actor,
+            event.target,
+            event.ip,
+            event.user_agent,
+            event.outcome.as_str(),
+            event.occurred_at,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AuditLogError::UnexpectedError(e.into()))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving audit events from PostgreSQL", skip_all)]
+    async fn user_events(
+        &self,
+        email: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<AuditEvent>, AuditLogError> {
+        let offset = i64::try_from(offset).map_err(|e| AuditLogError::UnexpectedError(e.into()))?;
+        let limit = i64::try_from(limit).map_err(|e| AuditLogError::UnexpectedError(e.into()))?;
+        let rows = sqlx::query!(
+            r#"
+            SELECT event_type, actor, target, ip, user_agent, outcome, occurred_at
+            FROM audit_events
+            WHERE actor = $1 OR target = $1
+            ORDER BY occurred_at DESC, id DESC
+            OFFSET $2 LIMIT $3
+            "#,
+            email,
+            offset,
+            limit,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AuditLogError::UnexpectedError(e.into()))?;
+
+        rows.into_iter()
+            .map(|row| {
+                let event_type: AuditEventType = row
+                    .event_type
+                    .parse()
+                    .map_err(|e: String| AuditLogError::UnexpectedError(eyre!(e)))?;
+                let outcome: AuditOutcome = row
+                    .outcome
+                    .parse()
+                    .map_err(|e: String| AuditLogError::UnexpectedError(eyre!(e)))?;
+                Ok(AuditEvent {
+                    event_type,
+                    actor: row.actor,
+                    target: row.target,
+                    ip: row.ip,
+                    user_agent: row.user_agent,
+                    outcome,
+                    occurred_at: row.occurred_at,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,243 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::domain::data_stores::{OAuthStore, OAuthStoreError};
+use crate::domain::{ApiKey, AuthorizationCode, OAuthClient, UserKey};
+
+#[derive(Clone)]
+pub struct PostgresOAuthStore {
+    pool: PgPool,
+}
+
+impl PostgresOAuthStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl OAuthStore for PostgresOAuthStore {
+    #[tracing::instrument(name = "Adding OAuth client to PostgreSQL", skip_all)]
+    async fn add_client(&mut self, client: OAuthClient) -> Result<(), OAuthStoreError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO oauth_clients (client_id, name, redirect_uris, secret_hash, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (client_id) DO NOTHING
+            "#,
+            client.client_id,
+            client.name,
+            &client.redirect_uris,
+            client.secret_hash,
+            client.created_at,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| OAuthStoreError::UnexpectedError(e.into()))?;
+        if result.rows_affected() == 0 {
+            return Err(OAuthStoreError::ClientAlreadyExists);
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving OAuth client from PostgreSQL", skip_all)]
+    async fn get_client(&self, client_id: &str) -> Result<OAuthClient, OAuthStoreError> {
+        sqlx::query_as!(
+            OAuthClient,
+            r#"
+            SELECT client_id, name, redirect_uris, secret_hash, created_at FROM oauth_clients
+            WHERE client_id = $1
+            "#,
+            client_id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| OAuthStoreError::UnexpectedError(e.into()))?
+        .ok_or(OAuthStoreError::ClientNotFound)
+    }
+
+    #[tracing::instrument(name = "Adding authorization code to PostgreSQL", skip_all)]
+    async fn add_code(&mut self, code: AuthorizationCode) -> Result<(), OAuthStoreError> {
+        sqlx::query!("DELETE FROM oauth_authorization_codes WHERE expires_at <= NOW()")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| OAuthStoreError::UnexpectedError(e.into()))?;
+        sqlx::query!(
+            r#"
+            INSERT INTO oauth_authorization_codes
+                (code, client_id, user_tenant, user_email, redirect_uri, scopes, nonce,
+                 code_challenge, auth_time, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            "#,
+            code.code,
+            code.client_id,
+            code.tenant_id.as_deref().unwrap_or_default(),
+            code.user_email,
+            code.redirect_uri,
+            &code.scopes,
+            code.nonce,
+            code.code_challenge,
+            code.auth_time,
+            code.expires_at,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| OAuthStoreError::UnexpectedError(e.into()))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Taking authorization code from PostgreSQL", skip_all)]
+    async fn take_code(&mut self, code: &str) -> Result<AuthorizationCode, OAuthStoreError> {
+        // deleted whether expired or not, it can't be used anymore
+        let code = sqlx::query_as!(
+            AuthorizationCode,
+            r#"
+            DELETE FROM oauth_authorization_codes WHERE code = $1
+            RETURNING code, client_id, user_email, NULLIF(user_tenant, '') AS tenant_id,
+                redirect_uri, scopes, nonce, code_challenge, auth_time, expires_at
+            "#,
+            code,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| OAuthStoreError::UnexpectedError(e.into()))?;
+        code.filter(|code| !code.is_expired())
+            .ok_or(OAuthStoreError::CodeNotFound)
+    }
+
+    #[tracing::instrument(name = "Retrieving OAuth consent from PostgreSQL", skip_all)]
+    async fn granted_scopes(
+        &self,
+        user: &UserKey,
+        client_id: &str,
+    ) -> Result<Vec<String>, OAuthStoreError> {
+        let scopes = sqlx::query_scalar!(
+            r#"
+            SELECT scopes FROM oauth_consents
+            WHERE user_tenant = $1 AND user_email = $2 AND client_id = $3
+            "#,
+            user.tenant_key(),
+            user.email,
+            client_id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| OAuthStoreError::UnexpectedError(e.into()))?;
+        Ok(scopes.unwrap_or_default())
+    }
+
+    #[tracing::instrument(name = "Storing OAuth consent in PostgreSQL", skip_all)]
+    async fn grant_consent(
+        &mut self,
+        user: &UserKey,
+        client_id: &str,
+        scopes: &[String],
+    ) -> Result<(), OAuthStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO oauth_consents (user_tenant, user_email, client_id, scopes)
+            VALUES ($1, $2, $3, ARRAY(SELECT DISTINCT unnest($4::TEXT[]) ORDER BY 1))
+            ON CONFLICT (user_tenant, user_email, client_id) DO UPDATE SET
+                scopes = ARRAY(
+                    SELECT DISTINCT unnest(oauth_consents.scopes || EXCLUDED.scopes) ORDER BY 1
+                ),
+                granted_at = NOW()
+            "#,
+            user.tenant_key(),
+            user.email,
+            client_id,
+            scopes,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| OAuthStoreError::UnexpectedError(e.into()))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Adding API key to PostgreSQL", skip_all)]
+    async fn add_api_key(&mut self, api_key: ApiKey) -> Result<(), OAuthStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO api_keys
+                (id, name, secret_hash, scopes, created_by, created_at, expires_at, last_used_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+            api_key.id,
+            api_key.name,
+            api_key.secret_hash,
+            &api_key.scopes,
+            api_key.created_by,
+            api_key.created_at,
+            api_key.expires_at,
+            api_key.last_used_at,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| OAuthStoreError::UnexpectedError(e.into()))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving API key from PostgreSQL", skip_all)]
+    async fn get_api_key(&self, id: &str) -> Result<ApiKey, OAuthStoreError> {
+        sqlx::query_as!(
+            ApiKey,
+            r#"
+            SELECT id, name, secret_hash, scopes, created_by, created_at, expires_at,
+                last_used_at
+            FROM api_keys WHERE id = $1
+            "#,
+            id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| OAuthStoreError::UnexpectedError(e.into()))?
+        .ok_or(OAuthStoreError::ApiKeyNotFound)
+    }
+
+    #[tracing::instrument(name = "Listing API keys from PostgreSQL", skip_all)]
+    async fn api_keys(&self) -> Result<Vec<ApiKey>, OAuthStoreError> {
+        sqlx::query_as!(
+            ApiKey,
+            r#"
+            SELECT id, name, secret_hash, scopes, created_by, created_at, expires_at,
+                last_used_at
+            FROM api_keys ORDER BY created_at DESC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| OAuthStoreError::UnexpectedError(e.into()))
+    }
+
+    #[tracing::instrument(name = "Removing API key from PostgreSQL", skip_all)]
+    async fn remove_api_key(&mut self, id: &str) -> Result<(), OAuthStoreError> {
+        let result = sqlx::query!("DELETE FROM api_keys WHERE id = $1", id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| OAuthStoreError::UnexpectedError(e.into()))?;
+        if result.rows_affected() == 0 {
+            return Err(OAuthStoreError::ApiKeyNotFound);
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Recording API key use in PostgreSQL", skip_all)]
+    async fn record_api_key_use(
+        &mut self,
+        id: &str,
+        used_at: DateTime<Utc>,
+    ) -> Result<(), OAuthStoreError> {
+        let result = sqlx::query!(
+            "UPDATE api_keys SET last_used_at = $2 WHERE id = $1",
+            id,
+            used_at,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| OAuthStoreError::UnexpectedError(e.into()))?;
+        if result.rows_affected() == 0 {
+            return Err(OAuthStoreError::ApiKeyNotFound);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+use sqlx::PgPool;
+
+use crate::domain::data_stores::{OrganizationStore, OrganizationStoreError};
+use crate::domain::{Email, Invitation, Organization, Role, TenantSettings};
+
+/// Members are the users whose tenant is the organization.
+#[derive(Clone)]
+pub struct PostgresOrganizationStore {
+    pool: PgPool,
+}
+
+impl PostgresOrganizationStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl OrganizationStore for PostgresOrganizationStore {
+    #[tracing::instrument(name = "Adding organization to PostgreSQL", skip_all)]
+    async fn add_organization(
+        &mut self,
+        organization: Organization,
+    ) -> Result<(), OrganizationStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO organizations
+                (id, name, owner_email, require_2fa, min_password_length, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            organization.id,
+            organization.name,
+            organization.owner_email,
+            organization.settings.require_2fa,
+            organization.settings.min_password_length as i32,
+            organization.created_at,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| OrganizationStoreError::UnexpectedError(e.into()))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving organization from PostgreSQL", skip_all)]
+    async fn get_organization(&self, id: &str) -> Result<Organization, OrganizationStoreError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, name, owner_email, require_2fa, min_password_length, created_at
+            FROM organizations WHERE id = $1
+            "#,
+            id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| OrganizationStoreError::UnexpectedError(e.into()))?
+        .ok_or(OrganizationStoreError::OrganizationNotFound)?;
+        Ok(Organization {
+            id: row.id,
+            name: row.name,
+            owner_email: row.owner_email,
+            settings: TenantSettings {
+                require_2fa: row.require_2fa,
+                min_password_length: row.min_password_length as usize,
+            },
+            created_at: row.created_at,
+        })
+    }
+
+    // accounts and invitations go with it, through ON DELETE CASCADE
+    #[tracing::instrument(name = "Removing organization from PostgreSQL", skip_all)]
+    async fn remove_organization(&mut self, id: &str) -> Result<(), OrganizationStoreError> {
+        let result = sqlx::query!("DELETE FROM organizations WHERE id = $1", id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| OrganizationStoreError::UnexpectedError(e.into()))?;
+        if result.rows_affected() == 0 {
+            return Err(OrganizationStoreError::OrganizationNotFound);
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Updating organization settings in PostgreSQL", skip_all)]
+    async fn update_organization_settings(
+        &mut self,
+        id: &str,
+        settings: TenantSettings,
+    ) -> Result<Organization, OrganizationStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE organizations SET require_2fa = $2, min_password_length = $3
+            WHERE id = $1
+            "#,
+            id,
+            settings.require_2fa,
+            settings.min_password_length as i32,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| OrganizationStoreError::UnexpectedError(e.into()))?;
+        if result.rows_affected() == 0 {
+            return Err(OrganizationStoreError::OrganizationNotFound);
+        }
+        self.get_organization(id).await
+    }
+
+    #[tracing::instrument(name = "Listing organization members from PostgreSQL", skip_all)]
+    async fn organization_members(&self, id: &str) -> Result<Vec<String>, OrganizationStoreError> {
+        self.get_organization(id).await?;
+        sqlx::query_scalar!(
+            "SELECT email FROM users WHERE tenant_id = $1 ORDER BY email",
+            id,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| OrganizationStoreError::UnexpectedError(e.into()))
+    }
+
+    #[tracing::instrument(name = "Adding invitation to PostgreSQL", skip_all)]
+    async fn add_invitation(
+        &mut self,
+        invitation: Invitation,
+    ) -> Result<(), OrganizationStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO invitations
+                (token_hash, email, organization_id, role, invited_by, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            invitation.token_hash,
+            invitation.email.as_ref(),
+            invitation.organization_id,
+            invitation.role.as_ref().map(AsRef::as_ref),
+            invitation.invited_by,
+            invitation.expires_at,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e.as_database_error() {
+            Some(db_error) if db_error.is_foreign_key_violation() => {
+                OrganizationStoreError::OrganizationNotFound
+            }
+            _ => OrganizationStoreError::UnexpectedError(e.into()),
+        })?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving invitation from PostgreSQL", skip_all)]
+    async fn get_invitation(&self, token_hash: &str) -> Result<Invitation, OrganizationStoreError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT token_hash, email, organization_id, role, invited_by, expires_at
+            FROM invitations WHERE token_hash = $1
+            "#,
+            token_hash,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| OrganizationStoreError::UnexpectedError(e.into()))?
+        .ok_or(OrganizationStoreError::InvitationNotFound)?;
+        let email = Email::parse(&row.email)
+            .map_err(|e| OrganizationStoreError::UnexpectedError(e.into()))?;
+        Ok(Invitation {
+            token_hash: row.token_hash,
+            email,
+            organization_id: row.organization_id,
+            role: row.role.map(Role::new_no_validation),
+            invited_by: row.invited_by,
+            expires_at: row.expires_at,
+        })
+    }
+
+    #[tracing::instrument(name = "Taking invitation from PostgreSQL", skip_all)]
+    async fn take_invitation(
+        &mut self,
+        token_hash: &str,
+    ) -> Result<Invitation, OrganizationStoreError> {
+        let row = sqlx::query!(
+            r#"
+            DELETE FROM invitations WHERE token_hash = $1
+            RETURNING token_hash, email, organization_id, role, invited_by, expires_at
+            "#,
+            token_hash,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| OrganizationStoreError::UnexpectedError(e.into()))?
+        .ok_or(OrganizationStoreError::InvitationNotFound)?;
+        let email = Email::parse(&row.email)
+            .map_err(|e| OrganizationStoreError::UnexpectedError(e.into()))?;
+        Ok(Invitation {
+            token_hash: row.token_hash,
+            email,
+            organization_id: row.organization_id,
+            role: row.role.map(Role::new_no_validation),
+            invited_by: row.invited_by,
+            expires_at: row.expires_at,
+        })
+    }
+}
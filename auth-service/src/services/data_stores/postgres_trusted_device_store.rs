@@ -0,0 +1,120 @@
+use sqlx::PgPool;
+
+use crate::domain::data_stores::{TrustedDeviceStore, TrustedDeviceStoreError};
+use crate::domain::{TrustedDevice, UserKey};
+
+#[derive(Clone)]
+pub struct PostgresTrustedDeviceStore {
+    pool: PgPool,
+}
+
+impl PostgresTrustedDeviceStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl TrustedDeviceStore for PostgresTrustedDeviceStore {
+    #[tracing::instrument(name = "Adding trusted device to PostgreSQL", skip_all)]
+    async fn add_device(
+        &mut self,
+        user: &UserKey,
+        device: TrustedDevice,
+    ) -> Result<(), TrustedDeviceStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO trusted_devices
+                (id, user_tenant, user_email, user_agent, ip, created_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            device.id,
+            user.tenant_key(),
+            user.email,
+            device.user_agent,
+            device.ip,
+            device.created_at,
+            device.expires_at,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| TrustedDeviceStoreError::UnexpectedError(e.into()))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving trusted device from PostgreSQL", skip_all)]
+    async fn get_device(
+        &self,
+        user: &UserKey,
+        id: &str,
+    ) -> Result<TrustedDevice, TrustedDeviceStoreError> {
+        sqlx::query_as!(
+            TrustedDevice,
+            r#"
+            SELECT id, user_agent, ip, created_at, expires_at FROM trusted_devices
+            WHERE user_tenant = $1 AND user_email = $2 AND id = $3 AND expires_at > NOW()
+            "#,
+            user.tenant_key(),
+            user.email,
+            id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| TrustedDeviceStoreError::UnexpectedError(e.into()))?
+        .ok_or(TrustedDeviceStoreError::DeviceNotFound)
+    }
+
+    #[tracing::instrument(name = "Retrieving trusted devices from PostgreSQL", skip_all)]
+    async fn user_devices(
+        &self,
+        user: &UserKey,
+    ) -> Result<Vec<TrustedDevice>, TrustedDeviceStoreError> {
+        sqlx::query_as!(
+            TrustedDevice,
+            r#"
+            SELECT id, user_agent, ip, created_at, expires_at FROM trusted_devices
+            WHERE user_tenant = $1 AND user_email = $2 AND expires_at > NOW()
+            ORDER BY created_at DESC
+            "#,
+            user.tenant_key(),
+            user.email,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| TrustedDeviceStoreError::UnexpectedError(e.into()))
+    }
+
+    #[tracing::instrument(name = "Removing trusted device from PostgreSQL", skip_all)]
+    async fn remove_device(
+        &mut self,
+        user: &UserKey,
+        id: &str,
+    ) -> Result<(), TrustedDeviceStoreError> {
+        let result = sqlx::query!(
+            "DELETE FROM trusted_devices WHERE user_tenant = $1 AND user_email = $2 AND id = $3",
+            user.tenant_key(),
+            user.email,
+            id,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| TrustedDeviceStoreError::UnexpectedError(e.into()))?;
+        if result.rows_affected() == 0 {
+            return Err(TrustedDeviceStoreError::DeviceNotFound);
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Removing trusted devices from PostgreSQL", skip_all)]
+    async fn remove_user_devices(&mut self, user: &UserKey) -> Result<(), TrustedDeviceStoreError> {
+        sqlx::query!(
+            "DELETE FROM trusted_devices WHERE user_tenant = $1 AND user_email = $2",
+            user.tenant_key(),
+            user.email,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| TrustedDeviceStoreError::UnexpectedError(e.into()))?;
+        Ok(())
+    }
+}
@@ -7,8 +7,9 @@ use color_eyre::eyre::{Context, Result, eyre};
 use sqlx::PgPool;
 
 use crate::domain::{
-    Email, User,
-    data_stores::{UserStore, UserStoreError},
+    AccountStatus, DisplayName, Email, KnownDevice, Locale, Password, Role, User, UserKey,
+    UserProfile,
+    data_stores::{UserPage, UserQuery, UserStore, UserStoreError},
 };
 
 #[tracing::instrument(name = "Verify password hash", skip_all)]
@@ -50,6 +51,21 @@ async fn compute_password_hash(password: String) -> Result<String> {
     .await?
 }
 
+fn parse_status(status: &str) -> Result<AccountStatus, UserStoreError> {
+    status
+        .parse()
+        .map_err(|e: String| UserStoreError::UnexpectedError(eyre!(e)))
+}
+
+// `%` and `_` in a search are literal.
+fn like_pattern(search: &str) -> String {
+    let escaped = search
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
 #[derive(Clone)]
 pub struct PostgresUserStore {
     pool: PgPool,
@@ -68,8 +84,11 @@ impl UserStore for PostgresUserStore {
         let email = user.email_str();
         let password = user.password_str();
         let requires_2fa = user.requires_2fa();
+        let email_verified = user.email_verified();
+        let created_at = user.created_at();
+        let tenant_id = user.tenant_id();
 
-        match self.get_user(email).await {
+        match self.get_user(&user.key()).await {
             Ok(_) => return Err(UserStoreError::UserAlreadyExists),
             Err(UserStoreError::UserNotFound) => {} // do nothing
             Err(e) => return Err(UserStoreError::UnexpectedError(e.into())),
@@ -77,12 +96,19 @@ impl UserStore for PostgresUserStore {
 
         let password_hash = compute_password_hash(password.to_owned())
             .await
-            .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+            .map_err(UserStoreError::UnexpectedError)?;
         sqlx::query!(
-            "INSERT INTO users (email, password_hash, requires_2fa) VALUES ($1, $2, $3)",
+            r#"
+            INSERT INTO users
+                (email, password_hash, requires_2fa, email_verified, created_at, tenant_id)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
             email,
             password_hash,
             requires_2fa,
+            email_verified,
+            created_at,
+            tenant_id,
         )
         .execute(&self.pool)
         .await
@@ -92,28 +118,373 @@ impl UserStore for PostgresUserStore {
     }
 
     #[tracing::instrument(name = "Retrieving user from PostgreSQL", skip_all)]
-    async fn get_user(&self, email: &str) -> Result<User, UserStoreError> {
-        let email = Email::parse(email).map_err(|_| UserStoreError::InvalidCredentials)?;
-        sqlx::query!(
-            "SELECT email, password_hash, requires_2fa FROM users WHERE email = $1",
+    async fn get_user(&self, user: &UserKey) -> Result<User, UserStoreError> {
+        let email = Email::parse(&user.email).map_err(|_| UserStoreError::InvalidCredentials)?;
+        let row = sqlx::query!(
+            r#"
+            SELECT email, password_hash, requires_2fa, email_verified, created_at, display_name, locale, status,
+                tenant_id
+            FROM users WHERE tenant_key = $1 AND email = $2
+            "#,
+            user.tenant_key(),
             email.as_ref(),
         )
         .fetch_optional(&self.pool)
         .await
         .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
         .map(|row| {
-            Ok(User::parse(row.email, row.password_hash, row.requires_2fa)
-                .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?)
+            let profile = UserProfile {
+                display_name: row.display_name.map(DisplayName::new_no_validation),
+                locale: row.locale.map(Locale::new_no_validation),
+            };
+            let status = parse_status(&row.status)?;
+            // the hash isn't a password the policy applies to
+            let user = User::new_no_validation(row.email, row.password_hash, row.requires_2fa);
+            Ok(user
+                .with_details(row.email_verified, row.created_at, profile)
+                .with_status(status)
+                .with_tenant(row.tenant_id))
         })
-        .ok_or(UserStoreError::UserNotFound)?
+        .ok_or(UserStoreError::UserNotFound)??;
+
+        let roles = sqlx::query_scalar!(
+            "SELECT role FROM user_roles WHERE user_tenant = $1 AND user_email = $2",
+            user.tenant_key(),
+            email.as_ref(),
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+        Ok(row.with_roles(roles.into_iter().map(Role::new_no_validation).collect()))
     }
 
     #[tracing::instrument(name = "Validating user credentials in PostgreSQL", skip_all)]
-    async fn validate_user(&self, email: &str, password: &str) -> Result<(), UserStoreError> {
-        let user = self.get_user(email).await?;
+    async fn validate_user(&self, user: &UserKey, password: &str) -> Result<(), UserStoreError> {
+        let user = self.get_user(user).await?;
         match verify_password_hash(user.password_str().to_owned(), password.to_owned()).await {
             Ok(_) => Ok(()),
             Err(_) => Err(UserStoreError::InvalidCredentials),
         }
     }
+
+    #[tracing::instrument(name = "Updating user profile in PostgreSQL", skip_all)]
+    async fn update_profile(
+        &mut self,
+        user: &UserKey,
+        profile: UserProfile,
+    ) -> Result<User, UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users SET display_name = $3, locale = $4
+            WHERE tenant_key = $1 AND email = $2
+            "#,
+            user.tenant_key(),
+            user.email,
+            profile.display_name.as_ref().map(AsRef::as_ref),
+            profile.locale.as_ref().map(AsRef::as_ref),
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+        self.get_user(user).await
+    }
+
+    #[tracing::instrument(name = "Setting user roles in PostgreSQL", skip_all)]
+    async fn set_roles(
+        &mut self,
+        user: &UserKey,
+        roles: Vec<Role>,
+    ) -> Result<User, UserStoreError> {
+        let roles: Vec<String> = roles.into_iter().map(|role| role.role).collect();
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        let row = sqlx::query!(
+            "SELECT email FROM users WHERE tenant_key = $1 AND email = $2 FOR UPDATE",
+            user.tenant_key(),
+            user.email,
+        )
+        .fetch_optional(&mut *transaction)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+        if row.is_none() {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        // roles are added to the catalog the first time they are assigned
+        sqlx::query!(
+            "INSERT INTO roles (name) SELECT * FROM UNNEST($1::text[]) ON CONFLICT (name) DO NOTHING",
+            &roles,
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+        sqlx::query!(
+            "DELETE FROM user_roles WHERE user_tenant = $1 AND user_email = $2",
+            user.tenant_key(),
+            user.email,
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+        sqlx::query!(
+            r#"
+            INSERT INTO user_roles (user_tenant, user_email, role)
+            SELECT $1, $2, role FROM UNNEST($3::text[]) AS role
+            ON CONFLICT DO NOTHING
+            "#,
+            user.tenant_key(),
+            user.email,
+            &roles,
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        transaction
+            .commit()
+            .await
+            .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+        self.get_user(user).await
+    }
+
+    #[tracing::instrument(name = "Listing users from PostgreSQL", skip_all)]
+    async fn list_users(&self, query: &UserQuery) -> Result<UserPage, UserStoreError> {
+        let pattern = query.search.as_deref().map(like_pattern);
+        let offset =
+            i64::try_from(query.offset).map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+        let limit =
+            i64::try_from(query.limit).map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!" FROM users
+            WHERE $1::text IS NULL OR email ILIKE $1 OR display_name ILIKE $1
+            "#,
+            pattern,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT u.email, u.password_hash, u.requires_2fa, u.email_verified, u.created_at,
+                u.display_name, u.locale, u.status, u.tenant_id,
+                COALESCE(ARRAY_AGG(r.role) FILTER (WHERE r.role IS NOT NULL), '{}') AS "roles!"
+            FROM users u
+            LEFT JOIN user_roles r ON r.user_tenant = u.tenant_key AND r.user_email = u.email
+            WHERE $1::text IS NULL OR u.email ILIKE $1 OR u.display_name ILIKE $1
+            GROUP BY u.tenant_key, u.email
+            ORDER BY u.email, u.tenant_id NULLS FIRST
+            OFFSET $2 LIMIT $3
+            "#,
+            pattern,
+            offset,
+            limit,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        let users = rows
+            .into_iter()
+            .map(|row| {
+                let profile = UserProfile {
+                    display_name: row.display_name.map(DisplayName::new_no_validation),
+                    locale: row.locale.map(Locale::new_no_validation),
+                };
+                let status = parse_status(&row.status)?;
+                let roles = row.roles.into_iter().map(Role::new_no_validation).collect();
+                let user = User::new_no_validation(row.email, row.password_hash, row.requires_2fa);
+                Ok(user
+                    .with_details(row.email_verified, row.created_at, profile)
+                    .with_status(status)
+                    .with_tenant(row.tenant_id)
+                    .with_roles(roles))
+            })
+            .collect::<Result<Vec<User>, UserStoreError>>()?;
+        Ok(UserPage {
+            users,
+            total: total as usize,
+        })
+    }
+
+    #[tracing::instrument(name = "Setting user password in PostgreSQL", skip_all)]
+    async fn set_password(
+        &mut self,
+        user: &UserKey,
+        password: Password,
+    ) -> Result<(), UserStoreError> {
+        let password_hash = compute_password_hash(password.as_ref().to_owned())
+            .await
+            .map_err(UserStoreError::UnexpectedError)?;
+        let result = sqlx::query!(
+            "UPDATE users SET password_hash = $3 WHERE tenant_key = $1 AND email = $2",
+            user.tenant_key(),
+            user.email,
+            password_hash,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Setting user status in PostgreSQL", skip_all)]
+    async fn set_status(
+        &mut self,
+        user: &UserKey,
+        status: AccountStatus,
+    ) -> Result<User, UserStoreError> {
+        let result = sqlx::query!(
+            "UPDATE users SET status = $3 WHERE tenant_key = $1 AND email = $2",
+            user.tenant_key(),
+            user.email,
+            status.as_str(),
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+        self.get_user(user).await
+    }
+
+    #[tracing::instrument(name = "Setting user 2FA in PostgreSQL", skip_all)]
+    async fn set_requires_2fa(
+        &mut self,
+        user: &UserKey,
+        requires_2fa: bool,
+    ) -> Result<User, UserStoreError> {
+        let result = sqlx::query!(
+            "UPDATE users SET requires_2fa = $3 WHERE tenant_key = $1 AND email = $2",
+            user.tenant_key(),
+            user.email,
+            requires_2fa,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+        self.get_user(user).await
+    }
+
+    #[tracing::instrument(name = "Retrieving known devices from PostgreSQL", skip_all)]
+    async fn known_devices(&self, user: &UserKey) -> Result<Vec<KnownDevice>, UserStoreError> {
+        let devices = sqlx::query_as!(
+            KnownDevice,
+            r#"
+            SELECT device_id, user_agent, ip, last_seen FROM known_devices
+            WHERE user_tenant = $1 AND user_email = $2
+            "#,
+            user.tenant_key(),
+            user.email,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+        Ok(devices)
+    }
+
+    #[tracing::instrument(name = "Remembering device in PostgreSQL", skip_all)]
+    async fn remember_device(
+        &mut self,
+        user: &UserKey,
+        device: KnownDevice,
+    ) -> Result<(), UserStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO known_devices (user_tenant, user_email, device_id, user_agent, ip, last_seen)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (user_tenant, user_email, device_id) DO UPDATE
+            SET user_agent = EXCLUDED.user_agent, ip = EXCLUDED.ip, last_seen = EXCLUDED.last_seen
+            "#,
+            user.tenant_key(),
+            user.email,
+            device.device_id,
+            device.user_agent,
+            device.ip,
+            device.last_seen,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e.as_database_error() {
+            Some(db_error) if db_error.is_foreign_key_violation() => UserStoreError::UserNotFound,
+            _ => UserStoreError::UnexpectedError(e.into()),
+        })?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Forgetting devices in PostgreSQL", skip_all)]
+    async fn forget_devices(&mut self, user: &UserKey) -> Result<(), UserStoreError> {
+        sqlx::query!(
+            "DELETE FROM known_devices WHERE user_tenant = $1 AND user_email = $2",
+            user.tenant_key(),
+            user.email,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving linked user from PostgreSQL", skip_all)]
+    async fn linked_user(&self, provider: &str, subject: &str) -> Result<User, UserStoreError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT NULLIF(user_tenant, '') AS tenant_id, user_email FROM linked_identities
+            WHERE provider = $1 AND subject = $2
+            "#,
+            provider,
+            subject,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .ok_or(UserStoreError::UserNotFound)?;
+        self.get_user(&UserKey::new(row.tenant_id.as_deref(), &row.user_email))
+            .await
+    }
+
+    #[tracing::instrument(name = "Linking identity in PostgreSQL", skip_all)]
+    async fn link_identity(
+        &mut self,
+        user: &UserKey,
+        provider: &str,
+        subject: &str,
+    ) -> Result<(), UserStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO linked_identities (provider, subject, user_tenant, user_email)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (provider, subject) DO UPDATE
+            SET user_tenant = EXCLUDED.user_tenant, user_email = EXCLUDED.user_email,
+                linked_at = NOW()
+            "#,
+            provider,
+            subject,
+            user.tenant_key(),
+            user.email,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e.as_database_error() {
+            Some(db_error) if db_error.is_foreign_key_violation() => UserStoreError::UserNotFound,
+            _ => UserStoreError::UnexpectedError(e.into()),
+        })?;
+        Ok(())
+    }
 }
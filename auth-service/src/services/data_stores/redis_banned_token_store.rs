@@ -3,25 +3,35 @@ use std::sync::Arc;
 use redis::{Commands, Connection};
 use tokio::sync::RwLock;
 
-use crate::{
-    domain::data_stores::{BannedTokenStore, BannedTokenStoreError},
-    utils::auth::TOKEN_TTL_SECONDS,
+use crate::domain::{
+    UserKey,
+    data_stores::{BannedTokenStore, BannedTokenStoreError},
 };
 
 const BANNED_TOKEN_KEY_PREFIX: &str = "banned_token:";
+const BANNED_USER_TOKENS_KEY_PREFIX: &str = "banned_user_tokens:";
 
 fn get_key(token: &str) -> String {
     format!("{BANNED_TOKEN_KEY_PREFIX}{token}")
 }
 
+fn get_user_key(user: &UserKey) -> String {
+    match &user.tenant_id {
+        Some(tenant_id) => format!("{BANNED_USER_TOKENS_KEY_PREFIX}{tenant_id}:{}", user.email),
+        None => format!("{BANNED_USER_TOKENS_KEY_PREFIX}{}", user.email),
+    }
+}
+
 #[derive(Clone)]
 pub struct RedisBannedTokenStore {
     conn: Arc<RwLock<Connection>>,
+    // banned tokens only need to outlive the tokens themselves
+    ttl_seconds: u64,
 }
 
 impl RedisBannedTokenStore {
-    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
-        Self { conn }
+    pub fn new(conn: Arc<RwLock<Connection>>, ttl_seconds: u64) -> Self {
+        Self { conn, ttl_seconds }
     }
 }
 
@@ -31,7 +41,7 @@ impl BannedTokenStore for RedisBannedTokenStore {
         let key = get_key(&token);
         let mut banned_token_store = self.conn.write().await;
         let setting_result: Result<(), redis::RedisError> =
-            banned_token_store.set_ex(key, true, TOKEN_TTL_SECONDS as u64);
+            banned_token_store.set_ex(key, true, self.ttl_seconds);
         match setting_result {
             Ok(_) => Ok(()),
             Err(e) => Err(BannedTokenStoreError::UnexpectedError(e.into())),
@@ -47,4 +57,34 @@ impl BannedTokenStore for RedisBannedTokenStore {
             Err(e) => Err(BannedTokenStoreError::UnexpectedError(e.into())),
         }
     }
+
+    async fn ban_user_tokens(
+        &mut self,
+        user: &UserKey,
+        issued_before: i64,
+    ) -> Result<(), BannedTokenStoreError> {
+        let key = get_user_key(user);
+        let mut banned_token_store = self.conn.write().await;
+        // kept, unlike banned tokens: personal access tokens outlive sessions
+        // by up to a year and are checked against it too
+        let setting_result: Result<(), redis::RedisError> =
+            banned_token_store.set(key, issued_before);
+        match setting_result {
+            Ok(_) => Ok(()),
+            Err(e) => Err(BannedTokenStoreError::UnexpectedError(e.into())),
+        }
+    }
+
+    async fn user_tokens_banned_before(
+        &self,
+        user: &UserKey,
+    ) -> Result<Option<i64>, BannedTokenStoreError> {
+        let mut banned_token_store = self.conn.write().await;
+        let key = get_user_key(user);
+        let get_result: Result<Option<i64>, redis::RedisError> = banned_token_store.get(key);
+        match get_result {
+            Ok(result) => Ok(result),
+            Err(e) => Err(BannedTokenStoreError::UnexpectedError(e.into())),
+        }
+    }
 }
@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use redis::{Commands, Connection};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::domain::{
+    UserKey,
+    data_stores::{LinkPurpose, MagicLinkStore, MagicLinkStoreError},
+};
+
+#[derive(Serialize, Deserialize)]
+struct LinkUser(pub Option<String>, pub String);
+
+fn get_key(purpose: LinkPurpose, token_hash: &str) -> String {
+    format!("{}:{}", purpose.as_str(), token_hash)
+}
+
+#[derive(Clone)]
+pub struct RedisMagicLinkStore {
+    conn: Arc<RwLock<Connection>>,
+    ttl_seconds: u64,
+}
+
+impl RedisMagicLinkStore {
+    pub fn new(conn: Arc<RwLock<Connection>>, ttl_seconds: u64) -> Self {
+        Self { conn, ttl_seconds }
+    }
+}
+
+#[async_trait::async_trait]
+impl MagicLinkStore for RedisMagicLinkStore {
+    async fn add_token(
+        &mut self,
+        purpose: LinkPurpose,
+        token_hash: &str,
+        user: UserKey,
+    ) -> Result<(), MagicLinkStoreError> {
+        let key = get_key(purpose, token_hash);
+        let user = serde_json::to_string(&LinkUser(user.tenant_id, user.email))
+            .map_err(|e| MagicLinkStoreError::UnexpectedError(e.into()))?;
+        let mut conn = self.conn.write().await;
+        let setting_result: Result<(), redis::RedisError> =
+            conn.set_ex(key, user, self.ttl_seconds);
+        setting_result.map_err(|e| MagicLinkStoreError::UnexpectedError(e.into()))
+    }
+
+    async fn take_token(
+        &mut self,
+        purpose: LinkPurpose,
+        token_hash: &str,
+    ) -> Result<UserKey, MagicLinkStoreError> {
+        let key = get_key(purpose, token_hash);
+        let mut conn = self.conn.write().await;
+        // GETDEL, so two requests racing with the same link can't both use it
+        let user: Option<String> = conn
+            .get_del(key)
+            .map_err(|e| MagicLinkStoreError::UnexpectedError(e.into()))?;
+        let user = user.ok_or(MagicLinkStoreError::TokenNotFound)?;
+        let LinkUser(tenant_id, email) = serde_json::from_str(&user)
+            .map_err(|e| MagicLinkStoreError::UnexpectedError(e.into()))?;
+        Ok(UserKey { tenant_id, email })
+    }
+}
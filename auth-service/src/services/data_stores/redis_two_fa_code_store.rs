@@ -5,28 +5,31 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 
 use crate::domain::{
-    Email,
+    UserKey,
     data_stores::{LoginAttemptId, TwoFACode, TwoFACodeStore, TwoFACodeStoreError},
 };
 
 #[derive(Serialize, Deserialize)]
 struct TwoFATuple(pub String, pub String);
 
-const TEN_MINUTES_IN_SECONDS: u64 = 600;
 const TWO_FA_CODE_PREFIX: &str = "two_fa_code:";
 
-fn get_key(email: &Email) -> String {
-    format!("{}{}", TWO_FA_CODE_PREFIX, email.as_ref())
+fn get_key(user: &UserKey) -> String {
+    match &user.tenant_id {
+        Some(tenant_id) => format!("{}{}:{}", TWO_FA_CODE_PREFIX, tenant_id, user.email),
+        None => format!("{}{}", TWO_FA_CODE_PREFIX, user.email),
+    }
 }
 
 #[derive(Clone)]
 pub struct RedisTwoFACodeStore {
     conn: Arc<RwLock<Connection>>,
+    ttl_seconds: u64,
 }
 
 impl RedisTwoFACodeStore {
-    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
-        Self { conn }
+    pub fn new(conn: Arc<RwLock<Connection>>, ttl_seconds: u64) -> Self {
+        Self { conn, ttl_seconds }
     }
 }
 
@@ -34,11 +37,11 @@ impl RedisTwoFACodeStore {
 impl TwoFACodeStore for RedisTwoFACodeStore {
     async fn add_code(
         &mut self,
-        email: Email,
+        user: UserKey,
         login_attempt_id: LoginAttemptId,
         code: TwoFACode,
     ) -> Result<(), TwoFACodeStoreError> {
-        let key = get_key(&email);
+        let key = get_key(&user);
         let mut two_fa_store = self.conn.write().await;
         let two_fa_tuple = TwoFATuple(
             login_attempt_id.as_ref().to_string(),
@@ -49,15 +52,15 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
             Err(e) => return Err(TwoFACodeStoreError::UnexpectedError(e.into())),
         };
         let setting_result: Result<(), redis::RedisError> =
-            two_fa_store.set_ex(key, two_fa_tuple, TEN_MINUTES_IN_SECONDS);
+            two_fa_store.set_ex(key, two_fa_tuple, self.ttl_seconds);
         match setting_result {
             Ok(_) => Ok(()),
             Err(e) => Err(TwoFACodeStoreError::UnexpectedError(e.into())),
         }
     }
 
-    async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError> {
-        let key = get_key(email);
+    async fn remove_code(&mut self, user: &UserKey) -> Result<(), TwoFACodeStoreError> {
+        let key = get_key(user);
         let mut two_fa_store = self.conn.write().await;
         let del_result: Result<(), redis::RedisError> = two_fa_store.del(key);
         match del_result {
@@ -68,9 +71,9 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
 
     async fn get_code(
         &self,
-        email: &Email,
+        user: &UserKey,
     ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
-        let key = get_key(&email);
+        let key = get_key(user);
         let mut two_fa_store = self.conn.write().await;
         let get_result: Result<String, redis::RedisError> = two_fa_store.get(key);
         match get_result {
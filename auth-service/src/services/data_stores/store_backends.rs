@@ -0,0 +1,662 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+
+use crate::domain::{
+    AccountStatus, ApiKey, AuditEvent, AuthorizationCode, Invitation, KnownDevice, OAuthClient,
+    Organization, Password, PersonalAccessToken, Role, TenantSettings, TrustedDevice, User,
+    UserKey, UserProfile,
+    data_stores::{
+        AccessTokenStore, AccessTokenStoreError, AuditLog, AuditLogError, BannedTokenStore,
+        BannedTokenStoreError, LinkPurpose, LoginAttemptId, MagicLinkStore, MagicLinkStoreError,
+        OAuthStore, OAuthStoreError, OrganizationStore, OrganizationStoreError, TrustedDeviceStore,
+        TrustedDeviceStoreError, TwoFACode, TwoFACodeStore, TwoFACodeStoreError, UserPage,
+        UserQuery, UserStore, UserStoreError,
+    },
+};
+
+use super::{
+    hashmap_access_token_store::HashMapAccessTokenStore,
+    hashmap_magic_link_store::HashMapMagicLinkStore, hashmap_oauth_store::HashMapOAuthStore,
+    hashmap_organization_store::HashMapOrganizationStore,
+    hashmap_trusted_device_store::HashMapTrustedDeviceStore,
+    hashmap_two_fa_code_store::HashMapTwoFACodeStore, hashmap_user_store::HashmapUserStore,
+    hashset_banned_token_store::HashSetBannedTokenStore,
+    postgres_access_token_store::PostgresAccessTokenStore, postgres_audit_log::PostgresAuditLog,
+    postgres_oauth_store::PostgresOAuthStore,
+    postgres_organization_store::PostgresOrganizationStore,
+    postgres_trusted_device_store::PostgresTrustedDeviceStore,
+    postgres_user_store::PostgresUserStore, redis_banned_token_store::RedisBannedTokenStore,
+    redis_magic_link_store::RedisMagicLinkStore, redis_two_fa_code_store::RedisTwoFACodeStore,
+    vec_audit_log::VecAuditLog,
+};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Error, PartialEq)]
+#[error("Unknown store backend `{value}`, expected one of: {expected}")]
+pub struct ParseStoreBackendError {
+    value: String,
+    expected: &'static str,
+}
+
+/// Where users are persisted, selected with `USER_STORE=memory|postgres`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserStoreBackend {
+    Memory,
+    Postgres,
+}
+
+impl FromStr for UserStoreBackend {
+    type Err = ParseStoreBackendError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "memory" => Ok(Self::Memory),
+            "postgres" => Ok(Self::Postgres),
+            _ => Err(ParseStoreBackendError {
+                value: s.to_owned(),
+                expected: "memory, postgres",
+            }),
+        }
+    }
+}
+
+/// Where banned tokens and 2FA codes are kept, selected with `TOKEN_STORE=memory|redis`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenStoreBackend {
+    Memory,
+    Redis,
+}
+
+impl FromStr for TokenStoreBackend {
+    type Err = ParseStoreBackendError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "memory" => Ok(Self::Memory),
+            "redis" => Ok(Self::Redis),
+            _ => Err(ParseStoreBackendError {
+                value: s.to_owned(),
+                expected: "memory, redis",
+            }),
+        }
+    }
+}
+
+// built once and kept behind a lock, boxing the in-memory store gains nothing
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone)]
+pub enum AnyUserStore {
+    Memory(HashmapUserStore),
+    Postgres(PostgresUserStore),
+}
+
+#[async_trait::async_trait]
+impl UserStore for AnyUserStore {
+    async fn add_user(&mut self, user: User) -> Result<(), UserStoreError> {
+        match self {
+            Self::Memory(store) => store.add_user(user).await,
+            Self::Postgres(store) => store.add_user(user).await,
+        }
+    }
+
+    async fn get_user(&self, user: &UserKey) -> Result<User, UserStoreError> {
+        match self {
+            Self::Memory(store) => store.get_user(user).await,
+            Self::Postgres(store) => store.get_user(user).await,
+        }
+    }
+
+    async fn validate_user(&self, user: &UserKey, password: &str) -> Result<(), UserStoreError> {
+        match self {
+            Self::Memory(store) => store.validate_user(user, password).await,
+            Self::Postgres(store) => store.validate_user(user, password).await,
+        }
+    }
+
+    async fn update_profile(
+        &mut self,
+        user: &UserKey,
+        profile: UserProfile,
+    ) -> Result<User, UserStoreError> {
+        match self {
+            Self::Memory(store) => store.update_profile(user, profile).await,
+            Self::Postgres(store) => store.update_profile(user, profile).await,
+        }
+    }
+
+    async fn set_roles(
+        &mut self,
+        user: &UserKey,
+        roles: Vec<Role>,
+    ) -> Result<User, UserStoreError> {
+        match self {
+            Self::Memory(store) => store.set_roles(user, roles).await,
+            Self::Postgres(store) => store.set_roles(user, roles).await,
+        }
+    }
+
+    async fn list_users(&self, query: &UserQuery) -> Result<UserPage, UserStoreError> {
+        match self {
+            Self::Memory(store) => store.list_users(query).await,
+            Self::Postgres(store) => store.list_users(query).await,
+        }
+    }
+
+    async fn set_password(
+        &mut self,
+        user: &UserKey,
+        password: Password,
+    ) -> Result<(), UserStoreError> {
+        match self {
+            Self::Memory(store) => store.set_password(user, password).await,
+            Self::Postgres(store) => store.set_password(user, password).await,
+        }
+    }
+
+    async fn set_status(
+        &mut self,
+        user: &UserKey,
+        status: AccountStatus,
+    ) -> Result<User, UserStoreError> {
+        match self {
+            Self::Memory(store) => store.set_status(user, status).await,
+            Self::Postgres(store) => store.set_status(user, status).await,
+        }
+    }
+
+    async fn set_requires_2fa(
+        &mut self,
+        user: &UserKey,
+        requires_2fa: bool,
+    ) -> Result<User, UserStoreError> {
+        match self {
+            Self::Memory(store) => store.set_requires_2fa(user, requires_2fa).await,
+            Self::Postgres(store) => store.set_requires_2fa(user, requires_2fa).await,
+        }
+    }
+
+    async fn known_devices(&self, user: &UserKey) -> Result<Vec<KnownDevice>, UserStoreError> {
+        match self {
+            Self::Memory(store) => store.known_devices(user).await,
+            Self::Postgres(store) => store.known_devices(user).await,
+        }
+    }
+
+    async fn remember_device(
+        &mut self,
+        user: &UserKey,
+        device: KnownDevice,
+    ) -> Result<(), UserStoreError> {
+        match self {
+            Self::Memory(store) => store.remember_device(user, device).await,
+            Self::Postgres(store) => store.remember_device(user, device).await,
+        }
+    }
+
+    async fn forget_devices(&mut self, user: &UserKey) -> Result<(), UserStoreError> {
+        match self {
+            Self::Memory(store) => store.forget_devices(user).await,
+            Self::Postgres(store) => store.forget_devices(user).await,
+        }
+    }
+
+    async fn linked_user(&self, provider: &str, subject: &str) -> Result<User, UserStoreError> {
+        match self {
+            Self::Memory(store) => store.linked_user(provider, subject).await,
+            Self::Postgres(store) => store.linked_user(provider, subject).await,
+        }
+    }
+
+    async fn link_identity(
+        &mut self,
+        user: &UserKey,
+        provider: &str,
+        subject: &str,
+    ) -> Result<(), UserStoreError> {
+        match self {
+            Self::Memory(store) => store.link_identity(user, provider, subject).await,
+            Self::Postgres(store) => store.link_identity(user, provider, subject).await,
+        }
+    }
+}
+
+/// Kept alongside the users, so it follows [`UserStoreBackend`].
+#[derive(Clone)]
+pub enum AnyOrganizationStore {
+    Memory(HashMapOrganizationStore),
+    Postgres(PostgresOrganizationStore),
+}
+
+#[async_trait::async_trait]
+impl OrganizationStore for AnyOrganizationStore {
+    async fn add_organization(
+        &mut self,
+        organization: Organization,
+    ) -> Result<(), OrganizationStoreError> {
+        match self {
+            Self::Memory(store) => store.add_organization(organization).await,
+            Self::Postgres(store) => store.add_organization(organization).await,
+        }
+    }
+
+    async fn get_organization(&self, id: &str) -> Result<Organization, OrganizationStoreError> {
+        match self {
+            Self::Memory(store) => store.get_organization(id).await,
+            Self::Postgres(store) => store.get_organization(id).await,
+        }
+    }
+
+    async fn remove_organization(&mut self, id: &str) -> Result<(), OrganizationStoreError> {
+        match self {
+            Self::Memory(store) => store.remove_organization(id).await,
+            Self::Postgres(store) => store.remove_organization(id).await,
+        }
+    }
+
+    async fn update_organization_settings(
+        &mut self,
+        id: &str,
+        settings: TenantSettings,
+    ) -> Result<Organization, OrganizationStoreError> {
+        match self {
+            Self::Memory(store) => store.update_organization_settings(id, settings).await,
+            Self::Postgres(store) => store.update_organization_settings(id, settings).await,
+        }
+    }
+    async fn organization_members(&self, id: &str) -> Result<Vec<String>, OrganizationStoreError> {
+        match self {
+            Self::Memory(store) => store.organization_members(id).await,
+            Self::Postgres(store) => store.organization_members(id).await,
+        }
+    }
+
+    async fn add_invitation(
+        &mut self,
+        invitation: Invitation,
+    ) -> Result<(), OrganizationStoreError> {
+        match self {
+            Self::Memory(store) => store.add_invitation(invitation).await,
+            Self::Postgres(store) => store.add_invitation(invitation).await,
+        }
+    }
+
+    async fn get_invitation(&self, token_hash: &str) -> Result<Invitation, OrganizationStoreError> {
+        match self {
+            Self::Memory(store) => store.get_invitation(token_hash).await,
+            Self::Postgres(store) => store.get_invitation(token_hash).await,
+        }
+    }
+
+    async fn take_invitation(
+        &mut self,
+        token_hash: &str,
+    ) -> Result<Invitation, OrganizationStoreError> {
+        match self {
+            Self::Memory(store) => store.take_invitation(token_hash).await,
+            Self::Postgres(store) => store.take_invitation(token_hash).await,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum AnyBannedTokenStore {
+    Memory(HashSetBannedTokenStore),
+    Redis(RedisBannedTokenStore),
+}
+
+#[async_trait::async_trait]
+impl BannedTokenStore for AnyBannedTokenStore {
+    async fn add_token(&mut self, token: String) -> Result<(), BannedTokenStoreError> {
+        match self {
+            Self::Memory(store) => store.add_token(token).await,
+            Self::Redis(store) => store.add_token(token).await,
+        }
+    }
+
+    async fn contains_token(&self, token: &str) -> Result<bool, BannedTokenStoreError> {
+        match self {
+            Self::Memory(store) => store.contains_token(token).await,
+            Self::Redis(store) => store.contains_token(token).await,
+        }
+    }
+
+    async fn ban_user_tokens(
+        &mut self,
+        user: &UserKey,
+        issued_before: i64,
+    ) -> Result<(), BannedTokenStoreError> {
+        match self {
+            Self::Memory(store) => store.ban_user_tokens(user, issued_before).await,
+            Self::Redis(store) => store.ban_user_tokens(user, issued_before).await,
+        }
+    }
+
+    async fn user_tokens_banned_before(
+        &self,
+        user: &UserKey,
+    ) -> Result<Option<i64>, BannedTokenStoreError> {
+        match self {
+            Self::Memory(store) => store.user_tokens_banned_before(user).await,
+            Self::Redis(store) => store.user_tokens_banned_before(user).await,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum AnyTwoFACodeStore {
+    Memory(HashMapTwoFACodeStore),
+    Redis(RedisTwoFACodeStore),
+}
+
+#[async_trait::async_trait]
+impl TwoFACodeStore for AnyTwoFACodeStore {
+    async fn add_code(
+        &mut self,
+        user: UserKey,
+        login_attempt_id: LoginAttemptId,
+        code: TwoFACode,
+    ) -> Result<(), TwoFACodeStoreError> {
+        match self {
+            Self::Memory(store) => store.add_code(user, login_attempt_id, code).await,
+            Self::Redis(store) => store.add_code(user, login_attempt_id, code).await,
+        }
+    }
+
+    async fn remove_code(&mut self, user: &UserKey) -> Result<(), TwoFACodeStoreError> {
+        match self {
+            Self::Memory(store) => store.remove_code(user).await,
+            Self::Redis(store) => store.remove_code(user).await,
+        }
+    }
+
+    async fn get_code(
+        &self,
+        user: &UserKey,
+    ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
+        match self {
+            Self::Memory(store) => store.get_code(user).await,
+            Self::Redis(store) => store.get_code(user).await,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum AnyMagicLinkStore {
+    Memory(HashMapMagicLinkStore),
+    Redis(RedisMagicLinkStore),
+}
+
+#[async_trait::async_trait]
+impl MagicLinkStore for AnyMagicLinkStore {
+    async fn add_token(
+        &mut self,
+        purpose: LinkPurpose,
+        token_hash: &str,
+        user: UserKey,
+    ) -> Result<(), MagicLinkStoreError> {
+        match self {
+            Self::Memory(store) => store.add_token(purpose, token_hash, user).await,
+            Self::Redis(store) => store.add_token(purpose, token_hash, user).await,
+        }
+    }
+
+    async fn take_token(
+        &mut self,
+        purpose: LinkPurpose,
+        token_hash: &str,
+    ) -> Result<UserKey, MagicLinkStoreError> {
+        match self {
+            Self::Memory(store) => store.take_token(purpose, token_hash).await,
+            Self::Redis(store) => store.take_token(purpose, token_hash).await,
+        }
+    }
+}
+
+/// Kept alongside the users, so it follows [`UserStoreBackend`].
+#[derive(Clone)]
+pub enum AnyAuditLog {
+    Memory(VecAuditLog),
+    Postgres(PostgresAuditLog),
+}
+
+#[async_trait::async_trait]
+impl AuditLog for AnyAuditLog {
+    async fn record(&mut self, event: AuditEvent) -> Result<(), AuditLogError> {
+        match self {
+            Self::Memory(log) => log.record(event).await,
+            Self::Postgres(log) => log.record(event).await,
+        }
+    }
+
+    async fn user_events(
+        &self,
+        email: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<AuditEvent>, AuditLogError> {
+        match self {
+            Self::Memory(log) => log.user_events(email, offset, limit).await,
+            Self::Postgres(log) => log.user_events(email, offset, limit).await,
+        }
+    }
+}
+
+/// Kept alongside the users, so it follows [`UserStoreBackend`].
+#[derive(Clone)]
+pub enum AnyTrustedDeviceStore {
+    Memory(HashMapTrustedDeviceStore),
+    Postgres(PostgresTrustedDeviceStore),
+}
+
+#[async_trait::async_trait]
+impl TrustedDeviceStore for AnyTrustedDeviceStore {
+    async fn add_device(
+        &mut self,
+        user: &UserKey,
+        device: TrustedDevice,
+    ) -> Result<(), TrustedDeviceStoreError> {
+        match self {
+            Self::Memory(store) => store.add_device(user, device).await,
+            Self::Postgres(store) => store.add_device(user, device).await,
+        }
+    }
+
+    async fn get_device(
+        &self,
+        user: &UserKey,
+        id: &str,
+    ) -> Result<TrustedDevice, TrustedDeviceStoreError> {
+        match self {
+            Self::Memory(store) => store.get_device(user, id).await,
+            Self::Postgres(store) => store.get_device(user, id).await,
+        }
+    }
+
+    async fn user_devices(
+        &self,
+        user: &UserKey,
+    ) -> Result<Vec<TrustedDevice>, TrustedDeviceStoreError> {
+        match self {
+            Self::Memory(store) => store.user_devices(user).await,
+            Self::Postgres(store) => store.user_devices(user).await,
+        }
+    }
+
+    async fn remove_device(
+        &mut self,
+        user: &UserKey,
+        id: &str,
+    ) -> Result<(), TrustedDeviceStoreError> {
+        match self {
+            Self::Memory(store) => store.remove_device(user, id).await,
+            Self::Postgres(store) => store.remove_device(user, id).await,
+        }
+    }
+
+    async fn remove_user_devices(&mut self, user: &UserKey) -> Result<(), TrustedDeviceStoreError> {
+        match self {
+            Self::Memory(store) => store.remove_user_devices(user).await,
+            Self::Postgres(store) => store.remove_user_devices(user).await,
+        }
+    }
+}
+
+/// Kept alongside the users, so it follows [`UserStoreBackend`].
+#[derive(Clone)]
+pub enum AnyAccessTokenStore {
+    Memory(HashMapAccessTokenStore),
+    Postgres(PostgresAccessTokenStore),
+}
+
+#[async_trait::async_trait]
+impl AccessTokenStore for AnyAccessTokenStore {
+    async fn add_token(&mut self, token: PersonalAccessToken) -> Result<(), AccessTokenStoreError> {
+        match self {
+            Self::Memory(store) => store.add_token(token).await,
+            Self::Postgres(store) => store.add_token(token).await,
+        }
+    }
+
+    async fn get_token(&self, id: &str) -> Result<PersonalAccessToken, AccessTokenStoreError> {
+        match self {
+            Self::Memory(store) => store.get_token(id).await,
+            Self::Postgres(store) => store.get_token(id).await,
+        }
+    }
+
+    async fn user_tokens(
+        &self,
+        user: &UserKey,
+    ) -> Result<Vec<PersonalAccessToken>, AccessTokenStoreError> {
+        match self {
+            Self::Memory(store) => store.user_tokens(user).await,
+            Self::Postgres(store) => store.user_tokens(user).await,
+        }
+    }
+
+    async fn remove_token(
+        &mut self,
+        user: &UserKey,
+        id: &str,
+    ) -> Result<(), AccessTokenStoreError> {
+        match self {
+            Self::Memory(store) => store.remove_token(user, id).await,
+            Self::Postgres(store) => store.remove_token(user, id).await,
+        }
+    }
+
+    async fn record_use(
+        &mut self,
+        id: &str,
+        used_at: DateTime<Utc>,
+    ) -> Result<(), AccessTokenStoreError> {
+        match self {
+            Self::Memory(store) => store.record_use(id, used_at).await,
+            Self::Postgres(store) => store.record_use(id, used_at).await,
+        }
+    }
+}
+
+/// Kept alongside the users, so it follows [`UserStoreBackend`].
+#[derive(Clone)]
+pub enum AnyOAuthStore {
+    Memory(HashMapOAuthStore),
+    Postgres(PostgresOAuthStore),
+}
+
+#[async_trait::async_trait]
+impl OAuthStore for AnyOAuthStore {
+    async fn add_client(&mut self, client: OAuthClient) -> Result<(), OAuthStoreError> {
+        match self {
+            Self::Memory(store) => store.add_client(client).await,
+            Self::Postgres(store) => store.add_client(client).await,
+        }
+    }
+
+    async fn get_client(&self, client_id: &str) -> Result<OAuthClient, OAuthStoreError> {
+        match self {
+            Self::Memory(store) => store.get_client(client_id).await,
+            Self::Postgres(store) => store.get_client(client_id).await,
+        }
+    }
+
+    async fn add_code(&mut self, code: AuthorizationCode) -> Result<(), OAuthStoreError> {
+        match self {
+            Self::Memory(store) => store.add_code(code).await,
+            Self::Postgres(store) => store.add_code(code).await,
+        }
+    }
+
+    async fn take_code(&mut self, code: &str) -> Result<AuthorizationCode, OAuthStoreError> {
+        match self {
+            Self::Memory(store) => store.take_code(code).await,
+            Self::Postgres(store) => store.take_code(code).await,
+        }
+    }
+
+    async fn granted_scopes(
+        &self,
+        user: &UserKey,
+        client_id: &str,
+    ) -> Result<Vec<String>, OAuthStoreError> {
+        match self {
+            Self::Memory(store) => store.granted_scopes(user, client_id).await,
+            Self::Postgres(store) => store.granted_scopes(user, client_id).await,
+        }
+    }
+
+    async fn grant_consent(
+        &mut self,
+        user: &UserKey,
+        client_id: &str,
+        scopes: &[String],
+    ) -> Result<(), OAuthStoreError> {
+        match self {
+            Self::Memory(store) => store.grant_consent(user, client_id, scopes).await,
+            Self::Postgres(store) => store.grant_consent(user, client_id, scopes).await,
+        }
+    }
+
+    async fn add_api_key(&mut self, api_key: ApiKey) -> Result<(), OAuthStoreError> {
+        match self {
+            Self::Memory(store) => store.add_api_key(api_key).await,
+            Self::Postgres(store) => store.add_api_key(api_key).await,
+        }
+    }
+
+    async fn get_api_key(&self, id: &str) -> Result<ApiKey, OAuthStoreError> {
+        match self {
+            Self::Memory(store) => store.get_api_key(id).await,
+            Self::Postgres(store) => store.get_api_key(id).await,
+        }
+    }
+
+    async fn api_keys(&self) -> Result<Vec<ApiKey>, OAuthStoreError> {
+        match self {
+            Self::Memory(store) => store.api_keys().await,
+            Self::Postgres(store) => store.api_keys().await,
+        }
+    }
+
+    async fn remove_api_key(&mut self, id: &str) -> Result<(), OAuthStoreError> {
+        match self {
+            Self::Memory(store) => store.remove_api_key(id).await,
+            Self::Postgres(store) => store.remove_api_key(id).await,
+        }
+    }
+
+    async fn record_api_key_use(
+        &mut self,
+        id: &str,
+        used_at: DateTime<Utc>,
+    ) -> Result<(), OAuthStoreError> {
+        match self {
+            Self::Memory(store) => store.record_api_key_use(id, used_at).await,
+            Self::Postgres(store) => store.record_api_key_use(id, used_at).await,
+        }
+    }
+}
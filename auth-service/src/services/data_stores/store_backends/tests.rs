@@ -0,0 +1,77 @@
+use super::*;
+use crate::domain::PasswordPolicy;
+
+#[test]
+fn parse_user_store_backend() {
+    assert_eq!(
+        "memory".parse::<UserStoreBackend>(),
+        Ok(UserStoreBackend::Memory)
+    );
+    assert_eq!(
+        "Postgres".parse::<UserStoreBackend>(),
+        Ok(UserStoreBackend::Postgres)
+    );
+    assert!("redis".parse::<UserStoreBackend>().is_err());
+}
+
+#[test]
+fn parse_token_store_backend() {
+    assert_eq!(
+        "memory".parse::<TokenStoreBackend>(),
+        Ok(TokenStoreBackend::Memory)
+    );
+    assert_eq!(
+        " REDIS ".parse::<TokenStoreBackend>(),
+        Ok(TokenStoreBackend::Redis)
+    );
+    assert!("postgres".parse::<TokenStoreBackend>().is_err());
+}
+
+#[tokio::test]
+async fn memory_user_store_dispatches() {
+    let mut store = AnyUserStore::Memory(HashmapUserStore::default());
+    let user = User::parse(
+        "email@email.com".to_owned(),
+        "Kx7!vTq2Lm".to_owned(),
+        false,
+        &PasswordPolicy::default(),
+    )
+    .unwrap();
+    store.add_user(user).await.unwrap();
+    assert!(
+        store
+            .validate_user(&UserKey::new(None, "email@email.com"), "Kx7!vTq2Lm")
+            .await
+            .is_ok()
+    );
+    assert_eq!(
+        store
+            .get_user(&UserKey::new(None, "wrong@email.com"))
+            .await
+            .unwrap_err(),
+        UserStoreError::UserNotFound
+    );
+}
+
+#[tokio::test]
+async fn memory_token_stores_dispatch() {
+    let mut banned_token_store = AnyBannedTokenStore::Memory(HashSetBannedTokenStore::default());
+    banned_token_store
+        .add_token("token".to_owned())
+        .await
+        .unwrap();
+    assert!(banned_token_store.contains_token("token").await.unwrap());
+
+    let mut two_fa_code_store = AnyTwoFACodeStore::Memory(HashMapTwoFACodeStore::default());
+    let user = UserKey::new(None, "email@email.com");
+    let login_attempt_id = LoginAttemptId::default();
+    let code = TwoFACode::default();
+    two_fa_code_store
+        .add_code(user.clone(), login_attempt_id.clone(), code.clone())
+        .await
+        .unwrap();
+    assert_eq!(
+        two_fa_code_store.get_code(&user).await.unwrap(),
+        (login_attempt_id, code)
+    );
+}
@@ -0,0 +1,35 @@
+use crate::domain::AuditEvent;
+use crate::domain::data_stores::{AuditLog, AuditLogError};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Debug, Default)]
+pub struct VecAuditLog {
+    pub events: Vec<AuditEvent>,
+}
+
+#[async_trait::async_trait]
+impl AuditLog for VecAuditLog {
+    async fn record(&mut self, event: AuditEvent) -> Result<(), AuditLogError> {
+        self.events.push(event);
+        Ok(())
+    }
+
+    async fn user_events(
+        &self,
+        email: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<AuditEvent>, AuditLogError> {
+        Ok(self
+            .events
+            .iter()
+            .rev()
+            .filter(|event| event.concerns(email))
+            .skip(offset)
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+}
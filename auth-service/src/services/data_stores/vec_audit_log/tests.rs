@@ -0,0 +1,40 @@
+use super::*;
+use crate::domain::{AuditEventType, AuditOutcome, ClientInfo};
+
+fn event(event_type: AuditEventType, actor: &str) -> AuditEvent {
+    AuditEvent::new(event_type, &ClientInfo::default(), AuditOutcome::Success).with_actor(actor)
+}
+
+#[tokio::test]
+async fn user_events_are_most_recent_first() {
+    let mut log = VecAuditLog::default();
+    log.record(event(AuditEventType::Signup, "test@example.com"))
+        .await
+        .unwrap();
+    log.record(event(AuditEventType::Login, "other@example.com"))
+        .await
+        .unwrap();
+    log.record(event(AuditEventType::Login, "test@example.com"))
+        .await
+        .unwrap();
+    log.record(
+        event(AuditEventType::StatusChanged, "admin@example.com").with_target("test@example.com"),
+    )
+    .await
+    .unwrap();
+
+    let events = log.user_events("test@example.com", 0, 10).await.unwrap();
+    let event_types: Vec<_> = events.iter().map(|event| event.event_type).collect();
+    assert_eq!(
+        event_types,
+        [
+            AuditEventType::StatusChanged,
+            AuditEventType::Login,
+            AuditEventType::Signup
+        ]
+    );
+
+    let events = log.user_events("test@example.com", 1, 1).await.unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].event_type, AuditEventType::Login);
+}
@@ -1,7 +1,34 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
 use crate::domain::{Email, EmailClient, EmailClientError};
 
+const MAX_SENT_EMAILS: usize = 100;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SentEmail {
+    pub recipient: String,
+    pub subject: String,
+    pub content: String,
+}
+
+/// Prints emails instead of sending them, keeping the last ones so tests can
+/// read them.
 #[derive(Clone, Debug, Default)]
-pub struct MockEmailClient;
+pub struct MockEmailClient {
+    sent: Arc<Mutex<VecDeque<SentEmail>>>,
+}
+
+impl MockEmailClient {
+    pub fn sent_emails(&self) -> Vec<SentEmail> {
+        self.sent
+            .lock()
+            .expect("Sent emails lock poisoned")
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
 
 #[async_trait::async_trait]
 impl EmailClient for MockEmailClient {
@@ -18,6 +45,15 @@ impl EmailClient for MockEmailClient {
             content
         );
 
+        let mut sent = self.sent.lock().expect("Sent emails lock poisoned");
+        if sent.len() == MAX_SENT_EMAILS {
+            sent.pop_front();
+        }
+        sent.push_back(SentEmail {
+            recipient: recipient.as_ref().to_owned(),
+            subject: subject.to_owned(),
+            content: content.to_owned(),
+        });
         Ok(())
     }
 }
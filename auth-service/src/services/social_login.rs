@@ -0,0 +1,324 @@
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header};
+use reqwest::header::ACCEPT;
+use serde::Deserialize;
+use thiserror::Error;
+use url::Url;
+
+use crate::domain::{SocialIdentity, SocialProviderKind};
+use crate::settings::SocialProviderSettings;
+
+#[cfg(test)]
+mod tests;
+
+const GITHUB_AUTHORIZATION_ENDPOINT: &str = "https://github.com/login/oauth/authorize";
+const GITHUB_TOKEN_ENDPOINT: &str = "https://github.com/login/oauth/access_token";
+const GITHUB_USER_ENDPOINT: &str = "https://api.github.com/user";
+const GITHUB_EMAILS_ENDPOINT: &str = "https://api.github.com/user/emails";
+// Google documents both forms of its issuer in ID tokens
+const GOOGLE_LEGACY_ISSUER: &str = "accounts.google.com";
+const ID_TOKEN_ALGORITHMS: [Algorithm; 6] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::ES256,
+    Algorithm::ES384,
+];
+
+// shared so connections to the providers are reused
+static HTTP_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        // GitHub's API rejects requests without one
+        .user_agent(concat!("auth-service/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .expect("Failed to build the HTTP client")
+});
+
+#[derive(Debug, Error)]
+pub enum SocialLoginError {
+    #[error("The provider rejected the login: {0}")]
+    Rejected(String),
+    #[error("Invalid ID token: {0}")]
+    InvalidIdToken(String),
+    #[error("Failed to reach the provider")]
+    Unreachable(#[from] reqwest::Error),
+}
+
+#[derive(Debug, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    #[serde(default)]
+    userinfo_endpoint: Option<String>,
+    #[serde(default)]
+    jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProviderTokenResponse {
+    access_token: Option<String>,
+    id_token: Option<String>,
+    // GitHub reports errors with a 200
+    error: Option<String>,
+}
+
+/// The claims of an ID token, or of the userinfo endpoint, the service uses.
+#[derive(Debug, Deserialize)]
+struct ProviderClaims {
+    sub: String,
+    email: Option<String>,
+    email_verified: Option<bool>,
+    nonce: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubUser {
+    id: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GithubEmail {
+    email: String,
+    primary: bool,
+    verified: bool,
+}
+
+/// A provider of `social.providers`, as seen by the service when logging
+/// users in with it.
+pub struct SocialLoginProvider<'a> {
+    settings: &'a SocialProviderSettings,
+    redirect_uri: String,
+}
+
+impl<'a> SocialLoginProvider<'a> {
+    /// `redirect_uri` is the callback of the service registered at the provider.
+    pub fn new(settings: &'a SocialProviderSettings, redirect_uri: String) -> Self {
+        Self {
+            settings,
+            redirect_uri,
+        }
+    }
+
+    // GitHub's endpoints are fixed, the others are discovered from the issuer
+    // on every login, so providers can rotate them, and their keys, at will.
+    async fn metadata(&self) -> Result<ProviderMetadata, SocialLoginError> {
+        if self.settings.kind == SocialProviderKind::Github {
+            return Ok(ProviderMetadata {
+                issuer: String::new(),
+                authorization_endpoint: GITHUB_AUTHORIZATION_ENDPOINT.to_owned(),
+                token_endpoint: GITHUB_TOKEN_ENDPOINT.to_owned(),
+                userinfo_endpoint: Some(GITHUB_USER_ENDPOINT.to_owned()),
+                jwks_uri: String::new(),
+            });
+        }
+        let issuer = self
+            .settings
+            .issuer()
+            .unwrap_or_default()
+            .trim_end_matches('/');
+        let metadata: ProviderMetadata = HTTP_CLIENT
+            .get(format!("{issuer}/.well-known/openid-configuration"))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if metadata.issuer.trim_end_matches('/') != issuer {
+            return Err(SocialLoginError::Rejected(format!(
+                "the discovery document is for another issuer, {}",
+                metadata.issuer
+            )));
+        }
+        Ok(metadata)
+    }
+
+    /// Where to send the user to log in, with the PKCE challenge of the
+    /// verifier to send to [`Self::exchange_code`].
+    pub async fn authorization_url(
+        &self,
+        state: &str,
+        nonce: &str,
+        code_challenge: &str,
+    ) -> Result<String, SocialLoginError> {
+        let metadata = self.metadata().await?;
+        let scope = self.settings.scopes().join(" ");
+        let mut params = vec![
+            ("response_type", "code"),
+            ("client_id", self.settings.client_id.as_str()),
+            ("redirect_uri", self.redirect_uri.as_str()),
+            ("scope", scope.as_str()),
+            ("state", state),
+            ("code_challenge", code_challenge),
+            ("code_challenge_method", "S256"),
+        ];
+        if self.settings.kind != SocialProviderKind::Github {
+            params.push(("nonce", nonce));
+        }
+        let url =
+            Url::parse_with_params(&metadata.authorization_endpoint, params).map_err(|e| {
+                SocialLoginError::Rejected(format!("invalid authorization endpoint: {e}"))
+            })?;
+        Ok(url.into())
+    }
+
+    /// Exchanges the code the provider sent back for the identity of the
+    /// user, checking the ID token of OpenID Connect providers.
+    pub async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        nonce: &str,
+    ) -> Result<SocialIdentity, SocialLoginError> {
+        let metadata = self.metadata().await?;
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.redirect_uri.as_str()),
+            ("code_verifier", code_verifier),
+            ("client_id", self.settings.client_id.as_str()),
+        ];
+        if !self.settings.client_secret.is_empty() {
+            form.push(("client_secret", self.settings.client_secret.as_str()));
+        }
+        let response = HTTP_CLIENT
+            .post(&metadata.token_endpoint)
+            .header(ACCEPT, "application/json")
+            .form(&form)
+            .send()
+            .await?;
+        let status = response.status();
+        let tokens: ProviderTokenResponse = response.json().await?;
+        if let Some(error) = tokens.error {
+            return Err(SocialLoginError::Rejected(error));
+        }
+        if !status.is_success() {
+            return Err(SocialLoginError::Rejected(status.to_string()));
+        }
+        let access_token = tokens
+            .access_token
+            .ok_or_else(|| SocialLoginError::Rejected("no access token".to_owned()))?;
+
+        if self.settings.kind == SocialProviderKind::Github {
+            return github_identity(&access_token).await;
+        }
+        let id_token = tokens
+            .id_token
+            .ok_or_else(|| SocialLoginError::Rejected("no ID token".to_owned()))?;
+        let jwks: JwkSet = HTTP_CLIENT
+            .get(&metadata.jwks_uri)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let mut issuers = vec![metadata.issuer.as_str()];
+        if self.settings.kind == SocialProviderKind::Google {
+            issuers.push(GOOGLE_LEGACY_ISSUER);
+        }
+        let claims = verify_id_token(&id_token, &jwks, &issuers, &self.settings.client_id, nonce)?;
+        if claims.email.is_some() {
+            return Ok(identity(claims));
+        }
+
+        // some providers only share the email at their userinfo endpoint
+        let userinfo_endpoint = metadata
+            .userinfo_endpoint
+            .ok_or_else(|| SocialLoginError::Rejected("no email".to_owned()))?;
+        let userinfo: ProviderClaims = HTTP_CLIENT
+            .get(userinfo_endpoint)
+            .bearer_auth(&access_token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if userinfo.sub != claims.sub || userinfo.email.is_none() {
+            return Err(SocialLoginError::Rejected(
+                "no email for the user of the ID token".to_owned(),
+            ));
+        }
+        Ok(identity(userinfo))
+    }
+}
+
+fn identity(claims: ProviderClaims) -> SocialIdentity {
+    SocialIdentity {
+        subject: claims.sub,
+        email: claims.email.unwrap_or_default(),
+        email_verified: claims.email_verified.unwrap_or(false),
+    }
+}
+
+fn verify_id_token(
+    id_token: &str,
+    jwks: &JwkSet,
+    issuers: &[&str],
+    client_id: &str,
+    nonce: &str,
+) -> Result<ProviderClaims, SocialLoginError> {
+    let invalid = |e: jsonwebtoken::errors::Error| SocialLoginError::InvalidIdToken(e.to_string());
+    let header = decode_header(id_token).map_err(invalid)?;
+    if !ID_TOKEN_ALGORITHMS.contains(&header.alg) {
+        return Err(SocialLoginError::InvalidIdToken(format!(
+            "unsupported algorithm {:?}",
+            header.alg
+        )));
+    }
+    let jwk = match &header.kid {
+        Some(kid) => jwks.find(kid),
+        None => jwks.keys.first(),
+    }
+    .ok_or_else(|| SocialLoginError::InvalidIdToken("unknown signing key".to_owned()))?;
+    let key = DecodingKey::from_jwk(jwk).map_err(invalid)?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_issuer(issuers);
+    validation.set_audience(&[client_id]);
+    let claims = decode::<ProviderClaims>(id_token, &key, &validation)
+        .map_err(invalid)?
+        .claims;
+    // ties the token to the login started by this browser
+    if claims.nonce.as_deref() != Some(nonce) {
+        return Err(SocialLoginError::InvalidIdToken(
+            "the nonce doesn't match".to_owned(),
+        ));
+    }
+    Ok(claims)
+}
+
+async fn github_identity(access_token: &str) -> Result<SocialIdentity, SocialLoginError> {
+    let user: GithubUser = HTTP_CLIENT
+        .get(GITHUB_USER_ENDPOINT)
+        .bearer_auth(access_token)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let emails: Vec<GithubEmail> = HTTP_CLIENT
+        .get(GITHUB_EMAILS_ENDPOINT)
+        .bearer_auth(access_token)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let email = github_primary_email(&emails)
+        .ok_or_else(|| SocialLoginError::Rejected("no primary email".to_owned()))?;
+    Ok(SocialIdentity {
+        subject: user.id.to_string(),
+        email: email.email.clone(),
+        email_verified: email.verified,
+    })
+}
+
+// the one GitHub users chose for notifications, verified or not
+fn github_primary_email(emails: &[GithubEmail]) -> Option<&GithubEmail> {
+    emails.iter().find(|email| email.primary)
+}
@@ -0,0 +1,81 @@
+use super::*;
+use crate::utils::oidc::{SigningKey, generate_signing_key};
+use chrono::Utc;
+use serde_json::json;
+
+const ISSUER: &str = "https://issuer.example.com";
+
+fn sign(key: &SigningKey, aud: &str, nonce: &str) -> String {
+    let exp = Utc::now().timestamp() + 60;
+    key.sign(&json!({
+        "iss": ISSUER,
+        "aud": aud,
+        "exp": exp,
+        "sub": "user-1",
+        "email": "user@example.com",
+        "email_verified": true,
+        "nonce": nonce,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_verify_id_token() {
+    let key = SigningKey::from_base64_pkcs8(&generate_signing_key()).unwrap();
+    let token = sign(&key, "client", "nonce");
+
+    let claims = verify_id_token(&token, &key.jwks(), &[ISSUER], "client", "nonce").unwrap();
+    assert_eq!(claims.sub, "user-1");
+    let identity = identity(claims);
+    assert_eq!(identity.email, "user@example.com");
+    assert!(identity.email_verified);
+}
+
+#[test]
+fn test_verify_id_token_rejects_mismatches() {
+    let key = SigningKey::from_base64_pkcs8(&generate_signing_key()).unwrap();
+    let other_key = SigningKey::from_base64_pkcs8(&generate_signing_key()).unwrap();
+    let token = sign(&key, "client", "nonce");
+
+    for (jwks, issuer, client_id, nonce) in [
+        (other_key.jwks(), ISSUER, "client", "nonce"),
+        (key.jwks(), "https://other.example.com", "client", "nonce"),
+        (key.jwks(), ISSUER, "other-client", "nonce"),
+        (key.jwks(), ISSUER, "client", "other-nonce"),
+    ] {
+        assert!(matches!(
+            verify_id_token(&token, &jwks, &[issuer], client_id, nonce),
+            Err(SocialLoginError::InvalidIdToken(_))
+        ));
+    }
+}
+
+#[test]
+fn test_verify_id_token_rejects_symmetric_algorithms() {
+    let key = SigningKey::from_base64_pkcs8(&generate_signing_key()).unwrap();
+    let token = jsonwebtoken::encode(
+        &jsonwebtoken::Header::default(),
+        &json!({ "iss": ISSUER, "aud": "client", "sub": "user-1", "nonce": "nonce" }),
+        &jsonwebtoken::EncodingKey::from_secret(b"secret"),
+    )
+    .unwrap();
+
+    assert!(verify_id_token(&token, &key.jwks(), &[ISSUER], "client", "nonce").is_err());
+}
+
+#[test]
+fn test_github_primary_email() {
+    let email = |email: &str, primary, verified| GithubEmail {
+        email: email.to_owned(),
+        primary,
+        verified,
+    };
+    let emails = [
+        email("old@example.com", false, true),
+        email("main@example.com", true, false),
+    ];
+    let primary = github_primary_email(&emails).unwrap();
+    assert_eq!(primary.email, "main@example.com");
+    assert!(!primary.verified);
+    assert!(github_primary_email(&emails[..1]).is_none());
+}
@@ -0,0 +1,657 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::str::FromStr;
+
+use config::{Config, ConfigError, Environment, File};
+use dotenvy::dotenv;
+use serde::{Deserialize, Deserializer};
+use thiserror::Error;
+use url::Url;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::domain::SocialProviderKind;
+use crate::services::data_stores::store_backends::{TokenStoreBackend, UserStoreBackend};
+use crate::utils::constants::{
+    DEVICE_COOKIE_NAME, JWT_COOKIE_NAME, SOCIAL_LOGIN_COOKIE_NAME, TRUSTED_DEVICE_COOKIE_NAME,
+};
+use crate::utils::cors::AllowedOrigin;
+use crate::utils::oidc::SigningKey;
+
+#[cfg(test)]
+mod tests;
+
+pub const SETTINGS_FILE_ENV_VAR: &str = "SETTINGS_FILE";
+pub const DEFAULT_SETTINGS_FILE: &str = "settings.toml";
+/// Prefix for overriding any key, e.g. `AUTH__DATABASE__MAX_CONNECTIONS=10`.
+pub const ENV_PREFIX: &str = "AUTH";
+
+const MIN_CLIENT_SECRET_LENGTH: usize = 16;
+const MIN_ADMIN_API_KEY_LENGTH: usize = 32;
+
+/// Environment variables that predate the settings file, mapped to the key they override.
+const LEGACY_ENV_OVERRIDES: [(&str, &str); 7] = [
+    ("ENV_NAME", "env_name"),
+    ("DROPLET_IP", "application.droplet_ip"),
+    ("JWT_SECRET", "auth.jwt_secret"),
+    ("DATABASE_URL", "database.url"),
+    ("REDIS_HOST_NAME", "redis.host_name"),
+    ("USER_STORE", "stores.user_store"),
+    ("TOKEN_STORE", "stores.token_store"),
+];
+
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("Failed to load settings: {0}")]
+    Load(#[from] ConfigError),
+    #[error("Invalid settings:\n{0}")]
+    Invalid(#[from] ValidationErrors),
+}
+
+fn validate_socket_address(address: &str) -> Result<(), ValidationError> {
+    match address.parse::<SocketAddr>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(ValidationError::new("invalid_address")
+            .with_message("must be a socket address such as 0.0.0.0:3000".into())),
+    }
+}
+
+fn validate_origins(origins: &[String]) -> Result<(), ValidationError> {
+    let errors: Vec<String> = origins
+        .iter()
+        .filter_map(|origin| AllowedOrigin::parse(origin).err())
+        .collect();
+    if !errors.is_empty() {
+        return Err(ValidationError::new("invalid_origin").with_message(errors.join(", ").into()));
+    }
+    Ok(())
+}
+
+fn validate_methods(methods: &[String]) -> Result<(), ValidationError> {
+    let invalid: Vec<&str> = methods
+        .iter()
+        .filter(|method| method.parse::<axum::http::Method>().is_err())
+        .map(String::as_str)
+        .collect();
+    if !invalid.is_empty() {
+        return Err(ValidationError::new("invalid_method")
+            .with_message(format!("invalid HTTP methods: {}", invalid.join(", ")).into()));
+    }
+    Ok(())
+}
+
+fn validate_headers(headers: &[String]) -> Result<(), ValidationError> {
+    let invalid: Vec<&str> = headers
+        .iter()
+        .filter(|header| header.parse::<axum::http::HeaderName>().is_err())
+        .map(String::as_str)
+        .collect();
+    if !invalid.is_empty() {
+        return Err(ValidationError::new("invalid_header")
+            .with_message(format!("invalid header names: {}", invalid.join(", ")).into()));
+    }
+    Ok(())
+}
+
+fn validate_cookie_policy(cookie: &CookieSettings) -> Result<(), ValidationError> {
+    let secure_required = match cookie.name_prefix.as_str() {
+        "" => cookie.same_site == SameSitePolicy::None,
+        "__Secure-" => true,
+        "__Host-" => {
+            if !cookie.domain.is_empty() {
+                return Err(ValidationError::new("host_prefix_with_domain")
+                    .with_message("the __Host- prefix does not allow a domain".into()));
+            }
+            true
+        }
+        _ => {
+            return Err(ValidationError::new("invalid_cookie_prefix")
+                .with_message("name_prefix must be empty, __Secure- or __Host-".into()));
+        }
+    };
+    if secure_required && !cookie.secure {
+        return Err(ValidationError::new("insecure_cookie").with_message(
+            "secure must be true for SameSite=None and the __Secure-/__Host- prefixes".into(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_password_lengths(password: &PasswordSettings) -> Result<(), ValidationError> {
+    if password.max_length < password.min_length {
+        return Err(ValidationError::new("invalid_password_lengths")
+            .with_message("max_length must be at least min_length".into()));
+    }
+    Ok(())
+}
+
+fn validate_client_secrets(clients: &HashMap<String, String>) -> Result<(), ValidationError> {
+    let mut weak: Vec<&str> = clients
+        .iter()
+        .filter(|(_, secret)| !secret.is_empty() && secret.len() < MIN_CLIENT_SECRET_LENGTH)
+        .map(|(client_id, _)| client_id.as_str())
+        .collect();
+    if !weak.is_empty() {
+        weak.sort();
+        return Err(ValidationError::new("weak_client_secret").with_message(
+            format!(
+                "secrets must be at least {MIN_CLIENT_SECRET_LENGTH} characters long: {}",
+                weak.join(", ")
+            )
+            .into(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_admin_api_key(api_key: &str) -> Result<(), ValidationError> {
+    if !api_key.is_empty() && api_key.len() < MIN_ADMIN_API_KEY_LENGTH {
+        return Err(ValidationError::new("weak_admin_api_key").with_message(
+            format!("must be empty or at least {MIN_ADMIN_API_KEY_LENGTH} characters long").into(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_signing_key(signing_key: &str) -> Result<(), ValidationError> {
+    if !signing_key.is_empty() && SigningKey::from_base64_pkcs8(signing_key).is_err() {
+        return Err(ValidationError::new("invalid_signing_key")
+            .with_message("must be empty or a base64 PKCS#8 P-256 private key".into()));
+    }
+    Ok(())
+}
+
+fn validate_social_providers(social: &SocialSettings) -> Result<(), ValidationError> {
+    let mut errors: Vec<String> = social
+        .providers
+        .iter()
+        .filter_map(|(name, provider)| {
+            let valid_name = name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_'));
+            if name.is_empty() || !valid_name {
+                Some(format!("`{name}` must only use a-z, 0-9, - and _"))
+            } else if provider.client_id.is_empty() {
+                Some(format!("{name}: client_id must be set"))
+            } else if provider
+                .issuer()
+                .is_some_and(|issuer| Url::parse(issuer).is_err())
+            {
+                Some(format!("{name}: issuer must be a URL"))
+            } else if provider.kind == SocialProviderKind::Oidc && provider.issuer.is_empty() {
+                Some(format!("{name}: issuer must be set for oidc providers"))
+            } else {
+                None
+            }
+        })
+        .collect();
+    if !errors.is_empty() {
+        errors.sort();
+        return Err(
+            ValidationError::new("invalid_social_provider").with_message(errors.join(", ").into())
+        );
+    }
+    Ok(())
+}
+
+fn validate_database_url(settings: &Settings) -> Result<(), ValidationError> {
+    if settings.stores.user_store == UserStoreBackend::Postgres && settings.database.url.is_empty()
+    {
+        return Err(ValidationError::new("missing_database_url")
+            .with_message("database.url must be set to use Postgres (DATABASE_URL)".into()));
+    }
+    Ok(())
+}
+
+fn deserialize_from_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    let value = String::deserialize(deserializer)?;
+    value.parse().map_err(serde::de::Error::custom)
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Validate)]
+#[validate(schema(function = "validate_database_url", skip_on_field_errors = false))]
+pub struct Settings {
+    #[serde(default)]
+    #[validate(length(min = 1, message = "must be set (ENV_NAME)"))]
+    pub env_name: String,
+    #[serde(default)]
+    #[validate(nested)]
+    pub application: ApplicationSettings,
+    #[serde(default)]
+    #[validate(nested)]
+    pub cors: CorsSettings,
+    #[serde(default)]
+    #[validate(nested)]
+    pub auth: AuthSettings,
+    #[serde(default)]
+    #[validate(nested)]
+    pub introspection: IntrospectionSettings,
+    #[serde(default)]
+    #[validate(nested)]
+    pub admin: AdminSettings,
+    #[serde(default)]
+    #[validate(nested)]
+    pub oidc: OidcSettings,
+    #[serde(default)]
+    #[validate(nested)]
+    pub social: SocialSettings,
+    #[serde(default)]
+    #[validate(nested)]
+    pub database: DatabaseSettings,
+    #[serde(default)]
+    pub redis: RedisSettings,
+    #[serde(default)]
+    pub stores: StoreSettings,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+#[serde(default)]
+pub struct ApplicationSettings {
+    #[validate(custom(function = "validate_socket_address"))]
+    pub address: String,
+    /// Legacy: when set, `http://{droplet_ip}:8000` is added to the CORS allow-list.
+    pub droplet_ip: String,
+    /// Takes the client IP from `X-Real-IP`, only safe behind a proxy setting it.
+    pub trust_proxy_headers: bool,
+    /// Where users reach the service, for links in emails.
+    #[validate(url)]
+    pub public_url: String,
+}
+
+impl ApplicationSettings {
+    pub fn public_url(&self, path: &str) -> String {
+        format!("{}{}", self.public_url.trim_end_matches('/'), path)
+    }
+}
+
+impl Default for ApplicationSettings {
+    fn default() -> Self {
+        Self {
+            address: "0.0.0.0:3000".to_owned(),
+            droplet_ip: String::new(),
+            trust_proxy_headers: false,
+            public_url: "http://localhost:3000".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+#[serde(default)]
+pub struct CorsSettings {
+    /// Exact origins (`https://app.example.com`) or wildcard subdomains (`https://*.example.com`).
+    #[validate(custom(function = "validate_origins"))]
+    pub allowed_origins: Vec<String>,
+    #[validate(custom(function = "validate_methods"))]
+    pub allowed_methods: Vec<String>,
+    #[validate(custom(function = "validate_headers"))]
+    pub allowed_headers: Vec<String>,
+    pub allow_credentials: bool,
+}
+
+impl Default for CorsSettings {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec!["http://localhost:8000".to_owned()],
+            allowed_methods: vec![
+                "GET".to_owned(),
+                "POST".to_owned(),
+                "PUT".to_owned(),
+                "PATCH".to_owned(),
+                "DELETE".to_owned(),
+            ],
+            // bearer tokens are sent cross-origin in the authorization header
+            allowed_headers: vec![
+                "content-type".to_owned(),
+                "x-csrf-token".to_owned(),
+                "authorization".to_owned(),
+            ],
+            allow_credentials: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+#[serde(default)]
+pub struct AuthSettings {
+    #[validate(length(min = 1, message = "must be set (JWT_SECRET)"))]
+    pub jwt_secret: String,
+    #[validate(range(min = 1, message = "must be positive"))]
+    pub token_ttl_seconds: i64,
+    #[validate(range(min = 1, message = "must be positive"))]
+    pub two_fa_code_ttl_seconds: u64,
+    /// How long "remember this device" skips 2FA on a browser.
+    #[validate(range(min = 1, max = 365, message = "must be between 1 and 365"))]
+    pub trusted_device_ttl_days: i64,
+    /// How long an emailed link stays valid: sign-in, password reset and the
+    /// sign-out link of new sign-in alerts.
+    #[validate(range(min = 1, message = "must be positive"))]
+    pub magic_link_ttl_seconds: u64,
+    /// When off, only people invited by an admin or an organization can sign
+    /// up, social logins included.
+    pub open_registration: bool,
+    #[validate(nested)]
+    pub cookie: CookieSettings,
+    #[validate(nested)]
+    pub password: PasswordSettings,
+}
+
+impl AuthSettings {
+    pub fn trusted_device_ttl_seconds(&self) -> i64 {
+        self.trusted_device_ttl_days * 24 * 60 * 60
+    }
+}
+
+impl Default for AuthSettings {
+    fn default() -> Self {
+        Self {
+            jwt_secret: String::new(),
+            token_ttl_seconds: 600,
+            two_fa_code_ttl_seconds: 600,
+            trusted_device_ttl_days: 30,
+            magic_link_ttl_seconds: 900,
+            open_registration: true,
+            cookie: CookieSettings::default(),
+            password: PasswordSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSitePolicy {
+    Strict,
+    Lax,
+    None,
+}
+
+impl FromStr for SameSitePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "strict" => Ok(Self::Strict),
+            "lax" => Ok(Self::Lax),
+            "none" => Ok(Self::None),
+            _ => Err(format!(
+                "Unknown SameSite policy `{s}`, expected one of: strict, lax, none"
+            )),
+        }
+    }
+}
+
+/// Attributes of the auth cookie, its `Max-Age` always follows `token_ttl_seconds`.
+#[derive(Debug, Clone, Deserialize, Validate)]
+#[serde(default)]
+#[validate(schema(function = "validate_cookie_policy"))]
+pub struct CookieSettings {
+    pub secure: bool,
+    /// Empty for a host-only cookie.
+    pub domain: String,
+    #[serde(deserialize_with = "deserialize_from_str")]
+    pub same_site: SameSitePolicy,
+    /// `""`, `"__Secure-"` or `"__Host-"`, prepended to the names of the
+    /// cookies of the service, except `csrf_token`.
+    pub name_prefix: String,
+}
+
+impl Default for CookieSettings {
+    fn default() -> Self {
+        Self {
+            secure: false,
+            domain: String::new(),
+            same_site: SameSitePolicy::Lax,
+            name_prefix: String::new(),
+        }
+    }
+}
+
+impl CookieSettings {
+    pub fn auth_cookie_name(&self) -> String {
+        format!("{}{}", self.name_prefix, JWT_COOKIE_NAME)
+    }
+
+    pub fn device_cookie_name(&self) -> String {
+        format!("{}{}", self.name_prefix, DEVICE_COOKIE_NAME)
+    }
+
+    pub fn trusted_device_cookie_name(&self) -> String {
+        format!("{}{}", self.name_prefix, TRUSTED_DEVICE_COOKIE_NAME)
+    }
+
+    pub fn social_login_cookie_name(&self) -> String {
+        format!("{}{}", self.name_prefix, SOCIAL_LOGIN_COOKIE_NAME)
+    }
+}
+
+/// Rules for the passwords users choose, at signup or when joining an
+/// organization.
+#[derive(Debug, Clone, Deserialize, Validate)]
+#[serde(default)]
+#[validate(schema(function = "validate_password_lengths", skip_on_field_errors = false))]
+pub struct PasswordSettings {
+    /// In characters, not bytes.
+    #[validate(range(min = 1, max = 128, message = "must be between 1 and 128"))]
+    pub min_length: usize,
+    #[validate(range(min = 1, max = 1024, message = "must be between 1 and 1024"))]
+    pub max_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_special: bool,
+    /// Passwords at least this long skip the character class rules, so long
+    /// passphrases in any script are accepted. 0 turns it off.
+    pub passphrase_length: usize,
+    /// Lowest accepted strength estimate, from 0 (anything) to 4.
+    #[validate(range(max = 4, message = "must be between 0 and 4"))]
+    pub min_strength: u8,
+    /// Rejects passwords found in the breached passwords bundled with the
+    /// service and in `breached_passwords_file`.
+    pub check_breached: bool,
+    /// Extra breached passwords, one uppercase SHA-1 per line optionally
+    /// followed by `:count`, the format of the Have I Been Pwned downloads.
+    pub breached_passwords_file: String,
+}
+
+impl Default for PasswordSettings {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 128,
+            require_uppercase: true,
+            require_lowercase: true,
+            require_digit: true,
+            require_special: true,
+            passphrase_length: 20,
+            min_strength: 1,
+            check_breached: true,
+            breached_passwords_file: String::new(),
+        }
+    }
+}
+
+/// Clients allowed to call `/introspect`, authenticated with HTTP Basic.
+#[derive(Debug, Clone, Default, Deserialize, Validate)]
+#[serde(default)]
+pub struct IntrospectionSettings {
+    /// Client id to secret, e.g. `AUTH__INTROSPECTION__CLIENTS__APP_SERVICE=<secret>`
+    /// (ids from the environment are lowercased). An empty secret disables the client.
+    #[validate(custom(function = "validate_client_secrets"))]
+    pub clients: HashMap<String, String>,
+}
+
+impl IntrospectionSettings {
+    pub fn client_secret(&self, client_id: &str) -> Option<&str> {
+        self.clients
+            .get(client_id)
+            .map(String::as_str)
+            .filter(|secret| !secret.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Validate)]
+#[serde(default)]
+pub struct AdminSettings {
+    /// Static key accepted by the admin routes in `X-Admin-Api-Key`, for
+    /// operators and scripts, e.g. `AUTH__ADMIN__API_KEY=<key>`. Empty disables it.
+    #[validate(custom(function = "validate_admin_api_key"))]
+    pub api_key: String,
+}
+
+impl AdminSettings {
+    pub fn api_key(&self) -> Option<&str> {
+        Some(self.api_key.as_str()).filter(|key| !key.is_empty())
+    }
+}
+
+/// The service as an OpenID Connect provider, its issuer is
+/// `application.public_url`.
+#[derive(Debug, Clone, Deserialize, Validate)]
+#[serde(default)]
+pub struct OidcSettings {
+    /// Base64 PKCS#8 P-256 key signing the ID tokens, e.g.
+    /// `AUTH__OIDC__SIGNING_KEY=<key>`. When empty a key is generated at
+    /// startup, and ID tokens can't be verified after a restart.
+    #[validate(custom(function = "validate_signing_key"))]
+    pub signing_key: String,
+    #[validate(range(min = 1, max = 600, message = "must be between 1 and 600"))]
+    pub code_ttl_seconds: i64,
+}
+
+impl Default for OidcSettings {
+    fn default() -> Self {
+        Self {
+            signing_key: String::new(),
+            code_ttl_seconds: 60,
+        }
+    }
+}
+
+/// External providers users can sign in with, at `/login/{name}`.
+#[derive(Debug, Clone, Default, Deserialize, Validate)]
+#[serde(default)]
+#[validate(schema(function = "validate_social_providers"))]
+pub struct SocialSettings {
+    /// Name to provider, e.g. `AUTH__SOCIAL__PROVIDERS__GOOGLE__CLIENT_ID=<id>`.
+    pub providers: HashMap<String, SocialProviderSettings>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SocialProviderSettings {
+    #[serde(deserialize_with = "deserialize_from_str")]
+    pub kind: SocialProviderKind,
+    /// Required for `oidc` providers, whose endpoints are discovered from it.
+    #[serde(default)]
+    pub issuer: String,
+    pub client_id: String,
+    #[serde(default)]
+    pub client_secret: String,
+    /// Empty for the usual scopes of the kind.
+    #[serde(default)]
+    pub scopes: Vec<String>,
+}
+
+impl SocialProviderSettings {
+    pub fn issuer(&self) -> Option<&str> {
+        Some(self.issuer.as_str())
+            .filter(|issuer| !issuer.is_empty())
+            .or(self.kind.default_issuer())
+    }
+
+    pub fn scopes(&self) -> Vec<String> {
+        if self.scopes.is_empty() {
+            self.kind
+                .default_scopes()
+                .iter()
+                .map(|scope| (*scope).to_owned())
+                .collect()
+        } else {
+            self.scopes.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+#[serde(default)]
+pub struct DatabaseSettings {
+    pub url: String,
+    #[validate(range(min = 1, message = "must be at least 1"))]
+    pub max_connections: u32,
+}
+
+impl Default for DatabaseSettings {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            max_connections: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RedisSettings {
+    pub host_name: String,
+}
+
+impl Default for RedisSettings {
+    fn default() -> Self {
+        Self {
+            host_name: "127.0.0.1".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct StoreSettings {
+    #[serde(deserialize_with = "deserialize_from_str")]
+    pub user_store: UserStoreBackend,
+    #[serde(deserialize_with = "deserialize_from_str")]
+    pub token_store: TokenStoreBackend,
+}
+
+impl Default for StoreSettings {
+    fn default() -> Self {
+        Self {
+            user_store: UserStoreBackend::Postgres,
+            token_store: TokenStoreBackend::Redis,
+        }
+    }
+}
+
+impl Settings {
+    /// Loads `settings.toml` (or `SETTINGS_FILE`) if present, applies environment
+    /// overrides and validates the result, reporting every invalid field at once.
+    pub fn load() -> Result<Self, SettingsError> {
+        dotenv().ok();
+        let file = std::env::var(SETTINGS_FILE_ENV_VAR)
+            .unwrap_or_else(|_| DEFAULT_SETTINGS_FILE.to_owned());
+
+        let mut builder = Config::builder()
+            .add_source(File::with_name(&file).required(false))
+            .add_source(
+                Environment::with_prefix(ENV_PREFIX)
+                    .prefix_separator("__")
+                    .separator("__")
+                    .list_separator(",")
+                    .with_list_parse_key("cors.allowed_origins")
+                    .with_list_parse_key("cors.allowed_methods")
+                    .with_list_parse_key("cors.allowed_headers")
+                    .try_parsing(true),
+            );
+        for (env_var, key) in LEGACY_ENV_OVERRIDES {
+            builder = builder.set_override_option(key, std::env::var(env_var).ok())?;
+        }
+
+        let mut settings: Settings = builder.build()?.try_deserialize()?;
+        if !settings.application.droplet_ip.is_empty() {
+            let droplet_origin = format!("http://{}:8000", settings.application.droplet_ip);
+            settings.cors.allowed_origins.push(droplet_origin);
+        }
+        settings.validate()?;
+        Ok(settings)
+    }
+}
@@ -1,3 +1,9 @@
 pub mod auth;
 pub mod constants;
+pub mod cors;
+pub mod csrf;
+pub mod extractors;
+pub mod oidc;
+pub mod pagination;
+pub mod problem;
 pub mod tracing;
@@ -1,19 +1,71 @@
 use axum_extra::extract::cookie::{Cookie, SameSite};
 use chrono::Utc;
 use jsonwebtoken::{DecodingKey, EncodingKey, Validation, decode, encode};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use uuid::Uuid;
 
-use crate::domain::email::Email;
-
-use super::constants::{JWT_COOKIE_NAME, JWT_SECRET};
+use crate::domain::{Email, Role, UserKey};
+use crate::settings::{AuthSettings, CookieSettings, SameSitePolicy};
 
 #[cfg(test)]
 mod tests;
 
+/// Whose token it is: a user's, or a service's from the client credentials
+/// grant, whose `sub` is the id of its API key.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenKind {
+    #[default]
+    User,
+    Service,
+}
+
+impl TokenKind {
+    fn is_user(&self) -> bool {
+        *self == Self::User
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
     pub exp: usize,
+    // defaulted so tokens issued before these claims existed stay valid
+    #[serde(default)]
+    pub iat: usize,
+    /// Unique id of the token, identifies the session it belongs to.
+    #[serde(default)]
+    pub jti: String,
+    /// Space-separated scopes, user sessions have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// Roles of the user when the token was issued.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+    #[serde(default, skip_serializing_if = "TokenKind::is_user")]
+    pub kind: TokenKind,
+    /// Organization of the user when the token was issued.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+}
+
+impl Claims {
+    /// The user of a user token, whose `sub` is the email.
+    pub fn user(&self) -> UserKey {
+        UserKey::new(self.tenant.as_deref(), &self.sub)
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scope
+            .as_deref()
+            .unwrap_or_default()
+            .split_whitespace()
+            .any(|s| s == scope)
+    }
 }
 
 #[derive(Debug)]
@@ -22,50 +74,324 @@ pub enum GenerateTokenError {
     UnexpectedError,
 }
 
-fn create_token(claims: &Claims) -> Result<String, jsonwebtoken::errors::Error> {
+fn create_token<C: Serialize>(
+    claims: &C,
+    jwt_secret: &str,
+) -> Result<String, jsonwebtoken::errors::Error> {
     encode(
         &jsonwebtoken::Header::default(),
         &claims,
-        &EncodingKey::from_secret(JWT_SECRET.as_bytes()),
+        &EncodingKey::from_secret(jwt_secret.as_bytes()),
     )
 }
 
-pub async fn validate_token(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+pub async fn validate_token(
+    token: &str,
+    settings: &AuthSettings,
+) -> Result<Claims, jsonwebtoken::errors::Error> {
     decode::<Claims>(
         token,
-        &DecodingKey::from_secret(JWT_SECRET.as_bytes()),
+        &DecodingKey::from_secret(settings.jwt_secret.as_bytes()),
         &Validation::default(),
     )
     .map(|data| data.claims)
 }
-pub const TOKEN_TTL_SECONDS: i64 = 600;
 
-fn generate_auth_token(email: &Email) -> Result<String, GenerateTokenError> {
-    let delta = chrono::Duration::try_seconds(TOKEN_TTL_SECONDS)
-        .ok_or(GenerateTokenError::UnexpectedError)?;
-    let exp = Utc::now()
+/// `iat` and `exp` claims of a token issued now and valid for `ttl_seconds`.
+pub(crate) fn issued_and_expiry(ttl_seconds: i64) -> Result<(usize, usize), GenerateTokenError> {
+    let delta =
+        chrono::Duration::try_seconds(ttl_seconds).ok_or(GenerateTokenError::UnexpectedError)?;
+    let now = Utc::now();
+    let iat: usize = now
+        .timestamp()
+        .try_into()
+        .map_err(|_| GenerateTokenError::UnexpectedError)?;
+    let exp = now
         .checked_add_signed(delta)
         .ok_or(GenerateTokenError::UnexpectedError)?
         .timestamp();
     let exp: usize = exp
         .try_into()
         .map_err(|_| GenerateTokenError::UnexpectedError)?;
+    Ok((iat, exp))
+}
 
+fn generate_session_token(
+    email: &Email,
+    roles: &[Role],
+    tenant: Option<&str>,
+    scope: Option<String>,
+    settings: &AuthSettings,
+) -> Result<String, GenerateTokenError> {
+    let (iat, exp) = issued_and_expiry(settings.token_ttl_seconds)?;
     let sub = email.as_ref().to_owned();
 
-    let claims = Claims { sub, exp };
-    create_token(&claims).map_err(GenerateTokenError::TokenError)
+    let claims = Claims {
+        sub,
+        exp,
+        iat,
+        jti: Uuid::new_v4().to_string(),
+        scope,
+        roles: roles.iter().map(|role| role.as_ref().to_owned()).collect(),
+        kind: TokenKind::User,
+        tenant: tenant.map(str::to_owned),
+    };
+    create_token(&claims, &settings.jwt_secret).map_err(GenerateTokenError::TokenError)
+}
+
+pub fn generate_auth_token(
+    email: &Email,
+    roles: &[Role],
+    tenant: Option<&str>,
+    settings: &AuthSettings,
+) -> Result<String, GenerateTokenError> {
+    generate_session_token(email, roles, tenant, None, settings)
+}
+
+/// Access token handed to an OpenID Connect client, limited to `scopes` and
+/// carrying no roles.
+pub fn generate_access_token(
+    email: &Email,
+    scopes: &[String],
+    tenant: Option<&str>,
+    settings: &AuthSettings,
+) -> Result<String, GenerateTokenError> {
+    generate_session_token(email, &[], tenant, Some(scopes.join(" ")), settings)
+}
+
+/// Token of a service authenticated with the API key `api_key_id`, limited
+/// to `scopes`.
+pub fn generate_service_token(
+    api_key_id: &str,
+    scopes: &[String],
+    settings: &AuthSettings,
+) -> Result<String, GenerateTokenError> {
+    let (iat, exp) = issued_and_expiry(settings.token_ttl_seconds)?;
+    let claims = Claims {
+        sub: api_key_id.to_owned(),
+        exp,
+        iat,
+        jti: Uuid::new_v4().to_string(),
+        scope: Some(scopes.join(" ")),
+        roles: Vec::new(),
+        kind: TokenKind::Service,
+        tenant: None,
+    };
+    create_token(&claims, &settings.jwt_secret).map_err(GenerateTokenError::TokenError)
+}
+
+// Tokens with an audience are never accepted by `validate_token`, nor by the
+// validation of another audience.
+fn validate_audience_token<C: DeserializeOwned>(
+    token: &str,
+    audience: &str,
+    settings: &AuthSettings,
+) -> Result<C, jsonwebtoken::errors::Error> {
+    let mut validation = Validation::default();
+    validation.set_audience(&[audience]);
+    decode::<C>(
+        token,
+        &DecodingKey::from_secret(settings.jwt_secret.as_bytes()),
+        &validation,
+    )
+    .map(|data| data.claims)
+}
+
+const TRUSTED_DEVICE_AUDIENCE: &str = "trusted-device";
+
+/// Claims of the trusted-device cookie, `jti` is the id of the
+/// [`TrustedDevice`](crate::domain::TrustedDevice) in the store.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrustedDeviceClaims {
+    pub sub: String,
+    pub jti: String,
+    pub exp: usize,
+    pub iat: usize,
+    pub aud: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+}
+
+impl TrustedDeviceClaims {
+    pub fn user(&self) -> UserKey {
+        UserKey::new(self.tenant.as_deref(), &self.sub)
+    }
+}
+
+pub fn generate_trusted_device_token(
+    email: &Email,
+    tenant: Option<&str>,
+    device_id: &str,
+    settings: &AuthSettings,
+) -> Result<String, GenerateTokenError> {
+    let (iat, exp) = issued_and_expiry(settings.trusted_device_ttl_seconds())?;
+    let claims = TrustedDeviceClaims {
+        sub: email.as_ref().to_owned(),
+        jti: device_id.to_owned(),
+        exp,
+        iat,
+        aud: TRUSTED_DEVICE_AUDIENCE.to_owned(),
+        tenant: tenant.map(str::to_owned),
+    };
+    create_token(&claims, &settings.jwt_secret).map_err(GenerateTokenError::TokenError)
 }
 
-fn create_auth_cookie(token: String) -> Cookie<'static> {
-    Cookie::build((JWT_COOKIE_NAME, token))
+pub fn validate_trusted_device_token(
+    token: &str,
+    settings: &AuthSettings,
+) -> Result<TrustedDeviceClaims, jsonwebtoken::errors::Error> {
+    validate_audience_token(token, TRUSTED_DEVICE_AUDIENCE, settings)
+}
+
+const SOCIAL_LOGIN_AUDIENCE: &str = "social-login";
+const SOCIAL_LOGIN_TTL_SECONDS: i64 = 10 * 60;
+
+/// Claims of the cookie carrying a social login from `/login/{provider}` to
+/// the provider's redirect to `/callback/{provider}`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SocialLoginClaims {
+    pub provider: String,
+    /// Sent to the provider, which sends it back to the callback.
+    pub state: String,
+    pub nonce: String,
+    pub code_verifier: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub return_to: Option<String>,
+    pub exp: usize,
+    pub iat: usize,
+    pub aud: String,
+}
+
+pub fn generate_social_login_token(
+    provider: &str,
+    state: String,
+    nonce: String,
+    code_verifier: String,
+    return_to: Option<String>,
+    settings: &AuthSettings,
+) -> Result<String, GenerateTokenError> {
+    let (iat, exp) = issued_and_expiry(SOCIAL_LOGIN_TTL_SECONDS)?;
+    let claims = SocialLoginClaims {
+        provider: provider.to_owned(),
+        state,
+        nonce,
+        code_verifier,
+        return_to,
+        exp,
+        iat,
+        aud: SOCIAL_LOGIN_AUDIENCE.to_owned(),
+    };
+    create_token(&claims, &settings.jwt_secret).map_err(GenerateTokenError::TokenError)
+}
+
+pub fn validate_social_login_token(
+    token: &str,
+    settings: &AuthSettings,
+) -> Result<SocialLoginClaims, jsonwebtoken::errors::Error> {
+    validate_audience_token(token, SOCIAL_LOGIN_AUDIENCE, settings)
+}
+
+impl From<SameSitePolicy> for SameSite {
+    fn from(policy: SameSitePolicy) -> Self {
+        match policy {
+            SameSitePolicy::Strict => SameSite::Strict,
+            SameSitePolicy::Lax => SameSite::Lax,
+            SameSitePolicy::None => SameSite::None,
+        }
+    }
+}
+
+// Shared by the cookies and their removal, browsers only drop a cookie when
+// name, path and domain match the ones it was set with.
+fn base_cookie(name: String, value: String, settings: &CookieSettings) -> Cookie<'static> {
+    let mut cookie = Cookie::build((name, value))
         .path("/")
         .http_only(true)
-        .same_site(SameSite::Lax)
-        .build()
+        .secure(settings.secure)
+        .same_site(settings.same_site.into())
+        .build();
+    if !settings.domain.is_empty() {
+        cookie.set_domain(settings.domain.clone());
+    }
+    cookie
+}
+
+pub fn create_auth_cookie(token: String, settings: &AuthSettings) -> Cookie<'static> {
+    let mut cookie = base_cookie(settings.cookie.auth_cookie_name(), token, &settings.cookie);
+    cookie.set_max_age(time::Duration::seconds(settings.token_ttl_seconds));
+    cookie
+}
+
+pub fn generate_auth_cookie(
+    email: &Email,
+    roles: &[Role],
+    tenant: Option<&str>,
+    settings: &AuthSettings,
+) -> Result<Cookie<'static>, GenerateTokenError> {
+    let token = generate_auth_token(email, roles, tenant, settings)?;
+    Ok(create_auth_cookie(token, settings))
+}
+
+/// Cookie to pass to `CookieJar::remove` to clear the auth cookie.
+pub fn auth_removal_cookie(settings: &CookieSettings) -> Cookie<'static> {
+    base_cookie(settings.auth_cookie_name(), String::new(), settings)
+}
+
+const DEVICE_COOKIE_MAX_AGE_DAYS: i64 = 400;
+
+/// Long-lived cookie identifying the browser, to recognize known devices.
+pub fn create_device_cookie(device_id: String, settings: &CookieSettings) -> Cookie<'static> {
+    let mut cookie = base_cookie(settings.device_cookie_name(), device_id, settings);
+    cookie.set_max_age(time::Duration::days(DEVICE_COOKIE_MAX_AGE_DAYS));
+    cookie
+}
+
+pub fn create_trusted_device_cookie(token: String, settings: &AuthSettings) -> Cookie<'static> {
+    let mut cookie = base_cookie(
+        settings.cookie.trusted_device_cookie_name(),
+        token,
+        &settings.cookie,
+    );
+    cookie.set_max_age(time::Duration::seconds(
+        settings.trusted_device_ttl_seconds(),
+    ));
+    cookie
+}
+
+/// Cookie to pass to `CookieJar::remove` to clear the trusted-device cookie.
+pub fn trusted_device_removal_cookie(settings: &CookieSettings) -> Cookie<'static> {
+    base_cookie(
+        settings.trusted_device_cookie_name(),
+        String::new(),
+        settings,
+    )
+}
+
+/// The provider sends the browser back with a cross-site redirect, so the
+/// cookie is at most Lax, a Strict one wouldn't come along.
+pub fn create_social_login_cookie(token: String, settings: &AuthSettings) -> Cookie<'static> {
+    let mut cookie = base_cookie(
+        settings.cookie.social_login_cookie_name(),
+        token,
+        &settings.cookie,
+    );
+    if settings.cookie.same_site == SameSitePolicy::Strict {
+        cookie.set_same_site(SameSite::Lax);
+    }
+    cookie.set_max_age(time::Duration::seconds(SOCIAL_LOGIN_TTL_SECONDS));
+    cookie
+}
+
+/// Cookie to pass to `CookieJar::remove` to clear the social login cookie.
+pub fn social_login_removal_cookie(settings: &CookieSettings) -> Cookie<'static> {
+    base_cookie(settings.social_login_cookie_name(), String::new(), settings)
 }
 
-pub fn generate_auth_cookie(email: &Email) -> Result<Cookie<'static>, GenerateTokenError> {
-    let token = generate_auth_token(email)?;
-    Ok(create_auth_cookie(token))
+/// Compares secrets without leaking, through timing, where they differ.
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            == 0
 }
@@ -1,20 +1,31 @@
 use super::*;
+use crate::utils::constants::JWT_COOKIE_NAME;
+
+fn auth_settings() -> AuthSettings {
+    AuthSettings {
+        jwt_secret: "secret".to_owned(),
+        ..Default::default()
+    }
+}
 
 #[tokio::test]
 async fn test_generate_auth_cookie() {
     let email = Email::parse("test@example.com").unwrap();
-    let cookie = generate_auth_cookie(&email).unwrap();
+    let cookie = generate_auth_cookie(&email, &[], None, &auth_settings()).unwrap();
     assert_eq!(cookie.name(), JWT_COOKIE_NAME);
     assert_eq!(cookie.value().split('.').count(), 3);
     assert_eq!(cookie.path(), Some("/"));
     assert_eq!(cookie.http_only(), Some(true));
     assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+    assert_eq!(cookie.secure(), Some(false));
+    assert_eq!(cookie.domain(), None);
+    assert_eq!(cookie.max_age(), Some(time::Duration::seconds(600)));
 }
 
 #[tokio::test]
 async fn test_create_auth_cookie() {
     let token = "test_token".to_owned();
-    let cookie = create_auth_cookie(token.clone());
+    let cookie = create_auth_cookie(token.clone(), &auth_settings());
     assert_eq!(cookie.name(), JWT_COOKIE_NAME);
     assert_eq!(cookie.value(), token);
     assert_eq!(cookie.path(), Some("/"));
@@ -22,18 +33,52 @@ async fn test_create_auth_cookie() {
     assert_eq!(cookie.same_site(), Some(SameSite::Lax));
 }
 
+#[tokio::test]
+async fn test_create_auth_cookie_with_policy() {
+    let mut settings = auth_settings();
+    settings.token_ttl_seconds = 120;
+    settings.cookie = CookieSettings {
+        secure: true,
+        domain: "gabuzando.dev".to_owned(),
+        same_site: SameSitePolicy::Strict,
+        name_prefix: "__Secure-".to_owned(),
+    };
+    let cookie = create_auth_cookie("test_token".to_owned(), &settings);
+    assert_eq!(cookie.name(), "__Secure-jwt");
+    assert_eq!(cookie.secure(), Some(true));
+    assert_eq!(cookie.domain(), Some("gabuzando.dev"));
+    assert_eq!(cookie.same_site(), Some(SameSite::Strict));
+    assert_eq!(cookie.max_age(), Some(time::Duration::seconds(120)));
+}
+
+#[test]
+fn test_auth_removal_cookie_matches_auth_cookie() {
+    let settings = CookieSettings {
+        secure: true,
+        domain: String::new(),
+        same_site: SameSitePolicy::Lax,
+        name_prefix: "__Host-".to_owned(),
+    };
+    let cookie = auth_removal_cookie(&settings);
+    assert_eq!(cookie.name(), "__Host-jwt");
+    assert_eq!(cookie.value(), "");
+    assert_eq!(cookie.path(), Some("/"));
+    assert_eq!(cookie.secure(), Some(true));
+    assert_eq!(cookie.domain(), None);
+}
+
 #[tokio::test]
 async fn test_generate_auth_token() {
     let email = Email::parse("test@example.com").unwrap();
-    let result = generate_auth_token(&email).unwrap();
+    let result = generate_auth_token(&email, &[], None, &auth_settings()).unwrap();
     assert_eq!(result.split('.').count(), 3);
 }
 
 #[tokio::test]
 async fn test_validate_token_with_valid_token() {
     let email = Email::parse("test@example.com").unwrap();
-    let token = generate_auth_token(&email).unwrap();
-    let result = validate_token(&token).await.unwrap();
+    let token = generate_auth_token(&email, &[], None, &auth_settings()).unwrap();
+    let result = validate_token(&token, &auth_settings()).await.unwrap();
     assert_eq!(result.sub, "test@example.com");
 
     let exp = Utc::now()
@@ -42,11 +87,144 @@ async fn test_validate_token_with_valid_token() {
         .timestamp();
 
     assert!(result.exp > exp as usize);
+    assert!(result.iat <= Utc::now().timestamp() as usize);
+    assert!(!result.jti.is_empty());
+    assert_eq!(result.scope, None);
+    assert!(result.roles.is_empty());
+}
+
+#[tokio::test]
+async fn test_roles_are_embedded_in_token() {
+    let email = Email::parse("test@example.com").unwrap();
+    let roles = [Role::admin(), Role::parse("support").unwrap()];
+    let token = generate_auth_token(&email, &roles, None, &auth_settings()).unwrap();
+    let claims = validate_token(&token, &auth_settings()).await.unwrap();
+    assert_eq!(claims.roles, ["admin", "support"]);
+    assert!(claims.has_role("admin"));
+    assert!(!claims.has_role("billing"));
+}
+
+#[tokio::test]
+async fn test_tenant_is_embedded_in_token() {
+    let email = Email::parse("test@example.com").unwrap();
+    let token = generate_auth_token(&email, &[], Some("org-1"), &auth_settings()).unwrap();
+    let claims = validate_token(&token, &auth_settings()).await.unwrap();
+    assert_eq!(claims.tenant.as_deref(), Some("org-1"));
+
+    let token = generate_auth_token(&email, &[], None, &auth_settings()).unwrap();
+    let claims = validate_token(&token, &auth_settings()).await.unwrap();
+    assert_eq!(claims.tenant, None);
+}
+
+#[tokio::test]
+async fn test_access_tokens_carry_scopes_and_no_roles() {
+    let email = Email::parse("test@example.com").unwrap();
+    let scopes = ["email".to_owned(), "openid".to_owned()];
+    let token = generate_access_token(&email, &scopes, None, &auth_settings()).unwrap();
+    let claims = validate_token(&token, &auth_settings()).await.unwrap();
+    assert_eq!(claims.scope.as_deref(), Some("email openid"));
+    assert!(claims.roles.is_empty());
+}
+
+#[tokio::test]
+async fn test_service_tokens_are_told_apart_from_user_tokens() {
+    let scopes = ["reports:read".to_owned(), "reports:write".to_owned()];
+    let token = generate_service_token("ak_123", &scopes, &auth_settings()).unwrap();
+    let claims = validate_token(&token, &auth_settings()).await.unwrap();
+    assert_eq!(claims.sub, "ak_123");
+    assert_eq!(claims.kind, TokenKind::Service);
+    assert!(claims.has_scope("reports:write"));
+    assert!(!claims.has_scope("reports"));
+
+    let email = Email::parse("test@example.com").unwrap();
+    let token = generate_auth_token(&email, &[], None, &auth_settings()).unwrap();
+    let claims = validate_token(&token, &auth_settings()).await.unwrap();
+    assert_eq!(claims.kind, TokenKind::User);
+    assert!(!claims.has_scope("reports:write"));
+}
+
+#[tokio::test]
+async fn test_generated_tokens_have_unique_ids() {
+    let email = Email::parse("test@example.com").unwrap();
+    let first = generate_auth_token(&email, &[], None, &auth_settings()).unwrap();
+    let second = generate_auth_token(&email, &[], None, &auth_settings()).unwrap();
+    let first = validate_token(&first, &auth_settings()).await.unwrap();
+    let second = validate_token(&second, &auth_settings()).await.unwrap();
+    assert_ne!(first.jti, second.jti);
 }
 
 #[tokio::test]
 async fn test_validate_token_with_invalid_token() {
     let token = "invalid_token".to_owned();
-    let result = validate_token(&token).await;
+    let result = validate_token(&token, &auth_settings()).await;
     assert!(result.is_err());
 }
+
+#[test]
+fn test_create_device_cookie() {
+    let settings = CookieSettings {
+        name_prefix: "__Host-".to_owned(),
+        ..Default::default()
+    };
+    let cookie = create_device_cookie("device".to_owned(), &settings);
+    assert_eq!(cookie.name(), "__Host-device_id");
+    assert_eq!(cookie.value(), "device");
+    assert_eq!(cookie.http_only(), Some(true));
+    assert_eq!(cookie.max_age(), Some(time::Duration::days(400)));
+}
+
+#[tokio::test]
+async fn test_trusted_device_token_is_not_a_session_token() {
+    let email = Email::parse("test@example.com").unwrap();
+    let token = generate_trusted_device_token(&email, None, "device-1", &auth_settings()).unwrap();
+
+    let claims = validate_trusted_device_token(&token, &auth_settings()).unwrap();
+    assert_eq!(claims.sub, "test@example.com");
+    assert_eq!(claims.jti, "device-1");
+    assert!(validate_token(&token, &auth_settings()).await.is_err());
+}
+
+#[test]
+fn test_create_trusted_device_cookie() {
+    let cookie = create_trusted_device_cookie("token".to_owned(), &auth_settings());
+    assert_eq!(cookie.name(), "trusted_device");
+    assert_eq!(cookie.http_only(), Some(true));
+    assert_eq!(cookie.max_age(), Some(time::Duration::days(30)));
+}
+
+#[tokio::test]
+async fn test_social_login_token_round_trip() {
+    let token = generate_social_login_token(
+        "google",
+        "state".to_owned(),
+        "nonce".to_owned(),
+        "verifier".to_owned(),
+        None,
+        &auth_settings(),
+    )
+    .unwrap();
+
+    let claims = validate_social_login_token(&token, &auth_settings()).unwrap();
+    assert_eq!(claims.provider, "google");
+    assert_eq!(claims.state, "state");
+    assert!(validate_token(&token, &auth_settings()).await.is_err());
+    assert!(validate_trusted_device_token(&token, &auth_settings()).is_err());
+}
+
+#[test]
+fn test_social_login_cookie_is_never_strict() {
+    let mut settings = auth_settings();
+    settings.cookie.same_site = SameSitePolicy::Strict;
+    let cookie = create_social_login_cookie("token".to_owned(), &settings);
+    assert_eq!(cookie.name(), "social_login");
+    assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+    assert_eq!(cookie.max_age(), Some(time::Duration::minutes(10)));
+}
+
+#[test]
+fn test_constant_time_eq() {
+    assert!(constant_time_eq("abc", "abc"));
+    assert!(!constant_time_eq("abc", "abd"));
+    assert!(!constant_time_eq("abc", "abcd"));
+    assert!(!constant_time_eq("", "a"));
+}
@@ -0,0 +1,113 @@
+use axum::http::{HeaderName, HeaderValue, Method, request::Parts};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::settings::CorsSettings;
+
+#[cfg(test)]
+mod tests;
+
+/// An entry of the CORS allow-list, either an exact origin such as
+/// `https://bootcamp.gabuzando.dev` or a wildcard subdomain such as
+/// `https://*.gabuzando.dev`, which does not match the apex domain itself.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AllowedOrigin {
+    Exact(String),
+    WildcardSubdomain {
+        scheme: String,
+        // e.g. ".gabuzando.dev"
+        domain_suffix: String,
+        port: Option<u16>,
+    },
+}
+
+fn split_origin(origin: &str) -> Option<(&str, &str, Option<u16>)> {
+    let (scheme, rest) = origin.split_once("://")?;
+    if scheme.is_empty() || rest.is_empty() || rest.contains('/') {
+        return None;
+    }
+    match rest.rsplit_once(':') {
+        Some((host, port)) => Some((scheme, host, Some(port.parse().ok()?))),
+        None => Some((scheme, rest, None)),
+    }
+}
+
+impl AllowedOrigin {
+    pub fn parse(origin: &str) -> Result<Self, String> {
+        let origin = origin.trim().trim_end_matches('/').to_lowercase();
+        let (scheme, host, port) =
+            split_origin(&origin).ok_or_else(|| format!("`{origin}` is not a valid origin"))?;
+        if scheme != "http" && scheme != "https" {
+            return Err(format!("`{origin}` must use http or https"));
+        }
+        match host.strip_prefix('*') {
+            Some(domain_suffix) => {
+                if !domain_suffix.starts_with('.') || domain_suffix[1..].is_empty() {
+                    return Err(format!(
+                        "`{origin}` may only use a wildcard as the first label, e.g. https://*.example.com"
+                    ));
+                }
+                Ok(Self::WildcardSubdomain {
+                    scheme: scheme.to_owned(),
+                    domain_suffix: domain_suffix.to_owned(),
+                    port,
+                })
+            }
+            None if host.is_empty() || host.contains('*') => {
+                Err(format!("`{origin}` is not a valid origin"))
+            }
+            None => Ok(Self::Exact(origin.clone())),
+        }
+    }
+
+    pub fn matches(&self, origin: &str) -> bool {
+        let origin = origin.to_lowercase();
+        match self {
+            Self::Exact(allowed) => *allowed == origin,
+            Self::WildcardSubdomain {
+                scheme,
+                domain_suffix,
+                port,
+            } => match split_origin(&origin) {
+                Some((origin_scheme, host, origin_port)) => {
+                    origin_scheme == scheme
+                        && origin_port == *port
+                        && host.len() > domain_suffix.len()
+                        && host.ends_with(domain_suffix.as_str())
+                }
+                None => false,
+            },
+        }
+    }
+}
+
+/// Builds the CORS layer from validated settings.
+pub fn cors_layer(settings: &CorsSettings) -> CorsLayer {
+    let allowed_origins: Vec<AllowedOrigin> = settings
+        .allowed_origins
+        .iter()
+        .filter_map(|origin| AllowedOrigin::parse(origin).ok())
+        .collect();
+    let methods: Vec<Method> = settings
+        .allowed_methods
+        .iter()
+        .filter_map(|method| method.parse().ok())
+        .collect();
+    let headers: Vec<HeaderName> = settings
+        .allowed_headers
+        .iter()
+        .filter_map(|header| header.parse().ok())
+        .collect();
+
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(
+            move |origin: &HeaderValue, _: &Parts| match origin.to_str() {
+                Ok(origin) => allowed_origins
+                    .iter()
+                    .any(|allowed| allowed.matches(origin)),
+                Err(_) => false,
+            },
+        ))
+        .allow_methods(methods)
+        .allow_headers(headers)
+        .allow_credentials(settings.allow_credentials)
+}
@@ -0,0 +1,54 @@
+use super::*;
+
+#[test]
+fn parse_exact_origin() {
+    assert_eq!(
+        AllowedOrigin::parse("https://bootcamp.gabuzando.dev/"),
+        Ok(AllowedOrigin::Exact(
+            "https://bootcamp.gabuzando.dev".to_owned()
+        ))
+    );
+    assert_eq!(
+        AllowedOrigin::parse("http://localhost:8000"),
+        Ok(AllowedOrigin::Exact("http://localhost:8000".to_owned()))
+    );
+}
+
+#[test]
+fn parse_invalid_origin() {
+    let test_cases = [
+        "localhost:8000",
+        "ftp://example.com",
+        "https://example.com/path",
+        "https://",
+        "https://example.com:port",
+        "https://app.*.example.com",
+        "https://*example.com",
+        "https://*.",
+    ];
+    for tc in test_cases {
+        assert!(AllowedOrigin::parse(tc).is_err(), "failed for: {}", tc);
+    }
+}
+
+#[test]
+fn exact_origin_matches_only_itself() {
+    let allowed = AllowedOrigin::parse("https://bootcamp.gabuzando.dev").unwrap();
+    assert!(allowed.matches("https://bootcamp.gabuzando.dev"));
+    assert!(allowed.matches("HTTPS://Bootcamp.Gabuzando.dev"));
+    assert!(!allowed.matches("http://bootcamp.gabuzando.dev"));
+    assert!(!allowed.matches("https://bootcamp.gabuzando.dev:8443"));
+    assert!(!allowed.matches("https://evil.dev"));
+}
+
+#[test]
+fn wildcard_origin_matches_subdomains_only() {
+    let allowed = AllowedOrigin::parse("https://*.gabuzando.dev").unwrap();
+    assert!(allowed.matches("https://app.gabuzando.dev"));
+    assert!(allowed.matches("https://a.b.gabuzando.dev"));
+    assert!(!allowed.matches("https://gabuzando.dev"));
+    assert!(!allowed.matches("https://evilgabuzando.dev"));
+    assert!(!allowed.matches("https://app.gabuzando.dev.evil.dev"));
+    assert!(!allowed.matches("http://app.gabuzando.dev"));
+    assert!(!allowed.matches("https://app.gabuzando.dev:8443"));
+}
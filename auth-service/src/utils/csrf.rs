@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::Method,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::extract::cookie::{Cookie, CookieJar};
+use rand::Rng;
+
+use crate::domain::AuthAPIError;
+use crate::settings::Settings;
+use crate::utils::auth::constant_time_eq;
+
+use super::constants::{CSRF_COOKIE_NAME, CSRF_HEADER_NAME};
+
+#[cfg(test)]
+mod tests;
+
+/// The CSRF token of the current request, inserted as a request extension by
+/// [`csrf_cookie_middleware`] so handlers can hand it to clients.
+#[derive(Clone, Debug)]
+pub struct CsrfToken(pub String);
+
+fn generate_csrf_token() -> String {
+    let bytes: [u8; 32] = rand::rng().random();
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn is_safe_method(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    )
+}
+
+fn csrf_cookie_value(jar: &CookieJar) -> Option<String> {
+    jar.get(CSRF_COOKIE_NAME)
+        .map(|cookie| cookie.value().to_owned())
+        .filter(|token| !token.is_empty())
+}
+
+// Not HttpOnly on purpose, the front-end reads it to fill the header.
+fn create_csrf_cookie(token: String, settings: &Settings) -> Cookie<'static> {
+    let cookie_settings = &settings.auth.cookie;
+    let mut cookie = Cookie::build((CSRF_COOKIE_NAME, token))
+        .path("/")
+        .secure(cookie_settings.secure)
+        .same_site(cookie_settings.same_site.into())
+        .build();
+    if !cookie_settings.domain.is_empty() {
+        cookie.set_domain(cookie_settings.domain.clone());
+    }
+    cookie
+}
+
+/// Gives every client a random, script-readable `csrf_token` cookie and
+/// exposes it to handlers as a [`CsrfToken`] extension.
+pub async fn csrf_cookie_middleware(
+    State(settings): State<Arc<Settings>>,
+    jar: CookieJar,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let existing_token = csrf_cookie_value(&jar);
+    let (token, is_new) = match existing_token {
+        Some(token) => (token, false),
+        None => (generate_csrf_token(), true),
+    };
+    request.extensions_mut().insert(CsrfToken(token.clone()));
+
+    let response = next.run(request).await;
+    if is_new {
+        let jar = jar.add(create_csrf_cookie(token, &settings));
+        return (jar, response).into_response();
+    }
+    response
+}
+
+/// Double-submit check for cookie-authenticated routes: state-changing
+/// requests carrying the auth cookie must echo the `csrf_token` cookie in the
+/// `X-CSRF-Token` header, which a cross-site page cannot do.
+pub async fn require_csrf_token(
+    State(settings): State<Arc<Settings>>,
+    jar: CookieJar,
+    request: Request,
+    next: Next,
+) -> Result<Response, AuthAPIError> {
+    let auth_cookie_name = settings.auth.cookie.auth_cookie_name();
+    if !is_safe_method(request.method()) && jar.get(&auth_cookie_name).is_some() {
+        let header_token = request
+            .headers()
+            .get(CSRF_HEADER_NAME)
+            .and_then(|value| value.to_str().ok());
+        match (csrf_cookie_value(&jar), header_token) {
+            (Some(cookie_token), Some(header_token))
+                if constant_time_eq(&cookie_token, header_token) => {}
+            _ => return Err(AuthAPIError::InvalidCsrfToken),
+        }
+    }
+    Ok(next.run(request).await)
+}
@@ -0,0 +1,27 @@
+use super::*;
+
+#[test]
+fn generated_tokens_are_random_hex() {
+    let token = generate_csrf_token();
+    assert_eq!(token.len(), 64);
+    assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_ne!(token, generate_csrf_token());
+}
+
+#[test]
+fn only_read_only_methods_are_safe() {
+    assert!(is_safe_method(&Method::GET));
+    assert!(is_safe_method(&Method::OPTIONS));
+    assert!(!is_safe_method(&Method::POST));
+    assert!(!is_safe_method(&Method::PATCH));
+    assert!(!is_safe_method(&Method::DELETE));
+}
+
+#[test]
+fn csrf_cookie_is_readable_by_scripts() {
+    let settings = Settings::default();
+    let cookie = create_csrf_cookie("token".to_owned(), &settings);
+    assert_eq!(cookie.name(), CSRF_COOKIE_NAME);
+    assert_eq!(cookie.path(), Some("/"));
+    assert_eq!(cookie.http_only(), None);
+}
@@ -0,0 +1,600 @@
+use async_trait::async_trait;
+use axum::{
+    Json,
+    extract::{
+        ConnectInfo, Form, FromRequest, FromRequestParts, Path, Query, Request, State,
+        rejection::{FormRejection, JsonRejection},
+    },
+    http::{
+        HeaderMap,
+        header::{AUTHORIZATION, USER_AGENT},
+        request::Parts,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::extract::cookie::CookieJar;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::de::DeserializeOwned;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use tokio::sync::RwLock;
+
+use crate::app_state::AppState;
+use crate::domain::{
+    ADMIN_ROLE, AccessTokenStore, AccessTokenStoreError, AuditLog, AuthAPIError, BannedTokenStore,
+    ClientInfo, Email, EmailClient, FieldError, MagicLinkStore, OAuthStore, OAuthStoreError,
+    OPENID_SCOPE, OrganizationStore, PersonalAccessToken, TrustedDeviceStore, TwoFACodeStore,
+    UserKey, UserStore, UserStoreError, personal_access_token_id,
+};
+use crate::settings::AuthSettings;
+use crate::utils::auth::{Claims, TokenKind, constant_time_eq, validate_token};
+use crate::utils::constants::{ADMIN_API_KEY_HEADER_NAME, REAL_IP_HEADER_NAME};
+
+#[cfg(test)]
+mod tests;
+
+const MAX_USER_AGENT_LENGTH: usize = 512;
+
+/// Where the token of an [`AuthenticatedUser`] came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenSource {
+    Cookie,
+    Bearer,
+}
+
+/// A user authenticated by a valid, non-banned JWT, taken from an
+/// `Authorization: Bearer` header or, failing that, from the auth cookie.
+#[derive(Debug)]
+pub struct AuthenticatedUser {
+    pub email: Email,
+    pub claims: Claims,
+    pub token: String,
+    pub source: TokenSource,
+}
+
+impl AuthenticatedUser {
+    pub fn key(&self) -> UserKey {
+        self.claims.user()
+    }
+}
+
+fn bearer_token(parts: &Parts) -> Option<String> {
+    let value = parts.headers.get(AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    let token = token.trim();
+    if !scheme.eq_ignore_ascii_case("bearer") || token.is_empty() {
+        return None;
+    }
+    Some(token.to_owned())
+}
+
+// `Authorization: Basic base64(client_id:client_secret)`
+pub(crate) fn basic_credentials(headers: &HeaderMap) -> Option<(String, String)> {
+    let value = headers.get(AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, encoded) = value.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+    let decoded = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (client_id, client_secret) = decoded.split_once(':')?;
+    Some((client_id.to_owned(), client_secret.to_owned()))
+}
+
+/// Checks that a token is neither banned, on its own or with the other tokens
+/// of its user, nor invalid, returning its claims.
+pub async fn validate_active_token<U: BannedTokenStore>(
+    token: &str,
+    banned_token_store: &RwLock<U>,
+    settings: &AuthSettings,
+) -> Result<Claims, AuthAPIError> {
+    let banned_token_store = banned_token_store.read().await;
+    match banned_token_store.contains_token(token).await {
+        Ok(true) => return Err(AuthAPIError::InvalidToken),
+        Ok(false) => {}
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+    let claims = validate_token(token, settings)
+        .await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+    match banned_token_store
+        .user_tokens_banned_before(&claims.user())
+        .await
+    {
+        Ok(Some(banned_before)) if claims.iat as i64 <= banned_before => {
+            Err(AuthAPIError::InvalidToken)
+        }
+        Ok(_) => Ok(claims),
+        Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+}
+
+/// Checks that the account of a token can still be used, so suspending it
+/// takes effect before its tokens expire.
+pub async fn ensure_account_active<T: UserStore>(
+    user: &UserKey,
+    user_store: &RwLock<T>,
+) -> Result<(), AuthAPIError> {
+    match user_store.read().await.get_user(user).await {
+        Ok(user) => user.status().ensure_active(),
+        // the token outlived its user
+        Err(UserStoreError::UserNotFound | UserStoreError::InvalidCredentials) => {
+            Err(AuthAPIError::InvalidToken)
+        }
+        Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+}
+
+/// Checks that the API key of a service token is neither revoked nor
+/// expired, so revoking it takes effect before its tokens expire.
+pub async fn ensure_api_key_active<Z: OAuthStore>(
+    id: &str,
+    oauth_store: &RwLock<Z>,
+) -> Result<(), AuthAPIError> {
+    match oauth_store.read().await.get_api_key(id).await {
+        Ok(api_key) if !api_key.is_expired() => Ok(()),
+        Ok(_) | Err(OAuthStoreError::ApiKeyNotFound) => Err(AuthAPIError::InvalidToken),
+        Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+}
+
+/// Checks a personal access token against its hash and expiry, that it wasn't
+/// created before the tokens of its user were banned, and that the account of
+/// its user can still be used, returning it.
+pub async fn validate_personal_access_token<
+    T: UserStore,
+    U: BannedTokenStore,
+    P: AccessTokenStore,
+>(
+    token: &str,
+    access_token_store: &RwLock<P>,
+    banned_token_store: &RwLock<U>,
+    user_store: &RwLock<T>,
+) -> Result<PersonalAccessToken, AuthAPIError> {
+    let id = personal_access_token_id(token).ok_or(AuthAPIError::InvalidToken)?;
+    let access_token = match access_token_store.read().await.get_token(id).await {
+        Ok(access_token) if access_token.verify(token) && !access_token.is_expired() => {
+            access_token
+        }
+        Ok(_) | Err(AccessTokenStoreError::TokenNotFound) => {
+            return Err(AuthAPIError::InvalidToken);
+        }
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+    // logging the user out everywhere revokes the tokens created until then
+    match banned_token_store
+        .read()
+        .await
+        .user_tokens_banned_before(&access_token.user())
+        .await
+    {
+        Ok(Some(banned_before)) if access_token.created_at.timestamp() <= banned_before => {
+            return Err(AuthAPIError::InvalidToken);
+        }
+        Ok(_) => {}
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+    ensure_account_active(&access_token.user(), user_store).await?;
+    Ok(access_token)
+}
+
+/// [`ensure_account_active`] for user tokens, [`ensure_api_key_active`] for
+/// service tokens.
+pub async fn ensure_subject_active<T: UserStore, Z: OAuthStore>(
+    claims: &Claims,
+    user_store: &RwLock<T>,
+    oauth_store: &RwLock<Z>,
+) -> Result<(), AuthAPIError> {
+    match claims.kind {
+        TokenKind::User => ensure_account_active(&claims.user(), user_store).await,
+        TokenKind::Service => ensure_api_key_active(&claims.sub, oauth_store).await,
+    }
+}
+
+#[async_trait]
+impl<T, U, V, W, X, Y, Z, M, P, O> FromRequestParts<AppState<T, U, V, W, X, Y, Z, M, P, O>>
+    for AuthenticatedUser
+where
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+{
+    type Rejection = AuthAPIError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    ) -> Result<Self, Self::Rejection> {
+        let auth_settings = &state.settings.auth;
+        let (token, source) = match bearer_token(parts) {
+            Some(token) => (token, TokenSource::Bearer),
+            None => {
+                let jar = CookieJar::from_headers(&parts.headers);
+                let cookie = jar
+                    .get(&auth_settings.cookie.auth_cookie_name())
+                    .ok_or(AuthAPIError::MissingToken)?;
+                (cookie.value().to_owned(), TokenSource::Cookie)
+            }
+        };
+
+        let claims =
+            validate_active_token(&token, &state.banned_token_store, auth_settings).await?;
+        // tokens of OpenID Connect clients only work where an AccessTokenUser is
+        // expected, service tokens never stand for a user
+        if claims.scope.is_some() || claims.kind != TokenKind::User {
+            return Err(AuthAPIError::InvalidToken);
+        }
+        let email = Email::parse(&claims.sub).map_err(|_| AuthAPIError::InvalidToken)?;
+
+        Ok(Self {
+            email,
+            claims,
+            token,
+            source,
+        })
+    }
+}
+
+/// A user authenticated by an access token issued to an OpenID Connect
+/// client, always sent as `Authorization: Bearer`.
+#[derive(Debug)]
+pub struct AccessTokenUser {
+    pub email: Email,
+    pub tenant: Option<String>,
+    pub scopes: Vec<String>,
+}
+
+impl AccessTokenUser {
+    pub fn key(&self) -> UserKey {
+        UserKey::new(self.tenant.as_deref(), self.email.as_ref())
+    }
+}
+
+#[async_trait]
+impl<T, U, V, W, X, Y, Z, M, P, O> FromRequestParts<AppState<T, U, V, W, X, Y, Z, M, P, O>>
+    for AccessTokenUser
+where
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+{
+    type Rejection = AuthAPIError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    ) -> Result<Self, Self::Rejection> {
+        let token = bearer_token(parts).ok_or(AuthAPIError::MissingToken)?;
+        let claims =
+            validate_active_token(&token, &state.banned_token_store, &state.settings.auth).await?;
+        let scopes: Vec<String> = claims
+            .scope
+            .as_deref()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_owned)
+            .collect();
+        if claims.kind != TokenKind::User || !scopes.iter().any(|scope| scope == OPENID_SCOPE) {
+            return Err(AuthAPIError::InvalidToken);
+        }
+        let email = Email::parse(&claims.sub).map_err(|_| AuthAPIError::InvalidToken)?;
+        Ok(Self {
+            email,
+            tenant: claims.tenant,
+            scopes,
+        })
+    }
+}
+
+/// Guard for routes restricted to a role, checked against the roles in the
+/// token:
+/// `route.layer(middleware::from_fn_with_state((state, RequireRole("admin")), require_role))`.
+/// Roles granted or revoked after login take effect with the next token.
+#[derive(Debug, Clone, Copy)]
+pub struct RequireRole(pub &'static str);
+
+impl RequireRole {
+    pub fn check(&self, claims: &Claims) -> Result<(), AuthAPIError> {
+        if claims.has_role(self.0) {
+            Ok(())
+        } else {
+            Err(AuthAPIError::MissingRole(self.0.to_owned()))
+        }
+    }
+}
+
+/// State of the [`require_role`] layer.
+pub type RoleGuardState<T, U, V, W, X, Y, Z, M, P, O> =
+    (AppState<T, U, V, W, X, Y, Z, M, P, O>, RequireRole);
+
+pub async fn require_role<T, U, V, W, X, Y, Z, M, P, O>(
+    State((state, required)): State<RoleGuardState<T, U, V, W, X, Y, Z, M, P, O>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AuthAPIError>
+where
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+{
+    let (mut parts, body) = request.into_parts();
+    let user = AuthenticatedUser::from_request_parts(&mut parts, &state).await?;
+    required.check(&user.claims)?;
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
+
+/// Who passed [`require_admin`], with no email for the API key.
+#[derive(Debug, Clone)]
+pub struct Admin {
+    pub email: Option<Email>,
+}
+
+/// Guard for the admin routes, which accept either a token with the admin
+/// role or the static API key from the settings in `X-Admin-Api-Key`.
+pub async fn require_admin<T, U, V, W, X, Y, Z, M, P, O>(
+    State(state): State<AppState<T, U, V, W, X, Y, Z, M, P, O>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AuthAPIError>
+where
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+{
+    let (mut parts, body) = request.into_parts();
+    match parts.headers.get(ADMIN_API_KEY_HEADER_NAME) {
+        Some(key) => {
+            let valid = match (key.to_str(), state.settings.admin.api_key()) {
+                (Ok(key), Some(api_key)) => constant_time_eq(key, api_key),
+                _ => false,
+            };
+            if !valid {
+                return Err(AuthAPIError::InvalidApiKey);
+            }
+            parts.extensions.insert(Admin { email: None });
+        }
+        None => {
+            let user = AuthenticatedUser::from_request_parts(&mut parts, &state).await?;
+            RequireRole(ADMIN_ROLE).check(&user.claims)?;
+            parts.extensions.insert(Admin {
+                email: Some(user.email),
+            });
+        }
+    }
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
+
+/// A client authenticated with HTTP Basic against the introspection clients
+/// in the settings.
+#[derive(Debug)]
+pub struct AuthenticatedClient {
+    pub client_id: String,
+}
+
+#[async_trait]
+impl<T, U, V, W, X, Y, Z, M, P, O> FromRequestParts<AppState<T, U, V, W, X, Y, Z, M, P, O>>
+    for AuthenticatedClient
+where
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+{
+    type Rejection = AuthAPIError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    ) -> Result<Self, Self::Rejection> {
+        let (client_id, client_secret) =
+            basic_credentials(&parts.headers).ok_or(AuthAPIError::InvalidClient)?;
+        match state.settings.introspection.client_secret(&client_id) {
+            Some(secret) if constant_time_eq(secret, &client_secret) => Ok(Self { client_id }),
+            _ => Err(AuthAPIError::InvalidClient),
+        }
+    }
+}
+
+fn header_value(parts: &Parts, name: &str) -> Option<String> {
+    let value = parts.headers.get(name)?.to_str().ok()?.trim();
+    (!value.is_empty()).then(|| value.to_owned())
+}
+
+#[async_trait]
+impl<T, U, V, W, X, Y, Z, M, P, O> FromRequestParts<AppState<T, U, V, W, X, Y, Z, M, P, O>>
+    for ClientInfo
+where
+    T: UserStore,
+    U: BannedTokenStore,
+    V: TwoFACodeStore,
+    W: EmailClient,
+    X: AuditLog,
+    Y: TrustedDeviceStore,
+    Z: OAuthStore,
+    M: MagicLinkStore,
+    P: AccessTokenStore,
+    O: OrganizationStore,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState<T, U, V, W, X, Y, Z, M, P, O>,
+    ) -> Result<Self, Self::Rejection> {
+        let forwarded_ip = if state.settings.application.trust_proxy_headers {
+            header_value(parts, REAL_IP_HEADER_NAME)
+        } else {
+            None
+        };
+        let ip = forwarded_ip.or_else(|| {
+            parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(address)| address.ip().to_string())
+        });
+        let user_agent = header_value(parts, USER_AGENT.as_str())
+            .map(|user_agent| user_agent.chars().take(MAX_USER_AGENT_LENGTH).collect());
+        Ok(Self { ip, user_agent })
+    }
+}
+
+/// `Json` for request bodies, rejecting those that don't deserialize with the
+/// field at fault rather than axum's plain text message.
+pub struct JsonBody<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for JsonBody<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<T>::from_request(request, state).await {
+            Ok(Json(value)) => Ok(Self(value)),
+            Err(JsonRejection::JsonDataError(e)) => {
+                let error = deserialize_error(&e.body_text());
+                Err(AuthAPIError::MalformedBody(vec![error]).into_response())
+            }
+            Err(JsonRejection::JsonSyntaxError(_)) => {
+                let error = FieldError::new("body", "invalid_json", "The body is not valid JSON.");
+                Err(AuthAPIError::InvalidFields(vec![error]).into_response())
+            }
+            // e.g. a missing `Content-Type: application/json`
+            Err(_) => Err(AuthAPIError::InvalidInput.into_response()),
+        }
+    }
+}
+
+/// `Path` rejecting parameters that don't deserialize with an
+/// [`AuthAPIError`], so they get the same body as other errors.
+pub struct PathParams<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for PathParams<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = AuthAPIError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Path(value) = Path::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|_| AuthAPIError::InvalidInput)?;
+        Ok(Self(value))
+    }
+}
+
+/// `Query` with an [`AuthAPIError`] rejection, like [`PathParams`].
+pub struct QueryParams<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for QueryParams<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AuthAPIError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(value) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|_| AuthAPIError::InvalidInput)?;
+        Ok(Self(value))
+    }
+}
+
+/// `Form` with an [`AuthAPIError`] rejection, naming the field at fault like
+/// [`JsonBody`].
+pub struct FormBody<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for FormBody<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AuthAPIError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Form::<T>::from_request(request, state).await {
+            Ok(Form(value)) => Ok(Self(value)),
+            Err(FormRejection::FailedToDeserializeFormBody(e)) => {
+                let body_text = e.body_text();
+                let detail = body_text
+                    .strip_prefix("Failed to deserialize form body: ")
+                    .unwrap_or(&body_text);
+                Err(AuthAPIError::MalformedBody(vec![deserialize_error(detail)]))
+            }
+            Err(_) => Err(AuthAPIError::InvalidInput),
+        }
+    }
+}
+
+// axum reports `<path>: <serde error> at line L column C`, the path being
+// empty for the top level, e.g. for a missing field.
+fn deserialize_error(body_text: &str) -> FieldError {
+    let detail = body_text
+        .split_once("target type: ")
+        .map_or(body_text, |(_, detail)| detail);
+    let detail = detail
+        .rsplit_once(" at line ")
+        .map_or(detail, |(detail, _)| detail);
+    let (path, message) = match detail.split_once(": ") {
+        Some((path, message)) if !path.contains(' ') => (path, message),
+        _ => ("", detail),
+    };
+    let missing = message
+        .strip_prefix("missing field `")
+        .and_then(|rest| rest.strip_suffix('`'));
+    if let Some(name) = missing {
+        let field = match path {
+            "" => name.to_owned(),
+            path => format!("{path}.{name}"),
+        };
+        return FieldError::new(&field, "missing_field", "This field is required.");
+    }
+    let code = if message.starts_with("invalid type") {
+        "invalid_type"
+    } else {
+        "invalid_value"
+    };
+    let field = if path.is_empty() { "body" } else { path };
+    FieldError::new(field, code, message)
+}
@@ -0,0 +1,159 @@
+use base64::{
+    Engine,
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, EllipticCurveKeyParameters,
+    EllipticCurveKeyType, Jwk, JwkSet, KeyAlgorithm, PublicKeyUse,
+};
+use jsonwebtoken::{Algorithm, EncodingKey, Header, encode};
+use ring::digest::{SHA256, digest};
+use ring::rand::SystemRandom;
+use ring::signature::{ECDSA_P256_SHA256_FIXED_SIGNING, EcdsaKeyPair, KeyPair};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::User;
+
+#[cfg(test)]
+mod tests;
+
+// an uncompressed P-256 point: 0x04, then x and y
+const P256_COORDINATE_LENGTH: usize = 32;
+
+/// Generates a P-256 key, as base64 PKCS#8 like `oidc.signing_key`.
+pub fn generate_signing_key() -> String {
+    let pkcs8 =
+        EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &SystemRandom::new())
+            .expect("Failed to generate a P-256 key");
+    STANDARD.encode(pkcs8.as_ref())
+}
+
+/// The ES256 key signing ID tokens, published at `/.well-known/jwks.json`.
+#[derive(Clone)]
+pub struct SigningKey {
+    encoding_key: EncodingKey,
+    jwk: Jwk,
+}
+
+impl SigningKey {
+    pub fn from_base64_pkcs8(key: &str) -> Result<Self, String> {
+        let pkcs8 = STANDARD
+            .decode(key.trim())
+            .map_err(|_| "The signing key is not valid base64".to_owned())?;
+        let key_pair = EcdsaKeyPair::from_pkcs8(
+            &ECDSA_P256_SHA256_FIXED_SIGNING,
+            &pkcs8,
+            &SystemRandom::new(),
+        )
+        .map_err(|_| "The signing key is not a PKCS#8 P-256 key".to_owned())?;
+
+        let point = key_pair.public_key().as_ref();
+        let (x, y) = point[1..].split_at(P256_COORDINATE_LENGTH);
+        let (x, y) = (URL_SAFE_NO_PAD.encode(x), URL_SAFE_NO_PAD.encode(y));
+        // RFC 7638 thumbprint, stable for a given key
+        let thumbprint = format!(r#"{{"crv":"P-256","kty":"EC","x":"{x}","y":"{y}"}}"#);
+        let kid = URL_SAFE_NO_PAD.encode(digest(&SHA256, thumbprint.as_bytes()));
+
+        let jwk = Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(KeyAlgorithm::ES256),
+                key_id: Some(kid),
+                ..Default::default()
+            },
+            algorithm: AlgorithmParameters::EllipticCurve(EllipticCurveKeyParameters {
+                key_type: EllipticCurveKeyType::EC,
+                curve: EllipticCurve::P256,
+                x,
+                y,
+            }),
+        };
+        Ok(Self {
+            encoding_key: EncodingKey::from_ec_der(&pkcs8),
+            jwk,
+        })
+    }
+
+    /// The key from the settings, or a new one when none is set.
+    pub fn from_settings(signing_key: &str) -> Result<Self, String> {
+        if signing_key.is_empty() {
+            tracing::warn!(
+                "No OIDC signing key set, ID tokens won't be verifiable after a restart"
+            );
+            return Self::from_base64_pkcs8(&generate_signing_key());
+        }
+        Self::from_base64_pkcs8(signing_key)
+    }
+
+    pub fn kid(&self) -> &str {
+        self.jwk.common.key_id.as_deref().unwrap_or_default()
+    }
+
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: vec![self.jwk.clone()],
+        }
+    }
+
+    pub fn sign<C: Serialize>(&self, claims: &C) -> Result<String, jsonwebtoken::errors::Error> {
+        let mut header = Header::new(Algorithm::ES256);
+        header.kid = Some(self.kid().to_owned());
+        encode(&header, claims, &self.encoding_key)
+    }
+}
+
+/// What a client learns about a user, in the ID token and from
+/// `/userinfo`. The `email` and `profile` claims are only set when their
+/// scope was granted.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserInfo {
+    pub sub: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_verified: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+}
+
+impl UserInfo {
+    pub fn new(user: &User, scopes: &[String]) -> Self {
+        let granted = |scope: &str| scopes.iter().any(|granted| granted == scope);
+        let mut info = Self {
+            sub: user.email_str().to_owned(),
+            ..Default::default()
+        };
+        if granted("email") {
+            info.email = Some(user.email_str().to_owned());
+            info.email_verified = Some(user.email_verified());
+        }
+        if granted("profile") {
+            let profile = user.profile();
+            info.name = profile
+                .display_name
+                .as_ref()
+                .map(|name| name.as_ref().to_owned());
+            info.locale = profile
+                .locale
+                .as_ref()
+                .map(|locale| locale.as_ref().to_owned());
+        }
+        info
+    }
+}
+
+/// Claims of the ID tokens handed to OpenID Connect clients.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub aud: String,
+    pub exp: usize,
+    pub iat: usize,
+    pub auth_time: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    #[serde(flatten)]
+    pub user: UserInfo,
+}
@@ -0,0 +1,73 @@
+use jsonwebtoken::{DecodingKey, Validation, decode, decode_header};
+
+use super::*;
+
+#[test]
+fn keys_are_loaded_from_base64_pkcs8() {
+    let encoded = generate_signing_key();
+    let key = SigningKey::from_base64_pkcs8(&encoded).unwrap();
+    let same_key = SigningKey::from_base64_pkcs8(&encoded).unwrap();
+
+    assert_eq!(key.kid(), same_key.kid());
+    assert_ne!(key.kid(), SigningKey::from_settings("").unwrap().kid());
+    assert!(SigningKey::from_base64_pkcs8("not base64!").is_err());
+    assert!(SigningKey::from_base64_pkcs8(&STANDARD.encode("not a key")).is_err());
+}
+
+#[test]
+fn signed_tokens_verify_with_the_published_key() {
+    let key = SigningKey::from_settings("").unwrap();
+    let claims = IdTokenClaims {
+        iss: "http://localhost:3000".to_owned(),
+        aud: "client".to_owned(),
+        exp: usize::MAX,
+        nonce: Some("nonce".to_owned()),
+        user: UserInfo {
+            sub: "test@example.com".to_owned(),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let token = key.sign(&claims).unwrap();
+
+    let header = decode_header(&token).unwrap();
+    assert_eq!(header.alg, Algorithm::ES256);
+    assert_eq!(header.kid.as_deref(), Some(key.kid()));
+
+    let jwks = key.jwks();
+    let jwk = jwks.find(key.kid()).expect("Key missing from the JWKS");
+    let mut validation = Validation::new(Algorithm::ES256);
+    validation.set_audience(&["client"]);
+    let decoded =
+        decode::<IdTokenClaims>(&token, &DecodingKey::from_jwk(jwk).unwrap(), &validation)
+            .unwrap()
+            .claims;
+    assert_eq!(decoded.user.sub, "test@example.com");
+    assert_eq!(decoded.nonce.as_deref(), Some("nonce"));
+    assert_eq!(decoded.user.email, None);
+}
+
+#[test]
+fn user_info_follows_the_granted_scopes() {
+    use crate::domain::{DisplayName, Email, Password, PasswordPolicy, UserProfile};
+
+    let mut user = User::new(
+        Email::parse("test@example.com").unwrap(),
+        Password::parse("Kx7!vTq2Lm", &PasswordPolicy::default()).unwrap(),
+        false,
+    );
+    user.set_profile(UserProfile {
+        display_name: Some(DisplayName::parse("Test User").unwrap()),
+        locale: None,
+    });
+    let scopes = |scopes: &[&str]| scopes.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+    let info = UserInfo::new(&user, &scopes(&["openid"]));
+    assert_eq!(info.sub, "test@example.com");
+    assert_eq!((info.email, info.name), (None, None));
+
+    let info = UserInfo::new(&user, &scopes(&["openid", "email", "profile"]));
+    assert_eq!(info.email.as_deref(), Some("test@example.com"));
+    assert_eq!(info.email_verified, Some(false));
+    assert_eq!(info.name.as_deref(), Some("Test User"));
+}
@@ -0,0 +1,27 @@
+use crate::domain::AuthAPIError;
+
+pub const DEFAULT_PER_PAGE: usize = 20;
+pub const MAX_PER_PAGE: usize = 100;
+
+/// A 1-based page of `per_page` items, from the `page` and `perPage` query
+/// parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Page {
+    pub page: usize,
+    pub per_page: usize,
+}
+
+impl Page {
+    pub fn parse(page: Option<usize>, per_page: Option<usize>) -> Result<Self, AuthAPIError> {
+        let page = page.unwrap_or(1);
+        let per_page = per_page.unwrap_or(DEFAULT_PER_PAGE);
+        if page == 0 || !(1..=MAX_PER_PAGE).contains(&per_page) {
+            return Err(AuthAPIError::InvalidInput);
+        }
+        Ok(Self { page, per_page })
+    }
+
+    pub fn offset(&self) -> usize {
+        (self.page - 1).saturating_mul(self.per_page)
+    }
+}
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use url::Url;
+
+use crate::domain::{AuthAPIError, ProblemDetails};
+use crate::settings::{ApplicationSettings, Settings};
+
+#[cfg(test)]
+mod tests;
+
+pub const PROBLEM_JSON: &str = "application/problem+json";
+/// The date of the `tag:` URIs of problem types, when they were first named.
+const PROBLEM_TYPE_TAG_DATE: &str = "2026";
+
+/// Sends the RFC 7807 problem details of an error response to clients asking
+/// for `application/problem+json`, other clients keep the `{"error": ...}`
+/// body.
+pub async fn problem_details_middleware(
+    State(settings): State<Arc<Settings>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let wants_problem = accepts_problem_json(request.headers());
+    let mut response = next.run(request).await;
+    let Some(mut problem) = response.extensions_mut().remove::<ProblemDetails>() else {
+        return response;
+    };
+    response
+        .headers_mut()
+        .append(header::VARY, HeaderValue::from_static("accept"));
+    if !wants_problem {
+        return response;
+    }
+
+    problem.problem_type = problem_type_uri(&settings.application, &problem.problem_type);
+    let (mut parts, _) = response.into_parts();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.status = StatusCode::from_u16(problem.status).unwrap_or(parts.status);
+    let mut response = (parts, Json(problem)).into_response();
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+    response
+}
+
+/// Answers requests no route or asset matches, as an error like the others.
+pub async fn route_not_found() -> AuthAPIError {
+    AuthAPIError::RouteNotFound
+}
+
+/// Answers requests to a route with a method it doesn't have; axum still adds
+/// the `Allow` header.
+pub async fn method_not_allowed() -> AuthAPIError {
+    AuthAPIError::MethodNotAllowed
+}
+
+/// A `tag:` URI (RFC 4151) under the host of the public URL: it names the
+/// kind of problem without being a page to fetch.
+fn problem_type_uri(application: &ApplicationSettings, problem_type: &str) -> String {
+    let host = Url::parse(&application.public_url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_owned))
+        .unwrap_or_else(|| "localhost".to_owned());
+    format!("tag:{host},{PROBLEM_TYPE_TAG_DATE}:problems/{problem_type}")
+}
+
+/// Whether a media range of the `Accept` header names problem+json, unless
+/// with `q=0`. Wildcards don't count, they are what most clients send.
+fn accepts_problem_json(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|range| {
+            let mut params = range.split(';').map(str::trim);
+            let media_type = params.next().unwrap_or_default();
+            let refused = params.any(|param| {
+                param
+                    .strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .is_some_and(|q| q == 0.0)
+            });
+            media_type.eq_ignore_ascii_case(PROBLEM_JSON) && !refused
+        })
+}
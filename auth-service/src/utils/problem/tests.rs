@@ -0,0 +1,43 @@
+use super::*;
+
+fn accept(value: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(header::ACCEPT, HeaderValue::from_str(value).unwrap());
+    headers
+}
+
+#[test]
+fn problem_json_is_accepted_when_asked_for() {
+    for value in [
+        "application/problem+json",
+        "application/json, application/problem+json",
+        "Application/Problem+JSON; q=0.5",
+    ] {
+        assert!(accepts_problem_json(&accept(value)), "failed for: {value}");
+    }
+}
+
+#[test]
+fn other_media_types_keep_the_error_body() {
+    for value in [
+        "application/json",
+        "*/*",
+        "application/*",
+        "application/problem+json;q=0",
+    ] {
+        assert!(!accepts_problem_json(&accept(value)), "failed for: {value}");
+    }
+    assert!(!accepts_problem_json(&HeaderMap::new()));
+}
+
+#[test]
+fn problem_types_are_tag_uris_under_the_public_host() {
+    let application = ApplicationSettings {
+        public_url: "https://auth.example.com:8443/base/".to_owned(),
+        ..Default::default()
+    };
+    assert_eq!(
+        problem_type_uri(&application, "missing-token"),
+        "tag:auth.example.com,2026:problems/missing-token"
+    );
+}
@@ -32,11 +32,11 @@ pub async fn metrics_middleware(request: Request, next: Next) -> Response {
     response
 }
 
-pub fn init_tracing() -> Result<logfire::ShutdownGuard> {
+pub fn init_tracing(env_name: &str) -> Result<logfire::ShutdownGuard> {
     let logfire = logfire::configure()
         .local()
         .with_service_name("bootcamp")
-        .with_environment(crate::utils::constants::ENV_NAME.to_owned())
+        .with_environment(env_name.to_owned())
         .with_default_level_filter(tracing::level_filters::LevelFilter::DEBUG)
         .finish()?;
 
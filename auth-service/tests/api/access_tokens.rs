@@ -0,0 +1,189 @@
+use crate::helpers::{TestApp, get_random_email};
+use auth_service::domain::AccountStatus;
+use auth_service::routes::{
+    AccessTokenListResponse, CreatedAccessTokenResponse, VerifyTokenResponse,
+};
+use serde_json::json;
+
+async fn create_deploy_token(app: &TestApp) -> CreatedAccessTokenResponse {
+    let body = json!({
+        "name": "Deploy script",
+        "scopes": ["repo:read", "repo:write"],
+        "expiresInDays": 30,
+    });
+    let response = app.post_access_token(&body).await;
+    assert_eq!(response.status(), 201);
+    response
+        .json::<CreatedAccessTokenResponse>()
+        .await
+        .expect("Could not deserialize response body to CreatedAccessTokenResponse")
+}
+
+async fn list_access_tokens(app: &TestApp) -> AccessTokenListResponse {
+    let response = app.get_access_tokens().await;
+    assert_eq!(response.status(), 200);
+    response
+        .json::<AccessTokenListResponse>()
+        .await
+        .expect("Could not deserialize response body to AccessTokenListResponse")
+}
+
+#[tokio::test]
+async fn should_create_list_and_revoke_access_tokens() {
+    let mut app = TestApp::new().await;
+    app.signup_and_login(&get_random_email()).await;
+
+    let created = create_deploy_token(&app).await;
+    assert!(
+        created
+            .token
+            .starts_with(&format!("{}.", created.access_token.id))
+    );
+    assert_eq!(created.access_token.scopes, ["repo:read", "repo:write"]);
+
+    let listed = list_access_tokens(&app).await;
+    assert_eq!(listed.tokens.len(), 1);
+    assert_eq!(listed.tokens[0].id, created.access_token.id);
+    assert_eq!(listed.tokens[0].last_used_at, None);
+
+    let response = app.delete_access_token(&created.access_token.id).await;
+    assert_eq!(response.status(), 204);
+    let response = app.delete_access_token(&created.access_token.id).await;
+    assert_eq!(response.status(), 404);
+    assert!(list_access_tokens(&app).await.tokens.is_empty());
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_400_or_422_for_invalid_access_tokens() {
+    let mut app = TestApp::new().await;
+    app.signup_and_login(&get_random_email()).await;
+
+    let test_cases = [
+        json!({ "name": " ", "scopes": ["repo"], "expiresInDays": 1 }),
+        json!({ "name": "script", "scopes": [], "expiresInDays": 1 }),
+        json!({ "name": "script", "scopes": ["openid"], "expiresInDays": 1 }),
+        json!({ "name": "script", "scopes": ["repo"], "expiresInDays": 0 }),
+        json!({ "name": "script", "scopes": ["repo"], "expiresInDays": 366 }),
+    ];
+    for body in test_cases {
+        let response = app.post_access_token(&body).await;
+        assert_eq!(response.status(), 400, "Failed for: {body}");
+    }
+    let body = json!({ "name": "script", "scopes": ["repo"] });
+    assert_eq!(app.post_access_token(&body).await.status(), 422);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_require_a_session() {
+    let mut app = TestApp::new().await;
+
+    assert_eq!(app.get_access_tokens().await.status(), 400);
+    let body = json!({ "name": "script", "scopes": ["repo"], "expiresInDays": 1 });
+    assert_eq!(app.post_access_token(&body).await.status(), 400);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_verify_access_tokens_with_their_scopes() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    app.signup_and_login(&email).await;
+    let token = create_deploy_token(&app).await.token;
+
+    let test_cases = [
+        (json!({ "token": token }), 200),
+        (
+            json!({ "token": token, "requiredScope": "repo:write" }),
+            200,
+        ),
+        (
+            json!({ "token": token, "requiredScope": "admin:read" }),
+            403,
+        ),
+        // they carry no roles
+        (json!({ "token": token, "requiredRole": "user" }), 403),
+        (json!({ "token": format!("{token}x") }), 401),
+        (json!({ "token": "pat_unknown.secret" }), 401),
+    ];
+    for (body, status) in test_cases {
+        let response = app.post_verify_token(&body).await;
+        assert_eq!(response.status(), status, "Failed for: {body}");
+    }
+    let verified = app
+        .post_verify_token(&json!({ "token": token }))
+        .await
+        .json::<VerifyTokenResponse>()
+        .await
+        .expect("Could not deserialize response body to VerifyTokenResponse");
+    assert_eq!(verified.sub, email);
+    assert_eq!(verified.scope.as_deref(), Some("repo:read repo:write"));
+    assert_eq!(verified.roles, Vec::<String>::new());
+    assert!(
+        list_access_tokens(&app).await.tokens[0]
+            .last_used_at
+            .is_some()
+    );
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reject_revoked_tokens_and_tokens_of_disabled_users() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    app.signup_and_login(&email).await;
+    let revoked = create_deploy_token(&app).await;
+    let token = create_deploy_token(&app).await.token;
+
+    app.delete_access_token(&revoked.access_token.id).await;
+    let response = app
+        .post_verify_token(&json!({ "token": revoked.token }))
+        .await;
+    assert_eq!(response.status(), 401);
+
+    app.set_status(&email, AccountStatus::Disabled).await;
+    let response = app.post_verify_token(&json!({ "token": token })).await;
+    assert_eq!(response.status(), 403);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_only_let_the_owner_revoke_a_token() {
+    let mut app = TestApp::new().await;
+    app.signup_and_login(&get_random_email()).await;
+    let created = create_deploy_token(&app).await;
+
+    app.signup_and_login(&get_random_email()).await;
+    assert!(list_access_tokens(&app).await.tokens.is_empty());
+    let response = app.delete_access_token(&created.access_token.id).await;
+    assert_eq!(response.status(), 404);
+    let response = app
+        .post_verify_token(&json!({ "token": created.token }))
+        .await;
+    assert_eq!(response.status(), 200);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_not_accept_access_tokens_as_sessions() {
+    let mut app = TestApp::new().await;
+    app.signup_and_login(&get_random_email()).await;
+    let token = create_deploy_token(&app).await.token;
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/me", app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), 401);
+
+    app.clean_up().await;
+}
@@ -0,0 +1,174 @@
+use crate::helpers::{ADMIN_API_KEY, TestApp, admin_api_key, get_random_email};
+use auth_service::domain::{AuditEventType, AuditOutcome};
+use auth_service::routes::ActivityResponse;
+use reqwest::Method;
+
+async fn login(app: &TestApp, email: &str, password: &str) -> reqwest::Response {
+    let body = serde_json::json!({ "email": email, "password": password });
+    app.post_login(&body).await
+}
+
+async fn get_activity(app: &TestApp, query: &str) -> ActivityResponse {
+    let response = app.get_my_activity(query).await;
+    assert_eq!(response.status(), 200);
+    response
+        .json::<ActivityResponse>()
+        .await
+        .expect("Could not deserialize response body to ActivityResponse")
+}
+
+#[tokio::test]
+async fn should_return_400_if_not_logged_in() {
+    let mut app = TestApp::new().await;
+
+    let response = app.get_my_activity("").await;
+    assert_eq!(response.status(), 400);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_own_events_most_recent_first() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+    assert_eq!(login(&app, &email, "Rw4#nYs8Pe").await.status(), 401);
+    assert_eq!(login(&app, &email, "Kx7!vTq2Lm").await.status(), 200);
+    let body = serde_json::json!({ "displayName": "Ada" });
+    assert_eq!(app.patch_me(&body).await.status(), 200);
+    // someone else's events are not part of the history
+    app.signup(&get_random_email(), false).await;
+
+    let body = get_activity(&app, "").await;
+    let events: Vec<_> = body
+        .events
+        .iter()
+        .map(|event| (event.event_type, event.outcome))
+        .collect();
+    assert_eq!(
+        events,
+        [
+            (AuditEventType::ProfileUpdated, AuditOutcome::Success),
+            (AuditEventType::Login, AuditOutcome::Success),
+            (AuditEventType::Login, AuditOutcome::Failure),
+            (AuditEventType::Signup, AuditOutcome::Success),
+        ]
+    );
+    assert_eq!(body.events[0].ip.as_deref(), Some("127.0.0.1"));
+    assert!(
+        body.events
+            .windows(2)
+            .all(|pair| pair[0].occurred_at >= pair[1].occurred_at)
+    );
+
+    let body = get_activity(&app, "?page=2&perPage=3").await;
+    assert_eq!((body.page, body.per_page), (2, 3));
+    assert_eq!(body.events.len(), 1);
+    assert_eq!(body.events[0].event_type, AuditEventType::Signup);
+
+    for query in ["?page=0", "?perPage=0", "?perPage=101"] {
+        let response = app.get_my_activity(query).await;
+        assert_eq!(response.status(), 400, "Failed for query: {}", query);
+    }
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_record_2fa_and_logout() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    let body = serde_json::json!({
+        "email": email,
+        "password": "Kx7!vTq2Lm",
+        "requires2FA": true
+    });
+    app.post_signup(&body).await;
+    assert_eq!(login(&app, &email, "Kx7!vTq2Lm").await.status(), 206);
+    let body = serde_json::json!({
+        "email": email,
+        "loginAttemptId": uuid::Uuid::new_v4().to_string(),
+        "2FACode": "123456",
+    });
+    assert_eq!(app.post_verify_2fa(&body).await.status(), 401);
+
+    // logged in with another account, to see its history
+    let other_email = get_random_email();
+    app.signup(&other_email, false).await;
+    assert_eq!(login(&app, &other_email, "Kx7!vTq2Lm").await.status(), 200);
+    assert_eq!(app.post_logout().await.status(), 200);
+    assert_eq!(login(&app, &other_email, "Kx7!vTq2Lm").await.status(), 200);
+
+    let body = get_activity(&app, "").await;
+    let events: Vec<_> = body.events.iter().map(|event| event.event_type).collect();
+    assert_eq!(
+        events,
+        [
+            AuditEventType::Login,
+            AuditEventType::Logout,
+            AuditEventType::Login,
+            AuditEventType::Signup,
+        ]
+    );
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_show_admin_actions_without_admin_client() {
+    let mut app = TestApp::with_settings(admin_api_key).await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+
+    let response = app
+        .admin_request(
+            Method::PUT,
+            &format!("/users/{email}/roles"),
+            Some(ADMIN_API_KEY),
+        )
+        .await
+        .json(&serde_json::json!({ "roles": ["support"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(login(&app, &email, "Kx7!vTq2Lm").await.status(), 200);
+
+    let body = get_activity(&app, "").await;
+    let roles_changed = &body.events[1];
+    assert_eq!(roles_changed.event_type, AuditEventType::RolesChanged);
+    assert_eq!(roles_changed.outcome, AuditOutcome::Success);
+    assert_eq!(roles_changed.ip, None);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_take_ip_from_proxy_header_only_if_trusted() {
+    for trusted in [false, true] {
+        let mut app = TestApp::with_settings(|settings| {
+            settings.application.trust_proxy_headers = trusted;
+        })
+        .await;
+        let email = get_random_email();
+        let body = serde_json::json!({
+            "email": email,
+            "password": "Kx7!vTq2Lm",
+            "requires2FA": false
+        });
+        let response = app
+            .http_client
+            .post(format!("{}/signup", app.address))
+            .header("x-real-ip", "203.0.113.7")
+            .header("user-agent", "activity-test")
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 201);
+        assert_eq!(login(&app, &email, "Kx7!vTq2Lm").await.status(), 200);
+
+        let body = get_activity(&app, "").await;
+        let signup = &body.events[1];
+        let expected_ip = if trusted { "203.0.113.7" } else { "127.0.0.1" };
+        assert_eq!(signup.ip.as_deref(), Some(expected_ip));
+        assert_eq!(signup.user_agent.as_deref(), Some("activity-test"));
+        app.clean_up().await;
+    }
+}
@@ -0,0 +1,371 @@
+use crate::helpers::{ADMIN_API_KEY, TestApp, admin_api_key, get_random_email};
+use auth_service::domain::{AccountStatus, TwoFACodeStore, UserKey};
+use auth_service::routes::{AdminUserResponse, TokenResponse, UserListResponse};
+use reqwest::Method;
+
+async fn login(app: &TestApp, email: &str, password: &str) -> reqwest::Response {
+    let body = serde_json::json!({
+        "email": email,
+        "password": password,
+        "tokenDelivery": "body"
+    });
+    app.post_login(&body).await
+}
+
+async fn login_token(app: &TestApp, email: &str) -> String {
+    login(app, email, "Kx7!vTq2Lm")
+        .await
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse")
+        .token
+}
+
+async fn is_valid(app: &TestApp, token: &str) -> bool {
+    let body = serde_json::json!({ "token": token });
+    app.post_verify_token(&body).await.status() == 200
+}
+
+#[tokio::test]
+async fn should_reject_requests_without_admin_credentials() {
+    let mut app = TestApp::with_settings(admin_api_key).await;
+
+    let response = app.admin_request(Method::GET, "/users", None).await;
+    let response = response.send().await.unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = app
+        .admin_request(Method::GET, "/users", Some("wrong-key"))
+        .await;
+    assert_eq!(response.send().await.unwrap().status(), 401);
+
+    // a regular user, authenticated with the cookie
+    let email = get_random_email();
+    app.signup(&email, false).await;
+    let body = serde_json::json!({ "email": email, "password": "Kx7!vTq2Lm" });
+    assert_eq!(app.post_login(&body).await.status(), 200);
+    let response = app.admin_request(Method::GET, "/users", None).await;
+    assert_eq!(response.send().await.unwrap().status(), 403);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_401_for_api_key_if_not_configured() {
+    let mut app = TestApp::new().await;
+
+    let response = app
+        .admin_request(Method::GET, "/users", Some(ADMIN_API_KEY))
+        .await;
+    assert_eq!(response.send().await.unwrap().status(), 401);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_accept_admin_role() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+    app.set_roles(&email, &["admin"]).await;
+    let body = serde_json::json!({ "email": email, "password": "Kx7!vTq2Lm" });
+    assert_eq!(app.post_login(&body).await.status(), 200);
+
+    let response = app.admin_request(Method::GET, "/users", None).await;
+    assert_eq!(response.send().await.unwrap().status(), 200);
+
+    // unsafe methods with the cookie still need the CSRF header
+    let response = app
+        .http_client
+        .post(format!("{}/admin/users/{}/enable", app.address, email))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_list_users_with_pagination_and_search() {
+    let mut app = TestApp::with_settings(admin_api_key).await;
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let emails: Vec<String> = ["carol", "alice", "bob"]
+        .iter()
+        .map(|name| format!("{name}.{suffix}@example.com"))
+        .collect();
+    for email in &emails {
+        app.signup(email, false).await;
+    }
+
+    let response = app
+        .admin_request(
+            Method::GET,
+            &format!("/users?search={suffix}&page=2&perPage=2"),
+            Some(ADMIN_API_KEY),
+        )
+        .await;
+    let response = response.send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body = response
+        .json::<UserListResponse>()
+        .await
+        .expect("Could not deserialize response body to UserListResponse");
+    assert_eq!(body.total, 3);
+    assert_eq!((body.page, body.per_page), (2, 2));
+    assert_eq!(body.users.len(), 1);
+    assert_eq!(body.users[0].user.email, emails[0]);
+    assert_eq!(body.users[0].status, AccountStatus::Active);
+
+    for query in ["page=0", "perPage=0", "perPage=101", "page=abc"] {
+        let response = app
+            .admin_request(Method::GET, &format!("/users?{query}"), Some(ADMIN_API_KEY))
+            .await;
+        let response = response.send().await.unwrap();
+        assert_eq!(response.status(), 400, "Failed for query: {}", query);
+    }
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_user_or_404() {
+    let mut app = TestApp::with_settings(admin_api_key).await;
+    let email = get_random_email();
+    app.signup(&email, true).await;
+
+    let response = app
+        .admin_request(Method::GET, &format!("/users/{email}"), Some(ADMIN_API_KEY))
+        .await;
+    let response = response.send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body = response
+        .json::<AdminUserResponse>()
+        .await
+        .expect("Could not deserialize response body to AdminUserResponse");
+    assert_eq!(body.user.email, email);
+    assert!(body.user.requires_2fa);
+
+    let response = app
+        .admin_request(
+            Method::GET,
+            &format!("/users/{}", get_random_email()),
+            Some(ADMIN_API_KEY),
+        )
+        .await;
+    assert_eq!(response.send().await.unwrap().status(), 404);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_set_roles() {
+    let mut app = TestApp::with_settings(admin_api_key).await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+
+    let response = app
+        .admin_request(
+            Method::PUT,
+            &format!("/users/{email}/roles"),
+            Some(ADMIN_API_KEY),
+        )
+        .await
+        .json(&serde_json::json!({ "roles": ["support", "admin"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body = response
+        .json::<AdminUserResponse>()
+        .await
+        .expect("Could not deserialize response body to AdminUserResponse");
+    assert_eq!(body.user.roles, ["admin", "support"]);
+
+    let response = app
+        .admin_request(
+            Method::PUT,
+            &format!("/users/{email}/roles"),
+            Some(ADMIN_API_KEY),
+        )
+        .await
+        .json(&serde_json::json!({ "roles": ["Not A Role"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_disable_and_enable_user() {
+    let mut app = TestApp::with_settings(admin_api_key).await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+    let token = login_token(&app, &email).await;
+
+    let response = app
+        .admin_request(
+            Method::POST,
+            &format!("/users/{email}/disable"),
+            Some(ADMIN_API_KEY),
+        )
+        .await;
+    let response = response.send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body = response
+        .json::<AdminUserResponse>()
+        .await
+        .expect("Could not deserialize response body to AdminUserResponse");
+    assert_eq!(body.status, AccountStatus::Disabled);
+    assert!(!is_valid(&app, &token).await);
+    assert_eq!(login(&app, &email, "Kx7!vTq2Lm").await.status(), 403);
+    // a wrong password doesn't tell the account is disabled
+    assert_eq!(login(&app, &email, "Rw4#nYs8Pe").await.status(), 401);
+
+    let response = app
+        .admin_request(
+            Method::POST,
+            &format!("/users/{email}/enable"),
+            Some(ADMIN_API_KEY),
+        )
+        .await;
+    assert_eq!(response.send().await.unwrap().status(), 200);
+    assert_eq!(login(&app, &email, "Kx7!vTq2Lm").await.status(), 200);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_set_status() {
+    let mut app = TestApp::with_settings(admin_api_key).await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+    let token = login_token(&app, &email).await;
+
+    let set_status = |status: &'static str| {
+        let app = &app;
+        let email = email.clone();
+        async move {
+            app.admin_request(
+                Method::PUT,
+                &format!("/users/{email}/status"),
+                Some(ADMIN_API_KEY),
+            )
+            .await
+            .json(&serde_json::json!({ "status": status }))
+            .send()
+            .await
+            .unwrap()
+        }
+    };
+
+    let response = set_status("locked").await;
+    assert_eq!(response.status(), 200);
+    let body = response
+        .json::<AdminUserResponse>()
+        .await
+        .expect("Could not deserialize response body to AdminUserResponse");
+    assert_eq!(body.status, AccountStatus::Locked);
+    assert_eq!(login(&app, &email, "Kx7!vTq2Lm").await.status(), 423);
+
+    let response = set_status("active").await;
+    assert_eq!(response.status(), 200);
+    // unlocking doesn't bring back the revoked tokens
+    assert!(!is_valid(&app, &token).await);
+    assert_eq!(login(&app, &email, "Kx7!vTq2Lm").await.status(), 200);
+
+    assert_eq!(set_status("suspended").await.status(), 422);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reset_2fa() {
+    let mut app = TestApp::with_settings(admin_api_key).await;
+    let email = get_random_email();
+    app.signup(&email, true).await;
+    assert_eq!(login(&app, &email, "Kx7!vTq2Lm").await.status(), 206);
+
+    let response = app
+        .admin_request(
+            Method::POST,
+            &format!("/users/{email}/reset-2fa"),
+            Some(ADMIN_API_KEY),
+        )
+        .await;
+    let response = response.send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body = response
+        .json::<AdminUserResponse>()
+        .await
+        .expect("Could not deserialize response body to AdminUserResponse");
+    assert!(!body.user.requires_2fa);
+    // the pending code can't be used anymore
+    let pending_code = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&UserKey::new(None, &email))
+        .await;
+    assert!(pending_code.is_err());
+    assert_eq!(login(&app, &email, "Kx7!vTq2Lm").await.status(), 200);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_revoke_all_tokens() {
+    let mut app = TestApp::with_settings(admin_api_key).await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+    let other_email = get_random_email();
+    app.signup(&other_email, false).await;
+    let tokens = [
+        login_token(&app, &email).await,
+        login_token(&app, &email).await,
+    ];
+    let other_token = login_token(&app, &other_email).await;
+
+    let response = app
+        .admin_request(
+            Method::POST,
+            &format!("/users/{email}/revoke-tokens"),
+            Some(ADMIN_API_KEY),
+        )
+        .await;
+    assert_eq!(response.send().await.unwrap().status(), 200);
+    for token in &tokens {
+        assert!(!is_valid(&app, token).await);
+    }
+    assert!(is_valid(&app, &other_token).await);
+
+    let response = app
+        .admin_request(
+            Method::POST,
+            &format!("/users/{}/revoke-tokens", get_random_email()),
+            Some(ADMIN_API_KEY),
+        )
+        .await;
+    assert_eq!(response.send().await.unwrap().status(), 404);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reset_password() {
+    let mut app = TestApp::with_settings(admin_api_key).await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+    let token = login_token(&app, &email).await;
+
+    let response = app
+        .admin_request(
+            Method::POST,
+            &format!("/users/{email}/password-reset"),
+            Some(ADMIN_API_KEY),
+        )
+        .await;
+    assert_eq!(response.send().await.unwrap().status(), 200);
+    assert!(!is_valid(&app, &token).await);
+    assert_eq!(login(&app, &email, "Kx7!vTq2Lm").await.status(), 401);
+
+    // a link to choose the new password, never a password
+    let sent = app.email_client.sent_emails();
+    let sent = sent.last().expect("No reset email sent");
+    assert_eq!(sent.recipient, email);
+    assert!(sent.content.contains("/#password_reset="));
+    assert!(!sent.content.to_lowercase().contains("password is"));
+    app.clean_up().await;
+}
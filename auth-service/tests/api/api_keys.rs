@@ -0,0 +1,268 @@
+use crate::helpers::{
+    ADMIN_API_KEY, INTROSPECTION_CLIENT, TestApp, admin_api_key, introspection_client, oauth_error,
+};
+use auth_service::routes::{
+    ApiKeyListResponse, CreatedApiKeyResponse, IntrospectResponse, ServiceTokenResponse,
+};
+use reqwest::Method;
+use serde_json::{Value, json};
+
+async fn app() -> TestApp {
+    TestApp::with_settings(|settings| {
+        admin_api_key(settings);
+        introspection_client(settings);
+    })
+    .await
+}
+
+async fn create_api_key(app: &TestApp, body: &Value) -> reqwest::Response {
+    app.admin_request(Method::POST, "/api-keys", Some(ADMIN_API_KEY))
+        .await
+        .json(body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn create_reports_key(app: &TestApp) -> CreatedApiKeyResponse {
+    let body = json!({ "name": "Nightly report", "scopes": ["reports:read", "reports:write"] });
+    let response = create_api_key(app, &body).await;
+    assert_eq!(response.status(), 201);
+    response
+        .json::<CreatedApiKeyResponse>()
+        .await
+        .expect("Could not deserialize response body to CreatedApiKeyResponse")
+}
+
+async fn list_api_keys(app: &TestApp) -> ApiKeyListResponse {
+    let response = app
+        .admin_request(Method::GET, "/api-keys", Some(ADMIN_API_KEY))
+        .await
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), 200);
+    response
+        .json::<ApiKeyListResponse>()
+        .await
+        .expect("Could not deserialize response body to ApiKeyListResponse")
+}
+
+async fn revoke_api_key(app: &TestApp, id: &str) -> reqwest::Response {
+    app.admin_request(
+        Method::DELETE,
+        &format!("/api-keys/{id}"),
+        Some(ADMIN_API_KEY),
+    )
+    .await
+    .send()
+    .await
+    .expect("Failed to execute request.")
+}
+
+async fn service_token(app: &TestApp, created: &CreatedApiKeyResponse) -> ServiceTokenResponse {
+    let form = [("grant_type", "client_credentials")];
+    let credentials = (created.api_key.id.as_str(), created.key.as_str());
+    let response = app.post_token(&form, Some(credentials)).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers().get("cache-control").unwrap(), "no-store");
+    response
+        .json::<ServiceTokenResponse>()
+        .await
+        .expect("Could not deserialize response body to ServiceTokenResponse")
+}
+
+#[tokio::test]
+async fn should_create_list_and_revoke_api_keys() {
+    let mut app = app().await;
+
+    let created = create_reports_key(&app).await;
+    assert!(created.key.starts_with(&format!("{}.", created.api_key.id)));
+    assert_eq!(created.api_key.scopes, ["reports:read", "reports:write"]);
+    assert_eq!(created.api_key.expires_at, None);
+
+    let listed = list_api_keys(&app).await;
+    assert_eq!(listed.api_keys.len(), 1);
+    assert_eq!(listed.api_keys[0].id, created.api_key.id);
+    assert_eq!(listed.api_keys[0].last_used_at, None);
+
+    assert_eq!(
+        revoke_api_key(&app, &created.api_key.id).await.status(),
+        204
+    );
+    assert_eq!(
+        revoke_api_key(&app, &created.api_key.id).await.status(),
+        404
+    );
+    assert!(list_api_keys(&app).await.api_keys.is_empty());
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_400_for_invalid_api_keys() {
+    let mut app = app().await;
+
+    let test_cases = [
+        json!({ "name": " ", "scopes": ["jobs"] }),
+        json!({ "name": "job", "scopes": [] }),
+        json!({ "name": "job", "scopes": ["openid"] }),
+        json!({ "name": "job", "scopes": ["a b/c"] }),
+        json!({ "name": "job", "scopes": ["jobs"], "expiresInDays": 0 }),
+    ];
+    for body in test_cases {
+        let response = create_api_key(&app, &body).await;
+        assert_eq!(response.status(), 400, "Failed for: {body}");
+    }
+
+    let body = json!({ "name": "job", "scopes": ["jobs"], "expiresInDays": 30 });
+    let response = create_api_key(&app, &body).await;
+    assert_eq!(response.status(), 201);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_require_an_admin() {
+    let mut app = app().await;
+
+    let response = app
+        .admin_request(Method::GET, "/api-keys", None)
+        .await
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), 400);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_issue_service_tokens_for_api_keys() {
+    let mut app = app().await;
+    let created = create_reports_key(&app).await;
+
+    let token = service_token(&app, &created).await;
+    assert_eq!(token.token_type, "Bearer");
+    assert_eq!(token.scope, "reports:read reports:write");
+    assert!(list_api_keys(&app).await.api_keys[0].last_used_at.is_some());
+
+    // the credentials can be in the form too, for fewer scopes
+    let form = [
+        ("grant_type", "client_credentials"),
+        ("client_id", created.api_key.id.as_str()),
+        ("client_secret", created.key.as_str()),
+        ("scope", "reports:read"),
+    ];
+    let response = app.post_token(&form, None).await;
+    assert_eq!(response.status(), 200);
+    let token = response
+        .json::<ServiceTokenResponse>()
+        .await
+        .expect("Could not deserialize response body to ServiceTokenResponse");
+    assert_eq!(token.scope, "reports:read");
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reject_invalid_client_credentials() {
+    let mut app = app().await;
+    let created = create_reports_key(&app).await;
+    let id = created.api_key.id.as_str();
+    let form = [("grant_type", "client_credentials")];
+
+    let forged = format!("{id}.forged");
+    for credentials in [
+        None,
+        Some((id, forged.as_str())),
+        Some(("ak_unknown", created.key.as_str())),
+    ] {
+        let response = app.post_token(&form, credentials).await;
+        assert_eq!(response.status(), 401, "Failed for: {credentials:?}");
+        assert_eq!(oauth_error(response).await, "invalid_client");
+    }
+
+    let form = [
+        ("grant_type", "client_credentials"),
+        ("scope", "reports:delete"),
+    ];
+    let response = app.post_token(&form, Some((id, &created.key))).await;
+    assert_eq!(response.status(), 400);
+    assert_eq!(oauth_error(response).await, "invalid_scope");
+
+    revoke_api_key(&app, id).await;
+    let form = [("grant_type", "client_credentials")];
+    let response = app.post_token(&form, Some((id, &created.key))).await;
+    assert_eq!(response.status(), 401);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_verify_service_tokens_until_the_key_is_revoked() {
+    let mut app = app().await;
+    let created = create_reports_key(&app).await;
+    let token = service_token(&app, &created).await.access_token;
+
+    let test_cases = [
+        (json!({ "token": token }), 200),
+        (
+            json!({ "token": token, "requiredScope": "reports:write" }),
+            200,
+        ),
+        (
+            json!({ "token": token, "requiredScope": "users:read" }),
+            403,
+        ),
+        (json!({ "token": token, "requiredRole": "admin" }), 403),
+    ];
+    for (body, status) in test_cases {
+        let response = app.post_verify_token(&body).await;
+        assert_eq!(response.status(), status, "Failed for: {body}");
+    }
+    let introspected = app
+        .post_introspect(&token, Some(INTROSPECTION_CLIENT))
+        .await
+        .json::<IntrospectResponse>()
+        .await
+        .expect("Could not deserialize response body to IntrospectResponse");
+    assert!(introspected.active);
+    assert_eq!(introspected.client_id, Some(created.api_key.id.clone()));
+    assert_eq!(
+        introspected.scope.as_deref(),
+        Some("reports:read reports:write")
+    );
+
+    revoke_api_key(&app, &created.api_key.id).await;
+    let response = app.post_verify_token(&json!({ "token": token })).await;
+    assert_eq!(response.status(), 401);
+    let introspected = app
+        .post_introspect(&token, Some(INTROSPECTION_CLIENT))
+        .await
+        .json::<IntrospectResponse>()
+        .await
+        .expect("Could not deserialize response body to IntrospectResponse");
+    assert!(!introspected.active);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_not_accept_service_tokens_as_user_sessions() {
+    let mut app = app().await;
+    let created = create_reports_key(&app).await;
+    let token = service_token(&app, &created).await.access_token;
+
+    for path in ["/me", "/userinfo"] {
+        let response = reqwest::Client::new()
+            .get(format!("{}{path}", app.address))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(response.status(), 401, "Failed for: {path}");
+    }
+
+    app.clean_up().await;
+}
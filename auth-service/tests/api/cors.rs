@@ -0,0 +1,126 @@
+use crate::helpers::TestApp;
+
+const ALLOW_ORIGIN: &str = "access-control-allow-origin";
+const ALLOW_CREDENTIALS: &str = "access-control-allow-credentials";
+const ALLOW_METHODS: &str = "access-control-allow-methods";
+const ALLOW_HEADERS: &str = "access-control-allow-headers";
+
+async fn get_app() -> TestApp {
+    TestApp::with_settings(|settings| {
+        settings.cors.allowed_origins = vec![
+            "https://bootcamp.gabuzando.dev".to_owned(),
+            "https://*.gabuzando.dev".to_owned(),
+        ];
+        settings.cors.allowed_methods = vec!["GET".to_owned(), "POST".to_owned()];
+        settings.cors.allowed_headers = vec!["content-type".to_owned()];
+        settings.cors.allow_credentials = true;
+    })
+    .await
+}
+
+#[tokio::test]
+async fn preflight_from_allowed_origin_is_accepted() {
+    let mut app = get_app().await;
+    let test_cases = [
+        // exact origin
+        "https://bootcamp.gabuzando.dev",
+        // wildcard subdomain
+        "https://app.gabuzando.dev",
+    ];
+    for origin in test_cases {
+        let response = app.preflight("/login", origin, "POST").await;
+        assert_eq!(response.status(), 200, "Failed for origin: {}", origin);
+        let headers = response.headers();
+        assert_eq!(headers.get(ALLOW_ORIGIN).unwrap(), origin);
+        assert_eq!(headers.get(ALLOW_CREDENTIALS).unwrap(), "true");
+        assert_eq!(headers.get(ALLOW_METHODS).unwrap(), "GET,POST");
+        assert_eq!(headers.get(ALLOW_HEADERS).unwrap(), "content-type");
+    }
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn preflight_from_rejected_origin_has_no_allow_origin() {
+    let mut app = get_app().await;
+    let test_cases = [
+        "https://evil.dev",
+        // apex domain is not covered by the wildcard
+        "https://gabuzando.dev",
+        // scheme must match
+        "http://bootcamp.gabuzando.dev",
+        "https://app.gabuzando.dev.evil.dev",
+    ];
+    for origin in test_cases {
+        let response = app.preflight("/login", origin, "POST").await;
+        assert!(
+            response.headers().get(ALLOW_ORIGIN).is_none(),
+            "Failed for origin: {}",
+            origin
+        );
+    }
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn credentials_are_not_allowed_when_disabled() {
+    let mut app = TestApp::with_settings(|settings| {
+        settings.cors.allowed_origins = vec!["https://bootcamp.gabuzando.dev".to_owned()];
+        settings.cors.allow_credentials = false;
+    })
+    .await;
+    let response = app
+        .preflight("/login", "https://bootcamp.gabuzando.dev", "POST")
+        .await;
+    let headers = response.headers();
+    assert_eq!(
+        headers.get(ALLOW_ORIGIN).unwrap(),
+        "https://bootcamp.gabuzando.dev"
+    );
+    assert!(headers.get(ALLOW_CREDENTIALS).is_none());
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn bearer_tokens_are_allowed_by_default() {
+    let mut app = TestApp::with_settings(|settings| {
+        settings.cors.allowed_origins = vec!["https://bootcamp.gabuzando.dev".to_owned()];
+    })
+    .await;
+    let response = app
+        .http_client
+        .request(reqwest::Method::OPTIONS, format!("{}/me", app.address))
+        .header("Origin", "https://bootcamp.gabuzando.dev")
+        .header("Access-Control-Request-Method", "GET")
+        .header("Access-Control-Request-Headers", "authorization")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let allowed = response.headers().get(ALLOW_HEADERS).unwrap();
+    assert!(allowed.to_str().unwrap().contains("authorization"));
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn put_routes_are_allowed_by_default() {
+    let mut app = TestApp::with_settings(|settings| {
+        settings.cors.allowed_origins = vec!["https://bootcamp.gabuzando.dev".to_owned()];
+    })
+    .await;
+    // e.g. the roles of a user and the settings of an organization
+    for path in ["/admin/users/user@example.com/roles", "/orgs/acme/settings"] {
+        let response = app
+            .preflight(path, "https://bootcamp.gabuzando.dev", "PUT")
+            .await;
+        assert_eq!(response.status(), 200, "Failed for: {path}");
+        let allowed = response.headers().get(ALLOW_METHODS).unwrap();
+        assert!(
+            allowed
+                .to_str()
+                .unwrap()
+                .split(',')
+                .any(|method| method == "PUT"),
+            "Failed for: {path}"
+        );
+    }
+    app.clean_up().await;
+}
@@ -0,0 +1,108 @@
+use crate::helpers::{TestApp, get_random_email, get_set_cookie_header};
+use auth_service::{
+    domain::{BannedTokenStore, ErrorResponse},
+    routes::CsrfTokenResponse,
+    utils::constants::{CSRF_COOKIE_NAME, JWT_COOKIE_NAME},
+};
+
+// Signs up and logs in without 2FA, returns the issued JWT.
+async fn login(app: &TestApp) -> String {
+    let email = get_random_email();
+    let body_signup = serde_json::json!({
+        "email": email,
+        "password": "Kx7!vTq2Lm",
+        "requires2FA": false
+    });
+    let _ = app.post_signup(&body_signup).await;
+    let body_login = serde_json::json!({
+        "email": email,
+        "password": "Kx7!vTq2Lm"
+    });
+    let response = app.post_login(&body_login).await;
+    assert_eq!(response.status(), 200);
+    response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found")
+        .value()
+        .to_owned()
+}
+
+#[tokio::test]
+async fn should_issue_csrf_cookie_and_token() {
+    let mut app = TestApp::new().await;
+
+    let response = app.get_csrf_token().await;
+    assert_eq!(response.status(), 200);
+    let set_cookie = get_set_cookie_header(&response, CSRF_COOKIE_NAME);
+    assert!(!set_cookie.contains("HttpOnly"), "{}", set_cookie);
+    let cookie_token = response
+        .cookies()
+        .find(|cookie| cookie.name() == CSRF_COOKIE_NAME)
+        .expect("No CSRF cookie found")
+        .value()
+        .to_owned();
+    let body = response
+        .json::<CsrfTokenResponse>()
+        .await
+        .expect("Could not deserialize response body to CsrfTokenResponse");
+    assert_eq!(body.csrf_token, cookie_token);
+
+    // the token is stable for the lifetime of the cookie
+    let response = app.get_csrf_token().await;
+    assert!(response.headers().get("set-cookie").is_none());
+    let body = response
+        .json::<CsrfTokenResponse>()
+        .await
+        .expect("Could not deserialize response body to CsrfTokenResponse");
+    assert_eq!(body.csrf_token, cookie_token);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_403_if_csrf_header_missing() {
+    let mut app = TestApp::new().await;
+    let token = login(&app).await;
+
+    let response = app.post_logout_with_csrf_header(None).await;
+    assert_eq!(response.status(), 403);
+    assert_eq!(
+        response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse")
+            .error,
+        "Invalid CSRF token".to_owned()
+    );
+
+    // the forged request must not have logged the user out
+    let banned_token_store = app.banned_token_store.read().await.clone();
+    assert!(!banned_token_store.contains_token(&token).await.unwrap());
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_403_if_csrf_header_does_not_match_cookie() {
+    let mut app = TestApp::new().await;
+    let _ = login(&app).await;
+    let _ = app.get_csrf_token().await;
+
+    let response = app
+        .post_logout_with_csrf_header(Some("forged-csrf-token"))
+        .await;
+    assert_eq!(response.status(), 403);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_200_if_csrf_header_matches_cookie() {
+    let mut app = TestApp::new().await;
+    let token = login(&app).await;
+
+    let response = app.post_logout().await;
+    assert_eq!(response.status(), 200);
+
+    let banned_token_store = app.banned_token_store.read().await.clone();
+    assert!(banned_token_store.contains_token(&token).await.unwrap());
+    app.clean_up().await;
+}
@@ -1,22 +1,19 @@
 use auth_service::Application;
-use auth_service::app_state::AppState;
-// use auth_service::services::data_stores::hashmap_two_fa_code_store::HashMapTwoFACodeStore;
-use auth_service::services::data_stores::postgres_user_store::PostgresUserStore;
-use auth_service::services::data_stores::redis_two_fa_code_store::RedisTwoFACodeStore;
-// use auth_service::services::data_stores::hashmap_user_store::HashmapUserStore;
-use auth_service::get_postgres_pool;
-use auth_service::get_redis_client;
-use auth_service::utils::constants::REDIS_HOST_NAME;
-// use auth_service::services::data_stores::hashset_banned_token_store::HashSetBannedTokenStore;
-use auth_service::services::data_stores::redis_banned_token_store::RedisBannedTokenStore;
+use auth_service::app_state::AppStateBuilder;
+use auth_service::domain::{AccountStatus, OAuthErrorResponse, Role, UserKey, UserStore};
+use auth_service::routes::CsrfTokenResponse;
+use auth_service::services::data_stores::store_backends::{
+    AnyBannedTokenStore, AnyTwoFACodeStore, AnyUserStore, UserStoreBackend,
+};
 use auth_service::services::mock_mail_client::MockEmailClient;
-use auth_service::utils::constants::DATABASE_URL;
+use auth_service::settings::{CookieSettings, SameSitePolicy, Settings};
 use auth_service::utils::constants::test::APP_ADDRESS;
+use auth_service::utils::constants::{ADMIN_API_KEY_HEADER_NAME, CSRF_HEADER_NAME};
+use reqwest::Url;
 use reqwest::cookie::Jar;
 use sqlx::Connection;
 use sqlx::Executor;
 use sqlx::PgConnection;
-use sqlx::PgPool;
 use sqlx::postgres::PgPoolOptions;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -32,44 +29,22 @@ async fn configure_database(db_conn_string: &str, db_name: &str) {
         .execute(format!(r#"CREATE DATABASE "{}";"#, db_name).as_str())
         .await
         .expect("Failed to create database.");
-
-    // Connect to new database
-    let db_conn_string = format!("{}/{}", db_conn_string, db_name);
-
-    let connection = PgPoolOptions::new()
-        .connect(&db_conn_string)
-        .await
-        .expect("Failed to create Postgres connection pool.");
-
-    // Run migrations against new database
-    sqlx::migrate!()
-        .run(&connection)
-        .await
-        .expect("Failed to migrate the database");
 }
 
-async fn configure_postgresql() -> (String, PgPool) {
-    let postgresql_conn_url = DATABASE_URL.to_owned();
-
+// Creates a fresh database and returns its name and connection string, the
+// migrations are run by `AppStateBuilder::build`.
+async fn configure_postgresql(postgresql_conn_url: &str) -> (String, String) {
     let db_name = Uuid::new_v4().to_string();
 
-    configure_database(&postgresql_conn_url, &db_name).await;
+    configure_database(postgresql_conn_url, &db_name).await;
 
     let postgresql_conn_url_with_db = format!("{}/{}", postgresql_conn_url, db_name);
 
-    // Create a new connection pool and return it
-    (
-        db_name,
-        get_postgres_pool(&postgresql_conn_url_with_db)
-            .await
-            .expect("Failed to create Postgres connection pool!"),
-    )
+    (db_name, postgresql_conn_url_with_db)
 }
 
-async fn delete_database(db_name: &str) {
-    let postgresql_conn_url: String = DATABASE_URL.to_owned();
-
-    let mut connection = PgConnection::connect(&postgresql_conn_url)
+async fn delete_database(postgresql_conn_url: &str, db_name: &str) {
+    let mut connection = PgConnection::connect(postgresql_conn_url)
         .await
         .expect("Failed to connect to Postgres");
 
@@ -97,44 +72,52 @@ async fn delete_database(db_name: &str) {
         .expect("Failed to drop the database.");
 }
 
-fn configure_redis() -> redis::Connection {
-    get_redis_client(REDIS_HOST_NAME.to_owned())
-        .expect("Failed to get Redis client")
-        .get_connection()
-        .expect("Failed to get Redis connection")
-}
-
 pub struct TestApp {
     pub address: String,
     pub cookie_jar: Arc<Jar>,
     pub http_client: reqwest::Client,
-    pub banned_token_store: Arc<RwLock<RedisBannedTokenStore>>,
-    pub two_fa_code_store: Arc<RwLock<RedisTwoFACodeStore>>,
-    db_name: String,
+    pub user_store: Arc<RwLock<AnyUserStore>>,
+    pub banned_token_store: Arc<RwLock<AnyBannedTokenStore>>,
+    pub two_fa_code_store: Arc<RwLock<AnyTwoFACodeStore>>,
+    pub email_client: Arc<MockEmailClient>,
+    database_url: String,
+    db_name: Option<String>,
     clean_up_called: bool,
 }
 
 impl TestApp {
     pub async fn new() -> Self {
-        let (db_name, pg_pool) = configure_postgresql().await;
-        let user_store = Arc::new(RwLock::new(PostgresUserStore::new(pg_pool)));
-        let redis_conn = configure_redis();
-        let redis_conn = Arc::new(RwLock::new(redis_conn));
-        let banned_token_store = RedisBannedTokenStore::new(redis_conn.clone());
-        let banned_token_store = Arc::new(RwLock::new(banned_token_store));
-        let two_fa_code_store = RedisTwoFACodeStore::new(redis_conn.clone());
-        let two_fa_code_store = Arc::new(RwLock::new(two_fa_code_store));
-        let email_client = MockEmailClient::default();
-        let email_client = Arc::new(email_client);
-        let app_state = AppState::new(
-            user_store,
-            // this is because we need access at testing, and it also goes to Self
-            banned_token_store.clone(),
-            // this is because we need access at testing, and it also goes to Self
-            two_fa_code_store.clone(),
-            email_client,
-        );
-        let app = Application::build(app_state, APP_ADDRESS)
+        Self::with_settings(|_| {}).await
+    }
+
+    // For tests that need a specific configuration, e.g. CORS origins.
+    pub async fn with_settings(customize: impl FnOnce(&mut Settings)) -> Self {
+        // the settings are loaded like the binary does, only the address and
+        // the database are replaced so tests can run in parallel
+        let mut settings = Settings::load().expect("Failed to load settings");
+        customize(&mut settings);
+        settings.application.address = APP_ADDRESS.to_owned();
+        let database_url = settings.database.url.clone();
+        let db_name = match settings.stores.user_store {
+            UserStoreBackend::Postgres => {
+                let (db_name, db_url) = configure_postgresql(&database_url).await;
+                settings.database.url = db_url;
+                Some(db_name)
+            }
+            UserStoreBackend::Memory => None,
+        };
+        let app_state = AppStateBuilder::new(settings)
+            .build()
+            .await
+            .expect("Failed to build app state");
+        // this is because we need access at testing, and it also goes to Self
+        let user_store = app_state.user_store.clone();
+        // this is because we need access at testing, and it also goes to Self
+        let banned_token_store = app_state.banned_token_store.clone();
+        // this is because we need access at testing, and it also goes to Self
+        let two_fa_code_store = app_state.two_fa_code_store.clone();
+        let email_client = app_state.email_client.clone();
+        let app = Application::build(app_state)
             .await
             .expect("Failed to build app");
         let address = format!("http://{}", app.address.clone());
@@ -150,13 +133,41 @@ impl TestApp {
             address,
             cookie_jar,
             http_client,
-            banned_token_store: banned_token_store.clone(),
-            two_fa_code_store: two_fa_code_store.clone(),
+            user_store,
+            banned_token_store,
+            two_fa_code_store,
+            email_client,
+            database_url,
             db_name,
             clean_up_called: false,
         }
     }
 
+    // Sets roles in the store directly, for tests not about the admin routes.
+    pub async fn set_roles(&self, email: &str, roles: &[&str]) {
+        let roles = roles
+            .iter()
+            .map(|role| Role::parse(role).expect("Invalid role"))
+            .collect();
+        self.user_store
+            .write()
+            .await
+            .set_roles(&UserKey::new(None, email), roles)
+            .await
+            .expect("Failed to set roles");
+    }
+
+    /// Sets the status in the store directly, bypassing the token revocation
+    /// of the admin routes.
+    pub async fn set_status(&self, email: &str, status: AccountStatus) {
+        self.user_store
+            .write()
+            .await
+            .set_status(&UserKey::new(None, email), status)
+            .await
+            .expect("Failed to set status");
+    }
+
     pub async fn get_root(&self) -> reqwest::Response {
         self.http_client
             .get(format!("{}/", self.address))
@@ -189,6 +200,72 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    pub async fn post_magic_link<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/login/magic-link", self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_password_reset<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/password-reset", self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_magic_link_callback(&self, query: &str) -> reqwest::Response {
+        self.get_without_redirect(&format!("/login/magic-link/callback{query}"))
+            .await
+    }
+
+    // Logs in from another device, a client with its own cookies.
+    pub async fn post_login_from<Body>(
+        &self,
+        http_client: &reqwest::Client,
+        body: &Body,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        http_client
+            .post(format!("{}/login", self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_revoke_sessions(&self, query: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/revoke-sessions{}", self.address, query))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_revoke_sessions<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/revoke-sessions", self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn post_verify_2fa<Body>(&self, body: &Body) -> reqwest::Response
     where
         Body: serde::Serialize,
@@ -201,9 +278,260 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    pub async fn get_csrf_token(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/csrf-token", self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    // Logs out like the front-end does, echoing the CSRF cookie in the header.
     pub async fn post_logout(&self) -> reqwest::Response {
+        let csrf_token = self
+            .get_csrf_token()
+            .await
+            .json::<CsrfTokenResponse>()
+            .await
+            .expect("Could not deserialize response body to CsrfTokenResponse")
+            .csrf_token;
+        self.post_logout_with_csrf_header(Some(&csrf_token)).await
+    }
+
+    pub async fn post_logout_with_csrf_header(
+        &self,
+        csrf_token: Option<&str>,
+    ) -> reqwest::Response {
+        let mut request = self.http_client.post(format!("{}/logout", self.address));
+        if let Some(csrf_token) = csrf_token {
+            request = request.header(CSRF_HEADER_NAME, csrf_token);
+        }
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn post_logout_with_bearer(&self, token: &str) -> reqwest::Response {
         self.http_client
             .post(format!("{}/logout", self.address))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_me(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/me", self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_my_activity(&self, query: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/me/activity{}", self.address, query))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    // A request to `/admin{path}`, with the API key when given and the
+    // cookies of the client otherwise. The CSRF header is always set.
+    pub async fn admin_request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        api_key: Option<&str>,
+    ) -> reqwest::RequestBuilder {
+        let csrf_token = self
+            .get_csrf_token()
+            .await
+            .json::<CsrfTokenResponse>()
+            .await
+            .expect("Could not deserialize response body to CsrfTokenResponse")
+            .csrf_token;
+        let request = self
+            .http_client
+            .request(method, format!("{}/admin{}", self.address, path))
+            .header(CSRF_HEADER_NAME, csrf_token);
+        match api_key {
+            Some(api_key) => request.header(ADMIN_API_KEY_HEADER_NAME, api_key),
+            None => request,
+        }
+    }
+
+    // Like the front-end, echoing the CSRF cookie in the header.
+    pub async fn patch_me<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let csrf_token = self
+            .get_csrf_token()
+            .await
+            .json::<CsrfTokenResponse>()
+            .await
+            .expect("Could not deserialize response body to CsrfTokenResponse")
+            .csrf_token;
+        self.http_client
+            .patch(format!("{}/me", self.address))
+            .header(CSRF_HEADER_NAME, csrf_token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_trusted_devices(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/me/trusted-devices", self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    // Forgets one trusted device, or all of them without an id.
+    pub async fn delete_trusted_devices(&self, id: Option<&str>) -> reqwest::Response {
+        let csrf_token = self
+            .get_csrf_token()
+            .await
+            .json::<CsrfTokenResponse>()
+            .await
+            .expect("Could not deserialize response body to CsrfTokenResponse")
+            .csrf_token;
+        let path = match id {
+            Some(id) => format!("/me/trusted-devices/{id}"),
+            None => "/me/trusted-devices".to_owned(),
+        };
+        self.http_client
+            .delete(format!("{}{}", self.address, path))
+            .header(CSRF_HEADER_NAME, csrf_token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_access_tokens(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/tokens", self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_access_token<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let csrf_token = self
+            .get_csrf_token()
+            .await
+            .json::<CsrfTokenResponse>()
+            .await
+            .expect("Could not deserialize response body to CsrfTokenResponse")
+            .csrf_token;
+        self.http_client
+            .post(format!("{}/tokens", self.address))
+            .header(CSRF_HEADER_NAME, csrf_token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_access_token(&self, id: &str) -> reqwest::Response {
+        let csrf_token = self
+            .get_csrf_token()
+            .await
+            .json::<CsrfTokenResponse>()
+            .await
+            .expect("Could not deserialize response body to CsrfTokenResponse")
+            .csrf_token;
+        self.http_client
+            .delete(format!("{}/tokens/{id}", self.address))
+            .header(CSRF_HEADER_NAME, csrf_token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_organization<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let csrf_token = self
+            .get_csrf_token()
+            .await
+            .json::<CsrfTokenResponse>()
+            .await
+            .expect("Could not deserialize response body to CsrfTokenResponse")
+            .csrf_token;
+        self.http_client
+            .post(format!("{}/orgs", self.address))
+            .header(CSRF_HEADER_NAME, csrf_token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_organization(&self, id: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/orgs/{id}", self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_organization_settings<Body>(&self, id: &str, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let csrf_token = self
+            .get_csrf_token()
+            .await
+            .json::<CsrfTokenResponse>()
+            .await
+            .expect("Could not deserialize response body to CsrfTokenResponse")
+            .csrf_token;
+        self.http_client
+            .put(format!("{}/orgs/{id}/settings", self.address))
+            .header(CSRF_HEADER_NAME, csrf_token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_organization_invitation<Body>(
+        &self,
+        id: &str,
+        body: &Body,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let csrf_token = self
+            .get_csrf_token()
+            .await
+            .json::<CsrfTokenResponse>()
+            .await
+            .expect("Could not deserialize response body to CsrfTokenResponse")
+            .csrf_token;
+        self.http_client
+            .post(format!("{}/orgs/{id}/invitations", self.address))
+            .header(CSRF_HEADER_NAME, csrf_token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_accept_invitation<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/orgs/invitations/accept", self.address))
+            .json(body)
             .send()
             .await
             .expect("Failed to execute request.")
@@ -221,8 +549,137 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    pub async fn post_introspect(
+        &self,
+        token: &str,
+        credentials: Option<(&str, &str)>,
+    ) -> reqwest::Response {
+        let mut request = self
+            .http_client
+            .post(format!("{}/introspect", self.address))
+            .form(&[("token", token)]);
+        if let Some((client_id, client_secret)) = credentials {
+            request = request.basic_auth(client_id, Some(client_secret));
+        }
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    // Without following redirects, to check where a flow sends the browser.
+    async fn get_without_redirect(&self, path: &str) -> reqwest::Response {
+        reqwest::Client::builder()
+            .cookie_provider(self.cookie_jar.clone())
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap()
+            .get(format!("{}{}", self.address, path))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_authorize(&self, query: &str) -> reqwest::Response {
+        self.get_without_redirect(&format!("/authorize{query}"))
+            .await
+    }
+
+    pub async fn get_social_login(&self, provider: &str, query: &str) -> reqwest::Response {
+        self.get_without_redirect(&format!("/login/{provider}{query}"))
+            .await
+    }
+
+    pub async fn get_social_callback(&self, provider: &str, query: &str) -> reqwest::Response {
+        self.get_without_redirect(&format!("/callback/{provider}{query}"))
+            .await
+    }
+
+    // Answers the consent page like the front-end does.
+    pub async fn post_authorize<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let csrf_token = self
+            .get_csrf_token()
+            .await
+            .json::<CsrfTokenResponse>()
+            .await
+            .expect("Could not deserialize response body to CsrfTokenResponse")
+            .csrf_token;
+        self.http_client
+            .post(format!("{}/authorize", self.address))
+            .header(CSRF_HEADER_NAME, csrf_token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    // Called by the OAuth client, which has none of the user's cookies.
+    pub async fn post_token(
+        &self,
+        form: &[(&str, &str)],
+        credentials: Option<(&str, &str)>,
+    ) -> reqwest::Response {
+        let mut request = reqwest::Client::new()
+            .post(format!("{}/token", self.address))
+            .form(form);
+        if let Some((client_id, client_secret)) = credentials {
+            request = request.basic_auth(client_id, Some(client_secret));
+        }
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn get_userinfo(&self, access_token: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("{}/userinfo", self.address))
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_well_known(&self, name: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/.well-known/{}", self.address, name))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn preflight(&self, path: &str, origin: &str, method: &str) -> reqwest::Response {
+        self.http_client
+            .request(
+                reqwest::Method::OPTIONS,
+                format!("{}{}", self.address, path),
+            )
+            .header("Origin", origin)
+            .header("Access-Control-Request-Method", method)
+            .header("Access-Control-Request-Headers", "content-type")
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    // Signs up `email` with the password of the fixtures.
+    pub async fn signup(&self, email: &str, requires_2fa: bool) {
+        let body = serde_json::json!({
+            "email": email,
+            "password": "Kx7!vTq2Lm",
+            "requires2FA": requires_2fa
+        });
+        assert_eq!(self.post_signup(&body).await.status(), 201);
+    }
+
+    // Signs up `email` without 2FA and logs in, the client keeps the cookie.
+    pub async fn signup_and_login(&self, email: &str) {
+        self.signup(email, false).await;
+        let body = serde_json::json!({ "email": email, "password": "Kx7!vTq2Lm" });
+        assert_eq!(self.post_login(&body).await.status(), 200);
+    }
+
     pub async fn clean_up(&mut self) {
-        delete_database(&self.db_name).await;
+        if let Some(db_name) = &self.db_name {
+            delete_database(&self.database_url, db_name).await;
+        }
         self.clean_up_called = true;
     }
 }
@@ -238,3 +695,58 @@ impl Drop for TestApp {
 pub fn get_random_email() -> String {
     format!("{}@example.com", Uuid::new_v4())
 }
+
+// Strictest cookie policy, used to check the Set-Cookie attributes end to end.
+pub fn host_cookie_policy(settings: &mut Settings) {
+    settings.auth.cookie = CookieSettings {
+        secure: true,
+        domain: String::new(),
+        same_site: SameSitePolicy::Strict,
+        name_prefix: "__Host-".to_owned(),
+    };
+}
+
+pub const ADMIN_API_KEY: &str = "an-admin-api-key-only-for-the-tests";
+
+pub fn admin_api_key(settings: &mut Settings) {
+    settings.admin.api_key = ADMIN_API_KEY.to_owned();
+}
+
+pub const INTROSPECTION_CLIENT: (&str, &str) = ("app_service", "app-service-secret");
+
+pub fn introspection_client(settings: &mut Settings) {
+    let (client_id, client_secret) = INTROSPECTION_CLIENT;
+    settings
+        .introspection
+        .clients
+        .insert(client_id.to_owned(), client_secret.to_owned());
+}
+
+pub fn get_set_cookie_header(response: &reqwest::Response, cookie_name: &str) -> String {
+    response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .find(|value| value.starts_with(&format!("{}=", cookie_name)))
+        .expect("No Set-Cookie header found")
+        .to_owned()
+}
+
+pub fn location(response: &reqwest::Response) -> Url {
+    let location = response
+        .headers()
+        .get("location")
+        .expect("No Location header")
+        .to_str()
+        .unwrap();
+    Url::parse(location).expect("Invalid Location header")
+}
+
+pub async fn oauth_error(response: reqwest::Response) -> String {
+    response
+        .json::<OAuthErrorResponse>()
+        .await
+        .expect("Could not deserialize response body to OAuthErrorResponse")
+        .error
+}
@@ -0,0 +1,137 @@
+use crate::helpers::{INTROSPECTION_CLIENT, TestApp, get_random_email, introspection_client};
+use auth_service::domain::AccountStatus;
+use auth_service::domain::error::ErrorResponse;
+use auth_service::routes::{IntrospectResponse, TokenResponse};
+
+async fn login(app: &TestApp, email: &str) -> String {
+    let body_signup = serde_json::json!({
+        "email": email,
+        "password": "Kx7!vTq2Lm",
+        "requires2FA": false
+    });
+    let _ = app.post_signup(&body_signup).await;
+    let body_login = serde_json::json!({
+        "email": email,
+        "password": "Kx7!vTq2Lm",
+        "tokenDelivery": "body"
+    });
+    app.post_login(&body_login)
+        .await
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse")
+        .token
+}
+
+#[tokio::test]
+async fn should_return_401_without_valid_client_credentials() {
+    let mut app = TestApp::with_settings(introspection_client).await;
+    let token = login(&app, &get_random_email()).await;
+
+    let (client_id, _) = INTROSPECTION_CLIENT;
+    let test_cases = [
+        None,
+        Some((client_id, "wrong-secret")),
+        Some(("unknown", "app-service-secret")),
+    ];
+    for credentials in test_cases {
+        let response = app.post_introspect(&token, credentials).await;
+        assert_eq!(response.status(), 401, "Failed for: {:?}", credentials);
+        assert!(response.headers().get("www-authenticate").is_some());
+    }
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_claims_for_active_token() {
+    let mut app = TestApp::with_settings(introspection_client).await;
+    let email = get_random_email();
+    let token = login(&app, &email).await;
+
+    let response = app
+        .post_introspect(&token, Some(INTROSPECTION_CLIENT))
+        .await;
+    assert_eq!(response.status(), 200);
+    let body = response
+        .json::<IntrospectResponse>()
+        .await
+        .expect("Could not deserialize response body to IntrospectResponse");
+    assert!(body.active);
+    assert_eq!(body.sub, Some(email));
+    assert_eq!(body.token_type.as_deref(), Some("Bearer"));
+    let (iat, exp) = (body.iat.unwrap(), body.exp.unwrap());
+    assert_eq!(exp - iat, 600);
+    assert!(body.jti.is_some_and(|jti| !jti.is_empty()));
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_roles_of_token() {
+    let mut app = TestApp::with_settings(introspection_client).await;
+    let email = get_random_email();
+    let _ = login(&app, &email).await;
+    app.set_roles(&email, &["support", "admin"]).await;
+    let token = login(&app, &email).await;
+
+    let body = app
+        .post_introspect(&token, Some(INTROSPECTION_CLIENT))
+        .await
+        .json::<IntrospectResponse>()
+        .await
+        .expect("Could not deserialize response body to IntrospectResponse");
+    assert!(body.active);
+    assert_eq!(body.roles, ["admin", "support"]);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_inactive_for_invalid_or_logged_out_token() {
+    let mut app = TestApp::with_settings(introspection_client).await;
+    let token = login(&app, &get_random_email()).await;
+    let _ = app.post_logout_with_bearer(&token).await;
+
+    for token in [token.as_str(), "invalid"] {
+        let response = app.post_introspect(token, Some(INTROSPECTION_CLIENT)).await;
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body, serde_json::json!({ "active": false }));
+    }
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_inactive_for_locked_account() {
+    let mut app = TestApp::with_settings(introspection_client).await;
+    let email = get_random_email();
+    let token = login(&app, &email).await;
+    app.set_status(&email, AccountStatus::Locked).await;
+
+    let response = app
+        .post_introspect(&token, Some(INTROSPECTION_CLIENT))
+        .await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body, serde_json::json!({ "active": false }));
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_422_if_token_missing() {
+    let mut app = TestApp::with_settings(introspection_client).await;
+
+    let response = app
+        .http_client
+        .post(format!("{}/introspect", app.address))
+        .basic_auth(INTROSPECTION_CLIENT.0, Some(INTROSPECTION_CLIENT.1))
+        .form(&[("token_type_hint", "access_token")])
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), 422);
+    let error = response
+        .json::<ErrorResponse>()
+        .await
+        .expect("Could not deserialize response body to ErrorResponse");
+    assert_eq!(error.errors[0].field, "token");
+    app.clean_up().await;
+}
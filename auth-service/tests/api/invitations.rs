@@ -0,0 +1,184 @@
+use crate::helpers::{ADMIN_API_KEY, TestApp, admin_api_key, get_random_email};
+use auth_service::routes::{InvitationResponse, MeResponse};
+use auth_service::settings::Settings;
+use reqwest::{Method, Url};
+use serde_json::{Value, json};
+
+const PUBLIC_URL: &str = "https://auth.example.com";
+
+async fn app(open_registration: bool) -> TestApp {
+    TestApp::with_settings(|settings: &mut Settings| {
+        admin_api_key(settings);
+        settings.application.public_url = PUBLIC_URL.to_owned();
+        settings.auth.open_registration = open_registration;
+    })
+    .await
+}
+
+async fn create_invitation(app: &TestApp, body: &Value) -> reqwest::Response {
+    app.admin_request(Method::POST, "/invitations", Some(ADMIN_API_KEY))
+        .await
+        .json(body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+// Invites `email`, returning the token of the emailed link.
+async fn invite(app: &TestApp, body: &Value) -> String {
+    let response = create_invitation(app, body).await;
+    assert_eq!(response.status(), 201);
+    let sent = app.email_client.sent_emails();
+    let sent = sent.last().expect("No invitation sent");
+    assert_eq!(sent.recipient, body["email"]);
+    let link = sent
+        .content
+        .split_whitespace()
+        .find(|word| word.starts_with(PUBLIC_URL))
+        .expect("No link in the email");
+    let link = Url::parse(link).unwrap();
+    link.query_pairs()
+        .find(|(name, _)| name == "invite")
+        .map(|(_, token)| token.into_owned())
+        .expect("No token in the link")
+}
+
+fn signup_body(email: &str, invite_token: Option<&str>) -> Value {
+    json!({
+        "email": email,
+        "password": "Kx7!vTq2Lm",
+        "requires2FA": false,
+        "inviteToken": invite_token,
+    })
+}
+
+#[tokio::test]
+async fn should_only_sign_up_invited_users_when_registration_is_closed() {
+    let mut app = app(false).await;
+    let email = get_random_email();
+
+    let response = app.post_signup(&signup_body(&email, None)).await;
+    assert_eq!(response.status(), 403);
+
+    let body = json!({ "email": email, "role": "support" });
+    let response = create_invitation(&app, &body).await;
+    assert_eq!(response.status(), 201);
+    let invitation = response
+        .json::<InvitationResponse>()
+        .await
+        .expect("Could not deserialize response body to InvitationResponse");
+    assert_eq!(invitation.email, email);
+    assert_eq!(invitation.role.as_deref(), Some("support"));
+
+    let token = invite(&app, &body).await;
+    // bound to the invited email
+    let response = app
+        .post_signup(&signup_body(&get_random_email(), Some(&token)))
+        .await;
+    assert_eq!(response.status(), 400);
+    let response = app.post_signup(&signup_body(&email, Some("unknown"))).await;
+    assert_eq!(response.status(), 400);
+
+    let response = app.post_signup(&signup_body(&email, Some(&token))).await;
+    assert_eq!(response.status(), 201);
+    let response = app.post_signup(&signup_body(&email, Some(&token))).await;
+    assert_eq!(response.status(), 400);
+
+    let body = json!({ "email": email, "password": "Kx7!vTq2Lm" });
+    assert_eq!(app.post_login(&body).await.status(), 200);
+    let user = app
+        .get_me()
+        .await
+        .json::<MeResponse>()
+        .await
+        .expect("Could not deserialize response body to MeResponse");
+    assert_eq!(user.roles, ["support"]);
+    assert!(user.email_verified);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_accept_invitations_when_registration_is_open() {
+    let mut app = app(true).await;
+    let email = get_random_email();
+    let token = invite(&app, &json!({ "email": email })).await;
+
+    let response = app.post_signup(&signup_body(&email, Some("unknown"))).await;
+    assert_eq!(response.status(), 400);
+    let response = app.post_signup(&signup_body(&email, Some(&token))).await;
+    assert_eq!(response.status(), 201);
+    let response = app
+        .post_signup(&signup_body(&get_random_email(), None))
+        .await;
+    assert_eq!(response.status(), 201);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_400_or_422_for_invalid_invitations() {
+    let mut app = app(false).await;
+
+    let test_cases = [
+        json!({ "email": "not-an-email" }),
+        json!({ "email": get_random_email(), "role": "Not A Role" }),
+        json!({ "email": get_random_email(), "expiresInDays": 0 }),
+        json!({ "email": get_random_email(), "expiresInDays": 31 }),
+    ];
+    for body in test_cases {
+        let response = create_invitation(&app, &body).await;
+        assert_eq!(response.status(), 400, "Failed for: {body}");
+    }
+    let response = create_invitation(&app, &json!({ "role": "support" })).await;
+    assert_eq!(response.status(), 422);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_not_invite_existing_users() {
+    let mut app = app(true).await;
+    let email = get_random_email();
+    assert_eq!(
+        app.post_signup(&signup_body(&email, None)).await.status(),
+        201
+    );
+
+    let response = create_invitation(&app, &json!({ "email": email })).await;
+    assert_eq!(response.status(), 409);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_require_an_admin() {
+    let mut app = app(false).await;
+
+    let response = app
+        .admin_request(Method::POST, "/invitations", None)
+        .await
+        .json(&json!({ "email": get_random_email() }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), 400);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_not_join_organizations_with_signup_invitations() {
+    let mut app = app(false).await;
+    let email = get_random_email();
+    let token = invite(&app, &json!({ "email": email })).await;
+
+    let body = json!({ "token": token, "password": "Kx7!vTq2Lm" });
+    let response = app.post_accept_invitation(&body).await;
+    assert_eq!(response.status(), 400);
+    // still usable at signup
+    let response = app.post_signup(&signup_body(&email, Some(&token))).await;
+    assert_eq!(response.status(), 201);
+
+    app.clean_up().await;
+}
@@ -1,9 +1,10 @@
 use crate::helpers::TestApp;
 use crate::helpers::get_random_email;
-use auth_service::domain::Email;
+use crate::helpers::{get_set_cookie_header, host_cookie_policy};
 use auth_service::domain::TwoFACodeStore;
 use auth_service::domain::error::ErrorResponse;
-use auth_service::routes::TwoFactorAuthResponse;
+use auth_service::domain::{AccountStatus, UserKey};
+use auth_service::routes::{TokenResponse, TwoFactorAuthResponse};
 use auth_service::utils::constants::JWT_COOKIE_NAME;
 
 #[tokio::test]
@@ -33,37 +34,13 @@ async fn should_return_400_if_invalid_input() {
         // email: no @
         serde_json::json!({
             "email": "wrong.email.com",
-            "password": "Password1!",
+            "password": "Kx7!vTq2Lm",
             "requires2FA": true
         }),
         // email: 2 @
         serde_json::json!({
             "email": "two@at@symbols",
-            "password": "Password1!",
-            "requires2FA": true
-        }),
-        // password: less than 8 characters
-        serde_json::json!({
-            "email": get_random_email(),
-            "password": "Small!",
-            "requires2FA": true
-        }),
-        // password: no special character
-        serde_json::json!({
-            "email": get_random_email(),
-            "password": "NoSpecialCharacters",
-            "requires2FA": true
-        }),
-        // password: no number
-        serde_json::json!({
-            "email": get_random_email(),
-            "password": "NoNumber!",
-            "requires2FA": true
-        }),
-        // password: no capital letter
-        serde_json::json!({
-            "email": get_random_email(),
-            "password": "no_capital_letter",
+            "password": "Kx7!vTq2Lm",
             "requires2FA": true
         }),
     ];
@@ -76,7 +53,7 @@ async fn should_return_400_if_invalid_input() {
                 .await
                 .expect("Could not deserialize response body to ErrorResponse")
                 .error,
-            "Invalid credentials".to_owned()
+            "Invalid input".to_owned()
         )
     }
     app.clean_up().await;
@@ -87,7 +64,7 @@ async fn should_return_401_if_incorrect_credentials() {
     let mut app = TestApp::new().await;
     let body = serde_json::json!({
         "email": "valid@email.com",
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
         "requires2FA": false
     });
     app.post_signup(&body).await;
@@ -95,7 +72,7 @@ async fn should_return_401_if_incorrect_credentials() {
         // wrong, but valid email
         serde_json::json!({
             "email": "wrong@email.com",
-            "password": "Password1!",
+            "password": "Kx7!vTq2Lm",
         }),
         // wrong, but valid password
         serde_json::json!({
@@ -118,6 +95,51 @@ async fn should_return_401_if_incorrect_credentials() {
     app.clean_up().await;
 }
 
+#[tokio::test]
+async fn should_reject_accounts_that_are_not_active() {
+    let mut app = TestApp::new().await;
+    let test_cases = [
+        (AccountStatus::Disabled, 403, "Account disabled"),
+        (AccountStatus::Locked, 423, "Account locked"),
+        (
+            AccountStatus::PendingVerification,
+            403,
+            "Account pending verification",
+        ),
+    ];
+    for (status, expected_status, expected_error) in test_cases {
+        let email = get_random_email();
+        let signup_body = serde_json::json!({
+            "email": email,
+            "password": "Kx7!vTq2Lm",
+            "requires2FA": false
+        });
+        app.post_signup(&signup_body).await;
+        app.set_status(&email, status).await;
+
+        let login_body = serde_json::json!({
+            "email": email,
+            "password": "Kx7!vTq2Lm",
+        });
+        let response = app.post_login(&login_body).await;
+        assert_eq!(
+            response.status(),
+            expected_status,
+            "Failed for status: {:?}",
+            status
+        );
+        assert_eq!(
+            response
+                .json::<ErrorResponse>()
+                .await
+                .expect("Could not deserialize response body to ErrorResponse")
+                .error,
+            expected_error
+        );
+    }
+    app.clean_up().await;
+}
+
 #[tokio::test]
 async fn should_return_200_if_valid_credentials_and_2fa_disabled() {
     let mut app = TestApp::new().await;
@@ -126,7 +148,7 @@ async fn should_return_200_if_valid_credentials_and_2fa_disabled() {
 
     let signup_body = serde_json::json!({
         "email": random_email,
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
         "requires2FA": false
     });
 
@@ -136,7 +158,7 @@ async fn should_return_200_if_valid_credentials_and_2fa_disabled() {
 
     let login_body = serde_json::json!({
         "email": random_email,
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
     });
 
     let response = app.post_login(&login_body).await;
@@ -157,11 +179,11 @@ async fn should_return_206_if_valid_credentials_and_2fa_enabled() {
     let mut app = TestApp::new().await;
 
     let random_email = get_random_email();
-    let email = Email::parse(&random_email).unwrap();
+    let email = UserKey::new(None, &random_email);
 
     let signup_body = serde_json::json!({
         "email": random_email,
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
         "requires2FA": true
     });
 
@@ -171,7 +193,7 @@ async fn should_return_206_if_valid_credentials_and_2fa_enabled() {
 
     let login_body = serde_json::json!({
         "email": random_email,
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
     });
 
     let response = app.post_login(&login_body).await;
@@ -200,3 +222,87 @@ async fn should_return_206_if_valid_credentials_and_2fa_enabled() {
     );
     app.clean_up().await;
 }
+
+#[tokio::test]
+async fn should_set_auth_cookie_with_configured_policy() {
+    let mut app = TestApp::with_settings(host_cookie_policy).await;
+
+    let random_email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "Kx7!vTq2Lm",
+        "requires2FA": false
+    });
+    app.post_signup(&signup_body).await;
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "Kx7!vTq2Lm",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let set_cookie = get_set_cookie_header(&response, "__Host-jwt");
+    for attribute in [
+        "HttpOnly",
+        "SameSite=Strict",
+        "Secure",
+        "Path=/",
+        "Max-Age=600",
+    ] {
+        assert!(
+            set_cookie.contains(attribute),
+            "missing {} in {}",
+            attribute,
+            set_cookie
+        );
+    }
+    assert!(!set_cookie.contains("Domain="));
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_token_in_body_if_requested() {
+    let mut app = TestApp::new().await;
+    let random_email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "Kx7!vTq2Lm",
+        "requires2FA": false
+    });
+    let _ = app.post_signup(&signup_body).await;
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "Kx7!vTq2Lm",
+        "tokenDelivery": "body",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(
+        response
+            .cookies()
+            .all(|cookie| cookie.name() != JWT_COOKIE_NAME)
+    );
+
+    let body = response
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse");
+    let verify_token_body = serde_json::json!({ "token": body.token });
+    let response = app.post_verify_token(&verify_token_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_422_if_unknown_token_delivery() {
+    let mut app = TestApp::new().await;
+    let login_body = serde_json::json!({
+        "email": get_random_email(),
+        "password": "Kx7!vTq2Lm",
+        "tokenDelivery": "carrier-pigeon",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 422);
+    app.clean_up().await;
+}
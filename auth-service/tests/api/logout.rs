@@ -1,5 +1,8 @@
 use crate::helpers::TestApp;
-use auth_service::{domain::BannedTokenStore, utils::constants::JWT_COOKIE_NAME};
+use crate::helpers::{get_set_cookie_header, host_cookie_policy};
+use auth_service::{
+    domain::BannedTokenStore, routes::TokenResponse, utils::constants::JWT_COOKIE_NAME,
+};
 use reqwest::Url;
 
 #[tokio::test]
@@ -34,13 +37,13 @@ async fn should_return_200_if_valid_jwt_cookie() {
     let mut app = TestApp::new().await;
     let body_signup = serde_json::json!({
         "email": "valid@email.com",
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
         "requires2FA": false
     });
     let _ = app.post_signup(&body_signup).await;
     let body_login = serde_json::json!({
         "email": "valid@email.com",
-        "password": "Password1!"
+        "password": "Kx7!vTq2Lm"
     });
     let response = app.post_login(&body_login).await;
     let auth_cookie = response
@@ -54,7 +57,7 @@ async fn should_return_200_if_valid_jwt_cookie() {
     let banned_token_store = app.banned_token_store.read().await.clone();
     assert!(
         banned_token_store
-            .contains_token(&auth_cookie.value())
+            .contains_token(auth_cookie.value())
             .await
             .unwrap()
     );
@@ -66,13 +69,13 @@ async fn should_return_400_if_logout_called_twice_in_a_row() {
     let mut app = TestApp::new().await;
     let body_signup = serde_json::json!({
         "email": "valid@email.com",
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
         "requires2FA": true
     });
     let _ = app.post_signup(&body_signup).await;
     let body_login = serde_json::json!({
         "email": "valid@email.com",
-        "password": "Password1!"
+        "password": "Kx7!vTq2Lm"
     });
     let _ = app.post_login(&body_login).await;
     let _ = app.post_logout().await;
@@ -80,3 +83,96 @@ async fn should_return_400_if_logout_called_twice_in_a_row() {
     assert_eq!(response.status(), 400);
     app.clean_up().await;
 }
+
+#[tokio::test]
+async fn should_remove_auth_cookie_with_configured_policy() {
+    let mut app = TestApp::with_settings(host_cookie_policy).await;
+    let body_signup = serde_json::json!({
+        "email": "valid@email.com",
+        "password": "Kx7!vTq2Lm",
+        "requires2FA": false
+    });
+    let _ = app.post_signup(&body_signup).await;
+    let body_login = serde_json::json!({
+        "email": "valid@email.com",
+        "password": "Kx7!vTq2Lm"
+    });
+    let response = app.post_login(&body_login).await;
+    assert_eq!(response.status(), 200);
+
+    let response = app.post_logout().await;
+    assert_eq!(response.status(), 200);
+
+    let set_cookie = get_set_cookie_header(&response, "__Host-jwt");
+    for attribute in [
+        "__Host-jwt=;",
+        "HttpOnly",
+        "SameSite=Strict",
+        "Secure",
+        "Path=/",
+        "Max-Age=0",
+    ] {
+        assert!(
+            set_cookie.contains(attribute),
+            "missing {} in {}",
+            attribute,
+            set_cookie
+        );
+    }
+    app.clean_up().await;
+}
+
+// Logs in a new user without 2FA and returns the token from the body.
+async fn login_for_bearer_token(app: &TestApp) -> String {
+    let body_signup = serde_json::json!({
+        "email": "valid@email.com",
+        "password": "Kx7!vTq2Lm",
+        "requires2FA": false
+    });
+    let _ = app.post_signup(&body_signup).await;
+    let body_login = serde_json::json!({
+        "email": "valid@email.com",
+        "password": "Kx7!vTq2Lm",
+        "tokenDelivery": "body"
+    });
+    app.post_login(&body_login)
+        .await
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse")
+        .token
+}
+
+#[tokio::test]
+async fn should_return_200_if_valid_bearer_token() {
+    let mut app = TestApp::new().await;
+    let token = login_for_bearer_token(&app).await;
+
+    let response = app.post_logout_with_bearer(&token).await;
+    assert_eq!(response.status(), 200);
+    assert!(response.headers().get("set-cookie").is_none());
+
+    let banned_token_store = app.banned_token_store.read().await.clone();
+    assert!(banned_token_store.contains_token(&token).await.unwrap());
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_401_if_bearer_token_already_logged_out() {
+    let mut app = TestApp::new().await;
+    let token = login_for_bearer_token(&app).await;
+
+    let _ = app.post_logout_with_bearer(&token).await;
+    let response = app.post_logout_with_bearer(&token).await;
+    assert_eq!(response.status(), 401);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_401_if_invalid_bearer_token() {
+    let mut app = TestApp::new().await;
+
+    let response = app.post_logout_with_bearer("invalid").await;
+    assert_eq!(response.status(), 401);
+    app.clean_up().await;
+}
@@ -0,0 +1,159 @@
+use crate::helpers::{TestApp, get_random_email, location};
+use auth_service::domain::AccountStatus;
+use auth_service::routes::{MagicLinkResponse, MeResponse};
+use auth_service::settings::Settings;
+use reqwest::Url;
+use serde_json::json;
+use std::collections::HashMap;
+
+const PUBLIC_URL: &str = "https://auth.example.com";
+
+async fn app() -> TestApp {
+    TestApp::with_settings(|settings: &mut Settings| {
+        settings.application.public_url = PUBLIC_URL.to_owned();
+    })
+    .await
+}
+
+// Asks for a link, returning the query of the callback it leads to.
+async fn request_link(app: &TestApp, email: &str) -> String {
+    let response = app.post_magic_link(&json!({ "email": email })).await;
+    assert_eq!(response.status(), 202);
+    let sent = app.email_client.sent_emails();
+    let sent = sent.last().expect("No magic link sent");
+    assert_eq!(sent.recipient, email);
+    let link = sent
+        .content
+        .split_whitespace()
+        .find(|word| word.starts_with(PUBLIC_URL))
+        .expect("No link in the email");
+    let link = Url::parse(link).unwrap();
+    assert_eq!(link.path(), "/login/magic-link/callback");
+    format!("?{}", link.query().unwrap())
+}
+
+async fn me(app: &TestApp) -> MeResponse {
+    let response = app.get_me().await;
+    assert_eq!(response.status(), 200);
+    response
+        .json::<MeResponse>()
+        .await
+        .expect("Could not deserialize response body to MeResponse")
+}
+
+#[tokio::test]
+async fn should_log_in_with_the_emailed_link() {
+    let mut app = app().await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+
+    let query = request_link(&app, &email).await;
+    let response = app.get_magic_link_callback(&query).await;
+    assert_eq!(response.status(), 303);
+    assert_eq!(location(&response).as_str(), format!("{PUBLIC_URL}/"));
+    assert!(response.cookies().any(|cookie| cookie.name() == "jwt"));
+    assert_eq!(me(&app).await.email, email);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_only_accept_a_link_once() {
+    let mut app = app().await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+
+    let query = request_link(&app, &email).await;
+    assert_eq!(app.get_magic_link_callback(&query).await.status(), 303);
+    assert_eq!(app.get_magic_link_callback(&query).await.status(), 400);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reject_unknown_and_expired_links() {
+    let mut app = TestApp::with_settings(|settings: &mut Settings| {
+        settings.application.public_url = PUBLIC_URL.to_owned();
+        settings.auth.magic_link_ttl_seconds = 1;
+    })
+    .await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+
+    let response = app.get_magic_link_callback("?token=forged").await;
+    assert_eq!(response.status(), 400);
+
+    let query = request_link(&app, &email).await;
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    assert_eq!(app.get_magic_link_callback(&query).await.status(), 400);
+    assert_eq!(app.get_me().await.status(), 400);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_not_reveal_whether_an_account_exists() {
+    let mut app = app().await;
+    let disabled = get_random_email();
+    app.signup(&disabled, false).await;
+    app.set_status(&disabled, AccountStatus::Disabled).await;
+
+    for email in [get_random_email(), disabled] {
+        let response = app.post_magic_link(&json!({ "email": email })).await;
+        assert_eq!(response.status(), 202);
+        let body = response
+            .json::<MagicLinkResponse>()
+            .await
+            .expect("Could not deserialize response body to MagicLinkResponse");
+        assert!(body.message.contains("If the email belongs to an account"));
+    }
+    assert!(app.email_client.sent_emails().is_empty());
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_400_or_422_for_invalid_requests() {
+    let mut app = app().await;
+
+    let response = app
+        .post_magic_link(&json!({ "email": "not-an-email" }))
+        .await;
+    assert_eq!(response.status(), 400);
+    let response = app.post_magic_link(&json!({ "mail": "a@b.com" })).await;
+    assert_eq!(response.status(), 422);
+    assert_eq!(app.get_magic_link_callback("").await.status(), 400);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_ask_2fa_users_for_a_code() {
+    let mut app = app().await;
+    let email = get_random_email();
+    app.signup(&email, true).await;
+
+    let query = request_link(&app, &email).await;
+    let response = app.get_magic_link_callback(&query).await;
+    assert_eq!(response.status(), 303);
+    let url = location(&response);
+    assert_eq!(url.path(), "/");
+    let fragment: HashMap<String, String> =
+        url::form_urlencoded::parse(url.fragment().unwrap_or_default().as_bytes())
+            .into_owned()
+            .collect();
+    assert_eq!(fragment["email"], email);
+    assert_eq!(app.get_me().await.status(), 400);
+
+    let sent = app.email_client.sent_emails();
+    let code = &sent.last().expect("No 2FA code sent").content;
+    let body = json!({
+        "email": email,
+        "loginAttemptId": fragment["login_attempt_id"],
+        "2FACode": code,
+    });
+    assert_eq!(app.post_verify_2fa(&body).await.status(), 200);
+    assert_eq!(me(&app).await.email, email);
+
+    app.clean_up().await;
+}
@@ -1,7 +1,24 @@
+mod access_tokens;
+mod activity;
+mod admin;
+mod api_keys;
+mod cors;
+mod csrf;
 mod helpers;
+mod introspect;
+mod invitations;
 mod login;
 mod logout;
+mod magic_link;
+mod me;
+mod new_device;
+mod oauth;
+mod organizations;
+mod password_reset;
+mod problem_details;
 mod root;
 mod signup;
+mod social_login;
+mod trusted_devices;
 mod verify_2fa;
 mod verify_token;
@@ -0,0 +1,166 @@
+use crate::helpers::{TestApp, get_random_email};
+use auth_service::routes::{MeResponse, TokenResponse};
+
+#[tokio::test]
+async fn should_return_400_if_not_logged_in() {
+    let mut app = TestApp::new().await;
+
+    let response = app.get_me().await;
+    assert_eq!(response.status(), 400);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_current_user() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    app.signup_and_login(&email).await;
+
+    let response = app.get_me().await;
+    assert_eq!(response.status(), 200);
+    let body = response
+        .json::<MeResponse>()
+        .await
+        .expect("Could not deserialize response body to MeResponse");
+    assert_eq!(body.email, email);
+    assert!(!body.requires_2fa);
+    assert!(!body.email_verified);
+    assert!(chrono::Utc::now() - body.created_at < chrono::Duration::minutes(1));
+    assert_eq!(body.display_name, None);
+    assert_eq!(body.locale, None);
+    assert!(body.roles.is_empty());
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_list_roles() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    app.signup_and_login(&email).await;
+    app.set_roles(&email, &["admin"]).await;
+
+    let body = app
+        .get_me()
+        .await
+        .json::<MeResponse>()
+        .await
+        .expect("Could not deserialize response body to MeResponse");
+    assert_eq!(body.roles, ["admin"]);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_current_user_for_bearer_token() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    let body_signup = serde_json::json!({
+        "email": email,
+        "password": "Kx7!vTq2Lm",
+        "requires2FA": false
+    });
+    let _ = app.post_signup(&body_signup).await;
+    let body_login = serde_json::json!({
+        "email": email,
+        "password": "Kx7!vTq2Lm",
+        "tokenDelivery": "body"
+    });
+    let token = app
+        .post_login(&body_login)
+        .await
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse")
+        .token;
+
+    let response = app
+        .http_client
+        .get(format!("{}/me", app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), 200);
+    let body = response
+        .json::<MeResponse>()
+        .await
+        .expect("Could not deserialize response body to MeResponse");
+    assert_eq!(body.email, email);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_update_profile() {
+    let mut app = TestApp::new().await;
+    app.signup_and_login(&get_random_email()).await;
+
+    let response = app
+        .patch_me(&serde_json::json!({
+            "displayName": "Gabriel",
+            "locale": "pt-BR"
+        }))
+        .await;
+    assert_eq!(response.status(), 200);
+    let body = response.json::<MeResponse>().await.unwrap();
+    assert_eq!(body.display_name.as_deref(), Some("Gabriel"));
+    assert_eq!(body.locale.as_deref(), Some("pt-BR"));
+
+    // missing fields are left unchanged, null clears them
+    let response = app
+        .patch_me(&serde_json::json!({ "displayName": null }))
+        .await;
+    assert_eq!(response.status(), 200);
+
+    let body = app.get_me().await.json::<MeResponse>().await.unwrap();
+    assert_eq!(body.display_name, None);
+    assert_eq!(body.locale.as_deref(), Some("pt-BR"));
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_400_if_invalid_profile() {
+    let mut app = TestApp::new().await;
+    app.signup_and_login(&get_random_email()).await;
+
+    let test_cases = [
+        serde_json::json!({ "displayName": "" }),
+        serde_json::json!({ "displayName": "a".repeat(65) }),
+        serde_json::json!({ "locale": "pt_BR" }),
+    ];
+    for test_case in test_cases {
+        let response = app.patch_me(&test_case).await;
+        assert_eq!(response.status(), 400, "Failed for input: {:?}", test_case);
+    }
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_422_if_malformed_input() {
+    let mut app = TestApp::new().await;
+    app.signup_and_login(&get_random_email()).await;
+
+    let test_cases = [
+        serde_json::json!({ "email": "other@example.com" }),
+        serde_json::json!({ "displayName": 42 }),
+    ];
+    for test_case in test_cases {
+        let response = app.patch_me(&test_case).await;
+        assert_eq!(response.status(), 422, "Failed for input: {:?}", test_case);
+    }
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_403_if_patch_without_csrf_header() {
+    let mut app = TestApp::new().await;
+    app.signup_and_login(&get_random_email()).await;
+
+    let response = app
+        .http_client
+        .patch(format!("{}/me", app.address))
+        .json(&serde_json::json!({ "displayName": "Mallory" }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), 403);
+    app.clean_up().await;
+}
@@ -0,0 +1,250 @@
+use crate::helpers::{TestApp, get_random_email};
+use auth_service::domain::{ClientInfo, KnownDevice, TwoFACodeStore, UserKey, UserStore};
+use auth_service::routes::{CreatedAccessTokenResponse, RevokeSessionsResponse, TokenResponse};
+use auth_service::services::mock_mail_client::SentEmail;
+
+const ALERT_SUBJECT: &str = "New sign-in to your account";
+
+fn login_body(email: &str) -> serde_json::Value {
+    serde_json::json!({ "email": email, "password": "Kx7!vTq2Lm" })
+}
+
+// A browser the app hasn't seen, with its own cookies.
+fn other_device(user_agent: &str) -> reqwest::Client {
+    reqwest::Client::builder()
+        .cookie_store(true)
+        .user_agent(user_agent)
+        .build()
+        .unwrap()
+}
+
+fn alerts(app: &TestApp, email: &str) -> Vec<SentEmail> {
+    app.email_client
+        .sent_emails()
+        .into_iter()
+        .filter(|sent| sent.recipient == email && sent.subject == ALERT_SUBJECT)
+        .collect()
+}
+
+fn revoke_token(alert: &SentEmail) -> String {
+    let (_, token) = alert
+        .content
+        .split_once("/revoke-sessions?token=")
+        .expect("No revoke link in alert");
+    token
+        .split_whitespace()
+        .next()
+        .expect("No token in revoke link")
+        .to_owned()
+}
+
+#[tokio::test]
+async fn should_not_alert_on_first_device() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+
+    assert_eq!(app.post_login(&login_body(&email)).await.status(), 200);
+
+    assert!(alerts(&app, &email).is_empty());
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_alert_on_login_from_new_device() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+    assert_eq!(app.post_login(&login_body(&email)).await.status(), 200);
+
+    let device = other_device("Other Browser/1.0");
+    let response = app.post_login_from(&device, &login_body(&email)).await;
+    assert_eq!(response.status(), 200);
+
+    let alerts = alerts(&app, &email);
+    assert_eq!(alerts.len(), 1);
+    assert!(alerts[0].content.contains("Other Browser/1.0"));
+    assert!(alerts[0].content.contains("127.0.0.x"));
+    assert!(alerts[0].content.contains("/revoke-sessions?token="));
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_not_alert_on_known_device() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+    assert_eq!(app.post_login(&login_body(&email)).await.status(), 200);
+    let device = other_device("Other Browser/1.0");
+    app.post_login_from(&device, &login_body(&email)).await;
+
+    // both devices are known by now, through their device cookie
+    app.post_login_from(&device, &login_body(&email)).await;
+    assert_eq!(app.post_login(&login_body(&email)).await.status(), 200);
+
+    assert_eq!(alerts(&app, &email).len(), 1);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_alert_on_2fa_login_from_new_device() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    app.signup(&email, true).await;
+    let client = ClientInfo {
+        ip: Some("203.0.113.7".to_owned()),
+        user_agent: Some("Known Browser/1.0".to_owned()),
+    };
+    app.user_store
+        .write()
+        .await
+        .remember_device(
+            &UserKey::new(None, &email),
+            KnownDevice::new("known".to_owned(), &client),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(app.post_login(&login_body(&email)).await.status(), 206);
+    assert!(alerts(&app, &email).is_empty());
+    let (login_attempt_id, two_fa_code) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&UserKey::new(None, &email))
+        .await
+        .unwrap();
+    let body = serde_json::json!({
+        "email": email,
+        "loginAttemptId": login_attempt_id.as_ref(),
+        "2FACode": two_fa_code.as_ref(),
+    });
+    assert_eq!(app.post_verify_2fa(&body).await.status(), 200);
+
+    assert_eq!(alerts(&app, &email).len(), 1);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn revoke_link_should_log_the_user_out_everywhere() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+    assert_eq!(app.post_login(&login_body(&email)).await.status(), 200);
+    let device = other_device("Other Browser/1.0");
+    app.post_login_from(&device, &login_body(&email)).await;
+    let alert = alerts(&app, &email).pop().expect("No alert sent");
+
+    let body = serde_json::json!({ "token": revoke_token(&alert) });
+    let response = app.post_revoke_sessions(&body).await;
+    assert_eq!(response.status(), 200);
+    response
+        .json::<RevokeSessionsResponse>()
+        .await
+        .expect("Could not deserialize response body to RevokeSessionsResponse");
+
+    assert_eq!(app.get_me().await.status(), 401);
+    let devices = app
+        .user_store
+        .read()
+        .await
+        .known_devices(&UserKey::new(None, &email))
+        .await;
+    assert_eq!(devices.unwrap(), []);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn revoke_link_should_work_once() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+    assert_eq!(app.post_login(&login_body(&email)).await.status(), 200);
+    let device = other_device("Other Browser/1.0");
+    app.post_login_from(&device, &login_body(&email)).await;
+    let alert = alerts(&app, &email).pop().expect("No alert sent");
+    assert!(alert.content.contains("expires in 15 minutes"));
+
+    let body = serde_json::json!({ "token": revoke_token(&alert) });
+    assert_eq!(app.post_revoke_sessions(&body).await.status(), 200);
+    assert_eq!(app.post_revoke_sessions(&body).await.status(), 401);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn revoke_link_should_revoke_personal_access_tokens() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+    assert_eq!(app.post_login(&login_body(&email)).await.status(), 200);
+    let body = serde_json::json!({ "name": "script", "scopes": ["repo"], "expiresInDays": 30 });
+    let token = app
+        .post_access_token(&body)
+        .await
+        .json::<CreatedAccessTokenResponse>()
+        .await
+        .expect("Could not deserialize response body to CreatedAccessTokenResponse")
+        .token;
+    let body = serde_json::json!({ "token": token });
+    assert_eq!(app.post_verify_token(&body).await.status(), 200);
+    let device = other_device("Other Browser/1.0");
+    app.post_login_from(&device, &login_body(&email)).await;
+    let alert = alerts(&app, &email).pop().expect("No alert sent");
+
+    let revoke_body = serde_json::json!({ "token": revoke_token(&alert) });
+    assert_eq!(app.post_revoke_sessions(&revoke_body).await.status(), 200);
+
+    assert_eq!(app.post_verify_token(&body).await.status(), 401);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn revoke_link_should_reject_invalid_tokens() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+    // a session token is not a revoke link
+    let body = serde_json::json!({
+        "email": email,
+        "password": "Kx7!vTq2Lm",
+        "tokenDelivery": "body"
+    });
+    let session = app
+        .post_login(&body)
+        .await
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse")
+        .token;
+
+    let test_cases = [
+        (serde_json::json!({}), 422),
+        (serde_json::json!({ "token": "invalid" }), 401),
+        (serde_json::json!({ "token": session }), 401),
+    ];
+    for (body, status) in test_cases {
+        let response = app.post_revoke_sessions(&body).await;
+        assert_eq!(response.status(), status, "Failed for body: {body}");
+    }
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn following_the_revoke_link_should_only_ask_for_confirmation() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+    assert_eq!(app.post_login(&login_body(&email)).await.status(), 200);
+    let device = other_device("Other Browser/1.0");
+    app.post_login_from(&device, &login_body(&email)).await;
+    let alert = alerts(&app, &email).pop().expect("No alert sent");
+
+    let query = format!("?token={}", revoke_token(&alert));
+    let response = app.get_revoke_sessions(&query).await;
+    assert_eq!(response.status(), 200);
+    let content_type = response.headers()[reqwest::header::CONTENT_TYPE].clone();
+    assert!(content_type.to_str().unwrap().starts_with("text/html"));
+
+    assert_eq!(app.get_me().await.status(), 200);
+    app.clean_up().await;
+}
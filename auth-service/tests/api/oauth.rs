@@ -0,0 +1,418 @@
+use crate::helpers::{
+    ADMIN_API_KEY, TestApp, admin_api_key, get_random_email, location, oauth_error,
+};
+use auth_service::routes::{
+    AuthorizeResponse, OAuthTokenResponse, OpenIdConfiguration, RegisteredOAuthClientResponse,
+};
+use auth_service::settings::Settings;
+use auth_service::utils::oidc::{IdTokenClaims, UserInfo};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header};
+use reqwest::Url;
+use std::collections::HashMap;
+
+const PUBLIC_URL: &str = "https://auth.example.com";
+const REDIRECT_URI: &str = "http://localhost:8000/callback";
+// example of RFC 7636, appendix B
+const CODE_VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+const CODE_CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+fn oidc_settings(settings: &mut Settings) {
+    admin_api_key(settings);
+    settings.application.public_url = PUBLIC_URL.to_owned();
+}
+
+async fn register_client(app: &TestApp, confidential: bool) -> RegisteredOAuthClientResponse {
+    let body = serde_json::json!({
+        "name": "Example App",
+        "redirectUris": [REDIRECT_URI],
+        "confidential": confidential,
+    });
+    let response = app
+        .admin_request(reqwest::Method::POST, "/oauth-clients", Some(ADMIN_API_KEY))
+        .await
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), 201);
+    response
+        .json::<RegisteredOAuthClientResponse>()
+        .await
+        .expect("Could not deserialize response body to RegisteredOAuthClientResponse")
+}
+
+fn authorize_params(client_id: &str) -> Vec<(&'static str, String)> {
+    vec![
+        ("response_type", "code".to_owned()),
+        ("client_id", client_id.to_owned()),
+        ("redirect_uri", REDIRECT_URI.to_owned()),
+        ("scope", "openid email profile".to_owned()),
+        ("state", "xyz".to_owned()),
+        ("nonce", "n-0S6_WzA2Mj".to_owned()),
+        ("code_challenge", CODE_CHALLENGE.to_owned()),
+        ("code_challenge_method", "S256".to_owned()),
+    ]
+}
+
+fn query(params: &[(&str, String)]) -> String {
+    let url = Url::parse_with_params("http://localhost/", params).unwrap();
+    format!("?{}", url.query().unwrap_or_default())
+}
+
+fn query_pairs(url: &Url) -> HashMap<String, String> {
+    url.query_pairs().into_owned().collect()
+}
+
+// Approves the consent page, returning the code handed to the client.
+async fn approve(app: &TestApp, client_id: &str) -> String {
+    let mut body: serde_json::Map<String, serde_json::Value> = authorize_params(client_id)
+        .into_iter()
+        .map(|(key, value)| (key.to_owned(), value.into()))
+        .collect();
+    body.insert("approve".to_owned(), true.into());
+    let response = app.post_authorize(&body).await;
+    assert_eq!(response.status(), 200);
+    let redirect_to = response
+        .json::<AuthorizeResponse>()
+        .await
+        .expect("Could not deserialize response body to AuthorizeResponse")
+        .redirect_to;
+    let params = query_pairs(&Url::parse(&redirect_to).unwrap());
+    assert_eq!(params.get("state").map(String::as_str), Some("xyz"));
+    params.get("code").expect("No code in the redirect").clone()
+}
+
+async fn exchange(app: &TestApp, client_id: &str, code: &str, verifier: &str) -> reqwest::Response {
+    let form = [
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", REDIRECT_URI),
+        ("code_verifier", verifier),
+        ("client_id", client_id),
+    ];
+    app.post_token(&form, None).await
+}
+
+#[tokio::test]
+async fn should_complete_authorization_code_flow() {
+    let mut app = TestApp::with_settings(oidc_settings).await;
+    let email = get_random_email();
+    app.signup_and_login(&email).await;
+    let client = register_client(&app, false).await;
+    assert_eq!(client.client_secret, None);
+    let authorize_query = query(&authorize_params(&client.client_id));
+
+    // first time, the user is asked for consent
+    let response = app.get_authorize(&authorize_query).await;
+    assert_eq!(response.status(), 303);
+    let consent = location(&response);
+    assert_eq!(consent.path(), "/consent.html");
+    assert_eq!(
+        query_pairs(&consent).get("client_id"),
+        Some(&client.client_id)
+    );
+
+    let code = approve(&app, &client.client_id).await;
+    let response = exchange(&app, &client.client_id, &code, CODE_VERIFIER).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["cache-control"], "no-store");
+    let tokens = response
+        .json::<OAuthTokenResponse>()
+        .await
+        .expect("Could not deserialize response body to OAuthTokenResponse");
+    assert_eq!(tokens.token_type, "Bearer");
+    assert_eq!(tokens.scope, "email openid profile");
+
+    // the ID token is verified with the published keys
+    let jwks = app
+        .get_well_known("jwks.json")
+        .await
+        .json::<JwkSet>()
+        .await
+        .expect("Could not deserialize response body to JwkSet");
+    let kid = decode_header(&tokens.id_token).unwrap().kid.unwrap();
+    let jwk = jwks.find(&kid).expect("Signing key not published");
+    let mut validation = Validation::new(Algorithm::ES256);
+    validation.set_audience(&[&client.client_id]);
+    validation.set_issuer(&[PUBLIC_URL]);
+    let claims = decode::<IdTokenClaims>(
+        &tokens.id_token,
+        &DecodingKey::from_jwk(jwk).unwrap(),
+        &validation,
+    )
+    .expect("Invalid ID token")
+    .claims;
+    assert_eq!(claims.user.sub, email);
+    assert_eq!(claims.user.email.as_deref(), Some(email.as_str()));
+    assert_eq!(claims.nonce.as_deref(), Some("n-0S6_WzA2Mj"));
+
+    let response = app.get_userinfo(&tokens.access_token).await;
+    assert_eq!(response.status(), 200);
+    let userinfo = response
+        .json::<UserInfo>()
+        .await
+        .expect("Could not deserialize response body to UserInfo");
+    assert_eq!(userinfo, claims.user);
+
+    // consent is remembered, the user goes straight back to the client
+    let response = app.get_authorize(&authorize_query).await;
+    assert_eq!(response.status(), 303);
+    let redirect = location(&response);
+    assert!(redirect.as_str().starts_with(REDIRECT_URI));
+    let params = query_pairs(&redirect);
+    assert!(params.contains_key("code"));
+    assert_eq!(params.get("state").map(String::as_str), Some("xyz"));
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_send_anonymous_users_to_login() {
+    let mut app = TestApp::with_settings(oidc_settings).await;
+    let client = register_client(&app, false).await;
+    let mut params = authorize_params(&client.client_id);
+
+    let response = app.get_authorize(&query(&params)).await;
+    assert_eq!(response.status(), 303);
+    let login = location(&response);
+    assert!(login.as_str().starts_with(&format!("{PUBLIC_URL}/?")));
+    let return_to = query_pairs(&login)["return_to"].clone();
+    assert!(return_to.starts_with(&format!("{PUBLIC_URL}/authorize?")));
+
+    params.push(("prompt", "none".to_owned()));
+    let response = app.get_authorize(&query(&params)).await;
+    let params = query_pairs(&location(&response));
+    assert_eq!(params["error"], "login_required");
+    assert_eq!(params["state"], "xyz");
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reject_unknown_client_or_redirect_uri_without_redirecting() {
+    let mut app = TestApp::with_settings(oidc_settings).await;
+    app.signup_and_login(&get_random_email()).await;
+    let client = register_client(&app, false).await;
+
+    let response = app
+        .get_authorize(&query(&authorize_params("unknown")))
+        .await;
+    assert_eq!(response.status(), 400);
+    assert_eq!(oauth_error(response).await, "invalid_request");
+
+    let mut params = authorize_params(&client.client_id);
+    params[2].1 = "https://evil.example.com/callback".to_owned();
+    let response = app.get_authorize(&query(&params)).await;
+    assert_eq!(response.status(), 400);
+    assert_eq!(oauth_error(response).await, "invalid_request");
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reject_malformed_requests_with_the_oauth_error_body() {
+    let mut app = TestApp::with_settings(oidc_settings).await;
+
+    let response = app.get_authorize("").await;
+    assert_eq!(response.status(), 400);
+    assert_eq!(oauth_error(response).await, "invalid_request");
+
+    let response = app.post_token(&[("code", "abc")], None).await;
+    assert_eq!(response.status(), 400);
+    assert_eq!(oauth_error(response).await, "invalid_request");
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_redirect_invalid_requests_back_to_the_client() {
+    let mut app = TestApp::with_settings(oidc_settings).await;
+    app.signup_and_login(&get_random_email()).await;
+    let client = register_client(&app, false).await;
+
+    let test_cases = [
+        (0, "token", "unsupported_response_type"),
+        (3, "email", "invalid_scope"),
+        (7, "plain", "invalid_request"),
+    ];
+    for (index, value, error) in test_cases {
+        let mut params = authorize_params(&client.client_id);
+        params[index].1 = value.to_owned();
+        let response = app.get_authorize(&query(&params)).await;
+        assert_eq!(response.status(), 303);
+        let redirect = location(&response);
+        assert!(redirect.as_str().starts_with(REDIRECT_URI));
+        assert_eq!(query_pairs(&redirect)["error"], error, "for {value}");
+    }
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_redirect_with_access_denied_when_consent_is_refused() {
+    let mut app = TestApp::with_settings(oidc_settings).await;
+    app.signup_and_login(&get_random_email()).await;
+    let client = register_client(&app, false).await;
+
+    let mut body: serde_json::Map<String, serde_json::Value> = authorize_params(&client.client_id)
+        .into_iter()
+        .map(|(key, value)| (key.to_owned(), value.into()))
+        .collect();
+    body.insert("approve".to_owned(), false.into());
+    let response = app.post_authorize(&body).await;
+    assert_eq!(response.status(), 200);
+    let redirect_to = response
+        .json::<AuthorizeResponse>()
+        .await
+        .unwrap()
+        .redirect_to;
+    let params = query_pairs(&Url::parse(&redirect_to).unwrap());
+    assert_eq!(params["error"], "access_denied");
+    assert!(!params.contains_key("code"));
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_only_exchange_a_code_once_with_the_right_verifier() {
+    let mut app = TestApp::with_settings(oidc_settings).await;
+    app.signup_and_login(&get_random_email()).await;
+    let client = register_client(&app, false).await;
+
+    let code = approve(&app, &client.client_id).await;
+    let wrong_verifier = CODE_VERIFIER.replace('d', "e");
+    let response = exchange(&app, &client.client_id, &code, &wrong_verifier).await;
+    assert_eq!(response.status(), 400);
+    assert_eq!(oauth_error(response).await, "invalid_grant");
+    // a failed exchange burns the code too
+    let response = exchange(&app, &client.client_id, &code, CODE_VERIFIER).await;
+    assert_eq!(oauth_error(response).await, "invalid_grant");
+
+    let code = approve(&app, &client.client_id).await;
+    let response = exchange(&app, &client.client_id, &code, CODE_VERIFIER).await;
+    assert_eq!(response.status(), 200);
+    let response = exchange(&app, &client.client_id, &code, CODE_VERIFIER).await;
+    assert_eq!(response.status(), 400);
+    assert_eq!(oauth_error(response).await, "invalid_grant");
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_require_the_secret_of_confidential_clients() {
+    let mut app = TestApp::with_settings(oidc_settings).await;
+    app.signup_and_login(&get_random_email()).await;
+    let client = register_client(&app, true).await;
+    let secret = client.client_secret.clone().expect("No client secret");
+
+    let code = approve(&app, &client.client_id).await;
+    let response = exchange(&app, &client.client_id, &code, CODE_VERIFIER).await;
+    assert_eq!(response.status(), 401);
+    assert_eq!(oauth_error(response).await, "invalid_client");
+
+    // the code is still there, the client wasn't authenticated
+    let form = [
+        ("grant_type", "authorization_code"),
+        ("code", code.as_str()),
+        ("redirect_uri", REDIRECT_URI),
+        ("code_verifier", CODE_VERIFIER),
+    ];
+    let response = app
+        .post_token(&form, Some((&client.client_id, &secret)))
+        .await;
+    assert_eq!(response.status(), 200);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_not_accept_access_tokens_as_sessions() {
+    let mut app = TestApp::with_settings(oidc_settings).await;
+    app.signup_and_login(&get_random_email()).await;
+    let client = register_client(&app, false).await;
+
+    let code = approve(&app, &client.client_id).await;
+    let tokens = exchange(&app, &client.client_id, &code, CODE_VERIFIER)
+        .await
+        .json::<OAuthTokenResponse>()
+        .await
+        .unwrap();
+    let response = reqwest::Client::new()
+        .get(format!("{}/me", app.address))
+        .bearer_auth(&tokens.access_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), 401);
+
+    // nor session tokens at /userinfo
+    let body = serde_json::json!({ "email": get_random_email(), "password": "Kx7!vTq2Lm", "requires2FA": false });
+    assert_eq!(app.post_signup(&body).await.status(), 201);
+    let mut login = body.clone();
+    login["tokenDelivery"] = "body".into();
+    let session_token = app
+        .post_login(&login)
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap()["token"]
+        .as_str()
+        .unwrap()
+        .to_owned();
+    assert_eq!(app.get_userinfo(&session_token).await.status(), 401);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_publish_discovery_document() {
+    let mut app = TestApp::with_settings(oidc_settings).await;
+
+    let response = app.get_well_known("openid-configuration").await;
+    assert_eq!(response.status(), 200);
+    let configuration = response
+        .json::<OpenIdConfiguration>()
+        .await
+        .expect("Could not deserialize response body to OpenIdConfiguration");
+    assert_eq!(configuration.issuer, PUBLIC_URL);
+    assert_eq!(
+        configuration.authorization_endpoint,
+        format!("{PUBLIC_URL}/authorize")
+    );
+    assert_eq!(
+        configuration.jwks_uri,
+        format!("{PUBLIC_URL}/.well-known/jwks.json")
+    );
+    assert_eq!(configuration.code_challenge_methods_supported, ["S256"]);
+
+    let jwks = app
+        .get_well_known("jwks.json")
+        .await
+        .json::<JwkSet>()
+        .await
+        .expect("Could not deserialize response body to JwkSet");
+    assert_eq!(jwks.keys.len(), 1);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reject_clients_with_invalid_redirect_uris() {
+    let mut app = TestApp::with_settings(oidc_settings).await;
+
+    let body = serde_json::json!({
+        "name": "Example App",
+        "redirectUris": ["http://example.com/callback"],
+    });
+    let response = app
+        .admin_request(reqwest::Method::POST, "/oauth-clients", Some(ADMIN_API_KEY))
+        .await
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), 400);
+
+    app.clean_up().await;
+}
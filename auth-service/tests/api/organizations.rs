@@ -0,0 +1,366 @@
+use crate::helpers::{TestApp, get_random_email};
+use auth_service::routes::{MeResponse, OrganizationResponse};
+use auth_service::settings::Settings;
+use reqwest::Url;
+use serde_json::json;
+
+const PUBLIC_URL: &str = "https://auth.example.com";
+
+async fn app() -> TestApp {
+    TestApp::with_settings(|settings: &mut Settings| {
+        settings.application.public_url = PUBLIC_URL.to_owned();
+    })
+    .await
+}
+
+// Logs into the account of `email` in `organization`, or outside any.
+async fn login(app: &TestApp, organization: Option<&str>, email: &str, password: &str) -> u16 {
+    let body = json!({
+        "email": email,
+        "password": password,
+        "organizationId": organization,
+    });
+    app.post_login(&body).await.status().as_u16()
+}
+
+// Creates an organization and logs its creator into their account in it.
+async fn create_organization(app: &TestApp, email: &str) -> OrganizationResponse {
+    let body = json!({ "name": "Acme", "password": "Kx7!vTq2Lm" });
+    let response = app.post_organization(&body).await;
+    assert_eq!(response.status(), 201);
+    let organization = response
+        .json::<OrganizationResponse>()
+        .await
+        .expect("Could not deserialize response body to OrganizationResponse");
+    assert_eq!(
+        login(app, Some(&organization.id), email, "Kx7!vTq2Lm").await,
+        200
+    );
+    organization
+}
+
+async fn get_organization(app: &TestApp, id: &str) -> OrganizationResponse {
+    let response = app.get_organization(id).await;
+    assert_eq!(response.status(), 200);
+    response
+        .json::<OrganizationResponse>()
+        .await
+        .expect("Could not deserialize response body to OrganizationResponse")
+}
+
+async fn me(app: &TestApp) -> MeResponse {
+    let response = app.get_me().await;
+    assert_eq!(response.status(), 200);
+    response
+        .json::<MeResponse>()
+        .await
+        .expect("Could not deserialize response body to MeResponse")
+}
+
+// Invites `email`, returning the token of the emailed link.
+async fn invite(app: &TestApp, id: &str, email: &str) -> String {
+    let response = app
+        .post_organization_invitation(id, &json!({ "email": email }))
+        .await;
+    assert_eq!(response.status(), 201);
+    let sent = app.email_client.sent_emails();
+    let sent = sent.last().expect("No invitation sent");
+    assert_eq!(sent.recipient, email);
+    let link = sent
+        .content
+        .split_whitespace()
+        .find(|word| word.starts_with(PUBLIC_URL))
+        .expect("No link in the email");
+    let link = Url::parse(link).unwrap();
+    link.query_pairs()
+        .find(|(name, _)| name == "org_invite")
+        .map(|(_, token)| token.into_owned())
+        .expect("No token in the link")
+}
+
+#[tokio::test]
+async fn should_create_an_organization_owned_by_its_creator() {
+    let mut app = app().await;
+    let email = get_random_email();
+    app.signup_and_login(&email).await;
+
+    let organization = create_organization(&app, &email).await;
+    assert_eq!(organization.name, "Acme");
+    assert_eq!(organization.owner_email, email);
+    assert_eq!(organization.members, vec![email.clone()]);
+    assert!(!organization.settings.require_2fa);
+    assert_eq!(organization.settings.min_password_length, 8);
+
+    assert_eq!(
+        me(&app).await.organization_id.as_deref(),
+        Some(organization.id.as_str())
+    );
+    assert_eq!(
+        get_organization(&app, &organization.id).await.members,
+        vec![email.clone()]
+    );
+
+    // the account outside the organization stays
+    assert_eq!(login(&app, None, &email, "Kx7!vTq2Lm").await, 200);
+    assert_eq!(me(&app).await.organization_id, None);
+    let body = json!({ "name": "Other", "password": "Kx7!vTq2Lm" });
+    assert_eq!(app.post_organization(&body).await.status(), 201);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_400_or_422_for_invalid_organizations() {
+    let mut app = app().await;
+    app.signup_and_login(&get_random_email()).await;
+
+    let test_cases = [
+        json!({ "name": " ", "password": "Kx7!vTq2Lm" }),
+        json!({ "name": "a".repeat(101), "password": "Kx7!vTq2Lm" }),
+        json!({ "name": "Acme", "password": "Kx7!vTq2Lm", "minPasswordLength": 7 }),
+        json!({ "name": "Acme", "password": "Kx7!vTq2Lm", "minPasswordLength": 129 }),
+        json!({ "name": "Acme", "password": "password" }),
+        // ten characters, too short for the organization
+        json!({ "name": "Acme", "password": "Kx7!vTq2Lm", "minPasswordLength": 12 }),
+    ];
+    for body in test_cases {
+        let response = app.post_organization(&body).await;
+        assert_eq!(response.status(), 400, "Failed for: {body}");
+    }
+    let test_cases = [
+        json!({ "password": "Kx7!vTq2Lm", "require2FA": true }),
+        json!({ "name": "Acme" }),
+    ];
+    for body in test_cases {
+        let response = app.post_organization(&body).await;
+        assert_eq!(response.status(), 422, "Failed for: {body}");
+    }
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_only_show_organizations_to_members_and_admins() {
+    let mut app = app().await;
+    let owner = get_random_email();
+    app.signup_and_login(&owner).await;
+    let organization = create_organization(&app, &owner).await;
+
+    // outside the organization, the owner is another account
+    assert_eq!(login(&app, None, &owner, "Kx7!vTq2Lm").await, 200);
+    assert_eq!(app.get_organization(&organization.id).await.status(), 404);
+
+    let outsider = get_random_email();
+    app.signup_and_login(&outsider).await;
+    assert_eq!(app.get_organization(&organization.id).await.status(), 404);
+    assert_eq!(app.get_organization("unknown").await.status(), 404);
+
+    app.set_roles(&outsider, &["admin"]).await;
+    assert_eq!(login(&app, None, &outsider, "Kx7!vTq2Lm").await, 200);
+    get_organization(&app, &organization.id).await;
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_only_let_the_owner_manage_the_organization() {
+    let mut app = app().await;
+    let owner = get_random_email();
+    app.signup_and_login(&owner).await;
+    let organization = create_organization(&app, &owner).await;
+    let settings = json!({ "require2FA": true, "minPasswordLength": 12 });
+
+    app.signup_and_login(&get_random_email()).await;
+    let response = app
+        .put_organization_settings(&organization.id, &settings)
+        .await;
+    assert_eq!(response.status(), 403);
+    let response = app
+        .post_organization_invitation(&organization.id, &json!({ "email": get_random_email() }))
+        .await;
+    assert_eq!(response.status(), 403);
+    // nor from their account outside the organization
+    assert_eq!(login(&app, None, &owner, "Kx7!vTq2Lm").await, 200);
+    let response = app
+        .put_organization_settings(&organization.id, &settings)
+        .await;
+    assert_eq!(response.status(), 403);
+
+    assert_eq!(
+        login(&app, Some(&organization.id), &owner, "Kx7!vTq2Lm").await,
+        200
+    );
+    let invalid = json!({ "require2FA": true, "minPasswordLength": 4 });
+    let response = app
+        .put_organization_settings(&organization.id, &invalid)
+        .await;
+    assert_eq!(response.status(), 400);
+    let response = app
+        .put_organization_settings(&organization.id, &settings)
+        .await;
+    assert_eq!(response.status(), 200);
+    let updated = get_organization(&app, &organization.id).await.settings;
+    assert!(updated.require_2fa);
+    assert_eq!(updated.min_password_length, 12);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_let_admins_manage_any_organization() {
+    let mut app = app().await;
+    let owner = get_random_email();
+    app.signup_and_login(&owner).await;
+    let organization = create_organization(&app, &owner).await;
+
+    let admin = get_random_email();
+    app.signup_and_login(&admin).await;
+    app.set_roles(&admin, &["admin"]).await;
+    assert_eq!(login(&app, None, &admin, "Kx7!vTq2Lm").await, 200);
+    let settings = json!({ "require2FA": true, "minPasswordLength": 12 });
+    let response = app
+        .put_organization_settings(&organization.id, &settings)
+        .await;
+    assert_eq!(response.status(), 200);
+    invite(&app, &organization.id, &get_random_email()).await;
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_create_the_account_of_an_invited_user() {
+    let mut app = app().await;
+    let owner = get_random_email();
+    app.signup_and_login(&owner).await;
+    let organization = create_organization(&app, &owner).await;
+    let settings = json!({ "require2FA": false, "minPasswordLength": 12 });
+    app.put_organization_settings(&organization.id, &settings)
+        .await;
+
+    let email = get_random_email();
+    let token = invite(&app, &organization.id, &email).await;
+
+    // ten characters, too short for the organization
+    let body = json!({ "token": token, "password": "Kx7!vTq2Lm" });
+    assert_eq!(app.post_accept_invitation(&body).await.status(), 400);
+    let body = json!({ "token": token, "password": "Hb3$kWz9Qd5!pL" });
+    let response = app.post_accept_invitation(&body).await;
+    assert_eq!(response.status(), 201);
+    let user = response
+        .json::<MeResponse>()
+        .await
+        .expect("Could not deserialize response body to MeResponse");
+    assert_eq!(user.email, email);
+    assert_eq!(user.organization_id, Some(organization.id.clone()));
+    assert!(user.email_verified);
+
+    // used up
+    assert_eq!(app.post_accept_invitation(&body).await.status(), 400);
+
+    assert_eq!(login(&app, None, &email, "Hb3$kWz9Qd5!pL").await, 401);
+    assert_eq!(
+        login(&app, Some(&organization.id), &email, "Hb3$kWz9Qd5!pL").await,
+        200
+    );
+    let mut members = vec![owner, email];
+    members.sort();
+    assert_eq!(
+        get_organization(&app, &organization.id).await.members,
+        members
+    );
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_give_existing_users_a_separate_account_in_the_organization() {
+    let mut app = app().await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+    let owner = get_random_email();
+    app.signup_and_login(&owner).await;
+    let organization = create_organization(&app, &owner).await;
+    let token = invite(&app, &organization.id, &email).await;
+
+    let body = json!({ "token": token, "password": "Zt6&cJm1Xr8@" });
+    assert_eq!(app.post_accept_invitation(&body).await.status(), 201);
+
+    assert_eq!(
+        login(&app, Some(&organization.id), &email, "Kx7!vTq2Lm").await,
+        401
+    );
+    assert_eq!(
+        login(&app, Some(&organization.id), &email, "Zt6&cJm1Xr8@").await,
+        200
+    );
+    assert_eq!(me(&app).await.organization_id, Some(organization.id));
+    assert_eq!(login(&app, None, &email, "Kx7!vTq2Lm").await, 200);
+    assert_eq!(me(&app).await.organization_id, None);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_refuse_a_second_account_in_the_same_organization() {
+    let mut app = app().await;
+    let email = get_random_email();
+    app.signup_and_login(&email).await;
+    // members of another organization can join
+    create_organization(&app, &email).await;
+    let owner = get_random_email();
+    app.signup_and_login(&owner).await;
+    let organization = create_organization(&app, &owner).await;
+    let first = invite(&app, &organization.id, &email).await;
+    let second = invite(&app, &organization.id, &email).await;
+
+    let body = json!({ "token": first, "password": "Kx7!vTq2Lm" });
+    assert_eq!(app.post_accept_invitation(&body).await.status(), 201);
+    let body = json!({ "token": second, "password": "Kx7!vTq2Lm" });
+    assert_eq!(app.post_accept_invitation(&body).await.status(), 409);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reject_unknown_invitations() {
+    let mut app = app().await;
+
+    let test_cases = [
+        (json!({ "token": "unknown", "password": "Kx7!vTq2Lm" }), 400),
+        (json!({ "token": "unknown" }), 422),
+    ];
+    for (body, status) in test_cases {
+        let response = app.post_accept_invitation(&body).await;
+        assert_eq!(response.status(), status, "Failed for: {body}");
+    }
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_ask_members_for_2fa_when_the_organization_requires_it() {
+    let mut app = app().await;
+    let member = get_random_email();
+    let owner = get_random_email();
+    app.signup_and_login(&owner).await;
+    let organization = create_organization(&app, &owner).await;
+    let token = invite(&app, &organization.id, &member).await;
+    let body = json!({ "token": token, "password": "Kx7!vTq2Lm" });
+    assert_eq!(app.post_accept_invitation(&body).await.status(), 201);
+
+    let id = Some(organization.id.as_str());
+    assert_eq!(login(&app, id, &member, "Kx7!vTq2Lm").await, 200);
+    assert_eq!(login(&app, id, &owner, "Kx7!vTq2Lm").await, 200);
+    let settings = json!({ "require2FA": true, "minPasswordLength": 8 });
+    let response = app
+        .put_organization_settings(&organization.id, &settings)
+        .await;
+    assert_eq!(response.status(), 200);
+
+    assert_eq!(login(&app, id, &member, "Kx7!vTq2Lm").await, 206);
+    assert_eq!(login(&app, id, &owner, "Kx7!vTq2Lm").await, 206);
+    // their accounts outside the organization aren't affected
+    assert_eq!(login(&app, None, &owner, "Kx7!vTq2Lm").await, 200);
+
+    app.clean_up().await;
+}
@@ -0,0 +1,107 @@
+use crate::helpers::{ADMIN_API_KEY, TestApp, get_random_email};
+use auth_service::domain::error::ErrorResponse;
+use auth_service::settings::Settings;
+use reqwest::Method;
+use serde_json::json;
+
+const PUBLIC_URL: &str = "https://auth.example.com";
+
+async fn app() -> TestApp {
+    TestApp::with_settings(|settings: &mut Settings| {
+        settings.admin.api_key = ADMIN_API_KEY.to_owned();
+        settings.application.public_url = PUBLIC_URL.to_owned();
+    })
+    .await
+}
+
+// Signs up a user and has an admin reset their password, returning the token
+// of the emailed link.
+async fn reset(app: &TestApp) -> (String, String) {
+    let email = get_random_email();
+    let body = json!({ "email": email, "password": "Kx7!vTq2Lm", "requires2FA": false });
+    assert_eq!(app.post_signup(&body).await.status(), 201);
+    let response = app
+        .admin_request(
+            Method::POST,
+            &format!("/users/{email}/password-reset"),
+            Some(ADMIN_API_KEY),
+        )
+        .await;
+    assert_eq!(response.send().await.unwrap().status(), 200);
+
+    let sent = app.email_client.sent_emails();
+    let link = sent
+        .last()
+        .expect("No reset email sent")
+        .content
+        .split_whitespace()
+        .find(|word| word.starts_with(PUBLIC_URL))
+        .expect("No link in the email")
+        .to_owned();
+    let token = link
+        .split_once("#password_reset=")
+        .expect("No token in the link")
+        .1
+        .to_owned();
+    (email, token)
+}
+
+async fn login(app: &TestApp, email: &str, password: &str) -> reqwest::Response {
+    app.post_login(&json!({ "email": email, "password": password }))
+        .await
+}
+
+#[tokio::test]
+async fn should_set_the_new_password_once() {
+    let mut app = app().await;
+    let (email, token) = reset(&app).await;
+
+    let body = json!({ "token": token, "password": "Another-Secret7" });
+    assert_eq!(app.post_password_reset(&body).await.status(), 200);
+    assert_eq!(login(&app, &email, "Another-Secret7").await.status(), 200);
+
+    let body = json!({ "token": token, "password": "Yet-Another-Secret8" });
+    assert_eq!(app.post_password_reset(&body).await.status(), 401);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_keep_the_link_when_the_password_is_rejected() {
+    let mut app = app().await;
+    let (email, token) = reset(&app).await;
+
+    let response = app
+        .post_password_reset(&json!({ "token": token, "password": "short" }))
+        .await;
+    assert_eq!(response.status(), 400);
+    let error = response
+        .json::<ErrorResponse>()
+        .await
+        .expect("Could not deserialize response body to ErrorResponse");
+    assert_eq!(error.errors[0].code, "too_short");
+
+    let body = json!({ "token": token, "password": "Another-Secret7" });
+    assert_eq!(app.post_password_reset(&body).await.status(), 200);
+    assert_eq!(login(&app, &email, "Another-Secret7").await.status(), 200);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reject_unknown_tokens() {
+    let mut app = app().await;
+    let body = json!({ "token": "unknown", "password": "Another-Secret7" });
+    assert_eq!(app.post_password_reset(&body).await.status(), 401);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_not_accept_a_reset_link_as_a_sign_in_link() {
+    let mut app = app().await;
+    let (_, token) = reset(&app).await;
+
+    let response = app
+        .get_magic_link_callback(&format!("?token={token}"))
+        .await;
+    assert_eq!(response.status(), 400);
+    app.clean_up().await;
+}
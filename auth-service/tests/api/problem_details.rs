@@ -0,0 +1,236 @@
+use crate::helpers::{TestApp, get_random_email};
+
+use auth_service::domain::AccountStatus;
+use auth_service::domain::error::{ErrorResponse, ProblemDetails};
+use auth_service::settings::Settings;
+use auth_service::utils::problem::PROBLEM_JSON;
+use reqwest::header::{ACCEPT, ALLOW, CONTENT_TYPE, VARY};
+
+async fn app() -> TestApp {
+    TestApp::with_settings(|settings: &mut Settings| {
+        settings.application.public_url = "https://auth.example.com".to_owned();
+    })
+    .await
+}
+
+#[tokio::test]
+async fn should_keep_the_error_body_by_default() {
+    let mut app = app().await;
+    let response = app.get_me().await;
+    assert_eq!(response.status(), 400);
+    assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+    assert_eq!(response.headers()[VARY], "accept");
+    let error = response
+        .json::<ErrorResponse>()
+        .await
+        .expect("Could not deserialize response body to ErrorResponse");
+    assert_eq!(error.error, "Invalid input");
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_problem_details_when_accepted() {
+    let mut app = app().await;
+    let response = app
+        .http_client
+        .get(format!("{}/me", app.address))
+        .header(ACCEPT, "application/problem+json, application/json;q=0.5")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    // a missing token is a 401 here, the error body keeps its 400
+    assert_eq!(response.status(), 401);
+    assert_eq!(response.headers()[CONTENT_TYPE], PROBLEM_JSON);
+    let problem = response
+        .json::<ProblemDetails>()
+        .await
+        .expect("Could not deserialize response body to ProblemDetails");
+    assert_eq!(
+        problem.problem_type,
+        "tag:auth.example.com,2026:problems/missing-token"
+    );
+    assert_eq!(problem.title, "Missing token");
+    assert_eq!(problem.status, 401);
+    assert!(problem.detail.is_some());
+    assert!(problem.instance.starts_with("urn:uuid:"));
+    assert!(problem.errors.is_empty());
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_give_each_occurrence_its_instance() {
+    let mut app = app().await;
+    let mut instances = Vec::new();
+    for _ in 0..2 {
+        let problem = app
+            .http_client
+            .get(format!("{}/me", app.address))
+            .header(ACCEPT, PROBLEM_JSON)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .json::<ProblemDetails>()
+            .await
+            .expect("Could not deserialize response body to ProblemDetails");
+        instances.push(problem.instance);
+    }
+    assert_ne!(instances[0], instances[1]);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_include_the_field_errors() {
+    let mut app = app().await;
+    let body = serde_json::json!({
+        "email": "wrong.email.com",
+        "password": "Kx7!vTq2Lm",
+        "requires2FA": false
+    });
+    let response = app
+        .http_client
+        .post(format!("{}/signup", app.address))
+        .header(ACCEPT, PROBLEM_JSON)
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), 400);
+    let problem = response
+        .json::<ProblemDetails>()
+        .await
+        .expect("Could not deserialize response body to ProblemDetails");
+    assert_eq!(
+        problem.problem_type,
+        "tag:auth.example.com,2026:problems/invalid-fields"
+    );
+    assert_eq!(problem.title, "Invalid input");
+    assert_eq!(problem.errors.len(), 1);
+    assert_eq!(problem.errors[0].field, "email");
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_tell_locked_accounts_they_are_locked() {
+    let mut app = app().await;
+    let email = get_random_email();
+    app.signup(&email, false).await;
+    app.set_status(&email, AccountStatus::Locked).await;
+    let response = app
+        .http_client
+        .post(format!("{}/login", app.address))
+        .header(ACCEPT, PROBLEM_JSON)
+        .json(&serde_json::json!({ "email": email, "password": "Kx7!vTq2Lm" }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), 423);
+    let problem = response
+        .json::<ProblemDetails>()
+        .await
+        .expect("Could not deserialize response body to ProblemDetails");
+    assert_eq!(
+        problem.problem_type,
+        "tag:auth.example.com,2026:problems/account-locked"
+    );
+    assert_eq!(
+        problem.detail.as_deref(),
+        Some("The account is temporarily locked.")
+    );
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_not_change_successful_responses() {
+    let mut app = app().await;
+    let response = app
+        .http_client
+        .get(format!("{}/csrf-token", app.address))
+        .header(ACCEPT, PROBLEM_JSON)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), 200);
+    assert_ne!(response.headers()[CONTENT_TYPE], PROBLEM_JSON);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_problem_details_for_rejected_requests() {
+    let mut app = app().await;
+    let requests = [
+        // a query without its token
+        app.http_client
+            .get(format!("{}/login/magic-link/callback", app.address)),
+        // a JSON body without its content type
+        app.http_client
+            .post(format!("{}/signup", app.address))
+            .body(r#"{"email": "user@example.com"}"#),
+    ];
+    for request in requests {
+        let response = request
+            .header(ACCEPT, PROBLEM_JSON)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(response.status(), 400);
+        assert_eq!(response.headers()[CONTENT_TYPE], PROBLEM_JSON);
+        let problem = response
+            .json::<ProblemDetails>()
+            .await
+            .expect("Could not deserialize response body to ProblemDetails");
+        assert_eq!(
+            problem.problem_type,
+            "tag:auth.example.com,2026:problems/invalid-input"
+        );
+    }
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_problem_details_for_unknown_routes() {
+    let mut app = app().await;
+    let response = app
+        .http_client
+        .get(format!("{}/unknown", app.address))
+        .header(ACCEPT, PROBLEM_JSON)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), 404);
+    let problem = response
+        .json::<ProblemDetails>()
+        .await
+        .expect("Could not deserialize response body to ProblemDetails");
+    assert_eq!(
+        problem.problem_type,
+        "tag:auth.example.com,2026:problems/route-not-found"
+    );
+
+    let response = app
+        .http_client
+        .delete(format!("{}/signup", app.address))
+        .header(ACCEPT, PROBLEM_JSON)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), 405);
+    assert_eq!(response.headers()[ALLOW], "POST");
+    let problem = response
+        .json::<ProblemDetails>()
+        .await
+        .expect("Could not deserialize response body to ProblemDetails");
+    assert_eq!(
+        problem.problem_type,
+        "tag:auth.example.com,2026:problems/method-not-allowed"
+    );
+
+    // assets are still served
+    let response = app
+        .http_client
+        .get(format!("{}/index.html", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), 200);
+    app.clean_up().await;
+}
@@ -1,8 +1,9 @@
 use crate::helpers::TestApp;
 use crate::helpers::get_random_email;
 
-use auth_service::domain::error::ErrorResponse;
+use auth_service::domain::error::{ErrorResponse, FieldError};
 use auth_service::routes::SignupResponse;
+use auth_service::settings::Settings;
 
 #[tokio::test]
 async fn should_return_400_if_invalid_input() {
@@ -12,51 +13,113 @@ async fn should_return_400_if_invalid_input() {
         // email: no @
         serde_json::json!({
             "email": "wrong.email.com",
-            "password": "Password1!",
+            "password": "Kx7!vTq2Lm",
             "requires2FA": true
         }),
         // email: 2 @
         serde_json::json!({
             "email": "two@at@symbols",
-            "password": "Password1!",
-            "requires2FA": true
-        }),
-        // password: less than 8 characters
-        serde_json::json!({
-            "email": get_random_email(),
-            "password": "Small!",
-            "requires2FA": true
-        }),
-        // password: no special character
-        serde_json::json!({
-            "email": get_random_email(),
-            "password": "NoSpecialCharacters",
-            "requires2FA": true
-        }),
-        // password: no number
-        serde_json::json!({
-            "email": get_random_email(),
-            "password": "NoNumber!",
-            "requires2FA": true
-        }),
-        // password: no capital letter
-        serde_json::json!({
-            "email": get_random_email(),
-            "password": "no_capital_letter",
+            "password": "Kx7!vTq2Lm",
             "requires2FA": true
         }),
     ];
     for tc in test_cases {
         let response = app.post_signup(&tc).await;
         assert_eq!(response.status(), 400, "Failed for input: {:?}", tc);
-        assert_eq!(
-            response
-                .json::<ErrorResponse>()
-                .await
-                .expect("Could not deserialize response body to ErrorResponse")
-                .error,
-            "Invalid credentials".to_owned()
-        )
+        let error = response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse");
+        assert_eq!(error.error, "Invalid input");
+        assert_eq!(error.errors, vec![FieldError::invalid_email("email")]);
+    }
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_an_error_for_each_invalid_field() {
+    let mut app = TestApp::new().await;
+    let body = serde_json::json!({
+        "email": "wrong.email.com",
+        "password": "Small1!",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&body).await;
+    assert_eq!(response.status(), 400);
+    let error = response
+        .json::<ErrorResponse>()
+        .await
+        .expect("Could not deserialize response body to ErrorResponse");
+    let fields: Vec<_> = error
+        .errors
+        .iter()
+        .map(|e| (e.field.as_str(), e.code.as_str()))
+        .collect();
+    assert_eq!(
+        fields,
+        [("email", "invalid_email"), ("password", "too_short")]
+    );
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_the_failing_password_rule() {
+    let mut app = TestApp::new().await;
+
+    let test_cases = [
+        ("Small1!", "too_short"),
+        ("no_capital_letter1", "missing_uppercase"),
+        ("NoNumber!", "missing_digit"),
+        ("NoSpecialChars1", "missing_special"),
+        ("P@ssw0rd1", "breached"),
+    ];
+    for (password, code) in test_cases {
+        let body = serde_json::json!({
+            "email": get_random_email(),
+            "password": password,
+            "requires2FA": false
+        });
+        let response = app.post_signup(&body).await;
+        assert_eq!(response.status(), 400, "Failed for: {password}");
+        let error = response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse");
+        let [field_error] = error.errors.as_slice() else {
+            panic!(
+                "Expected one field error for {password}: {:?}",
+                error.errors
+            );
+        };
+        assert_eq!(field_error.field, "password");
+        assert_eq!(field_error.code, code, "Failed for: {password}");
+        assert!(field_error.message.starts_with("Password "));
+    }
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_follow_the_configured_password_policy() {
+    let mut app = TestApp::with_settings(|settings: &mut Settings| {
+        settings.auth.password.min_length = 12;
+        settings.auth.password.passphrase_length = 16;
+    })
+    .await;
+
+    let test_cases = [
+        ("Kx7!vTq2Lm", 400),
+        // long passphrases in any script skip the character classes
+        ("ein langer satz über schnee", 201),
+        ("とても長くて覚えやすい日本語のパスフレーズです", 201),
+    ];
+    for (password, status) in test_cases {
+        let body = serde_json::json!({
+            "email": get_random_email(),
+            "password": password,
+            "requires2FA": false
+        });
+        let response = app.post_signup(&body).await;
+        assert_eq!(response.status(), status, "Failed for: {password}");
     }
     app.clean_up().await;
 }
@@ -66,7 +129,7 @@ async fn should_return_409_if_email_already_exists() {
     let mut app = TestApp::new().await;
     let body = serde_json::json!({
         "email": get_random_email(),
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
         "requires2FA": true
     });
     app.post_signup(&body).await;
@@ -89,23 +152,52 @@ async fn should_return_422_if_malformed_input() {
 
     let random_email = get_random_email();
     let test_cases = [
-        serde_json::json!({
-            "password": "Password1!",
-            "requires2FA": true
-        }),
-        serde_json::json!({
-            "email": random_email,
-            "password": "Password1!"
-        }),
-        serde_json::json!({
-            "email": random_email,
-            "requires2FA": true
-        }),
+        (
+            serde_json::json!({
+                "password": "Kx7!vTq2Lm",
+                "requires2FA": true
+            }),
+            "email",
+            "missing_field",
+        ),
+        (
+            serde_json::json!({
+                "email": random_email,
+                "password": "Kx7!vTq2Lm"
+            }),
+            "requires2FA",
+            "missing_field",
+        ),
+        (
+            serde_json::json!({
+                "email": random_email,
+                "requires2FA": true
+            }),
+            "password",
+            "missing_field",
+        ),
+        (
+            serde_json::json!({
+                "email": random_email,
+                "password": "Kx7!vTq2Lm",
+                "requires2FA": "yes"
+            }),
+            "requires2FA",
+            "invalid_type",
+        ),
     ];
 
-    for tc in test_cases {
+    for (tc, field, code) in test_cases {
         let response = app.post_signup(&tc).await;
         assert_eq!(response.status(), 422, "Failed for input: {:?}", tc);
+        let error = response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse");
+        assert_eq!(error.error, "Malformed request body");
+        assert_eq!(error.errors.len(), 1, "Failed for input: {:?}", tc);
+        assert_eq!(error.errors[0].field, field, "Failed for input: {:?}", tc);
+        assert_eq!(error.errors[0].code, code, "Failed for input: {:?}", tc);
     }
     app.clean_up().await;
 }
@@ -117,7 +209,7 @@ async fn should_return_201_if_valid_input() {
 
     let body = serde_json::json!({
         "email": random_email,
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
         "requires2FA": true
     });
     let response = app.post_signup(&body).await;
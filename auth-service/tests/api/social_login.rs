@@ -0,0 +1,421 @@
+use crate::helpers::{TestApp, get_random_email, location};
+use auth_service::domain::{AuditEventType, PasswordPolicy, User, UserStore, pkce_challenge};
+use auth_service::routes::{ActivityResponse, MeResponse};
+use auth_service::settings::{Settings, SocialProviderSettings};
+use auth_service::utils::oidc::{SigningKey, generate_signing_key};
+use axum::{
+    Form, Json, Router,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+const PUBLIC_URL: &str = "https://auth.example.com";
+const PROVIDER: &str = "fake";
+const CLIENT_ID: &str = "auth-service";
+const CODE: &str = "a-code-from-the-provider";
+
+/// A local OpenID Connect provider, handing out the ID token claims the test
+/// sets for the next login.
+#[derive(Clone)]
+struct FakeIssuer {
+    url: String,
+    key: Arc<SigningKey>,
+    claims: Arc<Mutex<Value>>,
+    code_challenge: Arc<Mutex<String>>,
+}
+
+impl FakeIssuer {
+    async fn start() -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = Self {
+            url: format!("http://{}", listener.local_addr().unwrap()),
+            key: Arc::new(SigningKey::from_base64_pkcs8(&generate_signing_key()).unwrap()),
+            claims: Arc::default(),
+            code_challenge: Arc::default(),
+        };
+        let router = Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/jwks", get(jwks))
+            .route("/token", post(token))
+            .with_state(issuer.clone());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        issuer
+    }
+
+    fn provider_settings(&self) -> SocialProviderSettings {
+        SocialProviderSettings {
+            kind: "oidc".parse().unwrap(),
+            issuer: self.url.clone(),
+            client_id: CLIENT_ID.to_owned(),
+            client_secret: "a-client-secret".to_owned(),
+            scopes: Vec::new(),
+        }
+    }
+
+    fn set_claims(&self, claims: Value) {
+        *self.claims.lock().unwrap() = claims;
+    }
+}
+
+async fn discovery(State(issuer): State<FakeIssuer>) -> Json<Value> {
+    Json(json!({
+        "issuer": issuer.url,
+        "authorization_endpoint": format!("{}/authorize", issuer.url),
+        "token_endpoint": format!("{}/token", issuer.url),
+        "jwks_uri": format!("{}/jwks", issuer.url),
+    }))
+}
+
+async fn jwks(State(issuer): State<FakeIssuer>) -> Response {
+    Json(issuer.key.jwks()).into_response()
+}
+
+async fn token(
+    State(issuer): State<FakeIssuer>,
+    Form(form): Form<HashMap<String, String>>,
+) -> Response {
+    let code_challenge = issuer.code_challenge.lock().unwrap().clone();
+    let valid = form.get("code").map(String::as_str) == Some(CODE)
+        && form.get("client_id").map(String::as_str) == Some(CLIENT_ID)
+        && form
+            .get("code_verifier")
+            .is_some_and(|verifier| pkce_challenge(verifier) == code_challenge);
+    if !valid {
+        let body = Json(json!({ "error": "invalid_grant" }));
+        return (StatusCode::BAD_REQUEST, body).into_response();
+    }
+    let claims = issuer.claims.lock().unwrap().clone();
+    Json(json!({
+        "access_token": "an-access-token",
+        "token_type": "Bearer",
+        "id_token": issuer.key.sign(&claims).unwrap(),
+    }))
+    .into_response()
+}
+
+async fn app_with_provider() -> (TestApp, FakeIssuer) {
+    let issuer = FakeIssuer::start().await;
+    let provider = issuer.provider_settings();
+    let app = TestApp::with_settings(|settings: &mut Settings| {
+        settings.application.public_url = PUBLIC_URL.to_owned();
+        settings
+            .social
+            .providers
+            .insert(PROVIDER.to_owned(), provider);
+    })
+    .await;
+    (app, issuer)
+}
+
+// Starts a login, returning the parameters sent to the provider.
+async fn start_login(app: &TestApp, issuer: &FakeIssuer, query: &str) -> HashMap<String, String> {
+    let response = app.get_social_login(PROVIDER, query).await;
+    assert_eq!(response.status(), 303);
+    let params: HashMap<String, String> = location(&response).query_pairs().into_owned().collect();
+    *issuer.code_challenge.lock().unwrap() = params["code_challenge"].clone();
+    params
+}
+
+fn id_token_claims(
+    issuer: &FakeIssuer,
+    nonce: &str,
+    subject: &str,
+    email: &str,
+    email_verified: bool,
+) -> Value {
+    json!({
+        "iss": issuer.url,
+        "aud": CLIENT_ID,
+        "exp": chrono::Utc::now().timestamp() + 60,
+        "sub": subject,
+        "email": email,
+        "email_verified": email_verified,
+        "nonce": nonce,
+    })
+}
+
+// Logs in at the provider as `subject`, returning the callback's response.
+async fn login_as(
+    app: &TestApp,
+    issuer: &FakeIssuer,
+    subject: &str,
+    email: &str,
+    email_verified: bool,
+) -> reqwest::Response {
+    let params = start_login(app, issuer, "").await;
+    issuer.set_claims(id_token_claims(
+        issuer,
+        &params["nonce"],
+        subject,
+        email,
+        email_verified,
+    ));
+    let query = format!("?code={CODE}&state={}", params["state"]);
+    app.get_social_callback(PROVIDER, &query).await
+}
+
+async fn me(app: &TestApp) -> MeResponse {
+    let response = app.get_me().await;
+    assert_eq!(response.status(), 200);
+    response
+        .json::<MeResponse>()
+        .await
+        .expect("Could not deserialize response body to MeResponse")
+}
+
+#[tokio::test]
+async fn should_return_404_for_unknown_provider() {
+    let (mut app, _issuer) = app_with_provider().await;
+
+    assert_eq!(app.get_social_login("unknown", "").await.status(), 404);
+    assert_eq!(app.get_social_callback("unknown", "").await.status(), 404);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_send_the_user_to_the_provider() {
+    let (mut app, issuer) = app_with_provider().await;
+
+    let response = app.get_social_login(PROVIDER, "").await;
+    assert_eq!(response.status(), 303);
+    let cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == "social_login")
+        .expect("No social_login cookie");
+    assert!(cookie.http_only());
+    let url = location(&response);
+    assert!(
+        url.as_str()
+            .starts_with(&format!("{}/authorize", issuer.url))
+    );
+    let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    assert_eq!(params["client_id"], CLIENT_ID);
+    assert_eq!(
+        params["redirect_uri"],
+        format!("{PUBLIC_URL}/callback/{PROVIDER}")
+    );
+    assert_eq!(params["scope"], "openid email profile");
+    assert_eq!(params["code_challenge_method"], "S256");
+    for param in ["state", "nonce", "code_challenge"] {
+        assert!(!params[param].is_empty(), "missing {param}");
+    }
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_create_and_link_a_user_on_first_login() {
+    let (mut app, issuer) = app_with_provider().await;
+    let email = get_random_email();
+
+    let response = login_as(&app, &issuer, "subject-1", &email, true).await;
+    assert_eq!(response.status(), 303);
+    assert_eq!(location(&response).as_str(), format!("{PUBLIC_URL}/"));
+    let user = me(&app).await;
+    assert_eq!(user.email, email);
+    assert!(user.email_verified);
+
+    // the link follows the subject, even when the email changes at the provider
+    app.post_logout().await;
+    let response = login_as(&app, &issuer, "subject-1", &get_random_email(), true).await;
+    assert_eq!(response.status(), 303);
+    assert_eq!(me(&app).await.email, email);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_link_existing_user_by_verified_email() {
+    let (mut app, issuer) = app_with_provider().await;
+    let email = get_random_email();
+    let body = json!({ "email": email, "password": "Kx7!vTq2Lm", "requires2FA": false });
+    assert_eq!(app.post_signup(&body).await.status(), 201);
+
+    let response = login_as(&app, &issuer, "subject-2", &email, true).await;
+    assert_eq!(response.status(), 303);
+    assert_eq!(me(&app).await.email, email);
+    let activity = app
+        .get_my_activity("")
+        .await
+        .json::<ActivityResponse>()
+        .await
+        .expect("Could not deserialize response body to ActivityResponse");
+    let events: Vec<_> = activity.events.iter().map(|e| e.event_type).collect();
+    assert!(
+        events.contains(&AuditEventType::AccountLinked),
+        "{events:?}"
+    );
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_only_sign_in_existing_users_when_registration_is_closed() {
+    let issuer = FakeIssuer::start().await;
+    let provider = issuer.provider_settings();
+    let mut app = TestApp::with_settings(|settings: &mut Settings| {
+        settings.application.public_url = PUBLIC_URL.to_owned();
+        settings.auth.open_registration = false;
+        settings
+            .social
+            .providers
+            .insert(PROVIDER.to_owned(), provider);
+    })
+    .await;
+
+    let response = login_as(&app, &issuer, "subject-4", &get_random_email(), true).await;
+    assert_eq!(response.status(), 403);
+    assert_eq!(app.get_me().await.status(), 400);
+
+    // created by the test, not signed up
+    let email = get_random_email();
+    let user = User::parse(
+        email.clone(),
+        "Kx7!vTq2Lm".to_owned(),
+        false,
+        &PasswordPolicy::default(),
+    )
+    .unwrap();
+    app.user_store.write().await.add_user(user).await.unwrap();
+    let response = login_as(&app, &issuer, "subject-5", &email, true).await;
+    assert_eq!(response.status(), 303);
+    assert_eq!(me(&app).await.email, email);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reject_unverified_email() {
+    let (mut app, issuer) = app_with_provider().await;
+    let email = get_random_email();
+    let body = json!({ "email": email, "password": "Kx7!vTq2Lm", "requires2FA": false });
+    assert_eq!(app.post_signup(&body).await.status(), 201);
+
+    let response = login_as(&app, &issuer, "subject-3", &email, false).await;
+    assert_eq!(response.status(), 403);
+    assert_eq!(app.get_me().await.status(), 400);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reject_invalid_state_and_nonce() {
+    let (mut app, issuer) = app_with_provider().await;
+    let email = get_random_email();
+
+    // a state from another login
+    let params = start_login(&app, &issuer, "").await;
+    issuer.set_claims(id_token_claims(
+        &issuer,
+        &params["nonce"],
+        "s",
+        &email,
+        true,
+    ));
+    let response = app
+        .get_social_callback(PROVIDER, &format!("?code={CODE}&state=forged"))
+        .await;
+    assert_eq!(response.status(), 400);
+    // the cookie is single use
+    let query = format!("?code={CODE}&state={}", params["state"]);
+    assert_eq!(
+        app.get_social_callback(PROVIDER, &query).await.status(),
+        400
+    );
+
+    // an ID token issued for another login
+    let params = start_login(&app, &issuer, "").await;
+    issuer.set_claims(id_token_claims(&issuer, "another-nonce", "s", &email, true));
+    let query = format!("?code={CODE}&state={}", params["state"]);
+    assert_eq!(
+        app.get_social_callback(PROVIDER, &query).await.status(),
+        401
+    );
+
+    // the user said no at the provider
+    let params = start_login(&app, &issuer, "").await;
+    let query = format!("?error=access_denied&state={}", params["state"]);
+    assert_eq!(
+        app.get_social_callback(PROVIDER, &query).await.status(),
+        401
+    );
+
+    assert_eq!(app.get_me().await.status(), 400);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_only_return_to_own_urls() {
+    let (mut app, issuer) = app_with_provider().await;
+
+    let return_to = format!("{PUBLIC_URL}/authorize?client_id=app");
+    let query = format!("?return_to={}", urlencode(&return_to));
+    let params = start_login(&app, &issuer, &query).await;
+    issuer.set_claims(id_token_claims(
+        &issuer,
+        &params["nonce"],
+        "subject-4",
+        &get_random_email(),
+        true,
+    ));
+    let query = format!("?code={CODE}&state={}", params["state"]);
+    let response = app.get_social_callback(PROVIDER, &query).await;
+    assert_eq!(location(&response).as_str(), return_to);
+
+    let query = format!("?return_to={}", urlencode("https://evil.example.com/"));
+    let params = start_login(&app, &issuer, &query).await;
+    issuer.set_claims(id_token_claims(
+        &issuer,
+        &params["nonce"],
+        "subject-4",
+        "unused@example.com",
+        true,
+    ));
+    let query = format!("?code={CODE}&state={}", params["state"]);
+    let response = app.get_social_callback(PROVIDER, &query).await;
+    assert_eq!(location(&response).as_str(), format!("{PUBLIC_URL}/"));
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_ask_2fa_users_for_a_code() {
+    let (mut app, issuer) = app_with_provider().await;
+    let email = get_random_email();
+    let body = json!({ "email": email, "password": "Kx7!vTq2Lm", "requires2FA": true });
+    assert_eq!(app.post_signup(&body).await.status(), 201);
+
+    let response = login_as(&app, &issuer, "subject-5", &email, true).await;
+    assert_eq!(response.status(), 303);
+    let url = location(&response);
+    assert_eq!(url.path(), "/");
+    let fragment: HashMap<String, String> =
+        url::form_urlencoded::parse(url.fragment().unwrap_or_default().as_bytes())
+            .into_owned()
+            .collect();
+    assert_eq!(fragment["email"], email);
+    let sent = app.email_client.sent_emails();
+    let code = &sent.last().expect("No 2FA code sent").content;
+    assert_eq!(app.get_me().await.status(), 400);
+
+    let body = json!({
+        "email": email,
+        "loginAttemptId": fragment["login_attempt_id"],
+        "2FACode": code,
+    });
+    assert_eq!(app.post_verify_2fa(&body).await.status(), 200);
+    assert_eq!(me(&app).await.email, email);
+
+    app.clean_up().await;
+}
+
+fn urlencode(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
@@ -0,0 +1,147 @@
+use crate::helpers::{TestApp, get_random_email};
+use auth_service::domain::{TwoFACodeStore, UserKey};
+use auth_service::routes::{TrustedDevicesResponse, TwoFactorAuthResponse};
+use auth_service::utils::constants::TRUSTED_DEVICE_COOKIE_NAME;
+
+async fn login(app: &TestApp, email: &str) -> reqwest::Response {
+    let body = serde_json::json!({ "email": email, "password": "Kx7!vTq2Lm" });
+    app.post_login(&body).await
+}
+
+// Logs in through the 2FA step, optionally asking to remember the browser.
+async fn login_with_2fa(app: &TestApp, email: &str, remember_device: bool) -> reqwest::Response {
+    let response = login(app, email).await;
+    assert_eq!(response.status(), 206);
+    response
+        .json::<TwoFactorAuthResponse>()
+        .await
+        .expect("Could not deserialize response body to TwoFactorAuthResponse");
+    let (login_attempt_id, two_fa_code) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&UserKey::new(None, email))
+        .await
+        .unwrap();
+    let body = serde_json::json!({
+        "email": email,
+        "loginAttemptId": login_attempt_id.as_ref(),
+        "2FACode": two_fa_code.as_ref(),
+        "rememberDevice": remember_device,
+    });
+    app.post_verify_2fa(&body).await
+}
+
+async fn trusted_devices(app: &TestApp) -> TrustedDevicesResponse {
+    let response = app.get_trusted_devices().await;
+    assert_eq!(response.status(), 200);
+    response
+        .json::<TrustedDevicesResponse>()
+        .await
+        .expect("Could not deserialize response body to TrustedDevicesResponse")
+}
+
+#[tokio::test]
+async fn should_skip_2fa_on_remembered_device() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    app.signup(&email, true).await;
+
+    let response = login_with_2fa(&app, &email, true).await;
+    assert_eq!(response.status(), 200);
+    assert!(
+        response
+            .cookies()
+            .any(|cookie| cookie.name() == TRUSTED_DEVICE_COOKIE_NAME)
+    );
+
+    assert_eq!(login(&app, &email).await.status(), 200);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_require_2fa_without_remember_device() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    app.signup(&email, true).await;
+
+    let response = login_with_2fa(&app, &email, false).await;
+    assert_eq!(response.status(), 200);
+    assert!(
+        !response
+            .cookies()
+            .any(|cookie| cookie.name() == TRUSTED_DEVICE_COOKIE_NAME)
+    );
+
+    assert_eq!(login(&app, &email).await.status(), 206);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn trusted_device_should_not_skip_2fa_for_other_users() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    let other_email = get_random_email();
+    app.signup(&email, true).await;
+    app.signup(&other_email, true).await;
+
+    assert_eq!(login_with_2fa(&app, &email, true).await.status(), 200);
+
+    assert_eq!(login(&app, &other_email).await.status(), 206);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_list_trusted_devices() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    app.signup(&email, true).await;
+    assert_eq!(login_with_2fa(&app, &email, true).await.status(), 200);
+
+    let body = trusted_devices(&app).await;
+    assert_eq!(body.devices.len(), 1);
+    assert!(body.devices[0].current);
+    assert!(body.devices[0].expires_at > body.devices[0].created_at);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_require_2fa_after_forgetting_device() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    app.signup(&email, true).await;
+    assert_eq!(login_with_2fa(&app, &email, true).await.status(), 200);
+    let id = trusted_devices(&app).await.devices[0].id.clone();
+
+    let response = app.delete_trusted_devices(Some(&id)).await;
+    assert_eq!(response.status(), 204);
+    assert!(trusted_devices(&app).await.devices.is_empty());
+    let response = app.delete_trusted_devices(Some(&id)).await;
+    assert_eq!(response.status(), 404);
+
+    assert_eq!(login(&app, &email).await.status(), 206);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_require_2fa_after_forgetting_all_devices() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    app.signup(&email, true).await;
+    assert_eq!(login_with_2fa(&app, &email, true).await.status(), 200);
+
+    let response = app.delete_trusted_devices(None).await;
+    assert_eq!(response.status(), 204);
+
+    assert_eq!(login(&app, &email).await.status(), 206);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_400_if_not_logged_in() {
+    let mut app = TestApp::new().await;
+
+    let response = app.get_trusted_devices().await;
+    assert_eq!(response.status(), 400);
+    app.clean_up().await;
+}
@@ -1,7 +1,9 @@
 use crate::helpers::TestApp;
 use crate::helpers::get_random_email;
-use auth_service::domain::{Email, TwoFACodeStore};
+use crate::helpers::{get_set_cookie_header, host_cookie_policy};
+use auth_service::domain::{AccountStatus, TwoFACodeStore, UserKey};
 use auth_service::domain::{LoginAttemptId, TwoFACode};
+use auth_service::routes::TokenResponse;
 use auth_service::utils::constants::JWT_COOKIE_NAME;
 
 #[tokio::test]
@@ -73,14 +75,14 @@ async fn should_return_401_if_incorrect_credentials() {
 
     let body = serde_json::json!({
         "email": random_email,
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
         "requires2FA": true
     });
     app.post_signup(&body).await;
 
     let login_body = serde_json::json!({
         "email": random_email,
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
     });
 
     // wrong, but valid email
@@ -89,7 +91,7 @@ async fn should_return_401_if_incorrect_credentials() {
         .two_fa_code_store
         .read()
         .await
-        .get_code(&Email::parse(&random_email).unwrap())
+        .get_code(&UserKey::new(None, &random_email))
         .await
         .unwrap();
 
@@ -107,7 +109,7 @@ async fn should_return_401_if_incorrect_credentials() {
         .two_fa_code_store
         .read()
         .await
-        .get_code(&Email::parse(&random_email).unwrap())
+        .get_code(&UserKey::new(None, &random_email))
         .await
         .unwrap();
 
@@ -126,7 +128,7 @@ async fn should_return_401_if_incorrect_credentials() {
         .two_fa_code_store
         .read()
         .await
-        .get_code(&Email::parse(&random_email).unwrap())
+        .get_code(&UserKey::new(None, &random_email))
         .await
         .unwrap();
 
@@ -146,20 +148,20 @@ async fn should_return_401_if_old_code() {
     let mut app = TestApp::new().await;
     let signup_body = serde_json::json!({
         "email": "valid@email.com",
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
         "requires2FA": true
     });
     app.post_signup(&signup_body).await;
     let login_body = serde_json::json!({
         "email": "valid@email.com",
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
     });
     app.post_login(&login_body).await;
     let old_2fa_code = app
         .two_fa_code_store
         .read()
         .await
-        .get_code(&Email::parse("valid@email.com").unwrap())
+        .get_code(&UserKey::new(None, "valid@email.com"))
         .await
         .unwrap()
         .1;
@@ -168,7 +170,7 @@ async fn should_return_401_if_old_code() {
         .two_fa_code_store
         .read()
         .await
-        .get_code(&Email::parse("valid@email.com").unwrap())
+        .get_code(&UserKey::new(None, "valid@email.com"))
         .await
         .unwrap()
         .0;
@@ -187,20 +189,20 @@ async fn should_return_401_if_same_code_twice() {
     let mut app = TestApp::new().await;
     let signup_body = serde_json::json!({
         "email": "valid@email.com",
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
         "requires2FA": true
     });
     app.post_signup(&signup_body).await;
     let login_body = serde_json::json!({
         "email": "valid@email.com",
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
     });
     app.post_login(&login_body).await;
     let (login_attempt_id, two_fa_code) = app
         .two_fa_code_store
         .read()
         .await
-        .get_code(&Email::parse("valid@email.com").unwrap())
+        .get_code(&UserKey::new(None, "valid@email.com"))
         .await
         .unwrap();
     let verify_2fa_body = serde_json::json!({
@@ -219,20 +221,20 @@ async fn should_return_200_if_correct_code() {
     let mut app = TestApp::new().await;
     let signup_body = serde_json::json!({
         "email": "valid@email.com",
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
         "requires2FA": true
     });
     app.post_signup(&signup_body).await;
     let login_body = serde_json::json!({
         "email": "valid@email.com",
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
     });
     app.post_login(&login_body).await;
     let (login_attempt_id, two_fa_code) = app
         .two_fa_code_store
         .read()
         .await
-        .get_code(&Email::parse("valid@email.com").unwrap())
+        .get_code(&UserKey::new(None, "valid@email.com"))
         .await
         .unwrap();
 
@@ -251,3 +253,179 @@ async fn should_return_200_if_correct_code() {
     assert!(!auth_cookie.value().is_empty());
     app.clean_up().await;
 }
+
+#[tokio::test]
+async fn should_return_423_if_account_locked_after_login() {
+    let mut app = TestApp::new().await;
+    let email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Kx7!vTq2Lm",
+        "requires2FA": true
+    });
+    app.post_signup(&signup_body).await;
+    let login_body = serde_json::json!({
+        "email": email,
+        "password": "Kx7!vTq2Lm",
+    });
+    assert_eq!(app.post_login(&login_body).await.status(), 206);
+    let (login_attempt_id, two_fa_code) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&UserKey::new(None, &email))
+        .await
+        .unwrap();
+    app.set_status(&email, AccountStatus::Locked).await;
+
+    let body = serde_json::json!({
+        "email": email,
+        "loginAttemptId": login_attempt_id.as_ref(),
+        "2FACode": two_fa_code.as_ref(),
+    });
+    let response = app.post_verify_2fa(&body).await;
+    assert_eq!(response.status(), 423);
+    assert!(
+        response
+            .cookies()
+            .all(|cookie| cookie.name() != JWT_COOKIE_NAME)
+    );
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_set_auth_cookie_with_configured_policy() {
+    let mut app = TestApp::with_settings(host_cookie_policy).await;
+    let signup_body = serde_json::json!({
+        "email": "valid@email.com",
+        "password": "Kx7!vTq2Lm",
+        "requires2FA": true
+    });
+    app.post_signup(&signup_body).await;
+    let login_body = serde_json::json!({
+        "email": "valid@email.com",
+        "password": "Kx7!vTq2Lm",
+    });
+    app.post_login(&login_body).await;
+    let (login_attempt_id, two_fa_code) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&UserKey::new(None, "valid@email.com"))
+        .await
+        .unwrap();
+
+    let test_case = serde_json::json!({
+        "email": "valid@email.com",
+        "loginAttemptId": login_attempt_id.as_ref(),
+        "2FACode": two_fa_code.as_ref(),
+    });
+    let response = app.post_verify_2fa(&test_case).await;
+    assert_eq!(response.status(), 200);
+
+    let set_cookie = get_set_cookie_header(&response, "__Host-jwt");
+    for attribute in [
+        "HttpOnly",
+        "SameSite=Strict",
+        "Secure",
+        "Path=/",
+        "Max-Age=600",
+    ] {
+        assert!(
+            set_cookie.contains(attribute),
+            "missing {} in {}",
+            attribute,
+            set_cookie
+        );
+    }
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_token_in_body_if_requested() {
+    let mut app = TestApp::new().await;
+    let signup_body = serde_json::json!({
+        "email": "valid@email.com",
+        "password": "Kx7!vTq2Lm",
+        "requires2FA": true
+    });
+    app.post_signup(&signup_body).await;
+    let login_body = serde_json::json!({
+        "email": "valid@email.com",
+        "password": "Kx7!vTq2Lm",
+    });
+    app.post_login(&login_body).await;
+    let (login_attempt_id, two_fa_code) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&UserKey::new(None, "valid@email.com"))
+        .await
+        .unwrap();
+
+    let test_case = serde_json::json!({
+        "email": "valid@email.com",
+        "loginAttemptId": login_attempt_id.as_ref(),
+        "2FACode": two_fa_code.as_ref(),
+        "tokenDelivery": "body",
+    });
+    let response = app.post_verify_2fa(&test_case).await;
+    assert_eq!(response.status(), 200);
+    assert!(
+        response
+            .cookies()
+            .all(|cookie| cookie.name() != JWT_COOKIE_NAME)
+    );
+    let body = response
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse");
+    assert!(!body.token.is_empty());
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_embed_roles_in_token() {
+    let mut app = TestApp::new().await;
+    let signup_body = serde_json::json!({
+        "email": "valid@email.com",
+        "password": "Kx7!vTq2Lm",
+        "requires2FA": true
+    });
+    app.post_signup(&signup_body).await;
+    app.set_roles("valid@email.com", &["admin"]).await;
+    let login_body = serde_json::json!({
+        "email": "valid@email.com",
+        "password": "Kx7!vTq2Lm",
+    });
+    app.post_login(&login_body).await;
+    let (login_attempt_id, two_fa_code) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&UserKey::new(None, "valid@email.com"))
+        .await
+        .unwrap();
+
+    let test_case = serde_json::json!({
+        "email": "valid@email.com",
+        "loginAttemptId": login_attempt_id.as_ref(),
+        "2FACode": two_fa_code.as_ref(),
+        "tokenDelivery": "body",
+    });
+    let token = app
+        .post_verify_2fa(&test_case)
+        .await
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse")
+        .token;
+
+    let verify_token_body = serde_json::json!({
+        "token": token,
+        "requiredRole": "admin"
+    });
+    let response = app.post_verify_token(&verify_token_body).await;
+    assert_eq!(response.status(), 200);
+    app.clean_up().await;
+}
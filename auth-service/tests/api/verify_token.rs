@@ -1,7 +1,31 @@
 use crate::helpers::TestApp;
 use crate::helpers::get_random_email;
+use auth_service::domain::AccountStatus;
+use auth_service::routes::{TokenResponse, VerifyTokenResponse};
 use auth_service::utils::constants::JWT_COOKIE_NAME;
 
+async fn login_with_roles(app: &TestApp, roles: &[&str]) -> String {
+    let email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "Kx7!vTq2Lm",
+        "requires2FA": false
+    });
+    let _ = app.post_signup(&signup_body).await;
+    app.set_roles(&email, roles).await;
+    let login_body = serde_json::json!({
+        "email": email,
+        "password": "Kx7!vTq2Lm",
+        "tokenDelivery": "body"
+    });
+    app.post_login(&login_body)
+        .await
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse")
+        .token
+}
+
 #[tokio::test]
 async fn should_return_422_if_malformed_input() {
     let mut app = TestApp::new().await;
@@ -18,14 +42,14 @@ async fn should_return_200_valid_token() {
     let random_email = get_random_email();
     let signup_body = serde_json::json!({
         "email": random_email,
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
         "requires2FA": false
     });
     let _ = app.post_signup(&signup_body).await;
 
     let login_body = serde_json::json!({
         "email": random_email,
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
     });
     let response = app.post_login(&login_body).await;
 
@@ -39,6 +63,12 @@ async fn should_return_200_valid_token() {
     });
     let response = app.post_verify_token(&verify_token_body).await;
     assert_eq!(response.status(), 200);
+    let verified = response
+        .json::<VerifyTokenResponse>()
+        .await
+        .expect("Could not deserialize response body to VerifyTokenResponse");
+    assert_eq!(verified.sub, random_email);
+    assert_eq!(verified.scope, None);
     app.clean_up().await;
 }
 
@@ -61,14 +91,14 @@ async fn should_return_401_if_banned_token() {
     let random_email = get_random_email();
     let signup_body = serde_json::json!({
         "email": random_email,
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
         "requires2FA": false
     });
     let _ = app.post_signup(&signup_body).await;
 
     let login_body = serde_json::json!({
         "email": random_email,
-        "password": "Password1!",
+        "password": "Kx7!vTq2Lm",
     });
     let response = app.post_login(&login_body).await;
 
@@ -86,3 +116,98 @@ async fn should_return_401_if_banned_token() {
     assert_eq!(response.status(), 401);
     app.clean_up().await;
 }
+
+#[tokio::test]
+async fn should_reject_token_once_account_is_not_active() {
+    let mut app = TestApp::new().await;
+    let test_cases = [
+        (AccountStatus::Disabled, 403),
+        (AccountStatus::Locked, 423),
+        (AccountStatus::PendingVerification, 403),
+    ];
+    for (status, expected_status) in test_cases {
+        let email = get_random_email();
+        let signup_body = serde_json::json!({
+            "email": email,
+            "password": "Kx7!vTq2Lm",
+            "requires2FA": false
+        });
+        app.post_signup(&signup_body).await;
+        let login_body = serde_json::json!({
+            "email": email,
+            "password": "Kx7!vTq2Lm",
+            "tokenDelivery": "body"
+        });
+        let token = app
+            .post_login(&login_body)
+            .await
+            .json::<TokenResponse>()
+            .await
+            .expect("Could not deserialize response body to TokenResponse")
+            .token;
+        let body = serde_json::json!({ "token": token });
+        assert_eq!(app.post_verify_token(&body).await.status(), 200);
+
+        // no tokens are banned, the status is checked on every verification
+        app.set_status(&email, status).await;
+        let response = app.post_verify_token(&body).await;
+        assert_eq!(
+            response.status(),
+            expected_status,
+            "Failed for status: {:?}",
+            status
+        );
+
+        app.set_status(&email, AccountStatus::Active).await;
+        assert_eq!(app.post_verify_token(&body).await.status(), 200);
+    }
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_200_if_token_has_required_role() {
+    let mut app = TestApp::new().await;
+    let token = login_with_roles(&app, &["admin"]).await;
+
+    let verify_token_body = serde_json::json!({
+        "token": token,
+        "requiredRole": "admin"
+    });
+    let response = app.post_verify_token(&verify_token_body).await;
+    assert_eq!(response.status(), 200);
+    let verified = response
+        .json::<VerifyTokenResponse>()
+        .await
+        .expect("Could not deserialize response body to VerifyTokenResponse");
+    assert_eq!(verified.roles, ["admin"]);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_403_if_token_lacks_required_role() {
+    let mut app = TestApp::new().await;
+    let test_cases: [&[&str]; 2] = [&[], &["support"]];
+    for roles in test_cases {
+        let token = login_with_roles(&app, roles).await;
+        let verify_token_body = serde_json::json!({
+            "token": token,
+            "requiredRole": "admin"
+        });
+        let response = app.post_verify_token(&verify_token_body).await;
+        assert_eq!(response.status(), 403, "Failed for roles: {:?}", roles);
+    }
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_401_if_invalid_token_with_required_role() {
+    let mut app = TestApp::new().await;
+
+    let verify_token_body = serde_json::json!({
+        "token": "wrong",
+        "requiredRole": "admin"
+    });
+    let response = app.post_verify_token(&verify_token_body).await;
+    assert_eq!(response.status(), 401);
+    app.clean_up().await;
+}